@@ -0,0 +1,249 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use api_db::{
+    repos::{AlertRepo, AlertRuleRepo, AvsConnectionRepo, AvsRepo, PlayLogRepo, UserRepo},
+    ApiDatabase, DatabaseError,
+};
+use chrono::{Duration, NaiveDateTime};
+use std::collections::HashMap;
+use types::api::{
+    alert::{Alert, AlertRule, NewAlert},
+    avs::Avs,
+};
+use utils::mail::Mail;
+
+/// A resolved alert is reopened without a new notification when its condition returns within the minutes.
+const REOPEN_MINUTES: i64 = 30;
+/// The window of the failed plays in hours.
+const FAILED_PLAYS_HOURS: i64 = 24;
+
+/// Health.
+/// The health of an avs that isn't stored on the avs row.
+struct Health {
+    /// When the avs disconnected, unset while it's online or if it never connected.
+    offline_since: Option<NaiveDateTime>,
+    failed_plays: i64,
+}
+
+/// Triggered.
+/// A threshold crossed by an avs.
+struct Triggered {
+    rule: AlertRule,
+    kind: &'static str,
+    message: String,
+    value: f64,
+}
+
+/// Parse a number reported by the avs.
+fn parse(value: &Option<String>) -> Option<f64> {
+    value.as_deref().and_then(|v| v.trim().parse::<f64>().ok())
+}
+
+/// Check the thresholds of the rule against the avs.
+fn check(rule: &AlertRule, avs: &Avs, health: &Health, now: NaiveDateTime) -> Vec<Triggered> {
+    let mut res = Vec::new();
+    let mut trigger = |kind, message, value| {
+        res.push(Triggered {
+            rule: rule.clone(),
+            kind,
+            message,
+            value,
+        })
+    };
+    if let (Some(max), Some(since)) = (rule.offline_minutes, health.offline_since) {
+        let minutes = (now - since).num_minutes();
+        if minutes >= max as i64 {
+            trigger(
+                "offline",
+                format!(
+                    "AVS {} has been offline for {} minutes",
+                    avs.unique_id, minutes
+                ),
+                minutes as f64,
+            );
+        }
+    }
+    // the avs reports the free disk space in bytes.
+    if let (Some(min), Some(free)) = (rule.disk_free_mb, parse(&avs.disk_free)) {
+        let free = free / 1024.0 / 1024.0;
+        if free < min as f64 {
+            trigger(
+                "disk_free",
+                format!(
+                    "AVS {} has {:.0} MB of free disk space",
+                    avs.unique_id, free
+                ),
+                free,
+            );
+        }
+    }
+    if let (Some(max), Some(temp)) = (rule.cpu_temp_max, parse(&avs.cpu_temp)) {
+        if temp > max {
+            trigger(
+                "cpu_temp",
+                format!("AVS {} cpu temperature is {:.1} °C", avs.unique_id, temp),
+                temp,
+            );
+        }
+    }
+    if let Some(max) = rule.failed_plays {
+        if health.failed_plays >= max as i64 {
+            trigger(
+                "failed_plays",
+                format!(
+                    "AVS {} failed {} plays in the last {} hours",
+                    avs.unique_id, health.failed_plays, FAILED_PLAYS_HOURS
+                ),
+                health.failed_plays as f64,
+            );
+        }
+    }
+    res
+}
+
+/// Mail the users of the rule group about the alert.
+/// The alert is marked as notified once a mail is sent, so it's only retried when every mail failed.
+async fn notify(db: &ApiDatabase, mail: &Mail, alert: &Alert, group_id: i32) {
+    let users = match db.repository::<UserRepo>().get_by_group(group_id) {
+        Ok(users) => users,
+        Err(e) => {
+            log::error!("Failed to get the users of group {}: {}", group_id, e);
+            return;
+        }
+    };
+    let mut sent = users.is_empty();
+    for user in users {
+        let res = mail
+            .send_alert(&user.name, &user.email, &alert.message)
+            .await
+            .map_err(|e| e.to_string());
+        match res {
+            Ok(_) => sent = true,
+            Err(e) => log::error!("Failed to send alert {} to {}: {}", alert.id, user.email, e),
+        }
+    }
+    if sent {
+        if let Err(e) = db.repository::<AlertRepo>().set_notified(alert.id) {
+            log::error!("Failed to mark alert {} as notified: {}", alert.id, e);
+        }
+    }
+}
+
+/// Evaluate the enabled alert rules.
+/// An alert is opened and mailed once when an avs crosses a threshold of its groups,
+/// and resolved when the avs is back under every threshold of the kind.
+/// When an avs is in several groups, the rule of the lowest group id opens the alert.
+pub async fn evaluate(db: &ApiDatabase, mail: &Mail) -> Result<(), DatabaseError> {
+    let rules = db.repository::<AlertRuleRepo>().get_enabled()?;
+    let avs_repo = db.repository::<AvsRepo>();
+    let mut devices = HashMap::<i32, (Avs, Vec<AlertRule>)>::new();
+    for rule in rules {
+        for avs in avs_repo.get_by_group(rule.user_group_id)? {
+            if avs.pending != 0 {
+                continue;
+            }
+            devices
+                .entry(avs.id)
+                .or_insert_with(|| (avs, vec![]))
+                .1
+                .push(rule.clone());
+        }
+    }
+
+    let now = utils::time::now();
+    let ids = devices.keys().copied().collect::<Vec<i32>>();
+    let disconnected = db
+        .repository::<AvsConnectionRepo>()
+        .last_disconnected(&ids)?
+        .into_iter()
+        .filter_map(|(id, at)| at.map(|at| (id, at)))
+        .collect::<HashMap<i32, NaiveDateTime>>();
+    let failed_plays = db
+        .repository::<PlayLogRepo>()
+        .count_failed(&ids, now - Duration::hours(FAILED_PLAYS_HOURS))?
+        .into_iter()
+        .collect::<HashMap<i32, i64>>();
+
+    let repo = db.repository::<AlertRepo>();
+    let mut active = repo
+        .get_active()?
+        .into_iter()
+        .map(|alert| ((alert.avs_id, alert.kind.clone()), alert))
+        .collect::<HashMap<(i32, String), Alert>>();
+    let reopen_after = now - Duration::minutes(REOPEN_MINUTES);
+    for (avs, rules) in devices.values() {
+        let health = Health {
+            offline_since: if avs.status != 1 {
+                disconnected.get(&avs.id).copied()
+            } else {
+                None
+            },
+            failed_plays: failed_plays.get(&avs.id).copied().unwrap_or_default(),
+        };
+        let mut triggered = HashMap::<&'static str, Triggered>::new();
+        for rule in rules {
+            for t in check(rule, avs, &health, now) {
+                triggered.entry(t.kind).or_insert(t);
+            }
+        }
+        for (kind, t) in triggered {
+            let alert = match active.remove(&(avs.id, kind.to_owned())) {
+                Some(mut alert) => {
+                    repo.refresh(alert.id, &t.message, t.value)?;
+                    alert.message = t.message;
+                    alert
+                }
+                None => {
+                    let alert = repo.open(
+                        NewAlert {
+                            avs_id: avs.id,
+                            alert_rule_id: Some(t.rule.id),
+                            kind: kind.to_owned(),
+                            message: t.message,
+                            value: t.value,
+                        },
+                        reopen_after,
+                    )?;
+                    log::info!("Opened alert {}: {}", alert.id, alert.message);
+                    alert
+                }
+            };
+            if alert.notified_at.is_none() {
+                notify(db, mail, &alert, t.rule.user_group_id).await;
+            }
+        }
+    }
+
+    // the conditions that cleared, or the avs no longer covered by a rule.
+    for alert in active.into_values() {
+        repo.resolve(alert.id)?;
+        log::info!("Resolved alert {}", alert.id);
+    }
+    Ok(())
+}
+
+/// Evaluate the alert rules every `interval` seconds.
+pub async fn run(db: ApiDatabase, mail: Mail, interval: u64) {
+    let mut tick = tokio::time::interval(std::time::Duration::from_secs(interval));
+    loop {
+        tick.tick().await;
+        if let Err(e) = evaluate(&db, &mail).await {
+            log::error!("Failed to evaluate alert rules: {}", e);
+        }
+    }
+}
@@ -15,5 +15,7 @@ MyRTS agrees to credit you as the developer of the software in all promotional m
 If MyRTS violates any of these terms, their license to use the software will automatically terminate.
 */
 
+pub mod alerts;
+pub mod config;
 pub mod services;
 pub mod states;
@@ -15,35 +15,119 @@ MyRTS agrees to credit you as the developer of the software in all promotional m
 If MyRTS violates any of these terms, their license to use the software will automatically terminate.
 */
 
-use api_db::{repos::AvsRepo, ApiDatabase};
+use crate::states::update::UpdateState;
+use api_db::{
+    repos::{
+        AvsConnectionRepo, AvsRepo, DeviceConfigRepo, EnrollmentRepo, PlaylistRepo, UpdatesRepo,
+        VolumeProfileRepo,
+    },
+    ApiDatabase,
+};
 use proto::{
     app::{Data, Stream},
     WsState,
 };
 use timeslots::TimeSlots;
-use types::{api::avs::NewAvs, proto::Authenticate};
+use types::{
+    api::avs::NewAvs,
+    proto::{Authenticate, Credential, DeviceConfig, Playlist, WsErr},
+};
+use utils::crypto::{constant_eq, hash, random_string};
+
+/// Reject the avs with the given reason and disconnect it.
+async fn reject(stream: Stream, msg: &str) {
+    let _ = stream
+        .write(
+            "auth:fail",
+            WsErr {
+                msg: msg.to_owned(),
+            },
+        )
+        .await;
+    let _ = stream.disconnect().await;
+}
+
+/// Check the credential against the stored hash.
+fn credential_matches(credential: Option<&str>, credential_hash: Option<&str>) -> bool {
+    match (credential, credential_hash) {
+        (Some(credential), Some(credential_hash)) => constant_eq(
+            hash(credential.as_bytes()).as_bytes(),
+            credential_hash.as_bytes(),
+        ),
+        _ => false,
+    }
+}
 
 pub(super) async fn auth(
     stream: Stream,
     data: Authenticate,
     state: Data<WsState>,
     db: Data<ApiDatabase>,
+    update_state: Data<UpdateState>,
 ) {
+    if let Some(_) = state.avs_by_id(data.client_id.clone()).await {
+        let _ = stream.disconnect().await;
+        return;
+    }
     let repo = db.repository::<AvsRepo>();
+    // The credential to deliver to the avs, set when a new one is issued.
+    let mut issued = None;
     let avs = match repo.get_unique(&data.client_id) {
-        Ok(val) => {
-            if let Some(avs) = val {
-                avs
-            } else {
-                let avs = repo.create(NewAvs {
+        Ok(Some(avs)) if avs.credential_hash.is_some() => {
+            if !credential_matches(data.credential.as_deref(), avs.credential_hash.as_deref()) {
+                log::warn!("Rejected avs {}: invalid credential", data.client_id);
+                reject(stream, "invalid credential").await;
+                return;
+            }
+            avs
+        }
+        // holding the issued credential proves the avs received it, even if its ack was lost.
+        Ok(Some(avs))
+            if credential_matches(
+                data.credential.as_deref(),
+                avs.credential_pending.as_deref(),
+            ) =>
+        {
+            if let Err(e) = repo.confirm_credential(avs.id) {
+                log::error!("Failed to confirm avs credential: {}", e);
+            }
+            avs
+        }
+        // avs registered before provisioning, reset or that never acknowledged its credential
+        // adopt a new one on first use, a pending avs with a token is accepted by enrolling.
+        Ok(Some(avs)) if avs.pending == 0 || data.token.is_none() => {
+            let credential = random_string(32);
+            if let Err(e) = repo.issue_credential(avs.id, &hash(credential.as_bytes())) {
+                log::error!("Failed to issue avs credential: {}", e);
+                let _ = stream.disconnect().await;
+                return;
+            }
+            log::info!("Issued a credential to avs {}", avs.unique_id);
+            issued = Some(credential);
+            avs
+        }
+        // unknown avs need an enrollment token.
+        Ok(_) => {
+            let token = match &data.token {
+                Some(token) => token,
+                None => {
+                    log::warn!("Rejected avs {}: not enrolled", data.client_id);
+                    reject(stream, "device is not enrolled").await;
+                    return;
+                }
+            };
+            let credential = random_string(32);
+            let avs = db.repository::<EnrollmentRepo>().enroll(
+                &hash(token.as_bytes()),
+                NewAvs {
                     unique_id: data.client_id.clone(),
-                    description: Some(data.client_description),
+                    description: Some(data.client_description.clone()),
                     status: 1,
                     lat: None,
                     lng: None,
-                    address: Some(data.client_address),
+                    address: Some(data.client_address.clone()),
                     kind: 1,
-                    pending: 1,
+                    pending: 0,
                     slots: TimeSlots::new().into(),
                     networks: None,
                     mem_total: None,
@@ -51,13 +135,25 @@ pub(super) async fn auth(
                     disk_total: None,
                     disk_free: None,
                     cpu_temp: None,
-                });
-                if let Err(_) = avs {
+                },
+                hash(credential.as_bytes()),
+            );
+            match avs {
+                Ok(Some(avs)) => {
+                    log::info!("Enrolled avs {}", avs.unique_id);
+                    issued = Some(credential);
+                    avs
+                }
+                Ok(None) => {
+                    log::warn!("Rejected avs {}: invalid enrollment token", data.client_id);
+                    reject(stream, "invalid enrollment token").await;
+                    return;
+                }
+                Err(e) => {
+                    log::error!("Failed to enroll avs: {}", e);
                     let _ = stream.disconnect().await;
                     return;
                 }
-                state.set_avs(data.client_id, stream).await;
-                return;
             }
         }
         Err(_) => {
@@ -65,13 +161,41 @@ pub(super) async fn auth(
             return;
         }
     };
-    if let Some(_) = state.avs_by_id(avs.unique_id.clone()).await {
-        let _ = stream.disconnect().await;
-        return;
-    }
     state.set_avs(data.client_id, stream.clone()).await;
     let _ = repo.connect(&avs.unique_id);
+    let remote_addr = stream.remote_addr().map(ToOwned::to_owned);
+    match db
+        .repository::<AvsConnectionRepo>()
+        .open(avs.id, remote_addr)
+    {
+        Ok(session) => state.set_session(stream.id().to_owned(), session.id).await,
+        Err(e) => log::error!("Failed to open avs connection: {}", e),
+    }
+    if let Some(credential) = issued {
+        let _ = stream.write("credential", Credential { credential }).await;
+    }
     if avs.pending == 0 {
         let _ = stream.write("authenticated", "").await;
+        match db.repository::<DeviceConfigRepo>().resolve(avs.id) {
+            Ok(config) => {
+                let profile = db
+                    .repository::<VolumeProfileRepo>()
+                    .resolve(avs.id)
+                    .unwrap_or_default();
+                let config = DeviceConfig::from(config).with_volume_profile(profile);
+                let _ = stream.write("config", config).await;
+            }
+            Err(e) => log::error!("Failed to resolve device config: {}", e),
+        }
+        match db.repository::<PlaylistRepo>().resolve(avs.id) {
+            Ok(playlist) => {
+                let playlist = playlist.map(Playlist::from).unwrap_or_default();
+                let _ = stream.write("playlist", playlist).await;
+            }
+            Err(e) => log::error!("Failed to resolve playlist: {}", e),
+        }
+        if let Ok(Some(update)) = db.repository::<UpdatesRepo>().get_pending(avs.id) {
+            let _ = stream.write("update", update_state.announce(update)).await;
+        }
     }
 }
@@ -15,7 +15,7 @@ MyRTS agrees to credit you as the developer of the software in all promotional m
 If MyRTS violates any of these terms, their license to use the software will automatically terminate.
 */
 
-use crate::services::auth::auth_avs;
+use crate::{services::auth::auth_avs, states::update::UpdateState};
 
 use super::auth_user;
 use api_db::ApiDatabase;
@@ -34,6 +34,7 @@ async fn auth(
     state: Data<WsState>,
     jwt: Data<Jwt>,
     db: Data<ApiDatabase>,
+    update_state: Data<UpdateState>,
 ) -> Result<()> {
     let data = data.into_inner();
     match data.client_type {
@@ -42,7 +43,7 @@ async fn auth(
             Ok(())
         }
         2 => {
-            auth_avs::auth(stream, data, state, db).await;
+            auth_avs::auth(stream, data, state, db, update_state).await;
             Ok(())
         }
         _ => Ok(()),
@@ -0,0 +1,60 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use api_db::{repos::AvsRepo, ApiDatabase};
+use proto::{
+    app::{Data, MsgData, Stream},
+    error::{OtherError, Result},
+    WsState,
+};
+use types::proto::Credential;
+use utils::crypto::{constant_eq, hash};
+
+#[proto::service("credential:ack")]
+async fn credential_ack(
+    stream: Stream,
+    data: MsgData<Credential>,
+    ws_state: Data<WsState>,
+    db: Data<ApiDatabase>,
+) -> Result<()> {
+    let avs_id = if let Some(avs) = ws_state.avs_id(stream.id().to_owned()).await {
+        avs
+    } else {
+        return Ok(());
+    };
+    let repo = db.repository::<AvsRepo>();
+    let avs = match repo.get_unique(&avs_id) {
+        Ok(Some(avs)) => avs,
+        _ => return Ok(()),
+    };
+    let pending = match avs.credential_pending {
+        Some(pending) => pending,
+        None => return Ok(()),
+    };
+    // a newer credential may have been issued since, only the pending one is confirmed.
+    if !constant_eq(
+        hash(data.into_inner().credential.as_bytes()).as_bytes(),
+        pending.as_bytes(),
+    ) {
+        log::warn!("Avs {} acknowledged an unknown credential", avs.unique_id);
+        return Ok(());
+    }
+    repo.confirm_credential(avs.id)
+        .map_err(|e| OtherError::String(e.to_string()))?;
+    log::info!("Avs {} acknowledged its credential", avs.unique_id);
+    Ok(())
+}
@@ -18,5 +18,7 @@ If MyRTS violates any of these terms, their license to use the software will aut
 mod auth_avs;
 mod auth_service;
 mod auth_user;
+mod credential_service;
 
 pub(super) use auth_service::auth;
+pub(super) use credential_service::credential_ack;
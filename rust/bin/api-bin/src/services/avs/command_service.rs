@@ -15,23 +15,136 @@ MyRTS agrees to credit you as the developer of the software in all promotional m
 If MyRTS violates any of these terms, their license to use the software will automatically terminate.
 */
 
+use api_db::{
+    repos::{AvsRepo, CommandAuditRepo, UserRepo},
+    ApiDatabase,
+};
 use proto::{
     app::{Data, Msg, Stream},
     error::Result,
     WsState,
 };
-use types::proto::{CmdRequest, CmdResponse};
+use types::{
+    api::{
+        avs::Avs,
+        command_audit::{CommandAudit, NewCommandAudit},
+    },
+    proto::{
+        action::{self, RAW_SHELL_ROLE},
+        CmdRequest, CmdResponse,
+    },
+};
+
+/// Maximum number of bytes of the output kept in the command audit.
+const OUTPUT_LIMIT: usize = 8 * 1024;
+
+/// Store the response of the command in the command audit of the avs.
+/// Returns the audited command, the response is only forwarded to the user who requested it.
+fn store_command_response(
+    db: &ApiDatabase,
+    avs_id: i32,
+    response: &CmdResponse,
+) -> Option<CommandAudit> {
+    let audit = response_audit(db, avs_id, response)?;
+    let (output, truncated) = audit_output(response);
+    let repo = db.repository::<CommandAuditRepo>();
+    if let Err(e) = repo.respond(audit.id, avs_id, response.status, output, truncated) {
+        log::error!("Failed to store command response: {}", e);
+    }
+    Some(audit)
+}
+
+/// Get the audited command of the response of the avs.
+/// Responses without an id belong to the oldest pending command of the user.
+pub(crate) fn response_audit(
+    db: &ApiDatabase,
+    avs_id: i32,
+    response: &CmdResponse,
+) -> Option<CommandAudit> {
+    let repo = db.repository::<CommandAuditRepo>();
+    let audit = match response.id {
+        Some(id) => repo.get_by_avs(id, avs_id),
+        None => repo.pending(response.sender, avs_id),
+    };
+    match audit {
+        Ok(Some(audit)) => Some(audit),
+        Ok(None) => {
+            log::warn!("Dropping response of an unknown command of avs {}", avs_id);
+            None
+        }
+        Err(e) => {
+            log::error!("Failed to get command audit: {}", e);
+            None
+        }
+    }
+}
+
+/// Get the output of the response kept in the command audit and whether it was truncated.
+pub(crate) fn audit_output(response: &CmdResponse) -> (String, bool) {
+    let mut output = response.response.clone();
+    let truncated = output.len() > OUTPUT_LIMIT;
+    if truncated {
+        let mut end = OUTPUT_LIMIT;
+        while !output.is_char_boundary(end) {
+            end -= 1;
+        }
+        output.truncate(end);
+    }
+    (output, truncated)
+}
+
+/// Check the request against the command policy of the user.
+/// Returns the command to audit, either the raw command or the described action.
+fn authorize(
+    db: &ApiDatabase,
+    user_id: i32,
+    avs: &Avs,
+    data: &CmdRequest,
+) -> Result<String, String> {
+    let user = db
+        .repository::<UserRepo>()
+        .get(user_id)
+        .map_err(|_| "user not found".to_owned())?;
+    if user.role_id != 1 && user.role_id != 2 && !user.device_ids.contains(&Some(avs.id)) {
+        return Err("avs not found".to_owned());
+    }
+    match &data.action {
+        Some(name) => {
+            let action = action::action(name)
+                .filter(|a| a.allows(user.role_id))
+                .ok_or_else(|| format!("action {} is not allowed", name))?;
+            action.validate(&data.params)?;
+            Ok(action.describe(&data.params))
+        }
+        None if user.role_id == RAW_SHELL_ROLE => Ok(data.command.clone()),
+        None => Err("raw commands are not allowed".to_owned()),
+    }
+}
 
 #[proto::service("command")]
-async fn command(stream: Stream, data: Msg, ws_state: Data<WsState>) -> Result<()> {
-    if let Some(_) = ws_state.avs_id(stream.id().to_string()).await {
-        let data: CmdResponse = match data.deserialize() {
+async fn command(
+    stream: Stream,
+    data: Msg,
+    ws_state: Data<WsState>,
+    db: Data<ApiDatabase>,
+) -> Result<()> {
+    if let Some(avs_id) = ws_state.avs_id(stream.id().to_string()).await {
+        let mut data: CmdResponse = match data.deserialize() {
             Ok(data) => data,
             Err(err) => {
                 log::error!("Failed to parse command data: {}", err);
                 return Ok(());
             }
         };
+        let audit = match db.repository::<AvsRepo>().get_unique(&avs_id) {
+            Ok(Some(avs)) => store_command_response(&db, avs.id, &data),
+            _ => None,
+        };
+        // the sender reported by the avs isn't trusted.
+        data.sender = match audit.and_then(|audit| audit.user_id) {
+            Some(user_id) => user_id,
+            None => return Ok(()),
+        };
 
         if let Some(user) = ws_state.user_by_id(data.sender).await {
             return user.write("command", data).await;
@@ -39,16 +152,55 @@ async fn command(stream: Stream, data: Msg, ws_state: Data<WsState>) -> Result<(
             log::warn!("User not found: {}", data.sender);
             return Ok(());
         }
-    } else if let Some(_) = ws_state.user_id(stream.id().to_string()).await {
-        let data: CmdRequest = match data.deserialize() {
+    } else if let Some(user_id) = ws_state.user_id(stream.id().to_string()).await {
+        let mut data: CmdRequest = match data.deserialize() {
             Ok(data) => data,
             Err(err) => {
                 log::error!("Failed to parse command data: {}", err);
                 return Ok(());
             }
         };
+        // the response goes back to the user who sent the command.
+        data.sender = user_id;
+
+        let checked = match db.repository::<AvsRepo>().get_unique(&data.target) {
+            Ok(Some(target)) => {
+                authorize(&db, user_id, &target, &data).map(|command| (target, command))
+            }
+            _ => Err("avs not found".to_owned()),
+        };
+        let (target, command) = match checked {
+            Ok(checked) => checked,
+            Err(e) => {
+                log::warn!("Command of {} rejected: {}", user_id, e);
+                return stream
+                    .write(
+                        "command",
+                        CmdResponse {
+                            response: format!("Command rejected: {}", e),
+                            sender: user_id,
+                            target: data.target,
+                            id: None,
+                            status: None,
+                        },
+                    )
+                    .await;
+            }
+        };
+
+        let avs = ws_state.avs_by_id(data.target.clone()).await;
+        let audit = db.repository::<CommandAuditRepo>().create(NewCommandAudit {
+            user_id: Some(user_id),
+            avs_id: target.id,
+            command,
+            delivered: avs.is_some(),
+        });
+        match audit {
+            Ok(audit) => data.id = Some(audit.id),
+            Err(e) => log::error!("Failed to create command audit: {}", e),
+        }
 
-        if let Some(avs) = ws_state.avs_by_id(data.target.clone()).await {
+        if let Some(avs) = avs {
             return avs.write("command", data).await;
         } else {
             log::warn!("AVS not found: {}", data.target);
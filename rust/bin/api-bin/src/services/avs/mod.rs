@@ -19,3 +19,11 @@ mod avs_info_service;
 pub(super) use avs_info_service::avs_info;
 mod command_service;
 pub(super) use command_service::command;
+pub(crate) use command_service::{audit_output, response_audit};
+mod telemetry_service;
+pub(super) use telemetry_service::telemetry;
+pub(crate) use telemetry_service::{new_telemetry, update_avs_info};
+mod ports_service;
+pub(super) use ports_service::ports;
+mod self_test_service;
+pub(super) use self_test_service::self_test;
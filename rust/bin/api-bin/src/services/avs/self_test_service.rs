@@ -0,0 +1,60 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use api_db::{
+    repos::{AvsRepo, SelfTestRepo},
+    ApiDatabase,
+};
+use proto::{
+    app::{Data, MsgData, Stream},
+    error::Result,
+    WsState,
+};
+use types::{api::self_test::NewSelfTestCheck, proto::SelfTestReport};
+
+#[proto::service("self_test")]
+async fn self_test(
+    stream: Stream,
+    data: MsgData<SelfTestReport>,
+    ws_state: Data<WsState>,
+    db: Data<ApiDatabase>,
+) -> Result<()> {
+    let report = data.into_inner();
+    let avs_id = if let Some(avs) = ws_state.avs_id(stream.id().to_owned()).await {
+        avs
+    } else {
+        return Ok(());
+    };
+    let avs = match db.repository::<AvsRepo>().get_unique(&avs_id) {
+        Ok(Some(avs)) => avs,
+        _ => return Ok(()),
+    };
+    let checks = report
+        .checks
+        .into_iter()
+        .map(NewSelfTestCheck::from)
+        .collect();
+    match db
+        .repository::<SelfTestRepo>()
+        .finish(report.id, avs.id, report.version, checks)
+    {
+        Ok(Some(_)) => {}
+        Ok(None) => log::warn!("Ignoring unknown self test {} from {}", report.id, avs_id),
+        Err(e) => log::error!("Failed to store self test {}: {}", report.id, e),
+    }
+    Ok(())
+}
@@ -0,0 +1,124 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use api_db::{
+    repos::{AvsRepo, TelemetryRepo},
+    ApiDatabase, DatabaseError,
+};
+use proto::{
+    app::{Data, MsgData, Stream},
+    error::Result,
+    WsState,
+};
+use std::collections::HashMap;
+use types::{
+    api::{
+        avs::{Avs, UpdateAvsInfo},
+        telemetry::{NewTelemetry, NewTelemetryNetwork},
+    },
+    proto::{InterfaceAddr, Telemetry},
+};
+
+#[proto::service("telemetry")]
+async fn telemetry(
+    stream: Stream,
+    data: MsgData<Telemetry>,
+    ws_state: Data<WsState>,
+    db: Data<ApiDatabase>,
+) -> Result<()> {
+    let data = data.into_inner();
+    let avs_id = if let Some(avs) = ws_state.avs_id(stream.id().to_owned()).await {
+        avs
+    } else {
+        return Ok(());
+    };
+    let avs = match db.repository::<AvsRepo>().get_unique(&avs_id) {
+        Ok(Some(avs)) => avs,
+        _ => return Ok(()),
+    };
+    if let Err(e) = store_telemetry(&db, &avs, data, None) {
+        log::error!("Failed to store telemetry: {}", e);
+    }
+    Ok(())
+}
+
+/// Store a telemetry sample of the avs, `created_at` defaults to now.
+fn store_telemetry(
+    db: &ApiDatabase,
+    avs: &Avs,
+    data: Telemetry,
+    created_at: Option<chrono::NaiveDateTime>,
+) -> Result<(), DatabaseError> {
+    update_avs_info(db, avs, &data);
+    let (new, networks) = new_telemetry(avs, data, created_at);
+    db.repository::<TelemetryRepo>().create(new, networks)?;
+    Ok(())
+}
+
+/// Keep the latest info on the avs for the overview.
+pub(crate) fn update_avs_info(db: &ApiDatabase, avs: &Avs, data: &Telemetry) {
+    let repo = db.repository::<AvsRepo>();
+    let networks = data
+        .networks
+        .iter()
+        .map(|n| (n.name.clone(), n.addrs.clone()))
+        .collect::<HashMap<String, Vec<InterfaceAddr>>>();
+    let update = UpdateAvsInfo {
+        networks: serde_json::to_string(&networks).ok(),
+        mem_total: data.mem_total.map(|v| v.to_string()),
+        mem_free: data.mem_free.map(|v| v.to_string()),
+        disk_total: data.disk_total.map(|v| v.to_string()),
+        disk_free: data.disk_free.map(|v| v.to_string()),
+        cpu_temp: data.cpu_temp.map(|v| v.to_string()),
+    };
+    let _ = repo.update_os_info(update, avs.id);
+}
+
+/// Convert a telemetry sample of the avs into the rows to store, `created_at` defaults to now.
+pub(crate) fn new_telemetry(
+    avs: &Avs,
+    data: Telemetry,
+    created_at: Option<chrono::NaiveDateTime>,
+) -> (NewTelemetry, Vec<NewTelemetryNetwork>) {
+    let networks = data
+        .networks
+        .into_iter()
+        .map(|n| NewTelemetryNetwork {
+            telemetry_id: 0,
+            name: n.name,
+            rx_bytes: n.rx_bytes,
+            tx_bytes: n.tx_bytes,
+        })
+        .collect();
+    let new = NewTelemetry {
+        avs_id: avs.id,
+        uptime: data.uptime,
+        cpu_load: data.cpu_load.map(|v| v as f64),
+        cpu_temp: data.cpu_temp.map(|v| v as f64),
+        mem_total: data.mem_total,
+        mem_free: data.mem_free,
+        disk_total: data.disk_total,
+        disk_free: data.disk_free,
+        audio_output: data.audio_output,
+        audio_ok: data.audio_ok,
+        audio_playing: data.audio_playing,
+        version: data.version,
+        rss: data.rss,
+        created_at,
+    };
+    (new, networks)
+}
@@ -15,7 +15,10 @@ MyRTS agrees to credit you as the developer of the software in all promotional m
 If MyRTS violates any of these terms, their license to use the software will automatically terminate.
 */
 
-use api_db::{repos::AvsRepo, ApiDatabase};
+use api_db::{
+    repos::{AvsConnectionRepo, AvsRepo},
+    ApiDatabase,
+};
 use proto::{
     app::{Data, Stream},
     WsState,
@@ -29,6 +32,7 @@ pub(super) async fn end(
     db: Data<ApiDatabase>,
     avs: String,
     stream_state: Data<StreamingState>,
+    reason: String,
 ) {
     let avs_clone = avs.clone();
     tokio::spawn(async move {
@@ -37,4 +41,15 @@ pub(super) async fn end(
     let repo = db.repository::<AvsRepo>();
     state.remove_avs(stream.id().to_string()).await;
     let _ = repo.disconnect(&avs);
+    // only the session of this stream, a newer connection of the avs stays open.
+    if let Some(session) = state.take_session(stream.id()).await {
+        let reason = if reason.is_empty() {
+            "connection closed"
+        } else {
+            reason.as_str()
+        };
+        if let Err(e) = db.repository::<AvsConnectionRepo>().close(session, reason) {
+            log::error!("Failed to close avs connection: {}", e);
+        }
+    }
 }
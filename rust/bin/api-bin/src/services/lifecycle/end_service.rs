@@ -21,7 +21,7 @@ use crate::{
 };
 use api_db::ApiDatabase;
 use proto::{
-    app::{Data, Stream},
+    app::{Data, Msg, Stream},
     error::Result,
     WsState,
 };
@@ -32,9 +32,11 @@ async fn end(
     state: Data<WsState>,
     stream_state: Data<StreamingState>,
     db: Data<ApiDatabase>,
+    data: Msg,
 ) -> Result<()> {
     if let Some(avs) = state.avs_id(stream.id().to_owned()).await {
-        end_avs::end(stream, state, db, avs, stream_state).await;
+        let reason = data.deserialize::<String>().unwrap_or_default();
+        end_avs::end(stream, state, db, avs, stream_state, reason).await;
         Ok(())
     } else {
         end_user::end(stream, state, stream_state).await;
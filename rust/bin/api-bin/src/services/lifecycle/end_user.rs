@@ -24,8 +24,10 @@ use crate::states::stream::StreamingState;
 
 pub(super) async fn end(stream: Stream, state: Data<WsState>, stream_state: Data<StreamingState>) {
     if let Some(user) = state.user_id(stream.id().to_owned()).await {
+        let listener_id = stream.id().to_owned();
         tokio::spawn(async move {
-            stream_state.close_streaming(user).await;
+            stream_state.stop_listening(&listener_id).await;
+            stream_state.close_live_streaming(user).await;
         });
     }
     state.remove_user(stream.id().to_owned()).await;
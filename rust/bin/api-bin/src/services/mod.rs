@@ -15,44 +15,87 @@ MyRTS agrees to credit you as the developer of the software in all promotional m
 If MyRTS violates any of these terms, their license to use the software will automatically terminate.
 */
 
-use crate::states::stream::StreamingState;
+use crate::{
+    config::Config,
+    states::{stream::StreamingState, update::UpdateState},
+};
 use api_db::ApiDatabase;
 use proto::{
     app::{App, Data},
     server::Server,
     WsState,
 };
-use utils::crypto::Jwt;
+use utils::{crypto::Jwt, files::ApiAssets};
 
 pub(crate) mod auth;
 pub(crate) mod avs;
 pub(crate) mod lifecycle;
+pub(crate) mod outbox;
 pub(crate) mod streaming;
 pub(crate) mod sync;
+pub(crate) mod updates;
 
 /// Start ws server.
-pub async fn start_ws(port: u16, db: ApiDatabase, jwt: Jwt, state: WsState) {
-    let streaming_state = StreamingState::new(state.streaming());
+pub async fn start_ws(
+    config: &Config,
+    db: ApiDatabase,
+    jwt: Jwt,
+    state: WsState,
+    assets: ApiAssets,
+) {
+    let port = config.stream_port;
+    let base_url = config.base_url.as_str();
+    let turn = config.turn.clone();
+    let trusted_proxies = config.trusted_proxies.clone();
+    let streaming_state = StreamingState::new(
+        state.streaming(),
+        turn.clone(),
+        db.clone(),
+        assets,
+        base_url,
+    );
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+    state.set_playback(tx).await;
+    tokio::spawn(streaming::playback::run(
+        rx,
+        state.clone(),
+        streaming_state.clone(),
+        db.clone(),
+    ));
     let app = App::new()
         .add_state(Data::new(state))
         .add_state(Data::new(db))
         .add_state(Data::new(jwt))
         .add_state(Data::new(streaming_state))
+        .add_state(Data::new(UpdateState::new(base_url)))
+        .add_state(Data::new(turn))
         .service(lifecycle::start)
         .service(lifecycle::ping)
         .service(lifecycle::end)
         .service(auth::auth)
+        .service(auth::credential_ack)
         .service(sync::sync)
         .service(streaming::turn)
         .service(streaming::offer)
         .service(streaming::answer)
         .service(streaming::ices)
         .service(streaming::volume)
+        .service(streaming::stats)
+        .service(streaming::listen)
+        .service(streaming::listen_answer)
+        .service(streaming::listen_ices)
+        .service(streaming::listen_stop)
         .service(avs::avs_info)
-        .service(avs::command);
+        .service(avs::telemetry)
+        .service(avs::ports)
+        .service(avs::command)
+        .service(avs::self_test)
+        .service(outbox::outbox)
+        .service(updates::update_status);
 
     tokio::spawn(async move {
-        match Server::new(app, port).run().await {
+        let server = Server::new(app, port).with_trusted_proxies(trusted_proxies);
+        match server.run().await {
             Ok(_) => {
                 std::process::exit(0);
             }
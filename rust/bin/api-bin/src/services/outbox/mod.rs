@@ -15,7 +15,5 @@ MyRTS agrees to credit you as the developer of the software in all promotional m
 If MyRTS violates any of these terms, their license to use the software will automatically terminate.
 */
 
-/// Load env or default.
-pub fn load_env(name: &str, default: &str) -> String {
-    std::env::var(name).unwrap_or_else(|_| default.to_string())
-}
+mod outbox_service;
+pub(super) use outbox_service::outbox;
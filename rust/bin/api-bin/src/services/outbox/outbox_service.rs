@@ -0,0 +1,159 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use crate::services::avs::{audit_output, new_telemetry, response_audit, update_avs_info};
+use api_db::{
+    repos::{AvsRepo, CommandAuditRepo, OutboxReceiptRepo, PlayLogRepo, TelemetryRepo},
+    ApiDatabase,
+};
+use proto::{
+    app::{Data, MsgData, Stream},
+    error::Result,
+    WsState,
+};
+use types::{
+    api::{
+        play_log::NewPlayLog,
+        telemetry::{NewTelemetry, NewTelemetryNetwork},
+    },
+    proto::{CmdResponse, OutboxAck, OutboxBatch, PlayLog, Telemetry},
+};
+
+/// Event.
+/// The rows an outbox entry stores.
+enum Event {
+    Telemetry(NewTelemetry, Vec<NewTelemetryNetwork>),
+    PlayLog(NewPlayLog),
+    /// The audit id, the exit status, the output and whether it was truncated.
+    Command(i32, Option<i32>, String, bool),
+    /// Invalid and unknown entries, acknowledged without storing anything.
+    Dropped,
+}
+
+/// Convert the play log reported by the avs.
+fn new_play_log(avs_id: i32, log: PlayLog) -> NewPlayLog {
+    NewPlayLog {
+        avs_id,
+        schedule_id: log.sid,
+        local_id: log.id,
+        record_url: log.record_url,
+        started_at: log.started_at,
+        ended_at: log.ended_at,
+        volume: log.volume.map(|v| v as f64),
+        outcome: log.outcome,
+        message: log.message,
+        utc_offset: log.utc_offset,
+    }
+}
+
+#[proto::service("outbox")]
+async fn outbox(
+    stream: Stream,
+    data: MsgData<OutboxBatch>,
+    ws_state: Data<WsState>,
+    db: Data<ApiDatabase>,
+) -> Result<()> {
+    let data = data.into_inner();
+    let avs_id = if let Some(avs) = ws_state.avs_id(stream.id().to_owned()).await {
+        avs
+    } else {
+        return Ok(());
+    };
+    let avs = match db.repository::<AvsRepo>().get_unique(&avs_id) {
+        Ok(Some(avs)) => avs,
+        _ => return Ok(()),
+    };
+
+    // entries are handled in order, the rest is sent again after a failure.
+    // an entry received before, when the acknowledgement was lost, is only acknowledged.
+    let receipts = db.repository::<OutboxReceiptRepo>();
+    let mut ids = vec![];
+    for entry in data.entries {
+        let mut info = None;
+        let mut forward = None;
+        let event = match entry.kind.as_str() {
+            "telemetry" => match serde_json::from_str::<Telemetry>(&entry.payload) {
+                Ok(telemetry) => {
+                    info = Some(telemetry.clone());
+                    let (new, networks) = new_telemetry(&avs, telemetry, Some(entry.created_at));
+                    Event::Telemetry(new, networks)
+                }
+                Err(e) => {
+                    log::error!("Invalid telemetry from {}: {}", avs_id, e);
+                    Event::Dropped
+                }
+            },
+            "play_log" => match serde_json::from_str::<PlayLog>(&entry.payload) {
+                Ok(log) => Event::PlayLog(new_play_log(avs.id, log)),
+                Err(e) => {
+                    log::error!("Invalid play log from {}: {}", avs_id, e);
+                    Event::Dropped
+                }
+            },
+            "command" => match serde_json::from_str::<CmdResponse>(&entry.payload) {
+                Ok(response) => match response_audit(&db, avs.id, &response) {
+                    Some(audit) => {
+                        let (output, truncated) = audit_output(&response);
+                        let event = Event::Command(audit.id, response.status, output, truncated);
+                        // nobody is waiting for the result once the user is gone.
+                        forward = audit.user_id.map(|user_id| (user_id, response));
+                        event
+                    }
+                    None => Event::Dropped,
+                },
+                Err(e) => {
+                    log::error!("Invalid command result from {}: {}", avs_id, e);
+                    Event::Dropped
+                }
+            },
+            kind => {
+                log::warn!("Dropping unknown {} event from {}", kind, avs_id);
+                Event::Dropped
+            }
+        };
+        let received = receipts.receive(avs.id, entry.id, entry.created_at, |conn| match event {
+            Event::Telemetry(new, networks) => {
+                TelemetryRepo::insert(conn, new, networks).map(|_| ())
+            }
+            Event::PlayLog(log) => PlayLogRepo::insert_many(conn, &[log]),
+            Event::Command(id, status, output, truncated) => {
+                CommandAuditRepo::set_response(conn, id, avs.id, status, output, truncated)
+            }
+            Event::Dropped => Ok(()),
+        });
+        match received {
+            Ok(true) => {
+                if let Some(telemetry) = info {
+                    update_avs_info(&db, &avs, &telemetry);
+                }
+                if let Some((user_id, mut response)) = forward {
+                    response.sender = user_id;
+                    if let Some(user) = ws_state.user_by_id(user_id).await {
+                        let _ = user.write("command", response).await;
+                    }
+                }
+            }
+            Ok(false) => {}
+            Err(e) => {
+                log::error!("Failed to store outbox entry of {}: {}", avs_id, e);
+                break;
+            }
+        }
+        ids.push(entry.id);
+    }
+    stream.write("outbox:ack", OutboxAck { ids }).await
+}
@@ -0,0 +1,110 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use crate::states::stream::StreamingState;
+use api_db::{
+    repos::{BroadcastRepo, UserRepo},
+    ApiDatabase,
+};
+use proto::{
+    app::{Data, MsgData, Stream},
+    error::Result,
+    WsState,
+};
+use types::proto::{Answer, Ices, Listen, WsErr};
+
+#[proto::service("listen")]
+async fn listen(
+    stream: Stream,
+    data: MsgData<Listen>,
+    ws_state: Data<WsState>,
+    stream_state: Data<StreamingState>,
+    db: Data<ApiDatabase>,
+) -> Result<()> {
+    let user = if let Some(user) = ws_state.user_id(stream.id().to_owned()).await {
+        user
+    } else {
+        stream.disconnect().await?;
+        return Ok(());
+    };
+    let user = if let Ok(user) = db.repository::<UserRepo>().get(user) {
+        user
+    } else {
+        stream.disconnect().await?;
+        return Ok(());
+    };
+    let allowed = match db.repository::<BroadcastRepo>().get(data.broadcast_id) {
+        Ok(_) if user.role_id == 1 || user.role_id == 2 => true,
+        Ok((broadcast, targets)) => {
+            broadcast.user_id == Some(user.id)
+                || targets
+                    .iter()
+                    .any(|t| user.device_ids.contains(&Some(t.avs_id)))
+        }
+        Err(_) => false,
+    };
+    if !allowed {
+        return stream
+            .write(
+                "listen:fail",
+                WsErr {
+                    msg: "broadcast not found".to_owned(),
+                },
+            )
+            .await;
+    }
+    tokio::spawn(async move {
+        if let Err(msg) = stream_state.listen(stream.clone(), data.broadcast_id).await {
+            let _ = stream.write("listen:fail", WsErr { msg }).await;
+        }
+    });
+    Ok(())
+}
+
+#[proto::service("listen:answer")]
+async fn listen_answer(
+    stream: Stream,
+    data: MsgData<Answer>,
+    stream_state: Data<StreamingState>,
+) -> Result<()> {
+    tokio::spawn(async move {
+        stream_state
+            .add_answer_listener(stream.id(), data.answer.clone())
+            .await;
+    });
+    Ok(())
+}
+
+#[proto::service("listen:ices")]
+async fn listen_ices(
+    stream: Stream,
+    data: MsgData<Ices>,
+    stream_state: Data<StreamingState>,
+) -> Result<()> {
+    tokio::spawn(async move {
+        stream_state
+            .add_ices_listener(stream.id(), data.ices.clone())
+            .await;
+    });
+    Ok(())
+}
+
+#[proto::service("listen:stop")]
+async fn listen_stop(stream: Stream, stream_state: Data<StreamingState>) -> Result<()> {
+    stream_state.stop_listening(stream.id()).await;
+    Ok(())
+}
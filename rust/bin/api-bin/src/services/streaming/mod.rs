@@ -25,3 +25,8 @@ mod ices_service;
 pub(super) use ices_service::ices;
 mod volume_service;
 pub(super) use volume_service::volume;
+mod stats_service;
+pub(super) use stats_service::stats;
+mod listen_service;
+pub(super) use listen_service::{listen, listen_answer, listen_ices, listen_stop};
+pub(super) mod playback;
@@ -15,9 +15,9 @@ MyRTS agrees to credit you as the developer of the software in all promotional m
 If MyRTS violates any of these terms, their license to use the software will automatically terminate.
 */
 
-use crate::states::stream::StreamingState;
+use crate::states::stream::{StreamOptions, StreamingState};
 use api_db::{
-    repos::{AvsRepo, UserRepo},
+    repos::{AvsPortRepo, AvsRepo, UserRepo},
     ApiDatabase,
 };
 use proto::{
@@ -26,7 +26,7 @@ use proto::{
     WsState,
 };
 use std::collections::HashMap;
-use types::proto::{Offer, WsErr};
+use types::proto::{stream_priority, Offer, WsErr};
 
 #[proto::service("offer")]
 async fn offer(
@@ -52,7 +52,7 @@ async fn offer(
             )
             .await;
     }
-    {
+    let role_id = {
         let repo = db.repository::<UserRepo>();
         let avs_repo = db.repository::<AvsRepo>();
         let user = if let Ok(user) = repo.get(user) {
@@ -61,6 +61,16 @@ async fn offer(
             stream.disconnect().await?;
             return Ok(());
         };
+        if data.emergency && user.role_id == 3 {
+            return stream
+                .write(
+                    "offer:fail",
+                    WsErr {
+                        msg: "emergency stream is not allowed".to_owned(),
+                    },
+                )
+                .await;
+        }
         if user.role_id == 3 {
             for target_id in data.target.iter() {
                 let avs_id = avs_repo
@@ -80,17 +90,30 @@ async fn offer(
                 }
             }
         }
-    }
+        user.role_id
+    };
+    let avs_repo = db.repository::<AvsRepo>();
+    let port_repo = db.repository::<AvsPortRepo>();
     let mut target = HashMap::new();
+    let mut offline = vec![];
     for target_id in data.target.iter() {
         if let Some(avs) = ws_state.avs_by_id(target_id.to_string()).await {
-            target.insert(target_id.to_string(), avs);
+            let outputs = match avs_repo.get_unique(target_id) {
+                Ok(Some(a)) => port_repo
+                    .resolve_outputs(a.id, &data.port_ids, &data.zones)
+                    .unwrap_or_default(),
+                _ => vec![],
+            };
+            target.insert(target_id.to_string(), (avs, outputs));
         } else {
             log::debug!(
                 "target avs {} not found in {:?}",
                 target_id,
                 ws_state.get_ref()
             );
+            if let Ok(Some(a)) = avs_repo.get_unique(target_id) {
+                offline.push(a.id);
+            }
         }
     }
     if target.is_empty() {
@@ -103,9 +126,15 @@ async fn offer(
             )
             .await;
     }
+    let options = StreamOptions {
+        emergency: data.emergency,
+        record: data.record,
+        priority: stream_priority(role_id, data.emergency, data.priority),
+        conflict: data.conflict,
+    };
     tokio::spawn(async move {
         stream_state
-            .new_streaming(stream, user, data.offer.clone(), target)
+            .new_streaming(stream, user, data.offer.clone(), options, target, offline)
             .await;
     });
     Ok(())
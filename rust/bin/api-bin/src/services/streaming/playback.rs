@@ -0,0 +1,136 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use crate::states::stream::{StreamOptions, StreamingState};
+use api_db::{
+    repos::{AvsPortRepo, AvsRepo},
+    ApiDatabase,
+};
+use proto::{PlaybackRequest, WsState};
+use std::collections::HashMap;
+use tokio::sync::mpsc;
+use types::proto::BusyTargets;
+
+/// Start a playback requested by the api.
+/// The busy targets are reported to the user like the ones of a live broadcast.
+async fn start(
+    ws_state: &WsState,
+    stream_state: &StreamingState,
+    db: &ApiDatabase,
+    user_id: i32,
+    path: String,
+    target_ids: Vec<String>,
+    options: StreamOptions,
+) -> Result<(i32, Vec<(String, String)>), String> {
+    let packets = tokio::task::spawn_blocking(move || utils::audio::encode_opus(&path))
+        .await
+        .map_err(|e| e.to_string())??;
+    if packets.is_empty() {
+        return Err("the record has no audio".to_owned());
+    }
+    let avs_repo = db.repository::<AvsRepo>();
+    let port_repo = db.repository::<AvsPortRepo>();
+    let mut target = HashMap::new();
+    let mut offline = vec![];
+    for target_id in target_ids.iter() {
+        let avs = match avs_repo.get_unique(target_id) {
+            Ok(Some(avs)) => avs,
+            _ => continue,
+        };
+        match ws_state.avs_by_id(target_id.to_owned()).await {
+            Some(stream) => {
+                let outputs = port_repo
+                    .resolve_outputs(avs.id, &[], &[])
+                    .unwrap_or_default();
+                target.insert(target_id.to_owned(), (stream, outputs));
+            }
+            None => offline.push(avs.id),
+        }
+    }
+    if target.is_empty() {
+        return Err("target avs not found".to_owned());
+    }
+    let (broadcast_id, busy) = stream_state
+        .new_playback(user_id, packets, options, target, offline)
+        .await?;
+    let reported = busy
+        .iter()
+        .map(|b| (b.target.clone(), b.status.clone()))
+        .collect();
+    if !busy.is_empty() {
+        if let Some(user) = ws_state.user_by_id(user_id).await {
+            let _ = user
+                .write("offer:busy", BusyTargets { targets: busy })
+                .await;
+        }
+    }
+    Ok((broadcast_id, reported))
+}
+
+/// Handle the playback requests of the api.
+pub(crate) async fn run(
+    mut rx: mpsc::UnboundedReceiver<PlaybackRequest>,
+    ws_state: WsState,
+    stream_state: StreamingState,
+    db: ApiDatabase,
+) {
+    while let Some(req) = rx.recv().await {
+        match req {
+            PlaybackRequest::Start {
+                user_id,
+                path,
+                target,
+                emergency,
+                priority,
+                conflict,
+                reply,
+            } => {
+                let options = StreamOptions {
+                    emergency,
+                    record: false,
+                    priority,
+                    conflict: conflict.parse().unwrap_or_default(),
+                };
+                let ws_state = ws_state.clone();
+                let stream_state = stream_state.clone();
+                let db = db.clone();
+                tokio::spawn(async move {
+                    let res = start(
+                        &ws_state,
+                        &stream_state,
+                        &db,
+                        user_id,
+                        path,
+                        target,
+                        options,
+                    )
+                    .await;
+                    if let Err(e) = &res {
+                        log::warn!("failed to start playback: {}", e);
+                    }
+                    let _ = reply.send(res);
+                });
+            }
+            PlaybackRequest::Stop {
+                broadcast_id,
+                reply,
+            } => {
+                let _ = reply.send(stream_state.stop_playback(broadcast_id).await);
+            }
+        }
+    }
+}
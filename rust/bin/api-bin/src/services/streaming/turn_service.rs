@@ -15,24 +15,20 @@ MyRTS agrees to credit you as the developer of the software in all promotional m
 If MyRTS violates any of these terms, their license to use the software will automatically terminate.
 */
 
+use crate::config::TurnConfig;
 use proto::{
     app::{Data, Stream},
     error::Result,
     WsState,
 };
-use types::proto::Turn;
 
 #[proto::service("turn")]
-async fn turn(stream: Stream, state: Data<WsState>) -> Result<()> {
+async fn turn(stream: Stream, state: Data<WsState>, turn: Data<TurnConfig>) -> Result<()> {
     let avs = state.avs(stream.id().to_owned()).await;
     let user = state.user(stream.id().to_owned()).await;
     if avs.is_none() && user.is_none() {
         return stream.disconnect().await;
     }
-    let turn = Turn {
-        url: "turn:159.223.68.165:3478".to_owned(),
-        username: "brandio".to_owned(),
-        password: "brandio".to_owned(),
-    };
-    stream.write("turn", turn).await
+    // every session gets its own short lived credentials.
+    stream.write("turn", turn.issue(stream.id())).await
 }
@@ -16,7 +16,7 @@ If MyRTS violates any of these terms, their license to use the software will aut
 */
 
 use api_db::{
-    repos::{AvsRepo, ScheduleRepo},
+    repos::{AvsPortRepo, AvsRepo, AvsSyncRepo, ScheduleRepo},
     ApiDatabase,
 };
 use proto::{
@@ -24,7 +24,43 @@ use proto::{
     error::Result,
     WsState,
 };
-use types::proto::{Schedule, Sync, SyncReq};
+use std::collections::HashMap;
+use types::{
+    api::avs_sync::NewAvsSync,
+    proto::{Schedule, Sync, SyncReq},
+};
+use utils::crypto::{hash, sync_digest};
+
+/// Decide how the avs catches up with the `current` schedules.
+/// `stored` is the version and the `(sid, rev)` pairs last sent to the avs, an avs that
+/// reports the same version and digest receives the changed schedules and the removed sids,
+/// any other avs receives every schedule as a full resync.
+fn plan(
+    current: &[Schedule],
+    stored: Option<(i64, &[(i32, String)])>,
+    version: i64,
+    digest: Option<&str>,
+) -> (Vec<Schedule>, Vec<i32>, bool) {
+    let stored_revs = match stored {
+        Some((stored_version, revs))
+            if version == stored_version && digest == Some(sync_digest(revs).as_str()) =>
+        {
+            revs.iter().cloned().collect::<HashMap<i32, String>>()
+        }
+        _ => return (current.to_vec(), vec![], true),
+    };
+    let remove = stored_revs
+        .keys()
+        .filter(|sid| !current.iter().any(|s| s.sid == **sid))
+        .copied()
+        .collect::<Vec<i32>>();
+    let add = current
+        .iter()
+        .filter(|s| stored_revs.get(&s.sid) != Some(&s.rev))
+        .cloned()
+        .collect::<Vec<Schedule>>();
+    (add, remove, false)
+}
 
 #[proto::service("sync")]
 async fn sync(
@@ -43,6 +79,7 @@ async fn sync(
     };
     let repo = db.repository::<AvsRepo>();
     let schedule_repo = db.repository::<ScheduleRepo>();
+    let port_repo = db.repository::<AvsPortRepo>();
 
     let avs = match repo.get_unique(&avs) {
         Ok(res) => {
@@ -66,10 +103,8 @@ async fn sync(
         }
     };
 
-    let mut filtered = vec![];
-    let mut new_local = vec![];
+    let mut current = vec![];
     for (schedule, record) in schedules {
-        new_local.push(schedule.id);
         let mut volume = None;
         for schedule_volume in schedule.volumes {
             if let Some(v) = schedule_volume {
@@ -80,35 +115,196 @@ async fn sync(
                 }
             }
         }
-        if !data.local.contains(&schedule.id) {
-            filtered.push(Schedule {
-                sid: schedule.id,
-                name: schedule.name,
-                days: schedule.days,
-                dates: schedule.dates,
-                times: schedule.times,
-                record_url: record.file_url,
-                kind: schedule.kind,
-                weeks: schedule.weeks,
-                month: schedule.month,
-                year: schedule.year,
-                volume,
-            });
+        let port_ids = schedule
+            .port_ids
+            .into_iter()
+            .flatten()
+            .collect::<Vec<i32>>();
+        let zones = schedule
+            .zones
+            .into_iter()
+            .flatten()
+            .collect::<Vec<String>>();
+        let outputs = port_repo
+            .resolve_outputs(avs.id, &port_ids, &zones)
+            .unwrap_or_default();
+        let mut schedule = Schedule {
+            sid: schedule.id,
+            name: schedule.name,
+            days: schedule.days,
+            dates: schedule.dates,
+            times: schedule.times,
+            record_url: record.file_url,
+            kind: schedule.kind,
+            weeks: schedule.weeks,
+            month: schedule.month,
+            year: schedule.year,
+            volume,
+            format: record.format,
+            outputs,
+            emergency: schedule.emergency,
+            rev: String::new(),
+        };
+        // The rev is a digest of what the avs receives, edits that don't change it are not sent.
+        schedule.rev = hash(&serde_json::to_vec(&schedule).unwrap_or_default());
+        current.push(schedule);
+    }
+
+    let version = match data.version {
+        Some(version) => version,
+        None => {
+            // Clients without versioned sync only receive the schedules they miss.
+            let remove = data
+                .local
+                .iter()
+                .filter(|sid| !current.iter().any(|s| s.sid == **sid))
+                .copied()
+                .collect::<Vec<i32>>();
+            let add = current
+                .into_iter()
+                .filter(|s| !data.local.contains(&s.sid))
+                .collect::<Vec<Schedule>>();
+            return stream
+                .write(
+                    "sync",
+                    Sync {
+                        add,
+                        remove,
+                        version: None,
+                        full: false,
+                    },
+                )
+                .await;
+        }
+    };
+
+    let sync_repo = db.repository::<AvsSyncRepo>();
+    let stored = match sync_repo.get(avs.id) {
+        Ok(stored) => stored,
+        Err(e) => {
+            log::error!("Failed to get sync state: {}", e);
+            return Ok(());
+        }
+    };
+    let (stored_version, stored_revs) = match &stored {
+        Some(stored) => (stored.version, stored.revs()),
+        None => (0, vec![]),
+    };
+    let stored = stored
+        .as_ref()
+        .map(|_| (stored_version, stored_revs.as_slice()));
+    let (add, remove, full) = plan(&current, stored, version, data.digest.as_deref());
+    if full {
+        log::info!(
+            "Full schedule sync for avs {}, version {} / {}",
+            avs.unique_id,
+            version,
+            stored_version
+        );
+    }
+
+    let mut new_version = stored_version;
+    if full || !add.is_empty() || !remove.is_empty() {
+        new_version += 1;
+        let entries = current
+            .iter()
+            .map(|s| Some(format!("{}:{}", s.sid, s.rev)))
+            .collect::<Vec<Option<String>>>();
+        if let Err(e) = sync_repo.set(NewAvsSync {
+            avs_id: avs.id,
+            version: new_version,
+            entries,
+            updated_at: utils::time::now(),
+        }) {
+            log::error!("Failed to store sync state: {}", e);
+            return Ok(());
         }
     }
 
-    let remove = data
-        .local
-        .into_iter()
-        .filter(|schedule_id| !new_local.contains(schedule_id))
-        .collect::<Vec<i32>>();
     stream
         .write(
             "sync",
             Sync {
-                add: filtered,
+                add,
                 remove,
+                version: Some(new_version),
+                full,
             },
         )
         .await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule(sid: i32, rev: &str) -> Schedule {
+        Schedule {
+            sid,
+            name: format!("schedule {}", sid),
+            days: vec![],
+            dates: vec![],
+            times: vec![Some("08:00".to_owned())],
+            record_url: String::new(),
+            kind: 1,
+            weeks: vec![],
+            month: None,
+            year: None,
+            volume: None,
+            format: "opus".to_owned(),
+            outputs: vec![],
+            emergency: false,
+            rev: rev.to_owned(),
+        }
+    }
+
+    fn revs(entries: &[(i32, &str)]) -> Vec<(i32, String)> {
+        entries
+            .iter()
+            .map(|(sid, rev)| (*sid, rev.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn upserts_changed_and_deletes_removed_schedules() {
+        let stored = revs(&[(1, "a"), (2, "b"), (3, "c")]);
+        let digest = sync_digest(&stored);
+        let current = vec![schedule(1, "a"), schedule(2, "b2"), schedule(4, "d")];
+        let (add, mut remove, full) = plan(&current, Some((5, &stored)), 5, Some(&digest));
+        remove.sort();
+        assert!(!full);
+        assert_eq!(add.iter().map(|s| s.sid).collect::<Vec<_>>(), vec![2, 4]);
+        assert_eq!(remove, vec![3]);
+    }
+
+    #[test]
+    fn sends_nothing_when_in_sync() {
+        let stored = revs(&[(1, "a")]);
+        let digest = sync_digest(&stored);
+        let (add, remove, full) = plan(&[schedule(1, "a")], Some((2, &stored)), 2, Some(&digest));
+        assert!(add.is_empty() && remove.is_empty() && !full);
+    }
+
+    #[test]
+    fn resyncs_fully_on_mismatch() {
+        let stored = revs(&[(1, "a"), (2, "b")]);
+        let digest = sync_digest(&stored);
+        let current = vec![schedule(1, "a")];
+        let cases = [
+            (None, 1, Some(digest.clone())),
+            (Some((1, stored.as_slice())), 2, Some(digest.clone())),
+            (
+                Some((1, stored.as_slice())),
+                1,
+                Some(sync_digest(&revs(&[(1, "a")]))),
+            ),
+            (Some((1, stored.as_slice())), 1, None),
+        ];
+        for (stored, version, digest) in cases {
+            let (add, remove, full) = plan(&current, stored, version, digest.as_deref());
+            assert!(full);
+            assert_eq!(add.len(), 1);
+            assert!(remove.is_empty());
+        }
+    }
+}
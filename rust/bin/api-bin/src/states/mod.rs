@@ -16,3 +16,4 @@ If MyRTS violates any of these terms, their license to use the software will aut
 */
 
 pub mod stream;
+pub mod update;
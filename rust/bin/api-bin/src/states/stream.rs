@@ -15,65 +15,296 @@ MyRTS agrees to credit you as the developer of the software in all promotional m
 If MyRTS violates any of these terms, their license to use the software will automatically terminate.
 */
 
+use crate::config::TurnConfig;
+use api_db::{
+    repos::{AvsRepo, BroadcastRepo, RecordsRepo},
+    ApiDatabase,
+};
+use audio::recorder::OggWriter;
 use proto::app::Stream;
-use rtc::{RTCForwader, RTCProvider};
-use std::{collections::HashMap, sync::Arc};
+use rtc::{RTCForwader, RTCProvider, RTCSource, TrackLocalStaticRTP};
+use std::{collections::HashMap, sync::Arc, time::Duration};
 use tokio::sync::RwLock;
-use types::proto::{Volume, WsErr};
+use types::{
+    api::{
+        broadcast::{NewBroadcast, NewBroadcastTarget},
+        records::NewRecords,
+    },
+    proto::{BusyTarget, BusyTargets, Conflict, StreamStats, StreamTargets, Volume, WsErr},
+};
+use utils::{audio::OPUS_FRAME_SAMPLES, files::ApiAssets, media::AudioFormat};
+
+/// How long the playback waits for the targets to connect before it starts.
+const PLAYBACK_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// StreamOptions.
+/// The options of a new streaming.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StreamOptions {
+    pub emergency: bool,
+    pub record: bool,
+    pub priority: u8,
+    pub conflict: Conflict,
+}
+
+/// Waiting.
+/// A streaming waiting for a busy target to be released.
+#[derive(Clone)]
+struct Waiting {
+    id: i32,
+    priority: u8,
+    avs: Stream,
+    outputs: Vec<String>,
+}
+
+/// BroadcastLog.
+/// Persists the lifecycle of the targets of a streaming.
+#[derive(Clone)]
+pub struct BroadcastLog {
+    id: i32,
+    avs_ids: Arc<HashMap<String, i32>>,
+    db: ApiDatabase,
+}
+
+impl BroadcastLog {
+    /// Create the broadcast along with its targets and the excluded avs.
+    fn create(
+        db: &ApiDatabase,
+        user_id: i32,
+        emergency: bool,
+        avs_ids: HashMap<String, i32>,
+        excluded: Vec<(i32, &str)>,
+    ) -> Option<Self> {
+        let targets = avs_ids
+            .values()
+            .map(|avs_id| (*avs_id, None))
+            .chain(excluded.into_iter().map(|(avs_id, e)| (avs_id, Some(e))))
+            .map(|(avs_id, excluded)| NewBroadcastTarget {
+                broadcast_id: 0,
+                avs_id,
+                excluded: excluded.map(ToOwned::to_owned),
+            })
+            .collect();
+        let new = NewBroadcast {
+            user_id: Some(user_id),
+            emergency,
+        };
+        match db.repository::<BroadcastRepo>().create(new, targets) {
+            Ok(broadcast) => Some(Self {
+                id: broadcast.id,
+                avs_ids: Arc::new(avs_ids),
+                db: db.clone(),
+            }),
+            Err(e) => {
+                log::error!("failed to create broadcast: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Get the id of the broadcast.
+    fn id(&self) -> i32 {
+        self.id
+    }
+
+    /// The target connected to the stream.
+    fn joined(&self, target: &str) {
+        if let Some(avs_id) = self.avs_ids.get(target) {
+            let _ = self
+                .db
+                .repository::<BroadcastRepo>()
+                .joined(self.id, *avs_id);
+        }
+    }
+
+    /// The connection to the target failed.
+    fn failed(&self, target: &str) {
+        if let Some(avs_id) = self.avs_ids.get(target) {
+            let _ = self
+                .db
+                .repository::<BroadcastRepo>()
+                .failed(self.id, *avs_id);
+        }
+    }
+
+    /// The target left the stream.
+    fn left(&self, target: &str, excluded: Option<&str>) {
+        if let Some(avs_id) = self.avs_ids.get(target) {
+            let _ = self
+                .db
+                .repository::<BroadcastRepo>()
+                .left(self.id, *avs_id, excluded);
+        }
+    }
+
+    /// The streaming ended.
+    fn finish(&self) {
+        if let Err(e) = self.db.repository::<BroadcastRepo>().finish(self.id) {
+            log::error!("failed to finish broadcast: {}", e);
+        }
+    }
+}
+
+/// Origin.
+/// The source of the stream of a Streaming.
+#[derive(Clone)]
+pub enum Origin {
+    /// Streamed live by the user.
+    User(Arc<RTCProvider>),
+    /// Played by the server from a record.
+    Server(Arc<RTCSource>),
+}
+
+impl Origin {
+    /// Get the track forwarded to the targets.
+    fn local_track(&self) -> Arc<TrackLocalStaticRTP> {
+        match self {
+            Self::User(provider) => provider.local_track(),
+            Self::Server(source) => source.local_track(),
+        }
+    }
+
+    /// Get the stream of the user, the server has none.
+    fn stream(&self) -> Option<Stream> {
+        match self {
+            Self::User(provider) => Some(provider.stream()),
+            Self::Server(_) => None,
+        }
+    }
+}
 
 /// Streaming.
+/// The listeners are listen-only users, they are never targets of the streaming.
 #[derive(Clone)]
 pub struct Streaming {
-    provider: Arc<RTCProvider>,
+    origin: Origin,
     forwarders: Arc<RwLock<HashMap<String, Arc<RTCForwader>>>>,
+    listeners: Arc<RwLock<HashMap<String, Arc<RTCForwader>>>>,
+    broadcast: Option<BroadcastLog>,
+    recording: Option<String>,
+    priority: u8,
+    emergency: bool,
+    turn: Arc<TurnConfig>,
 }
 
 impl Streaming {
     /// Create new Streaming.
-    pub fn new(provider: Arc<RTCProvider>, forwarders: HashMap<String, Arc<RTCForwader>>) -> Self {
+    /// The replacement of a failed forwarder is issued new credentials with `turn`.
+    pub fn new(
+        origin: Origin,
+        forwarders: HashMap<String, Arc<RTCForwader>>,
+        turn: Arc<TurnConfig>,
+    ) -> Self {
         Self {
-            provider,
+            origin,
             forwarders: Arc::new(RwLock::new(forwarders)),
+            listeners: Arc::new(RwLock::new(HashMap::new())),
+            broadcast: None,
+            recording: None,
+            priority: 0,
+            emergency: false,
+            turn,
         }
     }
 
+    /// Set the priority of the streaming and whether it is an emergency.
+    pub fn with_priority(mut self, priority: u8, emergency: bool) -> Self {
+        self.priority = priority;
+        self.emergency = emergency;
+        self
+    }
+
+    /// Set the audio asset the streaming is recorded to.
+    pub fn with_recording(mut self, recording: Option<String>) -> Self {
+        self.recording = recording;
+        self
+    }
+
+    /// Set the broadcast the streaming is persisted to.
+    pub fn with_broadcast(mut self, broadcast: Option<BroadcastLog>) -> Self {
+        self.broadcast = broadcast;
+        self
+    }
+
     /// Begin streaming.
     pub async fn begin(&self) {
         let lock = self.forwarders.read().await;
         for (id, forwarder) in lock.iter() {
-            let fw = self.forwarders.clone();
-            let id = id.to_owned();
-            let track = self.provider.local_track();
-            forwarder.on_failed(Box::new(move || {
-                let fw = fw.clone();
-                let id = id.clone();
-                let track = track.clone();
-                Box::pin(async move {
-                    let mut lock = fw.write().await;
-                    if let Some(forwarder) = lock.remove(&id) {
-                        forwarder.disconnect().await;
-                        let new_forwarder = RTCForwader::new(forwarder.stream(), track).await;
-                        if let Ok(forwarder) = new_forwarder {
-                            let forwarder = Arc::new(forwarder);
-                            lock.insert(id, forwarder.clone());
-                            let _ = forwarder.create_offer().await;
-                        }
-                    }
-                })
-            }));
+            self.watch(id.to_owned(), forwarder);
             if let Err(e) = forwarder.create_offer().await {
                 log::error!("failed to create offer: {:?}", e);
             }
         }
-        if let Err(e) = self.provider.answer().await {
-            log::error!("failed to create answer: {:?}", e);
+        if let Origin::User(provider) = &self.origin {
+            if let Err(e) = provider.answer().await {
+                log::error!("failed to create answer: {:?}", e);
+            }
+        }
+    }
+
+    /// Wait until every forwarder is connected or the timeout elapsed.
+    async fn wait_connected(&self, timeout: Duration) {
+        let start = tokio::time::Instant::now();
+        let mut tick = tokio::time::interval(Duration::from_millis(100));
+        while start.elapsed() < timeout {
+            tick.tick().await;
+            if self.forwarders.read().await.values().all(|f| f.connected()) {
+                break;
+            }
         }
     }
 
+    /// Watch the connection state of the forwarder, a failed forwarder is replaced.
+    fn watch(&self, id: String, forwarder: &RTCForwader) {
+        let broadcast = self.broadcast.clone();
+        let target = id.clone();
+        forwarder.on_connected(Box::new(move || {
+            let broadcast = broadcast.clone();
+            let target = target.clone();
+            Box::pin(async move {
+                if let Some(broadcast) = broadcast {
+                    broadcast.joined(&target);
+                }
+            })
+        }));
+        let streaming = self.clone();
+        forwarder.on_failed(Box::new(move || {
+            let streaming = streaming.clone();
+            let id = id.clone();
+            Box::pin(async move {
+                if let Some(broadcast) = &streaming.broadcast {
+                    broadcast.failed(&id);
+                }
+                let mut lock = streaming.forwarders.write().await;
+                if let Some(forwarder) = lock.remove(&id) {
+                    forwarder.disconnect().await;
+                    let track = streaming.origin.local_track();
+                    // the credentials of the failed forwarder may have expired.
+                    let turn = streaming.turn.issue(&id);
+                    let new_forwarder = RTCForwader::new(forwarder.stream(), track, turn).await;
+                    if let Ok(new_forwarder) = new_forwarder {
+                        let forwarder = Arc::new(
+                            new_forwarder
+                                .with_outputs(forwarder.outputs())
+                                .with_emergency(forwarder.emergency()),
+                        );
+                        lock.insert(id.clone(), forwarder.clone());
+                        streaming.watch(id, &forwarder);
+                        let _ = forwarder.create_offer().await;
+                    } else if let Some(broadcast) = &streaming.broadcast {
+                        broadcast.left(&id, Some("failed"));
+                    }
+                }
+            })
+        }));
+    }
+
     /// Add ices to provider.
     pub async fn add_ices_provider(&self, ices: String) {
-        if let Err(e) = self.provider.add_ices(ices).await {
-            log::error!("failed to add ices: {:?}", e);
+        if let Origin::User(provider) = &self.origin {
+            if let Err(e) = provider.add_ices(ices).await {
+                log::error!("failed to add ices: {:?}", e);
+            }
         }
     }
 
@@ -97,12 +328,65 @@ impl Streaming {
         }
     }
 
+    /// Add a forwarder to a target that joined after the streaming began.
+    async fn add_forwarder(&self, id: String, forwarder: Arc<RTCForwader>) {
+        self.forwarders
+            .write()
+            .await
+            .insert(id.clone(), forwarder.clone());
+        self.watch(id, &forwarder);
+        if let Err(e) = forwarder.create_offer().await {
+            log::error!("failed to create offer: {:?}", e);
+        }
+    }
+
+    /// Add a listener, a failed listener is dropped.
+    async fn add_listener(&self, id: String, listener: Arc<RTCForwader>) {
+        self.listeners
+            .write()
+            .await
+            .insert(id.clone(), listener.clone());
+        let listeners = self.listeners.clone();
+        listener.on_failed(Box::new(move || {
+            let listeners = listeners.clone();
+            let id = id.clone();
+            Box::pin(async move {
+                listeners.write().await.remove(&id);
+            })
+        }));
+        if let Err(e) = listener.create_offer().await {
+            log::error!("failed to create offer: {:?}", e);
+        }
+    }
+
+    /// Close listener.
+    async fn close_listener(&self, id: &str) {
+        if let Some(listener) = self.listeners.write().await.remove(id) {
+            listener.disconnect().await;
+        }
+    }
+
+    /// Notify the user of the streaming.
+    async fn notify(&self, event: &str, targets: Vec<String>) {
+        if let Some(stream) = self.origin.stream() {
+            let _ = stream.write(event, StreamTargets { targets }).await;
+        }
+    }
+
     /// Close forwarder.
-    pub async fn close_forwarder(&self, id: &str) {
+    /// `excluded` is set when the target left because of another streaming.
+    pub async fn close_forwarder(
+        &self,
+        id: &str,
+        excluded: Option<&str>,
+    ) -> Option<Arc<RTCForwader>> {
         let mut lock = self.forwarders.write().await;
-        if let Some(forwarder) = lock.remove(id) {
-            forwarder.disconnect().await;
+        let forwarder = lock.remove(id)?;
+        forwarder.disconnect().await;
+        if let Some(broadcast) = &self.broadcast {
+            broadcast.left(id, excluded);
         }
+        Some(forwarder)
     }
 }
 
@@ -111,38 +395,362 @@ impl Streaming {
 pub struct StreamingState {
     streaming: Arc<RwLock<HashMap<i32, Arc<Streaming>>>>,
     avs_map: Arc<RwLock<HashMap<String, i32>>>,
+    waiting: Arc<RwLock<HashMap<String, Vec<Waiting>>>>,
+    listening: Arc<RwLock<HashMap<String, i32>>>,
     on_going: Arc<RwLock<HashMap<i32, Vec<String>>>>,
+    turn: Arc<TurnConfig>,
+    db: ApiDatabase,
+    assets: ApiAssets,
+    base_url: Arc<String>,
 }
 
 impl StreamingState {
     /// Create new StreamingState.
-    pub fn new(on_going: Arc<RwLock<HashMap<i32, Vec<String>>>>) -> Self {
+    pub fn new(
+        on_going: Arc<RwLock<HashMap<i32, Vec<String>>>>,
+        turn: TurnConfig,
+        db: ApiDatabase,
+        assets: ApiAssets,
+        base_url: &str,
+    ) -> Self {
         Self {
             streaming: Arc::new(RwLock::new(HashMap::new())),
             avs_map: Arc::new(RwLock::new(HashMap::new())),
+            waiting: Arc::new(RwLock::new(HashMap::new())),
+            listening: Arc::new(RwLock::new(HashMap::new())),
             on_going,
+            turn: Arc::new(turn),
+            db,
+            assets,
+            base_url: Arc::new(base_url.to_owned()),
+        }
+    }
+
+    /// Start recording the stream of the provider into a new audio asset.
+    async fn start_recording(&self, provider: &RTCProvider) -> Option<String> {
+        let name = format!("{}.{}", utils::crypto::uuid(), AudioFormat::Opus.name());
+        match OggWriter::create(self.assets.audio_path(&name)) {
+            Ok(writer) => {
+                provider.start_recording(writer).await;
+                Some(name)
+            }
+            Err(e) => {
+                log::error!("failed to start recording: {:?}", e);
+                None
+            }
+        }
+    }
+
+    /// Finish the recording of the streaming and add it to the records of the user.
+    async fn finish_recording(&self, user_id: i32, streaming: &Streaming) {
+        let (name, writer) = match (&streaming.recording, &streaming.origin) {
+            (Some(name), Origin::User(provider)) => match provider.stop_recording().await {
+                Some(writer) => (name, writer),
+                None => return,
+            },
+            _ => return,
+        };
+        let duration = match writer.finish() {
+            Ok(duration) if duration > 0 => duration,
+            Ok(_) => {
+                let _ = self.assets.remove_audio(name);
+                return;
+            }
+            Err(e) => {
+                log::error!("failed to finish recording: {:?}", e);
+                let _ = self.assets.remove_audio(name);
+                return;
+            }
+        };
+        let hash = match self.assets.read_audio(name) {
+            Ok(data) => utils::crypto::hash(&data),
+            Err(e) => {
+                log::error!("failed to read recording: {}", e);
+                return;
+            }
+        };
+        let res = self.db.repository::<RecordsRepo>().new(NewRecords {
+            name: format!("Live {}", utils::time::local_now().format("%Y-%m-%d %H:%M")),
+            description: Some("Recorded live broadcast".to_owned()),
+            file_url: format!("{}/assets/audio/{}", self.base_url, name),
+            created_at: utils::time::now(),
+            hash,
+            user_id,
+            status: 1,
+            duration: duration.to_string(),
+            sender: None,
+            format: AudioFormat::Opus.name().to_owned(),
+        });
+        if let Err(e) = res {
+            log::error!("failed to create record: {}", e);
+        }
+    }
+
+    /// Get the id of the avs by its unique id.
+    fn avs_id(&self, unique_id: &str) -> Option<i32> {
+        self.db
+            .repository::<AvsRepo>()
+            .get_unique(unique_id)
+            .ok()
+            .flatten()
+            .map(|avs| avs.id)
+    }
+
+    /// Resolve the targets that are busy with another streaming by the conflict policy.
+    /// Preempted targets are taken over from the lower priority streaming, which waits for them
+    /// to be handed back. Queued targets are removed from the target and returned to wait for
+    /// their release. The other busy targets are excluded.
+    async fn resolve_busy(
+        &self,
+        options: &StreamOptions,
+        target: &mut HashMap<String, (Stream, Vec<String>)>,
+        excluded: &mut Vec<(i32, &'static str)>,
+        busy: &mut Vec<BusyTarget>,
+    ) -> Vec<(String, Stream, Vec<String>)> {
+        let mut queued = vec![];
+        let mut preempted: HashMap<i32, Vec<String>> = HashMap::new();
+        let holders = self.avs_map.read().await.clone();
+        for (target_id, holder) in holders {
+            if !target.contains_key(&target_id) {
+                continue;
+            }
+            let streaming = self.streaming.read().await.get(&holder).cloned();
+            let lower = streaming
+                .as_ref()
+                .map(|s| s.priority < options.priority)
+                .unwrap_or(true);
+            let status = match options.conflict {
+                Conflict::Preempt if lower => {
+                    if let Some(streaming) = streaming {
+                        self.preempt(holder, &streaming, &target_id).await;
+                        preempted.entry(holder).or_default().push(target_id.clone());
+                    }
+                    "preempted"
+                }
+                Conflict::Queue => {
+                    if let Some((avs, outputs)) = target.remove(&target_id) {
+                        queued.push((target_id.clone(), avs, outputs));
+                    }
+                    "queued"
+                }
+                _ => {
+                    target.remove(&target_id);
+                    if let Some(avs_id) = self.avs_id(&target_id) {
+                        excluded.push((avs_id, "busy"));
+                    }
+                    "rejected"
+                }
+            };
+            busy.push(BusyTarget {
+                target: target_id,
+                status: status.to_owned(),
+            });
+        }
+        for (holder, targets) in preempted {
+            if let Some(streaming) = self.streaming.read().await.get(&holder) {
+                streaming.notify("stream:preempted", targets).await;
+            }
+        }
+        queued
+    }
+
+    /// Take the target over from the streaming, which waits for it to be handed back.
+    async fn preempt(&self, holder: i32, streaming: &Streaming, target_id: &str) {
+        self.avs_map.write().await.remove(target_id);
+        if let Some(on_going) = self.on_going.write().await.get_mut(&holder) {
+            on_going.retain(|x| x != target_id);
+        }
+        if let Some(forwarder) = streaming
+            .close_forwarder(target_id, Some("preempted"))
+            .await
+        {
+            self.waiting
+                .write()
+                .await
+                .entry(target_id.to_owned())
+                .or_default()
+                .push(Waiting {
+                    id: holder,
+                    priority: streaming.priority,
+                    avs: forwarder.stream(),
+                    outputs: forwarder.outputs(),
+                });
+        }
+    }
+
+    /// Wait for the target to be released, it is handed over right away when it is free.
+    async fn wait(&self, target_id: &str, waiting: Waiting) {
+        self.waiting
+            .write()
+            .await
+            .entry(target_id.to_owned())
+            .or_default()
+            .push(waiting);
+        if !self.avs_map.read().await.contains_key(target_id) {
+            self.release(target_id).await;
+        }
+    }
+
+    /// Hand the released target over to the waiting streaming with the highest priority,
+    /// the streaming that waited the longest goes first among the same priority.
+    async fn release(&self, target_id: &str) {
+        loop {
+            let waiting = {
+                let mut lock = self.waiting.write().await;
+                let queue = match lock.get_mut(target_id) {
+                    Some(queue) if !queue.is_empty() => queue,
+                    _ => return,
+                };
+                let priority = queue.iter().map(|w| w.priority).max().unwrap_or_default();
+                let index = queue
+                    .iter()
+                    .position(|w| w.priority == priority)
+                    .unwrap_or_default();
+                let waiting = queue.remove(index);
+                if queue.is_empty() {
+                    lock.remove(target_id);
+                }
+                waiting
+            };
+            let streaming = match self.streaming.read().await.get(&waiting.id).cloned() {
+                Some(streaming) => streaming,
+                None => continue,
+            };
+            {
+                let mut avs_map = self.avs_map.write().await;
+                if avs_map.contains_key(target_id) {
+                    // taken in the meantime, the streaming keeps its turn for the next release.
+                    self.waiting
+                        .write()
+                        .await
+                        .entry(target_id.to_owned())
+                        .or_default()
+                        .insert(0, waiting);
+                    return;
+                }
+                avs_map.insert(target_id.to_owned(), waiting.id);
+            }
+            let turn = self.turn.issue(target_id);
+            let track = streaming.origin.local_track();
+            match RTCForwader::new(waiting.avs, track, turn).await {
+                Ok(forwarder) => {
+                    let forwarder = Arc::new(
+                        forwarder
+                            .with_outputs(waiting.outputs)
+                            .with_emergency(streaming.emergency),
+                    );
+                    if let Some(on_going) = self.on_going.write().await.get_mut(&waiting.id) {
+                        on_going.push(target_id.to_owned());
+                    }
+                    streaming
+                        .add_forwarder(target_id.to_owned(), forwarder)
+                        .await;
+                    streaming
+                        .notify("stream:resumed", vec![target_id.to_owned()])
+                        .await;
+                    return;
+                }
+                Err(_) => {
+                    log::error!("failed to create forwarder for {}", target_id);
+                    self.avs_map.write().await.remove(target_id);
+                    if let Some(broadcast) = &streaming.broadcast {
+                        broadcast.left(target_id, Some("failed"));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Create the forwarders of the track to the targets.
+    /// Returns the forwarders along with the avs ids of the targets,
+    /// a failed target is handed over to the streaming waiting for it.
+    async fn new_forwarders(
+        &self,
+        id: i32,
+        track: Arc<TrackLocalStaticRTP>,
+        emergency: bool,
+        target: HashMap<String, (Stream, Vec<String>)>,
+        excluded: &mut Vec<(i32, &'static str)>,
+    ) -> (HashMap<String, Arc<RTCForwader>>, HashMap<String, i32>) {
+        let mut forwarders = HashMap::new();
+        let mut avs_ids = HashMap::new();
+        log::debug!("create forwarders for target: {:?}", target);
+        for (target_id, (avs, outputs)) in target.iter() {
+            let turn = self.turn.issue(target_id);
+            let forwarder =
+                if let Ok(forwarder) = RTCForwader::new(avs.clone(), track.clone(), turn).await {
+                    self.avs_map.write().await.insert(target_id.to_owned(), id);
+                    Arc::new(
+                        forwarder
+                            .with_outputs(outputs.clone())
+                            .with_emergency(emergency),
+                    )
+                } else {
+                    log::error!("failed to create forwarder for {}", target_id);
+                    if let Some(avs_id) = self.avs_id(target_id) {
+                        excluded.push((avs_id, "failed"));
+                    }
+                    self.release(target_id).await;
+                    continue;
+                };
+            if let Some(avs_id) = self.avs_id(target_id) {
+                avs_ids.insert(target_id.to_owned(), avs_id);
+            }
+            forwarders.insert(target_id.to_owned(), forwarder);
+        }
+        (forwarders, avs_ids)
+    }
+
+    /// Check whether the user is playing a record.
+    async fn is_playing(&self, id: i32) -> bool {
+        self.streaming
+            .read()
+            .await
+            .get(&id)
+            .map(|s| matches!(s.origin, Origin::Server(_)))
+            .unwrap_or(false)
+    }
+
+    /// Queue the targets for the streaming, they join once released.
+    async fn queue(&self, id: i32, priority: u8, queued: Vec<(String, Stream, Vec<String>)>) {
+        for (target_id, avs, outputs) in queued {
+            let waiting = Waiting {
+                id,
+                priority,
+                avs,
+                outputs,
+            };
+            self.wait(&target_id, waiting).await;
         }
     }
 
     /// Create new Streaming.
+    /// The target maps the avs to its connection and the output devices to play on,
+    /// `offline` lists the requested avs that are not connected.
+    /// The busy targets are resolved by the conflict policy and reported to the user.
     pub async fn new_streaming(
         &self,
         stream: Stream,
         id: i32,
         offer: String,
-        mut target: HashMap<String, Stream>,
+        options: StreamOptions,
+        mut target: HashMap<String, (Stream, Vec<String>)>,
+        offline: Vec<i32>,
     ) {
-        {
-            let avs_map = self.avs_map.read().await;
-            // check if the target is not in the avs_map and filter them.
-            for (target_id, _) in target.clone().iter() {
-                if avs_map.contains_key(target_id) {
-                    target.remove(target_id);
-                }
-            }
+        if self.is_playing(id).await {
+            let _ = stream
+                .write(
+                    "offer:fail",
+                    WsErr {
+                        msg: "a record is being played".to_owned(),
+                    },
+                )
+                .await;
+            return;
         }
-
-        let provider = if let Ok(provider) = RTCProvider::new(stream.clone()).await {
+        // the provider is ready before any target is taken over from another streaming.
+        let turn = self.turn.issue(stream.id());
+        let provider = if let Ok(provider) = RTCProvider::new(stream.clone(), &turn).await {
             Arc::new(provider)
         } else {
             let _ = stream
@@ -158,6 +766,7 @@ impl StreamingState {
 
         if let Err(e) = provider.add_offer(offer).await {
             log::error!("failed to add offer: {:?}", e);
+            provider.disconnect().await;
             let _ = stream
                 .write(
                     "offer:fail",
@@ -169,28 +778,200 @@ impl StreamingState {
             return;
         }
 
-        let track = provider.local_track();
+        let mut excluded = offline
+            .into_iter()
+            .map(|avs_id| (avs_id, "offline"))
+            .collect::<Vec<(i32, &str)>>();
+        let mut busy = vec![];
+        let queued = self
+            .resolve_busy(&options, &mut target, &mut excluded, &mut busy)
+            .await;
+        if !busy.is_empty() {
+            let _ = stream
+                .write("offer:busy", BusyTargets { targets: busy })
+                .await;
+        }
 
-        let mut forwarders = HashMap::new();
-        let mut on_going = vec![];
-        log::debug!("create forwarders for target: {:?}", target);
-        for (target_id, avs) in target.iter() {
-            let forwarder =
-                if let Ok(forwarder) = RTCForwader::new(avs.clone(), track.clone()).await {
-                    self.avs_map.write().await.insert(target_id.to_owned(), id);
-                    on_going.push(target_id.to_owned());
-                    Arc::new(forwarder)
-                } else {
-                    log::error!("failed to create forwarder for {}", target_id);
-                    continue;
-                };
-            forwarders.insert(target_id.to_owned(), forwarder);
+        let emergency = options.emergency;
+        let (forwarders, mut avs_ids) = self
+            .new_forwarders(id, provider.local_track(), emergency, target, &mut excluded)
+            .await;
+        self.queued_ids(&queued, &mut avs_ids);
+        let on_going = forwarders.keys().cloned().collect();
+        let broadcast = BroadcastLog::create(&self.db, id, emergency, avs_ids, excluded);
+        let recording = if options.record {
+            self.start_recording(&provider).await
+        } else {
+            None
+        };
+        let streaming = Arc::new(
+            Streaming::new(Origin::User(provider), forwarders, self.turn.clone())
+                .with_broadcast(broadcast)
+                .with_recording(recording)
+                .with_priority(options.priority, emergency),
+        );
+        self.streaming.write().await.insert(id, streaming.clone());
+        self.on_going.write().await.insert(id, on_going);
+        streaming.begin().await;
+        self.queue(id, options.priority, queued).await;
+    }
+
+    /// Add the avs ids of the queued targets, they are targets of the broadcast.
+    fn queued_ids(
+        &self,
+        queued: &[(String, Stream, Vec<String>)],
+        avs_ids: &mut HashMap<String, i32>,
+    ) {
+        for (target_id, _, _) in queued {
+            if let Some(avs_id) = self.avs_id(target_id) {
+                avs_ids.insert(target_id.to_owned(), avs_id);
+            }
         }
+    }
 
-        let streaming = Arc::new(Streaming::new(provider, forwarders));
+    /// Play the opus packets of a record on the target on behalf of the user.
+    /// Returns the id of the broadcast along with the busy targets resolved by the conflict
+    /// policy, the playback runs in the background.
+    pub async fn new_playback(
+        &self,
+        id: i32,
+        packets: Vec<Vec<u8>>,
+        options: StreamOptions,
+        mut target: HashMap<String, (Stream, Vec<String>)>,
+        offline: Vec<i32>,
+    ) -> Result<(i32, Vec<BusyTarget>), String> {
+        if self.streaming.read().await.contains_key(&id) {
+            return Err("the user is already streaming".to_owned());
+        }
+        let mut excluded = offline
+            .into_iter()
+            .map(|avs_id| (avs_id, "offline"))
+            .collect::<Vec<(i32, &str)>>();
+        let mut busy = vec![];
+        let queued = self
+            .resolve_busy(&options, &mut target, &mut excluded, &mut busy)
+            .await;
+
+        let emergency = options.emergency;
+        let source = Arc::new(RTCSource::new());
+        let (forwarders, mut avs_ids) = self
+            .new_forwarders(id, source.local_track(), emergency, target, &mut excluded)
+            .await;
+        self.queued_ids(&queued, &mut avs_ids);
+        let on_going = forwarders.keys().cloned().collect();
+        let broadcast = match BroadcastLog::create(&self.db, id, emergency, avs_ids, excluded) {
+            Some(broadcast) => broadcast,
+            None => {
+                for (target_id, forwarder) in forwarders.iter() {
+                    forwarder.disconnect().await;
+                    self.avs_map.write().await.remove(target_id);
+                    self.release(target_id).await;
+                }
+                return Err("failed to create broadcast".to_owned());
+            }
+        };
+        let broadcast_id = broadcast.id();
+        let streaming = Arc::new(
+            Streaming::new(
+                Origin::Server(source.clone()),
+                forwarders,
+                self.turn.clone(),
+            )
+            .with_broadcast(Some(broadcast))
+            .with_priority(options.priority, emergency),
+        );
         self.streaming.write().await.insert(id, streaming.clone());
         self.on_going.write().await.insert(id, on_going);
         streaming.begin().await;
+        self.queue(id, options.priority, queued).await;
+
+        let state = self.clone();
+        tokio::spawn(async move {
+            streaming.wait_connected(PLAYBACK_CONNECT_TIMEOUT).await;
+            if source.play(packets, OPUS_FRAME_SAMPLES).await {
+                state.stop_playback(broadcast_id).await;
+            }
+        });
+        Ok((broadcast_id, busy))
+    }
+
+    /// Stop the playback of the broadcast, returns whether it was playing.
+    pub async fn stop_playback(&self, broadcast_id: i32) -> bool {
+        match self.by_broadcast(broadcast_id).await {
+            Some((id, streaming)) if matches!(streaming.origin, Origin::Server(_)) => {
+                self.close_streaming(id).await;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Get the streaming of the broadcast.
+    async fn by_broadcast(&self, broadcast_id: i32) -> Option<(i32, Arc<Streaming>)> {
+        self.streaming
+            .read()
+            .await
+            .iter()
+            .find(|(_, s)| s.broadcast.as_ref().map(|b| b.id()) == Some(broadcast_id))
+            .map(|(id, s)| (*id, s.clone()))
+    }
+
+    /// Listen to the streaming of the broadcast on the stream of the user.
+    /// The user listens to a single streaming at a time.
+    pub async fn listen(&self, stream: Stream, broadcast_id: i32) -> Result<(), String> {
+        let (id, streaming) = self
+            .by_broadcast(broadcast_id)
+            .await
+            .ok_or_else(|| "broadcast is not live".to_owned())?;
+        let listener_id = stream.id().to_owned();
+        self.stop_listening(&listener_id).await;
+        let turn = self.turn.issue(&listener_id);
+        let listener = RTCForwader::listener(stream, streaming.origin.local_track(), turn)
+            .await
+            .map_err(|_| "failed to create listener".to_owned())?;
+        self.listening.write().await.insert(listener_id.clone(), id);
+        streaming
+            .add_listener(listener_id, Arc::new(listener))
+            .await;
+        Ok(())
+    }
+
+    /// Get the streaming the user is listening to.
+    async fn listened(&self, listener_id: &str) -> Option<Arc<Streaming>> {
+        let id = *self.listening.read().await.get(listener_id)?;
+        self.streaming.read().await.get(&id).cloned()
+    }
+
+    /// Add listener answer.
+    pub async fn add_answer_listener(&self, listener_id: &str, answer: String) {
+        if let Some(streaming) = self.listened(listener_id).await {
+            if let Some(listener) = streaming.listeners.read().await.get(listener_id) {
+                if let Err(e) = listener.add_answer(answer).await {
+                    log::error!("failed to add answer: {:?}", e);
+                }
+            }
+        }
+    }
+
+    /// Add ices to listener.
+    pub async fn add_ices_listener(&self, listener_id: &str, ices: String) {
+        if let Some(streaming) = self.listened(listener_id).await {
+            if let Some(listener) = streaming.listeners.read().await.get(listener_id) {
+                if let Err(e) = listener.add_ices(ices).await {
+                    log::error!("failed to add ices: {:?}", e);
+                }
+            }
+        }
+    }
+
+    /// Stop listening.
+    pub async fn stop_listening(&self, listener_id: &str) {
+        if let Some(id) = self.listening.write().await.remove(listener_id) {
+            let streaming = self.streaming.read().await.get(&id).cloned();
+            if let Some(streaming) = streaming {
+                streaming.close_listener(listener_id).await;
+            }
+        }
     }
 
     /// Add ices to provider.
@@ -238,20 +1019,60 @@ impl StreamingState {
         }
     }
 
+    /// Forward the stream statistics of the avs to the user streaming to it.
+    pub async fn forward_stats(&self, id: &str, stats: StreamStats) {
+        if let Some(stream_id) = self.avs_map.read().await.get(id) {
+            if let Some(streaming) = self.streaming.read().await.get(stream_id) {
+                if let Origin::User(provider) = &streaming.origin {
+                    let _ = provider.stream().write("stream:stats", stats).await;
+                }
+            }
+        }
+    }
+
     /// Close streaming.
+    /// The targets are handed over to the streaming waiting for them.
     pub async fn close_streaming(&self, id: i32) {
-        if let Some(streaming) = self.streaming.write().await.remove(&id) {
+        let streaming = self.streaming.write().await.remove(&id);
+        if let Some(streaming) = streaming {
             self.on_going.write().await.remove(&id);
-            streaming.provider.disconnect().await;
-            for (id, forwarder) in streaming.forwarders.read().await.iter() {
+            for queue in self.waiting.write().await.values_mut() {
+                queue.retain(|w| w.id != id);
+            }
+            match &streaming.origin {
+                Origin::User(provider) => provider.disconnect().await,
+                Origin::Server(source) => source.stop(),
+            }
+            for (listener_id, listener) in streaming.listeners.read().await.iter() {
+                listener.disconnect().await;
+                self.listening.write().await.remove(listener_id);
+            }
+            let mut released = vec![];
+            for (target_id, forwarder) in streaming.forwarders.read().await.iter() {
                 forwarder.disconnect().await;
-                self.avs_map.write().await.remove(id);
+                self.avs_map.write().await.remove(target_id);
+                released.push(target_id.to_owned());
+            }
+            if let Some(broadcast) = &streaming.broadcast {
+                broadcast.finish();
+            }
+            self.finish_recording(id, &streaming).await;
+            for target_id in released {
+                self.release(&target_id).await;
             }
         }
     }
 
-    /// Close forwarder.
+    /// Close the live streaming of the user, the playback of a record is kept.
+    pub async fn close_live_streaming(&self, id: i32) {
+        if !self.is_playing(id).await {
+            self.close_streaming(id).await;
+        }
+    }
+
+    /// Close forwarder, the avs left so no streaming waits for it anymore.
     pub async fn close_forwarder(&self, id: &str) {
+        self.waiting.write().await.remove(id);
         if let Some(stream_id) = self.avs_map.write().await.remove(id) {
             let mut lock = self.streaming.write().await;
             if let Some(streaming) = lock.get_mut(&stream_id) {
@@ -259,7 +1080,7 @@ impl StreamingState {
                 if let Some(on_going) = lock2.get_mut(&stream_id) {
                     on_going.retain(|x| x != id);
                 }
-                streaming.close_forwarder(id).await;
+                streaming.close_forwarder(id, None).await;
             }
         }
     }
@@ -0,0 +1,46 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use types::{api::updates::Updates, proto::UpdateAnnounce};
+
+/// UpdateState.
+/// Holds what's needed to announce client updates.
+#[derive(Clone)]
+pub struct UpdateState {
+    base_url: String,
+}
+
+impl UpdateState {
+    /// Create new UpdateState.
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.to_string(),
+        }
+    }
+
+    /// Create the announcement of the update.
+    pub fn announce(&self, update: Updates) -> UpdateAnnounce {
+        UpdateAnnounce {
+            id: update.id,
+            version: update.version,
+            url: format!("{}/install/{}", self.base_url, update.file_name),
+            size: update.size,
+            sha256: update.sha256,
+            signature: update.signature,
+        }
+    }
+}
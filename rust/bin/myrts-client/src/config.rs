@@ -0,0 +1,141 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use utils::config::{Args, ConfigErrors};
+
+/// ClientConfig.
+/// The client configuration, loaded from `/etc/myrts/client.toml`, the `--config` file and the environment.
+#[derive(Debug, Clone)]
+pub struct ClientConfig {
+    /// The log level used until the server pushes one.
+    pub log_level: String,
+    pub log_path: String,
+    pub api_url: String,
+    pub database_url: String,
+    pub data_path: String,
+    pub device_description: String,
+    pub device_address: String,
+    /// The one-time token to enroll the device with.
+    pub enrollment_token: Option<String>,
+    /// The hex encoded ed25519 key to verify the updates with.
+    pub update_public_key: Option<String>,
+    /// Seconds the new version has to connect before it is rolled back.
+    pub update_deadline: u64,
+    /// The address of the local api, disabled when unset.
+    pub local_api_addr: Option<String>,
+    pub local_api_token: Option<String>,
+    /// Maximum number of events kept while the server is unreachable.
+    pub outbox_max_entries: i64,
+    /// Maximum size of the kept events in megabytes.
+    pub outbox_max_mb: i64,
+    /// The units the `restart_service` action may restart.
+    pub action_services: Vec<String>,
+}
+
+impl ClientConfig {
+    /// Load and validate the config.
+    pub fn load(args: &Args) -> Result<Self, ConfigErrors> {
+        let mut l = args
+            .loader("client")
+            .env("log_level", "LOG_LEVEL")
+            .env("log_path", "LOG_PATH")
+            .env("api_url", "API_URL")
+            .env("database_url", "DATABASE_URL")
+            .env("data_path", "DATA_PATH")
+            .env("device.description", "DEVICE_DESCRIPTION")
+            .env("device.address", "DEVICE_ADDRESS")
+            .env("update.public_key", "UPDATE_PUBLIC_KEY")
+            .env("update.deadline", "UPDATE_DEADLINE")
+            .env("local_api.addr", "LOCAL_API_ADDR")
+            .env("outbox.max_entries", "OUTBOX_MAX_ENTRIES")
+            .env("outbox.max_mb", "OUTBOX_MAX_MB")
+            .env("actions.services", "ACTION_SERVICES");
+        let config = Self {
+            log_level: l.get("log_level", "info".to_owned()),
+            log_path: l.get("log_path", "devdata/logs".to_owned()),
+            api_url: l.get("api_url", "ws://localhost:1452".to_owned()),
+            database_url: l.get("database_url", "devdata/db/myrts.db".to_owned()),
+            data_path: l.get("data_path", "devdata/assets".to_owned()),
+            device_description: l.get("device.description", "MyRTS".to_owned()),
+            device_address: l.get("device.address", "myrts".to_owned()),
+            enrollment_token: Some(l.secret("device.enrollment_token", "ENROLLMENT_TOKEN", ""))
+                .filter(|t| !t.is_empty()),
+            update_public_key: l
+                .get_opt::<String>("update.public_key")
+                .filter(|k| !k.is_empty()),
+            update_deadline: l.get("update.deadline", 180),
+            local_api_addr: l
+                .get_opt::<String>("local_api.addr")
+                .filter(|a| !a.is_empty()),
+            local_api_token: Some(l.secret("local_api.token", "LOCAL_API_TOKEN", ""))
+                .filter(|t| !t.is_empty()),
+            outbox_max_entries: l.get("outbox.max_entries", 20000),
+            outbox_max_mb: l.get("outbox.max_mb", 32),
+            action_services: l.get("actions.services", vec!["myrts-client".to_owned()]),
+        };
+        l.check(
+            "log_level",
+            ["debug", "info", "warn", "error"].contains(&config.log_level.as_str()),
+            "expected one of debug, info, warn, error",
+        );
+        l.check(
+            "api_url",
+            config.api_url.starts_with("ws://") || config.api_url.starts_with("wss://"),
+            "expected a ws(s):// url",
+        );
+        l.check(
+            "update.public_key",
+            config
+                .update_public_key
+                .as_ref()
+                .map(|k| k.len() == 64 && k.chars().all(|c| c.is_ascii_hexdigit()))
+                .unwrap_or(true),
+            "expected a hex encoded ed25519 public key",
+        );
+        l.check(
+            "update.deadline",
+            config.update_deadline > 0,
+            "must be positive",
+        );
+        l.check(
+            "outbox.max_entries",
+            config.outbox_max_entries > 0,
+            "must be positive",
+        );
+        l.check(
+            "outbox.max_mb",
+            config.outbox_max_mb > 0,
+            "must be positive",
+        );
+        l.check(
+            "local_api.addr",
+            config
+                .local_api_addr
+                .as_ref()
+                .map(|a| std::net::ToSocketAddrs::to_socket_addrs(a.as_str()).is_ok())
+                .unwrap_or(true),
+            "expected an address such as 127.0.0.1:8080",
+        );
+        l.check(
+            "local_api.token",
+            config.local_api_addr.is_none() || config.local_api_token.is_some(),
+            "required when local_api.addr is set",
+        );
+        l.finish()?;
+        Ok(config)
+    }
+}
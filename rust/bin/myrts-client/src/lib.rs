@@ -15,73 +15,106 @@ MyRTS agrees to credit you as the developer of the software in all promotional m
 If MyRTS violates any of these terms, their license to use the software will automatically terminate.
 */
 
-use std::collections::HashMap;
+use states::VERSION;
 use systemstat::{Platform, System};
-use types::proto::AvsInfo;
+use types::proto::{InterfaceAddr, InterfaceTelemetry, Telemetry};
 
+pub mod config;
+pub mod local_api;
 pub mod services;
 pub mod states;
 
-/// Get os specific info.
-pub fn get_os_info() -> AvsInfo {
-    let mut info = AvsInfo {
-        networks: None,
+/// Parse the log level, defaults to `info`.
+pub fn log_level(level: &str) -> log::LevelFilter {
+    match level {
+        "debug" => log::LevelFilter::Debug,
+        "info" => log::LevelFilter::Info,
+        "warn" => log::LevelFilter::Warn,
+        "error" => log::LevelFilter::Error,
+        _ => log::LevelFilter::Info,
+    }
+}
+
+/// Format an ip address of systemstat.
+fn format_ip(addr: systemstat::IpAddr) -> String {
+    match addr {
+        systemstat::IpAddr::Empty => "".to_string(),
+        systemstat::IpAddr::Unsupported => "".to_string(),
+        systemstat::IpAddr::V4(ip) => ip.to_string(),
+        systemstat::IpAddr::V6(ip) => ip.to_string(),
+    }
+}
+
+/// Get the resident set size of the current process.
+fn get_rss() -> Option<i64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kb = line.split_whitespace().nth(1)?.parse::<i64>().ok()?;
+    Some(kb * 1024)
+}
+
+/// Get os specific telemetry.
+/// The audio output status is filled by the caller.
+pub fn get_telemetry() -> Telemetry {
+    let mut telemetry = Telemetry {
+        uptime: None,
+        cpu_load: None,
+        cpu_temp: None,
         mem_total: None,
         mem_free: None,
         disk_total: None,
         disk_free: None,
-        cpu_temp: None,
+        networks: vec![],
+        audio_output: None,
+        audio_ok: false,
+        audio_playing: false,
+        version: VERSION.to_owned(),
+        rss: get_rss(),
     };
 
     let sys = System::new();
 
+    if let Ok(uptime) = sys.uptime() {
+        telemetry.uptime = Some(uptime.as_secs() as i64);
+    }
+
+    if let Ok(load) = sys.load_average() {
+        telemetry.cpu_load = Some(load.one);
+    }
+
     if let Ok(networks) = sys.networks() {
-        let mut map: HashMap<String, Vec<HashMap<String, String>>> = HashMap::new();
         for (_, network) in networks {
-            let mut map2: Vec<HashMap<String, String>> = Vec::new();
-            for addr in network.addrs {
-                let mut map3: HashMap<String, String> = HashMap::new();
-                let ip = match addr.addr {
-                    systemstat::IpAddr::Empty => "".to_string(),
-                    systemstat::IpAddr::Unsupported => "".to_string(),
-                    systemstat::IpAddr::V4(ip) => ip.to_string(),
-                    systemstat::IpAddr::V6(ip) => ip.to_string(),
-                };
-                map3.insert("ip".to_string(), ip);
-                let netmask = match addr.netmask {
-                    systemstat::IpAddr::Empty => "".to_string(),
-                    systemstat::IpAddr::Unsupported => "".to_string(),
-                    systemstat::IpAddr::V4(ip) => ip.to_string(),
-                    systemstat::IpAddr::V6(ip) => ip.to_string(),
-                };
-                map3.insert("netmask".to_string(), netmask);
-                map2.push(map3);
-            }
-            map.insert(network.name, map2);
-        }
-        if let Ok(networks) = serde_json::to_string(&map) {
-            info.networks = Some(networks);
+            let stats = sys.network_stats(&network.name).ok();
+            telemetry.networks.push(InterfaceTelemetry {
+                addrs: network
+                    .addrs
+                    .into_iter()
+                    .map(|addr| InterfaceAddr {
+                        ip: format_ip(addr.addr),
+                        netmask: format_ip(addr.netmask),
+                    })
+                    .collect(),
+                rx_bytes: stats.as_ref().map(|s| s.rx_bytes.as_u64() as i64),
+                tx_bytes: stats.as_ref().map(|s| s.tx_bytes.as_u64() as i64),
+                name: network.name,
+            });
         }
+        telemetry.networks.sort_by(|a, b| a.name.cmp(&b.name));
     }
 
     if let Ok(mem) = sys.memory() {
-        let total = format!("{}", mem.total.as_u64());
-        let free = format!("{}", mem.free.as_u64());
-        info.mem_total = Some(total);
-        info.mem_free = Some(free);
+        telemetry.mem_total = Some(mem.total.as_u64() as i64);
+        telemetry.mem_free = Some(mem.free.as_u64() as i64);
     }
 
     if let Ok(disk) = sys.mount_at("/") {
-        let total = format!("{}", disk.total.as_u64());
-        let free = format!("{}", disk.free.as_u64());
-        info.disk_total = Some(total);
-        info.disk_free = Some(free);
+        telemetry.disk_total = Some(disk.total.as_u64() as i64);
+        telemetry.disk_free = Some(disk.free.as_u64() as i64);
     }
 
     if let Ok(temp) = sys.cpu_temp() {
-        let temp = format!("{}", temp);
-        info.cpu_temp = Some(temp);
+        telemetry.cpu_temp = Some(temp);
     }
 
-    info
+    telemetry
 }
@@ -0,0 +1,195 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use super::LocalToken;
+use crate::{
+    send_sync,
+    states::{ClientState, ConnectionState, NowPlaying, VERSION},
+};
+use actix_web::{get, post, web, HttpRequest, HttpResponse};
+use proto::app::Stream;
+use proto_db::{repos::DeviceRepo, ProtoDatabase};
+use serde::{Deserialize, Serialize};
+use types::proto::{DeviceConfig, Playlist, Schedule, Telemetry};
+
+/// Status.
+/// The device status.
+#[derive(Serialize)]
+struct Status {
+    version: &'static str,
+    connection: ConnectionState,
+    uid: Option<String>,
+    description: Option<String>,
+    address: Option<String>,
+    now_playing: Vec<NowPlaying>,
+    streaming: bool,
+    telemetry: Telemetry,
+    config: DeviceConfig,
+    playlist: Playlist,
+}
+
+/// Upcoming.
+/// An upcoming schedule fire time.
+#[derive(Serialize)]
+struct Upcoming {
+    at: chrono::NaiveDateTime,
+    sid: i32,
+    name: String,
+    record_url: String,
+    volume: Option<f32>,
+}
+
+/// UpcomingQuery.
+#[derive(Deserialize)]
+struct UpcomingQuery {
+    hours: Option<i64>,
+}
+
+/// TestToneReq.
+#[derive(Deserialize)]
+struct TestToneReq {
+    seconds: Option<u64>,
+}
+
+/// VolumeReq.
+#[derive(Deserialize)]
+struct VolumeReq {
+    volume: f32,
+}
+
+/// Message.
+#[derive(Serialize)]
+struct Message {
+    message: String,
+}
+
+/// Create a message response.
+fn message(status: u16, message: &str) -> HttpResponse {
+    HttpResponse::build(actix_web::http::StatusCode::from_u16(status).unwrap()).json(Message {
+        message: message.to_string(),
+    })
+}
+
+/// Send the sync request and the queued events to the server.
+async fn send_resync(
+    state: &ClientState,
+    db: &ProtoDatabase,
+    stream: &Stream,
+) -> proto::error::Result<()> {
+    send_sync!(db, stream);
+    state.outbox().flush(stream).await;
+    Ok(())
+}
+
+#[get("/status")]
+pub(super) async fn status(
+    state: web::Data<ClientState>,
+    db: web::Data<ProtoDatabase>,
+) -> HttpResponse {
+    let device = db.repository::<DeviceRepo>().get().ok();
+    HttpResponse::Ok().json(Status {
+        version: VERSION,
+        connection: state.connection_state().await,
+        uid: device.as_ref().map(|d| d.uid.clone()),
+        description: device.as_ref().map(|d| d.description.clone()),
+        address: device.as_ref().map(|d| d.address.clone()),
+        now_playing: state.now_playing().await,
+        streaming: state.is_streaming().await,
+        telemetry: state.telemetry().await,
+        config: state.config().await,
+        playlist: state.playlist().await,
+    })
+}
+
+#[get("/schedules")]
+pub(super) async fn schedules(state: web::Data<ClientState>) -> HttpResponse {
+    HttpResponse::Ok().json(state.schedules())
+}
+
+#[get("/schedules/upcoming")]
+pub(super) async fn upcoming(
+    state: web::Data<ClientState>,
+    query: web::Query<UpcomingQuery>,
+) -> HttpResponse {
+    let hours = query.hours.unwrap_or(24).clamp(1, 24 * 7);
+    let upcoming = state
+        .upcoming(hours)
+        .into_iter()
+        .map(|(at, schedule): (_, Schedule)| Upcoming {
+            at,
+            sid: schedule.sid,
+            name: schedule.name,
+            record_url: schedule.record_url,
+            volume: schedule.volume,
+        })
+        .collect::<Vec<Upcoming>>();
+    HttpResponse::Ok().json(upcoming)
+}
+
+#[post("/actions/test_tone")]
+pub(super) async fn test_tone(
+    req: HttpRequest,
+    token: web::Data<LocalToken>,
+    state: web::Data<ClientState>,
+    data: Option<web::Json<TestToneReq>>,
+) -> HttpResponse {
+    if !token.verify(&req) {
+        return message(401, "Unauthorized");
+    }
+    let seconds = data.and_then(|d| d.seconds).unwrap_or(3).clamp(1, 30);
+    match state.test_tone(seconds).await {
+        Ok(_) => message(200, "ok"),
+        Err(e) => message(409, &e),
+    }
+}
+
+#[post("/actions/volume")]
+pub(super) async fn volume(
+    req: HttpRequest,
+    token: web::Data<LocalToken>,
+    state: web::Data<ClientState>,
+    data: web::Json<VolumeReq>,
+) -> HttpResponse {
+    if !token.verify(&req) {
+        return message(401, "Unauthorized");
+    }
+    if !(0.0..=2.0).contains(&data.volume) {
+        return message(400, "volume must be between 0.0 and 2.0");
+    }
+    state.set_volume(data.volume).await;
+    message(200, "ok")
+}
+
+#[post("/actions/resync")]
+pub(super) async fn resync(
+    req: HttpRequest,
+    token: web::Data<LocalToken>,
+    state: web::Data<ClientState>,
+    db: web::Data<ProtoDatabase>,
+) -> HttpResponse {
+    if !token.verify(&req) {
+        return message(401, "Unauthorized");
+    }
+    let stream = match state.connection().await {
+        Some(stream) => stream,
+        None => return message(503, "not connected to the server"),
+    };
+    match send_resync(&state, &db, &stream).await {
+        Ok(_) => message(200, "ok"),
+        Err(e) => message(500, &e.to_string()),
+    }
+}
@@ -0,0 +1,77 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+//! Local status and control HTTP API.
+//!
+//! Used by technicians on site to inspect and control the device without
+//! reading its log files. Every action requires the local token sent as
+//! `Authorization: Bearer <token>`.
+
+use crate::states::ClientState;
+use actix_web::{web, App, HttpRequest, HttpServer};
+use proto_db::ProtoDatabase;
+
+mod handlers;
+
+/// LocalToken.
+/// The token used to protect local actions.
+#[derive(Clone)]
+pub(crate) struct LocalToken(String);
+
+impl LocalToken {
+    /// Check if the request carries the local token.
+    pub(crate) fn verify(&self, req: &HttpRequest) -> bool {
+        req.headers()
+            .get("Authorization")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .map(|v| utils::crypto::constant_eq(v.as_bytes(), self.0.as_bytes()))
+            .unwrap_or(false)
+    }
+}
+
+/// Run the local http server.
+pub async fn run(addr: &str, token: &str, state: ClientState, db: ProtoDatabase) {
+    let token = LocalToken(token.to_string());
+    let server = HttpServer::new(move || {
+        App::new()
+            .app_data(web::Data::new(state.clone()))
+            .app_data(web::Data::new(db.clone()))
+            .app_data(web::Data::new(token.clone()))
+            .service(handlers::status)
+            .service(handlers::schedules)
+            .service(handlers::upcoming)
+            .service(handlers::test_tone)
+            .service(handlers::volume)
+            .service(handlers::resync)
+    })
+    .workers(1)
+    .bind(addr);
+
+    let server = match server {
+        Ok(server) => server,
+        Err(e) => {
+            log::error!("Failed to bind local api on {}: {}", addr, e);
+            return;
+        }
+    };
+
+    log::info!("Local api listening on {}", addr);
+    if let Err(e) = server.run().await {
+        log::error!("Local api error: {}", e);
+    }
+}
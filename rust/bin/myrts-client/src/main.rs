@@ -20,22 +20,19 @@ use log4rs::{
     config::{Appender, Root},
     Config,
 };
-use myrts_client::{services::start_service, states::ClientState};
+use myrts_client::{
+    config::ClientConfig,
+    local_api,
+    services::start_service,
+    states::{ClientState, UpdateState},
+};
 use proto_db::new_proto_database;
-use utils::files::ApiAssets;
-
-fn get_log_level() -> log::LevelFilter {
-    match &*utils::env::load_env("LOG_LEVEL", "info") {
-        "debug" => log::LevelFilter::Debug,
-        "info" => log::LevelFilter::Info,
-        "warn" => log::LevelFilter::Warn,
-        "error" => log::LevelFilter::Error,
-        _ => log::LevelFilter::Info,
-    }
-}
+use utils::{config::Args, files::ApiAssets};
 
 /// configure the logger.
-fn configure_logger(log_path: &str) {
+/// The root logger accepts everything, the level is controlled by `log::set_max_level`
+/// so it can be changed by the server config.
+fn configure_logger(log_path: &str, log_level: &str) {
     let stdout = ConsoleAppender::builder().build();
     let file = FileAppender::builder().build(log_path).unwrap();
     let config = Config::builder()
@@ -45,10 +42,11 @@ fn configure_logger(log_path: &str) {
             Root::builder()
                 .appender("stdout")
                 .appender("file")
-                .build(get_log_level()),
+                .build(log::LevelFilter::Trace),
         )
         .unwrap();
     let _ = log4rs::init_config(config).unwrap();
+    log::set_max_level(myrts_client::log_level(log_level));
 }
 
 #[tokio::main]
@@ -58,34 +56,69 @@ async fn main() {
         dotenvy::dotenv().ok();
     }
 
-    let log_path = utils::env::load_env("LOG_PATH", "devdata/logs");
-    let api_url = utils::env::load_env("API_URL", "ws://localhost:1452");
-    let db_url = utils::env::load_env("DATABASE_URL", "devdata/db/myrts.db");
-    let data_path = utils::env::load_env("DATA_PATH", "devdata/assets");
-    let device_description: String = utils::env::load_env("DEVICE_DESCRIPTION", "MyRTS");
-    let device_address: String = utils::env::load_env("DEVICE_ADDRESS", "myrts");
-    configure_logger(&format!("{}/myrts-client.log", log_path));
+    let args = match Args::parse() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}", e);
+            eprintln!("usage: myrts-client [--config <path>] [--check-config]");
+            std::process::exit(2);
+        }
+    };
+    let config = match ClientConfig::load(&args) {
+        Ok(config) => config,
+        Err(e) => {
+            eprint!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    if args.check_config {
+        println!("configuration ok");
+        return;
+    }
+    configure_logger(
+        &format!("{}/myrts-client.log", config.log_path),
+        &config.log_level,
+    );
 
-    let db = new_proto_database(&db_url).unwrap();
+    let db = new_proto_database(&config.database_url).unwrap();
     {
         use proto_db::repos::DeviceRepo;
         let repo = db.repository::<DeviceRepo>();
         if !repo.exists().unwrap() {
             repo.create(
                 &utils::crypto::random_string(8),
-                &device_description,
-                &device_address,
+                &config.device_description,
+                &config.device_address,
             )
             .unwrap();
         }
     }
 
-    let assets = ApiAssets::new(&data_path).unwrap();
+    let assets = ApiAssets::new(&config.data_path).unwrap();
+
+    let update_state = UpdateState::new(
+        db.clone(),
+        config.update_public_key.clone(),
+        std::time::Duration::from_secs(config.update_deadline),
+    );
+    update_state.startup();
 
-    let state = ClientState::new(assets, db.clone()).await;
+    let state = ClientState::new(assets, db.clone(), update_state, config.clone()).await;
+
+    // the token is required along with the address by the config.
+    if let (Some(local_api_addr), Some(local_api_token)) = (
+        config.local_api_addr.clone(),
+        config.local_api_token.clone(),
+    ) {
+        let state = state.clone();
+        let db = db.clone();
+        tokio::spawn(async move {
+            local_api::run(&local_api_addr, &local_api_token, state, db).await;
+        });
+    }
 
     loop {
-        start_service(&api_url, state.clone(), db.clone()).await;
+        start_service(&config.api_url, state.clone(), db.clone()).await;
         tokio::time::sleep(tokio::time::Duration::from_secs(15)).await;
     }
 }
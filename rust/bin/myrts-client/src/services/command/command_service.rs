@@ -15,16 +15,126 @@ MyRTS agrees to credit you as the developer of the software in all promotional m
 If MyRTS violates any of these terms, their license to use the software will automatically terminate.
 */
 
+use crate::states::ClientState;
 use proto::{
-    app::{MsgData, Stream},
+    app::{Data, MsgData},
     error::Result,
 };
-use std::process::Command;
-use types::proto::{CmdRequest, CmdResponse};
+use std::{collections::HashMap, process::Command};
+use types::proto::{action, CmdRequest, CmdResponse};
+
+/// Get the programs to run for the action of the catalog.
+fn action_programs(name: &str, params: &HashMap<String, String>) -> Vec<Vec<String>> {
+    let args = |args: &[&str]| args.iter().map(|a| a.to_string()).collect::<Vec<String>>();
+    let param = |name: &str| params.get(name).cloned().unwrap_or_default();
+    match name {
+        "restart_service" => vec![args(&["systemctl", "restart", &param("service")])],
+        "reboot" => vec![args(&["systemctl", "reboot"])],
+        "set_volume" => {
+            let control = params
+                .get("control")
+                .cloned()
+                .unwrap_or_else(|| "Master".to_owned());
+            vec![args(&[
+                "amixer",
+                "sset",
+                &control,
+                &format!("{}%", param("volume")),
+            ])]
+        }
+        "diagnostics" => vec![
+            args(&["uptime"]),
+            args(&["df", "-h"]),
+            args(&["free", "-m"]),
+            args(&["aplay", "-l"]),
+            args(&["journalctl", "-u", "myrts-client", "-n", "50", "--no-pager"]),
+        ],
+        _ => vec![],
+    }
+}
+
+/// Run the action of the catalog, returns the output and the exit status.
+/// The status is the first non-zero status, or the status of the last program.
+/// Only the `services` may be restarted.
+fn run_action(
+    name: &str,
+    params: &HashMap<String, String>,
+    services: &[String],
+) -> (String, Option<i32>) {
+    let action = match action::action(name) {
+        Some(action) => action,
+        None => return (format!("Action {} is not in the catalog", name), None),
+    };
+    if let Err(e) = action.validate(params) {
+        return (format!("Action {} is invalid: {}", name, e), None);
+    }
+    if let (Some(service), "restart_service") = (params.get("service"), name) {
+        if !services.contains(service) {
+            return (format!("Service {} is not allowed", service), None);
+        }
+    }
+    let mut res = vec![];
+    let mut status = None;
+    for program in action_programs(name, params) {
+        let output = match Command::new(&program[0]).args(&program[1..]).output() {
+            Ok(output) => output,
+            Err(e) => {
+                res.push(format!(
+                    "$ {}\nFailed to execute command: {}",
+                    program.join(" "),
+                    e
+                ));
+                return (res.join("\n"), None);
+            }
+        };
+        res.push(format!(
+            "$ {}\n{}{}",
+            program.join(" "),
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        ));
+        if status.unwrap_or(0) == 0 {
+            status = output.status.code();
+        }
+    }
+    (res.join("\n"), status)
+}
 
 #[proto::service("command")]
-async fn command(stream: Stream, data: MsgData<CmdRequest>) -> Result<()> {
+async fn command(data: MsgData<CmdRequest>, state: Data<ClientState>) -> Result<()> {
     let data = data.into_inner();
+    if let Some(name) = &data.action {
+        let services = &state.client_config().action_services;
+        let (response, status) = run_action(name, &data.params, services);
+        state.outbox().enqueue(
+            "command",
+            &CmdResponse {
+                sender: data.sender,
+                response,
+                target: data.target,
+                id: data.id,
+                status,
+            },
+        );
+        return Ok(());
+    }
+    for cmd in data.command.split("&&") {
+        let program = cmd.trim().split(' ').next().unwrap_or("");
+        if !state.command_allowed(program).await {
+            log::warn!("Command {} is not allowed", program);
+            state.outbox().enqueue(
+                "command",
+                &CmdResponse {
+                    sender: data.sender,
+                    response: format!("Command {} is not allowed", program),
+                    target: data.target,
+                    id: data.id,
+                    status: None,
+                },
+            );
+            return Ok(());
+        }
+    }
     if data.command.contains("&&") {
         let commands = data.command.split("&&").collect::<Vec<&str>>();
         if commands.len() < 1 {
@@ -35,23 +145,26 @@ async fn command(stream: Stream, data: MsgData<CmdRequest>) -> Result<()> {
         let procs = Command::new(cmds[0]).args(&cmds[1..]).output();
         #[allow(unused_assignments)]
         let mut res = String::new();
+        #[allow(unused_assignments)]
+        let mut status = None;
         if let Ok(output) = procs {
+            status = output.status.code();
             if !output.status.success() {
                 res = String::from_utf8_lossy(&output.stderr).to_string();
             } else {
                 res = String::from_utf8_lossy(&output.stdout).to_string();
             }
         } else {
-            stream
-                .write(
-                    "command",
-                    CmdResponse {
-                        sender: data.sender,
-                        response: "Failed to execute command".to_string(),
-                        target: data.target,
-                    },
-                )
-                .await?;
+            state.outbox().enqueue(
+                "command",
+                &CmdResponse {
+                    sender: data.sender,
+                    response: "Failed to execute command".to_string(),
+                    target: data.target,
+                    id: data.id,
+                    status: None,
+                },
+            );
             return Ok(());
         }
 
@@ -59,22 +172,23 @@ async fn command(stream: Stream, data: MsgData<CmdRequest>) -> Result<()> {
             let cmds = cmd.split(" ").collect::<Vec<&str>>();
             let procs = Command::new(cmds[0]).args(&cmds[1..]).output();
             if let Ok(output) = procs {
+                status = output.status.code();
                 if !output.status.success() {
                     res.push_str(format!("\n{}", String::from_utf8_lossy(&output.stderr)).as_str());
                 } else {
                     res.push_str(format!("\n{}", String::from_utf8_lossy(&output.stdout)).as_str());
                 }
             } else {
-                stream
-                    .write(
-                        "command",
-                        CmdResponse {
-                            sender: data.sender,
-                            response: format!("{}\nFailed to execute command", res),
-                            target: data.target,
-                        },
-                    )
-                    .await?;
+                state.outbox().enqueue(
+                    "command",
+                    &CmdResponse {
+                        sender: data.sender,
+                        response: format!("{}\nFailed to execute command", res),
+                        target: data.target,
+                        id: data.id,
+                        status: None,
+                    },
+                );
                 return Ok(());
             }
         }
@@ -83,9 +197,11 @@ async fn command(stream: Stream, data: MsgData<CmdRequest>) -> Result<()> {
             sender: data.sender,
             response: res,
             target: data.target,
+            id: data.id,
+            status,
         };
 
-        stream.write("command", response).await?;
+        state.outbox().enqueue("command", &response);
     } else {
         let cmds = data.command.split(" ").collect::<Vec<&str>>();
         let procs = Command::new(cmds[0]).args(&cmds[1..]).output();
@@ -96,27 +212,31 @@ async fn command(stream: Stream, data: MsgData<CmdRequest>) -> Result<()> {
                     sender: data.sender,
                     response: String::from_utf8_lossy(&output.stderr).to_string(),
                     target: data.target,
+                    id: data.id,
+                    status: output.status.code(),
                 };
-                stream.write("command", response).await?;
+                state.outbox().enqueue("command", &response);
             } else {
                 let response = CmdResponse {
                     sender: data.sender,
                     response: String::from_utf8_lossy(&output.stdout).to_string(),
                     target: data.target,
+                    id: data.id,
+                    status: output.status.code(),
                 };
-                stream.write("command", response).await?;
+                state.outbox().enqueue("command", &response);
             }
         } else {
-            stream
-                .write(
-                    "command",
-                    CmdResponse {
-                        sender: data.sender,
-                        response: "Failed to execute command".to_string(),
-                        target: data.target,
-                    },
-                )
-                .await?;
+            state.outbox().enqueue(
+                "command",
+                &CmdResponse {
+                    sender: data.sender,
+                    response: "Failed to execute command".to_string(),
+                    target: data.target,
+                    id: data.id,
+                    status: None,
+                },
+            );
         }
     }
 
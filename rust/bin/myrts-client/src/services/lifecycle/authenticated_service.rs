@@ -15,32 +15,31 @@ MyRTS agrees to credit you as the developer of the software in all promotional m
 If MyRTS violates any of these terms, their license to use the software will automatically terminate.
 */
 
-use crate::{get_os_info, send_sync};
+use crate::{send_sync, states::ClientState};
 use proto::{
     app::{Data, Stream},
     error::Result,
 };
 use proto_db::ProtoDatabase;
+use types::proto::Ports;
 
 #[proto::service("authenticated")]
-async fn authenticated(stream: Stream, db: Data<ProtoDatabase>) -> Result<()> {
+async fn authenticated(
+    stream: Stream,
+    db: Data<ProtoDatabase>,
+    state: Data<ClientState>,
+) -> Result<()> {
     log::info!("Syncing with server");
-    let stream_clone = stream.clone();
-    tokio::spawn(async move {
-        let stream = stream_clone;
-        let mut tick = tokio::time::interval(std::time::Duration::from_secs(60 * 5));
-        loop {
-            tick.tick().await;
-            let info = get_os_info();
-            match stream.write("avs_info", info).await {
-                Ok(_) => {}
-                Err(e) => match e {
-                    proto::error::Error::Connection(_) => break,
-                    _ => {}
-                },
-            }
-        }
-    });
+    state.authenticated().await;
+    state.confirm_update(&stream).await;
+    stream
+        .write(
+            "ports",
+            Ports {
+                ports: state.ports(),
+            },
+        )
+        .await?;
     send_sync!(db, stream);
     Ok(())
 }
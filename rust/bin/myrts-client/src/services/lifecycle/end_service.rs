@@ -21,6 +21,7 @@ use proto::{app::Data, error::Result};
 #[proto::service("end")]
 async fn end(state: Data<ClientState>) -> Result<()> {
     log::info!("Ending service");
+    state.disconnected().await;
     tokio::spawn(async move {
         state.close_streaming().await;
     });
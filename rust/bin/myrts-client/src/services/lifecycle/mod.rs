@@ -23,3 +23,7 @@ mod end_service;
 pub(super) use end_service::end;
 mod authenticated_service;
 pub(super) use authenticated_service::authenticated;
+mod credential_service;
+pub(super) use credential_service::credential;
+mod auth_fail_service;
+pub(super) use auth_fail_service::auth_fail;
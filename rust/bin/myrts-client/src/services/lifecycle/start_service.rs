@@ -15,6 +15,7 @@ MyRTS agrees to credit you as the developer of the software in all promotional m
 If MyRTS violates any of these terms, their license to use the software will automatically terminate.
 */
 
+use crate::states::ClientState;
 use proto::{
     app::{Data, Stream},
     error::{OtherError, Result},
@@ -23,8 +24,9 @@ use proto_db::{repos::DeviceRepo, ProtoDatabase};
 use types::proto::Authenticate;
 
 #[proto::service("start")]
-async fn start(stream: Stream, db: Data<ProtoDatabase>) -> Result<()> {
+async fn start(stream: Stream, db: Data<ProtoDatabase>, state: Data<ClientState>) -> Result<()> {
     log::info!("Starting service");
+    state.connected(stream.clone()).await;
     let repo = db.repository::<DeviceRepo>();
     let device = repo.get().map_err(|e| OtherError::String(e.to_string()))?;
     let auth = Authenticate {
@@ -32,6 +34,12 @@ async fn start(stream: Stream, db: Data<ProtoDatabase>) -> Result<()> {
         client_type: 2,
         client_description: device.description,
         client_address: device.address,
+        token: if device.credential.is_none() {
+            state.client_config().enrollment_token.clone()
+        } else {
+            None
+        },
+        credential: device.credential,
     };
     log::info!("Authenticating");
     stream.write("auth", auth).await?;
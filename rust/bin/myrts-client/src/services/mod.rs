@@ -23,28 +23,41 @@ use proto::{
 use proto_db::ProtoDatabase;
 
 mod command;
+mod config;
 mod lifecycle;
+mod outbox;
+mod playlist;
+mod self_test;
 mod streaming;
 mod syncing;
+mod update;
 
 /// Start the service.
 pub async fn start_service(url: &str, state: ClientState, db: ProtoDatabase) {
     let app = App::new()
-        .add_state(Data::new(state))
+        .add_state(Data::new(state.clone()))
         .add_state(Data::new(db))
         .service(lifecycle::start)
         .service(lifecycle::pong)
         .service(lifecycle::end)
         .service(lifecycle::authenticated)
+        .service(lifecycle::credential)
+        .service(lifecycle::auth_fail)
         .service(syncing::sync)
         .service(syncing::resync)
         .service(streaming::offer)
         .service(streaming::ices)
         .service(streaming::stream_close)
         .service(streaming::volume)
-        .service(command::command);
+        .service(command::command)
+        .service(config::config)
+        .service(playlist::playlist)
+        .service(update::update)
+        .service(outbox::outbox_ack)
+        .service(self_test::self_test);
 
     if let Err(e) = Client::new(app, url).run().await {
         log::error!("Error: {}", e);
     }
+    state.disconnected().await;
 }
@@ -0,0 +1,46 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use crate::states::{ClientState, VERSION};
+use proto::{
+    app::{Data, MsgData, Stream},
+    error::Result,
+};
+use types::proto::{SelfTestReport, SelfTestReq};
+
+#[proto::service("self_test")]
+async fn self_test(
+    stream: Stream,
+    data: MsgData<SelfTestReq>,
+    state: Data<ClientState>,
+) -> Result<()> {
+    let data = data.into_inner();
+    let state = state.clone();
+    // the tone takes a few seconds, don't hold the other messages.
+    tokio::spawn(async move {
+        let checks = state.self_test(data.tone).await;
+        let report = SelfTestReport {
+            id: data.id,
+            version: VERSION.to_string(),
+            checks,
+        };
+        if let Err(e) = stream.write("self_test", report).await {
+            log::error!("Failed to send self test report: {}", e);
+        }
+    });
+    Ok(())
+}
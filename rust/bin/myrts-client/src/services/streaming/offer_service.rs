@@ -20,13 +20,20 @@ use proto::{
     app::{Data, MsgData, Stream},
     error::Result,
 };
-use types::proto::Offer;
+use types::proto::{Offer, Turn};
 
 #[proto::service("offer")]
 async fn offer(stream: Stream, data: MsgData<Offer>, state: Data<ClientState>) -> Result<()> {
     let data = data.into_inner();
+    // older servers don't issue ice servers, the stream then only works on direct routes.
+    let turn = data.turn.unwrap_or_else(|| {
+        log::warn!("No ice servers issued for the stream");
+        Turn::default()
+    });
     tokio::spawn(async move {
-        state.start_streaming(stream, data.offer).await;
+        state
+            .start_streaming(stream, data.offer, data.outputs, data.emergency, turn)
+            .await;
     });
     Ok(())
 }
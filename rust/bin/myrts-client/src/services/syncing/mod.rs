@@ -27,11 +27,16 @@ macro_rules! send_sync {
         use proto_db::repos::ScheduleRepo;
         use types::proto::SyncReq;
         let repo = $db.repository::<ScheduleRepo>();
-        let schedule_ids = repo
-            .get_sids()
+        let revs = repo
+            .get_revs()
+            .map_err(|e| OtherError::String(e.to_string()))?;
+        let version = repo
+            .get_version()
             .map_err(|e| OtherError::String(e.to_string()))?;
         let sync = SyncReq {
-            local: schedule_ids,
+            local: revs.iter().map(|(sid, _)| *sid).collect(),
+            version: Some(version),
+            digest: Some(utils::crypto::sync_digest(&revs)),
         };
         $stream.write("sync", sync).await?;
     };
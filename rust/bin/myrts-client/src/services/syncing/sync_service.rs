@@ -21,7 +21,28 @@ use proto::{
     error::{OtherError, Result},
 };
 use proto_db::{models::NewSchedule, repos::ScheduleRepo, ProtoDatabase};
-use types::proto::Sync;
+use types::proto::{Schedule, Sync};
+
+/// Convert the schedule into the stored schedule.
+fn new_schedule(schedule: Schedule) -> NewSchedule {
+    NewSchedule {
+        sid: schedule.sid,
+        name: schedule.name,
+        days: serde_json::to_string(&schedule.days).unwrap(),
+        record_url: schedule.record_url,
+        kind: schedule.kind,
+        weeks: serde_json::to_string(&schedule.weeks).unwrap(),
+        dates: serde_json::to_string(&schedule.dates).unwrap(),
+        times: serde_json::to_string(&schedule.times).unwrap(),
+        month: schedule.month,
+        year: schedule.year,
+        volume: schedule.volume.map(|v| v as f64),
+        format: schedule.format,
+        outputs: serde_json::to_string(&schedule.outputs).unwrap(),
+        emergency: schedule.emergency as i32,
+        rev: schedule.rev,
+    }
+}
 
 #[proto::service("sync")]
 async fn sync(
@@ -31,25 +52,27 @@ async fn sync(
 ) -> Result<()> {
     let data = data.into_inner();
     let repo = db.repository::<ScheduleRepo>();
-    repo.remove(data.remove.clone())
-        .map_err(|e| OtherError::String(e.to_string()))?;
-    for schedule in data.add.clone() {
-        repo.create(NewSchedule {
-            sid: schedule.sid,
-            name: schedule.name,
-            days: serde_json::to_string(&schedule.days).unwrap(),
-            record_url: schedule.record_url,
-            kind: schedule.kind,
-            weeks: serde_json::to_string(&schedule.weeks).unwrap(),
-            dates: serde_json::to_string(&schedule.dates).unwrap(),
-            times: serde_json::to_string(&schedule.times).unwrap(),
-            month: schedule.month,
-            year: schedule.year,
-            volume: schedule.volume.map(|v| v as f64),
-        })
+    let changed = data.full || !data.remove.is_empty() || !data.add.is_empty();
+    if let Some(version) = data.version {
+        if data.full {
+            log::info!("Full schedule sync, version {}", version);
+        }
+        repo.apply(
+            data.full,
+            data.add.into_iter().map(new_schedule).collect(),
+            data.remove,
+            version,
+        )
         .map_err(|e| OtherError::String(e.to_string()))?;
+    } else {
+        repo.remove(data.remove)
+            .map_err(|e| OtherError::String(e.to_string()))?;
+        for schedule in data.add {
+            repo.create(new_schedule(schedule))
+                .map_err(|e| OtherError::String(e.to_string()))?;
+        }
     }
-    if !data.remove.is_empty() || !data.add.is_empty() {
+    if changed {
         state.update_schedule();
     }
     Ok(())
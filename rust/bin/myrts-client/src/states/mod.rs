@@ -15,37 +15,403 @@ MyRTS agrees to credit you as the developer of the software in all promotional m
 If MyRTS violates any of these terms, their license to use the software will automatically terminate.
 */
 
-use self::schedule_state::ScheduleState;
-use audio::{audio::AudioPlayer, decoder::Decoder};
+use self::{
+    outbox_state::OutboxState, output_state::OutputState, playlist_state::PlaylistState,
+    playout::Playout, schedule_state::ScheduleState, self_test::Outcome,
+};
+use crate::config::ClientConfig;
+use audio::{audio::AudioPlayer, jitter::JitterBuffer};
 use proto::app::Stream;
-use proto_db::ProtoDatabase;
+use proto_db::{models::NewConfig, repos::ConfigRepo, ProtoDatabase};
+use rodio::Source;
 use rtc::RTCConsumer;
 use std::sync::Arc;
 use tokio::sync::RwLock;
+use types::proto::{
+    DeviceConfig, Playlist, Port, Schedule, SelfTestCheck, Telemetry, Turn, UpdateAnnounce,
+};
 use utils::files::ApiAssets;
 
+pub use self::schedule_state::NowPlaying;
+pub use self::update_state::{UpdateState, VERSION};
+
+mod outbox_state;
+mod output_state;
+mod playlist_state;
+mod playout;
 mod schedule_state;
+mod self_test;
+mod update_state;
+
+/// ConnectionState.
+/// The state of the connection to the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionState {
+    Disconnected,
+    Connected,
+    Authenticated,
+}
 
 /// ClientState holds the state of the client.
 #[derive(Clone)]
 pub struct ClientState {
     player: Arc<AudioPlayer>,
+    outputs: OutputState,
     schedule_state: ScheduleState,
+    playlist_state: PlaylistState,
     streaming: Arc<RwLock<Option<Arc<RTCConsumer>>>>,
+    streaming_outputs: Arc<RwLock<Vec<(String, Arc<AudioPlayer>)>>>,
     streaming_volume: Arc<RwLock<f32>>,
+    connection: Arc<RwLock<Option<Stream>>>,
+    connection_state: Arc<RwLock<ConnectionState>>,
+    config: Arc<RwLock<DeviceConfig>>,
+    db: ProtoDatabase,
+    update_state: UpdateState,
+    client_config: Arc<ClientConfig>,
+    outbox: OutboxState,
+}
+
+/// Load the stored device config.
+fn load_config(db: &ProtoDatabase) -> DeviceConfig {
+    let repo = db.repository::<ConfigRepo>();
+    match repo.get() {
+        Ok(Some(config)) => DeviceConfig {
+            volume: config.volume.map(|v| v as f32),
+            log_level: config.log_level,
+            quiet_start: config.quiet_start,
+            quiet_end: config.quiet_end,
+            telemetry_interval: config.telemetry_interval,
+            allowed_commands: config
+                .allowed_commands
+                .and_then(|c| serde_json::from_str(&c).ok()),
+            audio_device: config.audio_device,
+            volume_profile: config
+                .volume_profile
+                .and_then(|p| serde_json::from_str(&p).ok()),
+        },
+        Ok(None) => DeviceConfig::default(),
+        Err(e) => {
+            log::error!("Failed to load config: {}", e);
+            DeviceConfig::default()
+        }
+    }
 }
 
 impl ClientState {
     /// New client state.
-    pub async fn new(assets: ApiAssets, db: ProtoDatabase) -> Self {
+    pub async fn new(
+        assets: ApiAssets,
+        db: ProtoDatabase,
+        update_state: UpdateState,
+        client_config: ClientConfig,
+    ) -> Self {
         let player = Arc::new(AudioPlayer::new());
-        let schedule_state = ScheduleState::new(db, assets, player.clone());
+        let config = load_config(&db);
+        let config = Arc::new(RwLock::new(config));
+        let outputs = OutputState::new(player.clone());
+        let outbox = OutboxState::new(
+            db.clone(),
+            client_config.outbox_max_entries,
+            client_config.outbox_max_mb,
+        );
+        let schedule_state = ScheduleState::new(
+            db.clone(),
+            assets.clone(),
+            outputs.clone(),
+            config.clone(),
+            outbox.clone(),
+        );
         schedule_state.run().await;
-        Self {
+        let playlist_state = PlaylistState::new(
+            db.clone(),
+            assets,
+            player.clone(),
+            schedule_state.clone(),
+            config.clone(),
+        );
+        playlist_state.run().await;
+        let state = Self {
             player,
+            outputs,
             schedule_state,
+            playlist_state,
             streaming: Arc::new(RwLock::new(None)),
+            streaming_outputs: Arc::new(RwLock::new(vec![])),
             streaming_volume: Arc::new(RwLock::new(1.0)),
+            connection: Arc::new(RwLock::new(None)),
+            connection_state: Arc::new(RwLock::new(ConnectionState::Disconnected)),
+            config,
+            db,
+            update_state,
+            client_config: Arc::new(client_config),
+            outbox,
+        };
+        let config = state.config().await;
+        state.apply_config(&config, &DeviceConfig::default());
+        state.run_telemetry();
+        state.run_outbox();
+        state
+    }
+
+    /// Queue the telemetry on every interval, regardless of the connection.
+    fn run_telemetry(&self) {
+        let state = self.clone();
+        tokio::spawn(async move {
+            loop {
+                let telemetry = state.telemetry().await;
+                state.outbox.enqueue("telemetry", &telemetry);
+                // the interval is read every time so config changes apply without reconnecting.
+                tokio::time::sleep(state.telemetry_interval().await).await;
+            }
+        });
+    }
+
+    /// Flush the outbox whenever the server connection is authenticated.
+    fn run_outbox(&self) {
+        let state = self.clone();
+        tokio::spawn(async move {
+            loop {
+                if let Some(stream) = state.connection().await {
+                    state.outbox.flush(&stream).await;
+                }
+                state.outbox.wait().await;
+            }
+        });
+    }
+
+    /// Get the outbox of the events sent to the server.
+    pub fn outbox(&self) -> &OutboxState {
+        &self.outbox
+    }
+
+    /// Get the client config loaded at startup.
+    pub fn client_config(&self) -> &ClientConfig {
+        &self.client_config
+    }
+
+    /// Get the device config.
+    pub async fn config(&self) -> DeviceConfig {
+        self.config.read().await.clone()
+    }
+
+    /// Apply the changed parts of the device config.
+    fn apply_config(&self, config: &DeviceConfig, previous: &DeviceConfig) {
+        let level = config
+            .log_level
+            .clone()
+            .unwrap_or_else(|| self.client_config.log_level.clone());
+        log::set_max_level(crate::log_level(&level));
+        if config.audio_device != previous.audio_device {
+            log::info!("Switching audio output to {:?}", config.audio_device);
+            if let Err(e) = self.player.set_device(config.audio_device.as_deref()) {
+                log::error!("Failed to switch audio output: {}", e);
+            }
+        }
+    }
+
+    /// Store and apply the device config pushed by the server.
+    pub async fn set_config(&self, config: DeviceConfig) {
+        let previous = self.config().await;
+        if previous == config {
+            return;
+        }
+        log::info!("Applying device config");
+        let repo = self.db.repository::<ConfigRepo>();
+        let new = NewConfig {
+            volume: config.volume.map(|v| v as f64),
+            log_level: config.log_level.clone(),
+            quiet_start: config.quiet_start.clone(),
+            quiet_end: config.quiet_end.clone(),
+            telemetry_interval: config.telemetry_interval,
+            allowed_commands: config
+                .allowed_commands
+                .as_ref()
+                .and_then(|c| serde_json::to_string(c).ok()),
+            audio_device: config.audio_device.clone(),
+            volume_profile: config
+                .volume_profile
+                .as_ref()
+                .and_then(|p| serde_json::to_string(p).ok()),
+        };
+        if let Err(e) = repo.set(new) {
+            log::error!("Failed to store config: {}", e);
+        }
+        self.apply_config(&config, &previous);
+        *self.config.write().await = config;
+    }
+
+    /// Get the interval of the device info report.
+    pub async fn telemetry_interval(&self) -> std::time::Duration {
+        let interval = self
+            .config
+            .read()
+            .await
+            .telemetry_interval
+            .unwrap_or(60 * 5);
+        std::time::Duration::from_secs(interval.max(10) as u64)
+    }
+
+    /// Get the device telemetry along with the audio output status.
+    pub async fn telemetry(&self) -> Telemetry {
+        let mut telemetry = crate::get_telemetry();
+        telemetry.audio_output = self.player.device();
+        telemetry.audio_ok = self.player.is_available();
+        telemetry.audio_playing = self.outputs.is_playing().await || self.is_streaming().await;
+        telemetry
+    }
+
+    /// Check if the device config allows the program to run as a raw command.
+    /// Raw commands are denied unless the device config lists the program.
+    pub async fn command_allowed(&self, program: &str) -> bool {
+        match &self.config.read().await.allowed_commands {
+            Some(allowed) => allowed.iter().any(|c| c == program),
+            None => false,
+        }
+    }
+
+    /// Set the server connection.
+    pub async fn connected(&self, stream: Stream) {
+        *self.connection.write().await = Some(stream);
+        *self.connection_state.write().await = ConnectionState::Connected;
+    }
+
+    /// Mark the server connection as authenticated.
+    pub async fn authenticated(&self) {
+        *self.connection_state.write().await = ConnectionState::Authenticated;
+        // the batch sent on the previous connection is never acknowledged.
+        self.outbox.reset();
+    }
+
+    /// Remove the server connection.
+    pub async fn disconnected(&self) {
+        *self.connection.write().await = None;
+        *self.connection_state.write().await = ConnectionState::Disconnected;
+    }
+
+    /// Get the server connection state.
+    pub async fn connection_state(&self) -> ConnectionState {
+        *self.connection_state.read().await
+    }
+
+    /// Get the server connection if authenticated.
+    pub async fn connection(&self) -> Option<Stream> {
+        if self.connection_state().await != ConnectionState::Authenticated {
+            return None;
+        }
+        self.connection.read().await.clone()
+    }
+
+    /// Handle a client update announcement.
+    pub async fn update(&self, stream: Stream, announce: UpdateAnnounce) {
+        log::info!("Update {} announced", announce.version);
+        self.update_state.update(stream, announce).await;
+    }
+
+    /// Report the result of the pending client update.
+    pub async fn confirm_update(&self, stream: &Stream) {
+        self.update_state.confirm(stream).await;
+    }
+
+    /// Get stored schedules.
+    pub fn schedules(&self) -> Vec<Schedule> {
+        self.schedule_state.schedules()
+    }
+
+    /// Get the background playlist.
+    pub async fn playlist(&self) -> Playlist {
+        self.playlist_state.playlist().await
+    }
+
+    /// Store and play the background playlist pushed by the server.
+    pub async fn set_playlist(&self, playlist: Playlist) {
+        self.playlist_state.set(playlist).await;
+    }
+
+    /// Get upcoming schedules within the next `hours`.
+    pub fn upcoming(&self, hours: i64) -> Vec<(chrono::NaiveDateTime, Schedule)> {
+        self.schedule_state.upcoming(hours)
+    }
+
+    /// Get the output devices as ports.
+    pub fn ports(&self) -> Vec<Port> {
+        OutputState::ports()
+    }
+
+    /// Get the currently playing schedules.
+    pub async fn now_playing(&self) -> Vec<NowPlaying> {
+        self.schedule_state.now_playing().await
+    }
+
+    /// Check if a live stream is playing.
+    pub async fn is_streaming(&self) -> bool {
+        self.streaming.read().await.is_some()
+    }
+
+    /// Play a test tone.
+    pub async fn test_tone(&self, seconds: u64) -> Result<(), String> {
+        if self.is_streaming().await || self.player.is_playing() {
+            return Err("player is busy".to_string());
+        }
+        if self.schedule_state.is_quiet().await {
+            return Err("quiet hours".to_string());
+        }
+        log::info!("Playing test tone for {} seconds", seconds);
+        let tone = rodio::source::SineWave::new(440.0)
+            .take_duration(std::time::Duration::from_secs(seconds))
+            .amplify(0.5);
+        // the tone plays at the volume the schedules would play at.
+        self.player
+            .set_volume(self.schedule_state.default_volume().await);
+        self.player.append(tone);
+        self.player.play();
+        Ok(())
+    }
+
+    /// Run the self test of the audio output, records, disk and database.
+    /// The tone and the device reopen are skipped while something is playing.
+    pub async fn self_test(&self, tone: bool) -> Vec<SelfTestCheck> {
+        const TONE_SECONDS: u64 = 2;
+        log::info!("Running self test");
+        let busy = self.is_streaming().await
+            || self.outputs.is_playing().await
+            || self.player.has_background();
+        let mut checks = vec![self_test::audio_device(&self.player, busy)];
+        let played = if !tone {
+            Outcome::Skipped("not requested".to_string())
+        } else if busy {
+            Outcome::Skipped("player is busy".to_string())
+        } else {
+            self.test_tone(TONE_SECONDS)
+                .await
+                .map(|_| String::new())
+                .into()
+        };
+        checks.push(self_test::tone(&self.player, played, TONE_SECONDS).await);
+        let dir = self.schedule_state.assets().audio_dir().to_path_buf();
+        let db = self.db.clone();
+        let res = tokio::task::spawn_blocking(move || {
+            vec![
+                self_test::decoders(&dir),
+                self_test::disk(&dir),
+                self_test::database(&db),
+            ]
+        })
+        .await;
+        match res {
+            Ok(res) => checks.extend(res),
+            Err(e) => log::error!("Self test failed: {}", e),
+        }
+        checks
+    }
+
+    /// Set the volume of the current playback.
+    pub async fn set_volume(&self, volume: f32) {
+        if self.is_streaming().await {
+            self.set_streaming_volume(volume).await;
+        } else {
+            log::debug!("set player volume: {}", volume);
+            self.player.set_volume(volume);
         }
     }
 
@@ -55,16 +421,16 @@ impl ClientState {
         self.schedule_state.update();
     }
 
-    /// Block schedule.
-    pub async fn block_schedule(&self) {
+    /// Block schedule on the outputs.
+    pub async fn block_schedule(&self, outputs: &[(String, Arc<AudioPlayer>)]) {
         log::debug!("block schedule");
-        self.schedule_state.block().await;
+        self.schedule_state.block(outputs).await;
     }
 
-    /// Unblock schedule.
-    pub async fn unblock_schedule(&self) {
+    /// Unblock schedule on the outputs.
+    pub async fn unblock_schedule(&self, outputs: &[(String, Arc<AudioPlayer>)]) {
         log::debug!("unblock schedule");
-        self.schedule_state.unblock().await;
+        self.schedule_state.unblock(outputs).await;
     }
 
     /// Set streaming volume.
@@ -73,50 +439,67 @@ impl ClientState {
         *self.streaming_volume.write().await = volume;
     }
 
-    /// Start streaming.
-    pub async fn start_streaming(&self, stream: Stream, offer: String) {
-        log::debug!("start streaming");
-        {
-            let may_stream = self.streaming.write().await.take();
-            if let Some(consumer) = may_stream {
-                consumer.disconnect().await;
-            }
-        }
+    /// Start streaming on the output devices, empty for the default output.
+    /// The volume follows the profile unless the stream is emergency,
+    /// `turn` holds the ice servers issued by the server for this stream.
+    pub async fn start_streaming(
+        &self,
+        stream: Stream,
+        offer: String,
+        outputs: Vec<String>,
+        emergency: bool,
+        turn: Turn,
+    ) {
+        log::debug!("start streaming on {:?}", outputs);
+        self.stop_streaming().await;
         self.set_streaming_volume(1.0).await;
-        let consumer = if let Ok(consumer) = RTCConsumer::new(stream).await {
+        let consumer = if let Ok(consumer) = RTCConsumer::new(stream, &turn).await {
             Arc::new(consumer)
         } else {
             return;
         };
-        self.block_schedule().await;
+        let players = self.outputs.resolve(&outputs).await;
+        if players.is_empty() {
+            log::error!("outputs {:?} are not available", outputs);
+            consumer.disconnect().await;
+            return;
+        }
+        self.block_schedule(&players).await;
+        *self.streaming_outputs.write().await = players.clone();
         let consumer_clone = consumer.clone();
         let volume = self.streaming_volume.clone();
-        let player = self.player.clone();
+        let config = self.config.clone();
+        let connection = self.connection.clone();
+        let players = players
+            .into_iter()
+            .map(|(_, player)| player)
+            .collect::<Vec<Arc<AudioPlayer>>>();
         consumer.on_track(Box::new(move |track| {
-            let player = player.clone();
+            let buffer = Arc::new(std::sync::Mutex::new(JitterBuffer::default()));
+            let playout = Playout {
+                buffer: buffer.clone(),
+                players: players.clone(),
+                volume: volume.clone(),
+                config: config.clone(),
+                connection: connection.clone(),
+                consumer: consumer_clone.clone(),
+                emergency,
+            };
             let consumer_clone = consumer_clone.clone();
-            let volume = volume.clone();
             Box::pin(async move {
-                let mut decoder = Decoder::new().unwrap();
-                let mut current_colume = 1.0;
+                tokio::spawn(playout.run());
                 loop {
-                    {
-                        let lock = volume.read().await;
-                        if *lock != current_colume {
-                            current_colume = *lock;
-                            player.set_volume(current_colume);
-                        }
-                    }
                     if let Ok((p, _)) = track.read_rtp().await {
-                        log::debug!(
-                            "Playing {} bytes with {} volume",
+                        log::trace!(
+                            "Received {} bytes, seq {}",
                             p.payload.len(),
-                            current_colume
+                            p.header.sequence_number
+                        );
+                        buffer.lock().unwrap().push(
+                            p.header.sequence_number,
+                            p.header.timestamp,
+                            p.payload.to_vec(),
                         );
-                        for frame in decoder.decode(&p.payload) {
-                            player.append(frame);
-                        }
-                        player.play();
                     } else {
                         if !consumer_clone.connected() {
                             log::debug!("consumer disconnected");
@@ -128,17 +511,32 @@ impl ClientState {
         }));
         if let Err(e) = consumer.add_offer(offer).await {
             log::error!("failed to add offer: {:?}", e);
-            self.unblock_schedule().await;
+            self.release_outputs().await;
             return;
         }
         if let Err(e) = consumer.answer().await {
             log::error!("failed to answer: {:?}", e);
-            self.unblock_schedule().await;
+            self.release_outputs().await;
             return;
         }
         *self.streaming.write().await = Some(consumer);
     }
 
+    /// Unblock the schedule on the outputs of the stream.
+    async fn release_outputs(&self) {
+        let players = std::mem::take(&mut *self.streaming_outputs.write().await);
+        self.unblock_schedule(&players).await;
+    }
+
+    /// Disconnect the current stream.
+    async fn stop_streaming(&self) {
+        let may_stream = self.streaming.write().await.take();
+        if let Some(consumer) = may_stream {
+            consumer.disconnect().await;
+        }
+        self.release_outputs().await;
+    }
+
     /// Add ices.
     pub async fn add_ices(&self, ices: String) {
         log::debug!("add ices");
@@ -153,10 +551,6 @@ impl ClientState {
     /// Close streaming.
     pub async fn close_streaming(&self) {
         log::debug!("close streaming");
-        self.unblock_schedule().await;
-        let streaming = self.streaming.write().await.take();
-        if let Some(consumer) = streaming {
-            consumer.disconnect().await;
-        }
+        self.stop_streaming().await;
     }
 }
@@ -0,0 +1,141 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use proto::app::Stream;
+use proto_db::{models::NewOutboxEntry, repos::OutboxRepo, ProtoDatabase};
+use serde::Serialize;
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::sync::Notify;
+use types::proto::{OutboxBatch, OutboxEntry};
+
+/// Maximum number of entries sent in a single batch.
+const BATCH_SIZE: i64 = 50;
+
+/// Time to wait for the acknowledgement before the batch is sent again.
+const ACK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Outbox state holds the events waiting to be delivered to the server.
+/// Events are stored before sending, so they survive disconnects and restarts.
+#[derive(Clone)]
+pub struct OutboxState {
+    db: ProtoDatabase,
+    notify: Arc<Notify>,
+    /// When the unacknowledged batch was sent.
+    in_flight: Arc<Mutex<Option<Instant>>>,
+    max_entries: i64,
+    max_bytes: i64,
+}
+
+impl OutboxState {
+    /// New outbox state.
+    pub fn new(db: ProtoDatabase, max_entries: i64, max_mb: i64) -> Self {
+        Self {
+            db,
+            notify: Arc::new(Notify::new()),
+            in_flight: Arc::new(Mutex::new(None)),
+            max_entries,
+            max_bytes: max_mb * 1024 * 1024,
+        }
+    }
+
+    /// Queue an event, it is sent as soon as the server is reachable.
+    pub fn enqueue<T: Serialize>(&self, kind: &str, payload: &T) {
+        let payload = match serde_json::to_string(payload) {
+            Ok(payload) => payload,
+            Err(e) => {
+                log::error!("Failed to encode {} event: {}", kind, e);
+                return;
+            }
+        };
+        let new = NewOutboxEntry {
+            kind: kind.to_owned(),
+            size: payload.len() as i32,
+            payload,
+            created_at: chrono::Utc::now().naive_utc(),
+        };
+        let repo = self.db.repository::<OutboxRepo>();
+        match repo.enqueue(new, self.max_entries, self.max_bytes) {
+            Ok(0) => {}
+            Ok(evicted) => log::warn!("Outbox is full, dropped {} oldest events", evicted),
+            Err(e) => log::error!("Failed to queue {} event: {}", kind, e),
+        }
+        self.notify.notify_one();
+    }
+
+    /// Remove the acknowledged entries and send the next batch.
+    pub fn ack(&self, ids: Vec<i32>) {
+        let repo = self.db.repository::<OutboxRepo>();
+        if let Err(e) = repo.ack(ids) {
+            log::error!("Failed to remove acknowledged events: {}", e);
+        }
+        *self.in_flight.lock().unwrap() = None;
+        self.notify.notify_one();
+    }
+
+    /// Forget the unacknowledged batch, it is sent again on the next flush.
+    pub fn reset(&self) {
+        *self.in_flight.lock().unwrap() = None;
+        self.notify.notify_one();
+    }
+
+    /// Wait until there is something to flush or the retry interval passed.
+    pub async fn wait(&self) {
+        let _ = tokio::time::timeout(ACK_TIMEOUT, self.notify.notified()).await;
+    }
+
+    /// Send the oldest entries, unless a batch is still waiting for its acknowledgement.
+    /// Only one batch is in flight so the server receives the events in order.
+    pub async fn flush(&self, stream: &Stream) {
+        let in_flight = *self.in_flight.lock().unwrap();
+        if let Some(sent_at) = in_flight {
+            if sent_at.elapsed() < ACK_TIMEOUT {
+                return;
+            }
+        }
+        let repo = self.db.repository::<OutboxRepo>();
+        let entries = match repo.get_pending(BATCH_SIZE) {
+            Ok(entries) => entries,
+            Err(e) => {
+                log::error!("Failed to read the outbox: {}", e);
+                return;
+            }
+        };
+        if entries.is_empty() {
+            return;
+        }
+        let batch = OutboxBatch {
+            entries: entries
+                .into_iter()
+                .map(|e| OutboxEntry {
+                    id: e.id,
+                    kind: e.kind,
+                    payload: e.payload,
+                    created_at: e.created_at,
+                })
+                .collect(),
+        };
+        log::debug!("Sending {} queued events", batch.entries.len());
+        *self.in_flight.lock().unwrap() = Some(Instant::now());
+        if let Err(e) = stream.write("outbox", batch).await {
+            log::warn!("Failed to send queued events: {}", e);
+            *self.in_flight.lock().unwrap() = None;
+        }
+    }
+}
@@ -0,0 +1,107 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use audio::audio::AudioPlayer;
+use std::{collections::HashMap, sync::Arc};
+use tokio::sync::RwLock;
+use types::proto::Port;
+
+/// The key of the default output.
+pub const DEFAULT_OUTPUT: &str = "default";
+
+/// Get the speaker number of the output device, the 31 bit FNV-1a hash of its name.
+/// Unlike the enumeration index it doesn't change when devices come and go.
+fn speaker(device: &str) -> i32 {
+    let hash = device.bytes().fold(0x811c9dc5u32, |hash, b| {
+        (hash ^ b as u32).wrapping_mul(0x01000193)
+    });
+    (hash & 0x7fff_ffff) as i32
+}
+
+/// Output state holds one player per output device.
+#[derive(Clone)]
+pub struct OutputState {
+    default: Arc<AudioPlayer>,
+    ports: Arc<RwLock<HashMap<String, Arc<AudioPlayer>>>>,
+}
+
+impl OutputState {
+    /// New output state.
+    pub fn new(default: Arc<AudioPlayer>) -> Self {
+        Self {
+            default,
+            ports: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Get the player of the default output.
+    pub fn default_player(&self) -> Arc<AudioPlayer> {
+        self.default.clone()
+    }
+
+    /// Get the output devices as ports.
+    pub fn ports() -> Vec<Port> {
+        AudioPlayer::devices()
+            .into_iter()
+            .map(|device| Port {
+                speaker: speaker(&device),
+                device,
+            })
+            .collect()
+    }
+
+    /// Resolve the output devices into players, keyed by the output.
+    /// An empty list resolves into the default output, devices that can't be opened are skipped.
+    pub async fn resolve(&self, outputs: &[String]) -> Vec<(String, Arc<AudioPlayer>)> {
+        if outputs.is_empty() {
+            return vec![(DEFAULT_OUTPUT.to_owned(), self.default.clone())];
+        }
+        // the default player already holds its device open.
+        let default_device = self.default.device().or_else(AudioPlayer::default_device);
+        let mut ports = self.ports.write().await;
+        let mut res: Vec<(String, Arc<AudioPlayer>)> = vec![];
+        for name in outputs {
+            let (key, player) = if default_device.as_ref() == Some(name) {
+                (DEFAULT_OUTPUT.to_owned(), self.default.clone())
+            } else if let Some(player) = ports.get(name) {
+                (name.to_owned(), player.clone())
+            } else {
+                match AudioPlayer::open(Some(name)) {
+                    Ok(player) => {
+                        log::info!("Opened output {}", name);
+                        let player = Arc::new(player);
+                        ports.insert(name.to_owned(), player.clone());
+                        (name.to_owned(), player)
+                    }
+                    Err(e) => {
+                        log::error!("Failed to open output {}: {}", name, e);
+                        continue;
+                    }
+                }
+            };
+            if !res.iter().any(|(k, _)| *k == key) {
+                res.push((key, player));
+            }
+        }
+        res
+    }
+
+    /// Check if any output is playing.
+    pub async fn is_playing(&self) -> bool {
+        self.default.is_playing() || self.ports.read().await.values().any(|p| p.is_playing())
+    }
+}
@@ -0,0 +1,303 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use super::{
+    output_state::DEFAULT_OUTPUT,
+    schedule_state::{append_record, in_range, volume_multiplier, ScheduleState},
+};
+use audio::audio::AudioPlayer;
+use chrono::NaiveTime;
+use proto_db::{models::NewPlaylist, repos::PlaylistRepo, ProtoDatabase};
+use rand::seq::SliceRandom;
+use std::sync::Arc;
+use tokio::{sync::RwLock, task::JoinHandle};
+use types::proto::{DeviceConfig, Playlist, PlaylistTrack};
+use utils::{files::ApiAssets, media::AudioFormat, time::local_now};
+
+/// Interval of the playlist control loop in milliseconds.
+const TICK_MS: u64 = 200;
+/// Maximum volume change per tick when fading.
+const FADE_STEP: f32 = 0.05;
+
+/// The play order of the playlist.
+#[derive(Default)]
+struct Order {
+    tracks: Vec<usize>,
+    /// Index of the next track in `tracks`.
+    position: usize,
+}
+
+/// Playlist state holds the background playlist of the default output.
+/// The playlist plays on the background of the default player, it is ducked
+/// while a schedule is playing and paused while a live stream is playing.
+#[derive(Clone)]
+pub struct PlaylistState {
+    player: Arc<AudioPlayer>,
+    schedule_state: ScheduleState,
+    assets: ApiAssets,
+    db: ProtoDatabase,
+    config: Arc<RwLock<DeviceConfig>>,
+    playlist: Arc<RwLock<Playlist>>,
+    order: Arc<RwLock<Order>>,
+    runner: Arc<RwLock<Option<JoinHandle<()>>>>,
+}
+
+/// Load the stored playlist along with the track to resume from.
+fn load_playlist(db: &ProtoDatabase) -> (Playlist, usize) {
+    let repo = db.repository::<PlaylistRepo>();
+    match repo.get() {
+        Ok(Some(playlist)) => (
+            Playlist {
+                tracks: serde_json::from_str(&playlist.tracks).unwrap_or_default(),
+                shuffle: playlist.shuffle != 0,
+                repeat: playlist.repeat != 0,
+                start: playlist.start_time,
+                end: playlist.end_time,
+                volume: playlist.volume.map(|v| v as f32),
+                duck: playlist.duck as f32,
+            },
+            playlist.position.max(0) as usize,
+        ),
+        Ok(None) => (Playlist::default(), 0),
+        Err(e) => {
+            log::error!("Failed to load playlist: {}", e);
+            (Playlist::default(), 0)
+        }
+    }
+}
+
+/// Create the play order of the playlist.
+fn new_order(playlist: &Playlist) -> Vec<usize> {
+    let mut tracks = (0..playlist.tracks.len()).collect::<Vec<usize>>();
+    if playlist.shuffle {
+        tracks.shuffle(&mut rand::thread_rng());
+    }
+    tracks
+}
+
+/// Check if the time is within the time window of the playlist.
+fn in_window(playlist: &Playlist, at: NaiveTime) -> bool {
+    let parse = |t: &Option<String>| {
+        t.as_ref()
+            .and_then(|t| NaiveTime::parse_from_str(t, "%H:%M").ok())
+    };
+    match (parse(&playlist.start), parse(&playlist.end)) {
+        (Some(start), Some(end)) => in_range(start, end, at),
+        _ => true,
+    }
+}
+
+impl PlaylistState {
+    /// New playlist state.
+    pub fn new(
+        db: ProtoDatabase,
+        assets: ApiAssets,
+        player: Arc<AudioPlayer>,
+        schedule_state: ScheduleState,
+        config: Arc<RwLock<DeviceConfig>>,
+    ) -> Self {
+        let (playlist, position) = load_playlist(&db);
+        let order = Order {
+            tracks: new_order(&playlist),
+            position,
+        };
+        Self {
+            player,
+            schedule_state,
+            assets,
+            db,
+            config,
+            playlist: Arc::new(RwLock::new(playlist)),
+            order: Arc::new(RwLock::new(order)),
+            runner: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// Get the playlist.
+    pub async fn playlist(&self) -> Playlist {
+        self.playlist.read().await.clone()
+    }
+
+    /// Store the playlist pushed by the server and start it from the beginning.
+    pub async fn set(&self, playlist: Playlist) {
+        if *self.playlist.read().await == playlist {
+            return;
+        }
+        log::info!("Applying playlist of {} tracks", playlist.tracks.len());
+        let repo = self.db.repository::<PlaylistRepo>();
+        let new = if playlist.tracks.is_empty() {
+            None
+        } else {
+            Some(NewPlaylist {
+                tracks: serde_json::to_string(&playlist.tracks).unwrap_or_default(),
+                shuffle: playlist.shuffle as i32,
+                repeat: playlist.repeat as i32,
+                start_time: playlist.start.clone(),
+                end_time: playlist.end.clone(),
+                volume: playlist.volume.map(|v| v as f64),
+                duck: playlist.duck as f64,
+            })
+        };
+        if let Err(e) = repo.set(new) {
+            log::error!("Failed to store playlist: {}", e);
+        }
+        self.player.clear_background();
+        *self.order.write().await = Order {
+            tracks: new_order(&playlist),
+            position: 0,
+        };
+        *self.playlist.write().await = playlist;
+        self.download().await;
+    }
+
+    /// Download the tracks of the playlist in the background.
+    async fn download(&self) {
+        let tracks = self.playlist.read().await.tracks.clone();
+        let schedule_state = self.schedule_state.clone();
+        tokio::spawn(async move {
+            for track in tracks.iter() {
+                schedule_state
+                    .download(&track.record_url, &track.format)
+                    .await;
+            }
+        });
+    }
+
+    /// Get the next track to play, `None` when the playlist is finished.
+    async fn next_track(&self, playlist: &Playlist) -> Option<PlaylistTrack> {
+        let mut order = self.order.write().await;
+        if order.tracks.len() != playlist.tracks.len() {
+            order.tracks = new_order(playlist);
+            order.position = 0;
+        }
+        if order.position >= order.tracks.len() {
+            if !playlist.repeat {
+                return None;
+            }
+            order.tracks = new_order(playlist);
+            order.position = 0;
+        }
+        let track = playlist.tracks.get(order.tracks[order.position]).cloned();
+        order.position += 1;
+        let repo = self.db.repository::<PlaylistRepo>();
+        if let Err(e) = repo.set_position(order.position as i32) {
+            log::error!("Failed to store playlist position: {}", e);
+        }
+        track
+    }
+
+    /// Append the next playable track to the background.
+    /// Returns false when there is nothing to play.
+    async fn append_next(&self, playlist: &Playlist) -> bool {
+        for _ in 0..playlist.tracks.len() {
+            let track = match self.next_track(playlist).await {
+                Some(track) => track,
+                None => return false,
+            };
+            let file_name = track.record_url.split('/').last().unwrap_or("");
+            let format = match AudioFormat::from_name(&track.format) {
+                Some(format) => format,
+                None => continue,
+            };
+            if !self.assets.audio_exists(file_name) {
+                log::warn!("Skipping {} because it doesn't exist", file_name);
+                continue;
+            }
+            let path = self.assets.audio_path(file_name);
+            match append_record(&self.player, &path, format, true) {
+                Ok(_) => {
+                    log::info!("Playlist: {}", track.record_url);
+                    return true;
+                }
+                Err(e) => log::error!("Error: {}", e),
+            }
+        }
+        false
+    }
+
+    /// Get the volume the playlist should be playing at, `0.0` pauses it.
+    async fn target_volume(&self, playlist: &Playlist) -> f32 {
+        let now = local_now().time();
+        if !in_window(playlist, now) || self.schedule_state.is_quiet().await {
+            return 0.0;
+        }
+        // live streams pause the playlist.
+        if self.schedule_state.is_blocked(DEFAULT_OUTPUT).await {
+            return 0.0;
+        }
+        let config = self.config.read().await;
+        let mut volume =
+            playlist.volume.or(config.volume).unwrap_or(1.0) * volume_multiplier(&config, now);
+        // schedules duck the playlist.
+        if self.player.is_playing() {
+            volume *= playlist.duck.clamp(0.0, 1.0);
+        }
+        volume
+    }
+
+    /// Run the playlist.
+    pub async fn run(&self) {
+        log::info!("Starting playlist");
+        let state = self.clone();
+        let mut lock = self.runner.write().await;
+        if let Some(runner) = lock.take() {
+            runner.abort();
+        }
+        self.download().await;
+        *lock = Some(tokio::spawn(async move {
+            let mut tick = tokio::time::interval(tokio::time::Duration::from_millis(TICK_MS));
+            let mut current = 0.0f32;
+            let mut paused = true;
+            state.player.set_background_volume(current);
+            loop {
+                tick.tick().await;
+                let playlist = state.playlist().await;
+                if playlist.tracks.is_empty() {
+                    if state.player.has_background() {
+                        state.player.clear_background();
+                    }
+                    continue;
+                }
+                let target = state.target_volume(&playlist).await;
+                if target > 0.0 {
+                    if !state.player.has_background() && !state.append_next(&playlist).await {
+                        continue;
+                    }
+                    if paused {
+                        log::debug!("Resuming playlist");
+                        state.player.play_background();
+                        paused = false;
+                    }
+                }
+                // fade toward the target volume.
+                if current != target {
+                    current = if current < target {
+                        (current + FADE_STEP).min(target)
+                    } else {
+                        (current - FADE_STEP).max(target)
+                    };
+                    state.player.set_background_volume(current);
+                }
+                if target <= 0.0 && current <= 0.0 && !paused {
+                    log::debug!("Pausing playlist");
+                    state.player.pause_background();
+                    paused = true;
+                }
+            }
+        }));
+    }
+}
@@ -0,0 +1,142 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use super::schedule_state::volume_multiplier;
+use audio::{
+    audio::AudioPlayer,
+    decoder::RtpDecoder,
+    jitter::{Frame, JitterBuffer},
+};
+use proto::app::Stream;
+use rtc::RTCConsumer;
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::sync::RwLock;
+use types::proto::{DeviceConfig, StreamStats};
+
+/// Frames kept queued on the players.
+const QUEUED_FRAMES: usize = 2;
+/// Interval of the play out loop.
+const TICK: Duration = Duration::from_millis(5);
+/// Interval of the statistics report.
+const STATS_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Playout.
+/// Plays the live stream out of the jitter buffer, paced by the players.
+pub(super) struct Playout {
+    pub buffer: Arc<Mutex<JitterBuffer>>,
+    pub players: Vec<Arc<AudioPlayer>>,
+    pub volume: Arc<RwLock<f32>>,
+    pub config: Arc<RwLock<DeviceConfig>>,
+    pub connection: Arc<RwLock<Option<Stream>>>,
+    pub consumer: Arc<RTCConsumer>,
+    pub emergency: bool,
+}
+
+impl Playout {
+    /// Get the stream statistics.
+    fn stats(&self, decoder: &RtpDecoder) -> StreamStats {
+        let stats = self.buffer.lock().unwrap().stats();
+        StreamStats {
+            avs: String::new(),
+            received: stats.received,
+            lost: stats.lost,
+            recovered: decoder.recovered(),
+            concealed: decoder.concealed(),
+            late: stats.late,
+            duplicate: stats.duplicate,
+            dropped: stats.dropped,
+            underruns: stats.underruns,
+            jitter_ms: stats.jitter_ms,
+            delay_ms: stats.delay_ms,
+            target_ms: stats.target_ms,
+        }
+    }
+
+    /// Report the stream statistics to the server.
+    async fn report(&self, decoder: &RtpDecoder) {
+        let stats = self.stats(decoder);
+        log::debug!("stream stats: {:?}", stats);
+        let stream = self.connection.read().await.clone();
+        if let Some(stream) = stream {
+            if let Err(e) = stream.write("stream:stats", stats).await {
+                log::error!("failed to report stream stats: {:?}", e);
+            }
+        }
+    }
+
+    /// Run until the consumer is disconnected.
+    pub async fn run(self) {
+        let mut decoder = match RtpDecoder::new() {
+            Ok(decoder) => decoder,
+            Err(e) => {
+                log::error!("failed to create decoder: {}", e);
+                return;
+            }
+        };
+        let mut tick = tokio::time::interval(TICK);
+        let mut current_volume = 1.0;
+        let mut multiplier = 1.0;
+        let mut checked_at: Option<Instant> = None;
+        let mut reported_at = Instant::now();
+        loop {
+            tick.tick().await;
+            if !self.consumer.connected() {
+                log::debug!("consumer disconnected");
+                break;
+            }
+            if !self.emergency && checked_at.map_or(true, |t| t.elapsed() >= Duration::from_secs(1))
+            {
+                let config = self.config.read().await;
+                multiplier = volume_multiplier(&config, utils::time::local_now().time());
+                checked_at = Some(Instant::now());
+            }
+            {
+                let lock = self.volume.read().await;
+                if *lock * multiplier != current_volume {
+                    current_volume = *lock * multiplier;
+                    for player in self.players.iter() {
+                        player.set_volume(current_volume);
+                    }
+                }
+            }
+            while self.players.iter().all(|p| p.queued() < QUEUED_FRAMES) {
+                let frame = match self.buffer.lock().unwrap().pop() {
+                    Some(frame) => frame,
+                    None => break,
+                };
+                let sample = match frame {
+                    Frame::Packet(payload) => decoder.decode(&payload),
+                    Frame::Lost { fec, samples } => decoder.decode_lost(fec.as_deref(), samples),
+                };
+                if let Some(sample) = sample {
+                    for player in self.players.iter() {
+                        player.append(sample.clone());
+                        player.play();
+                    }
+                }
+            }
+            if reported_at.elapsed() >= STATS_INTERVAL {
+                reported_at = Instant::now();
+                self.report(&decoder).await;
+            }
+        }
+        self.report(&decoder).await;
+    }
+}
@@ -15,39 +15,222 @@ MyRTS agrees to credit you as the developer of the software in all promotional m
 If MyRTS violates any of these terms, their license to use the software will automatically terminate.
 */
 
-use audio::audio::AudioPlayer;
-use proto_db::{repos::ScheduleRepo, ProtoDatabase};
+use super::{outbox_state::OutboxState, output_state::OutputState};
+use audio::{audio::AudioPlayer, decoder::OpusFile};
+use chrono::{Duration, Local, NaiveDateTime, NaiveTime, TimeZone};
+use proto_db::{
+    models::{NewPlayLog, PlayLog},
+    repos::{PlayLogRepo, ScheduleRepo},
+    ProtoDatabase,
+};
 use rodio::Decoder;
-use std::{fs::File, io::BufReader, sync::Arc};
+use serde::Serialize;
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::BufReader,
+    path::Path,
+    sync::Arc,
+};
 use tokio::{sync::RwLock, task::JoinHandle};
-use types::proto::Schedule;
+use types::proto::{self, DeviceConfig, Schedule};
 use utils::{
     files::ApiAssets,
-    time::{current_month, current_year, day_no, day_of_week, week_no},
+    media::AudioFormat,
+    time::{local_now, Recurrence},
 };
 
+/// NowPlaying.
+/// The schedule that is currently playing.
+#[derive(Debug, Clone, Serialize)]
+pub struct NowPlaying {
+    pub sid: i32,
+    pub name: String,
+    pub record_url: String,
+    pub started_at: NaiveDateTime,
+    pub volume: Option<f32>,
+    /// The output devices, empty for the default output.
+    pub outputs: Vec<String>,
+}
+
+/// Get the recurrence of the schedule.
+fn recurrence(schedule: &Schedule) -> Recurrence<'_> {
+    Recurrence {
+        kind: schedule.kind,
+        weeks: &schedule.weeks,
+        days: &schedule.days,
+        dates: &schedule.dates,
+        month: schedule.month,
+        year: schedule.year,
+    }
+}
+
+/// Check if the time is within the range, the range may pass midnight.
+pub(super) fn in_range(start: NaiveTime, end: NaiveTime, at: NaiveTime) -> bool {
+    if start <= end {
+        at >= start && at < end
+    } else {
+        at >= start || at < end
+    }
+}
+
+/// Get the volume multiplier of the profile at the given time, the first matching range wins.
+pub(super) fn volume_multiplier(config: &DeviceConfig, at: NaiveTime) -> f32 {
+    let ranges = match &config.volume_profile {
+        Some(ranges) => ranges,
+        None => return 1.0,
+    };
+    for range in ranges {
+        let start = NaiveTime::parse_from_str(&range.start, "%H:%M");
+        let end = NaiveTime::parse_from_str(&range.end, "%H:%M");
+        if let (Ok(start), Ok(end)) = (start, end) {
+            if in_range(start, end, at) {
+                return range.multiplier.clamp(0.0, 1.0);
+            }
+        }
+    }
+    1.0
+}
+
+/// Decode the record and append it to the player, or to its background.
+pub(super) fn append_record(
+    player: &AudioPlayer,
+    path: &Path,
+    format: AudioFormat,
+    background: bool,
+) -> Result<(), String> {
+    let f = File::open(path).map_err(|e| e.to_string())?;
+    let reader = BufReader::new(f);
+    macro_rules! append {
+        ($source:expr) => {
+            if background {
+                player.append_background($source)
+            } else {
+                player.append($source)
+            }
+        };
+    }
+    match format {
+        AudioFormat::Mp3 => Decoder::new_mp3(reader)
+            .map(|d| append!(d))
+            .map_err(|e| e.to_string()),
+        AudioFormat::Wav => Decoder::new_wav(reader)
+            .map(|d| append!(d))
+            .map_err(|e| e.to_string()),
+        AudioFormat::Flac => Decoder::new_flac(reader)
+            .map(|d| append!(d))
+            .map_err(|e| e.to_string()),
+        AudioFormat::Opus => std::fs::read(path)
+            .map_err(|e| e.to_string())
+            .and_then(|data| OpusFile::new(data).map_err(|e| e.to_string()))
+            .map(|d| append!(d)),
+    }
+}
+
+/// Decode the beginning of the record to check that it is playable.
+pub(super) fn verify_record(path: &Path, format: AudioFormat) -> Result<(), String> {
+    const SAMPLES: usize = 48000;
+    let f = File::open(path).map_err(|e| e.to_string())?;
+    let reader = BufReader::new(f);
+    let decoded = match format {
+        AudioFormat::Mp3 => Decoder::new_mp3(reader)
+            .map(|d| d.take(SAMPLES).count())
+            .map_err(|e| e.to_string())?,
+        AudioFormat::Wav => Decoder::new_wav(reader)
+            .map(|d| d.take(SAMPLES).count())
+            .map_err(|e| e.to_string())?,
+        AudioFormat::Flac => Decoder::new_flac(reader)
+            .map(|d| d.take(SAMPLES).count())
+            .map_err(|e| e.to_string())?,
+        AudioFormat::Opus => std::fs::read(path)
+            .map_err(|e| e.to_string())
+            .and_then(|data| OpusFile::new(data).map_err(|e| e.to_string()))
+            .map(|d| d.take(SAMPLES).count())?,
+    };
+    if decoded == 0 {
+        return Err("no audio samples".to_string());
+    }
+    Ok(())
+}
+
+/// Convert the stored play log into the protocol type.
+fn to_proto(log: PlayLog) -> proto::PlayLog {
+    proto::PlayLog {
+        id: log.id,
+        sid: log.sid,
+        record_url: log.record_url,
+        started_at: log.started_at,
+        ended_at: log.ended_at,
+        volume: log.volume.map(|v| v as f32),
+        outcome: log.outcome,
+        message: log.message,
+        utc_offset: Local
+            .from_local_datetime(&log.started_at)
+            .earliest()
+            .map(|t| t.offset().local_minus_utc()),
+    }
+}
+
 /// Schedule state holds the state of the scheduler.
 #[derive(Clone)]
 pub struct ScheduleState {
     assets: ApiAssets,
     runner: Arc<RwLock<Vec<JoinHandle<()>>>>,
-    block: Arc<RwLock<bool>>,
+    blocked: Arc<RwLock<HashSet<String>>>,
+    attempts: Arc<RwLock<HashMap<i32, String>>>,
+    now_playing: Arc<RwLock<Vec<NowPlaying>>>,
     db: ProtoDatabase,
-    player: Arc<AudioPlayer>,
+    outputs: OutputState,
+    config: Arc<RwLock<DeviceConfig>>,
+    outbox: OutboxState,
 }
 
 impl ScheduleState {
     /// New schedule state.
-    pub fn new(db: ProtoDatabase, assets: ApiAssets, player: Arc<AudioPlayer>) -> Self {
+    pub fn new(
+        db: ProtoDatabase,
+        assets: ApiAssets,
+        outputs: OutputState,
+        config: Arc<RwLock<DeviceConfig>>,
+        outbox: OutboxState,
+    ) -> Self {
         Self {
             assets,
             runner: Arc::new(RwLock::new(vec![])),
-            block: Arc::new(RwLock::new(false)),
+            blocked: Arc::new(RwLock::new(HashSet::new())),
+            attempts: Arc::new(RwLock::new(HashMap::new())),
+            now_playing: Arc::new(RwLock::new(vec![])),
             db,
-            player,
+            outputs,
+            config,
+            outbox,
         }
     }
 
+    /// Check if the device is in the configured quiet hours.
+    pub(super) async fn is_quiet(&self) -> bool {
+        let config = self.config.read().await;
+        let parse = |t: &Option<String>| {
+            t.as_ref()
+                .and_then(|t| NaiveTime::parse_from_str(t, "%H:%M").ok())
+        };
+        match (parse(&config.quiet_start), parse(&config.quiet_end)) {
+            (Some(start), Some(end)) => in_range(start, end, local_now().time()),
+            _ => false,
+        }
+    }
+
+    /// Get the current volume multiplier of the profile.
+    async fn multiplier(&self) -> f32 {
+        volume_multiplier(&*self.config.read().await, local_now().time())
+    }
+
+    /// Get the default volume with the current multiplier of the profile applied.
+    pub(super) async fn default_volume(&self) -> f32 {
+        let volume = self.config.read().await.volume.unwrap_or(1.0);
+        volume * self.multiplier().await
+    }
+
     /// Clear runner.
     async fn clear_runner(&self) {
         let mut lock = self.runner.write().await;
@@ -56,10 +239,16 @@ impl ScheduleState {
         }
     }
 
+    /// Get the assets holding the records.
+    pub(super) fn assets(&self) -> &ApiAssets {
+        &self.assets
+    }
+
     /// Download record if it doesn't exist.
-    async fn download(&self, url: &str) {
+    pub(super) async fn download(&self, url: &str, format: &str) {
         let file_name = url.split('/').last().unwrap_or("");
-        if !file_name.ends_with(".mp3") {
+        if AudioFormat::from_name(format).is_none() {
+            log::warn!("Skipping {} because {} is not supported", file_name, format);
             return;
         }
         if !self.assets.audio_exists(file_name) {
@@ -74,13 +263,13 @@ impl ScheduleState {
         }
     }
 
-    /// Load schedule.
-    async fn load_schedule(&self) -> Vec<Schedule> {
+    /// Get schedules stored in the database.
+    pub fn schedules(&self) -> Vec<Schedule> {
         let repo = self.db.repository::<ScheduleRepo>();
         let schedules = repo.get().unwrap_or_default();
-        let mut formated = vec![];
-        for schedule in schedules {
-            let schedule = Schedule {
+        schedules
+            .into_iter()
+            .map(|schedule| Schedule {
                 sid: schedule.sid,
                 name: schedule.name,
                 days: serde_json::from_str(&schedule.days).unwrap(),
@@ -92,55 +281,307 @@ impl ScheduleState {
                 month: schedule.month,
                 year: schedule.year,
                 volume: schedule.volume.map(|v| v as f32),
+                format: schedule.format,
+                outputs: serde_json::from_str(&schedule.outputs).unwrap_or_default(),
+                emergency: schedule.emergency != 0,
+                rev: schedule.rev,
+            })
+            .collect()
+    }
+
+    /// Load schedule.
+    async fn load_schedule(&self) -> Vec<Schedule> {
+        let schedules = self.schedules();
+        for schedule in schedules.iter() {
+            self.download(&schedule.record_url, &schedule.format).await;
+        }
+        schedules
+    }
+
+    /// Get the fire times of the schedules within the next `hours`.
+    pub fn upcoming(&self, hours: i64) -> Vec<(NaiveDateTime, Schedule)> {
+        let from = local_now();
+        let to = from + Duration::hours(hours);
+        let mut res = vec![];
+        for schedule in self.schedules() {
+            let times = schedule
+                .times
+                .iter()
+                .flatten()
+                .filter_map(|t| NaiveTime::parse_from_str(t, "%H:%M").ok())
+                .collect::<Vec<NaiveTime>>();
+            let mut date = from.date();
+            while date <= to.date() {
+                for time in times.iter() {
+                    let at = date.and_time(*time);
+                    if at >= from && at < to && recurrence(&schedule).fires_at(at) {
+                        res.push((at, schedule.clone()));
+                    }
+                }
+                date = date + Duration::days(1);
+            }
+        }
+        res.sort_by_key(|(at, _)| *at);
+        res
+    }
+
+    /// Get the currently playing schedules.
+    pub async fn now_playing(&self) -> Vec<NowPlaying> {
+        self.now_playing.read().await.clone()
+    }
+
+    /// Record a play log.
+    fn log_play(
+        &self,
+        sid: i32,
+        url: &str,
+        volume: Option<f32>,
+        outcome: i32,
+        message: Option<String>,
+    ) -> Option<i32> {
+        let repo = self.db.repository::<PlayLogRepo>();
+        let log = NewPlayLog {
+            sid,
+            record_url: url.to_string(),
+            started_at: local_now(),
+            ended_at: if outcome == proto::OUTCOME_PLAYED {
+                None
+            } else {
+                Some(local_now())
+            },
+            volume: volume.map(|v| v as f64),
+            outcome,
+            message,
+        };
+        match repo.create(log) {
+            Ok(id) => {
+                self.queue_play_log(id);
+                Some(id)
+            }
+            Err(e) => {
+                log::error!("Failed to record play log: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Queue the play log in the outbox, sent again once it is finished.
+    fn queue_play_log(&self, id: i32) {
+        let repo = self.db.repository::<PlayLogRepo>();
+        match repo.get(id) {
+            Ok(Some(log)) => {
+                self.outbox.enqueue("play_log", &to_proto(log));
+                if let Err(e) = repo.mark_sent(vec![id]) {
+                    log::error!("Failed to mark play log as queued: {}", e);
+                }
+            }
+            Ok(None) => {}
+            Err(e) => log::error!("Failed to read play log: {}", e),
+        }
+    }
+
+    /// Queue the play logs recorded before the outbox existed.
+    fn queue_unsent_logs(&self) {
+        let repo = self.db.repository::<PlayLogRepo>();
+        loop {
+            let logs = match repo.get_unsent(50) {
+                Ok(logs) if !logs.is_empty() => logs,
+                Ok(_) => break,
+                Err(e) => {
+                    log::error!("Failed to read unsent play logs: {}", e);
+                    break;
+                }
             };
-            self.download(&schedule.record_url).await;
-            formated.push(schedule);
+            let ids = logs.iter().map(|log| log.id).collect::<Vec<i32>>();
+            for log in logs {
+                self.outbox.enqueue("play_log", &to_proto(log));
+            }
+            if let Err(e) = repo.mark_sent(ids) {
+                log::error!("Failed to mark play logs as queued: {}", e);
+                break;
+            }
         }
-        formated
+    }
+
+    /// Wait for the players to finish then clear the schedule from the playing ones
+    /// and close the play log, if it was recorded.
+    /// The volume follows the profile while playing unless the schedule is emergency.
+    fn finish_play(
+        &self,
+        id: Option<i32>,
+        sid: i32,
+        players: Vec<Arc<AudioPlayer>>,
+        volume: f32,
+        emergency: bool,
+    ) {
+        let state = self.clone();
+        tokio::spawn(async move {
+            let mut tick = tokio::time::interval(tokio::time::Duration::from_secs(1));
+            let mut current_multiplier = state.multiplier().await;
+            loop {
+                tick.tick().await;
+                if !players.iter().any(|p| p.is_playing()) {
+                    break;
+                }
+                if emergency {
+                    continue;
+                }
+                let multiplier = state.multiplier().await;
+                if multiplier != current_multiplier {
+                    current_multiplier = multiplier;
+                    for player in players.iter() {
+                        player.set_volume(volume * multiplier);
+                    }
+                }
+            }
+            state.now_playing.write().await.retain(|p| p.sid != sid);
+            if let Some(id) = id {
+                let repo = state.db.repository::<PlayLogRepo>();
+                if let Err(e) = repo.finish(id, local_now()) {
+                    log::error!("Failed to finish play log: {}", e);
+                }
+                state.queue_play_log(id);
+            }
+        });
     }
 
     /// play job.
-    async fn play(&self, name: &str, url: &str, volume: Option<f32>) {
+    async fn play(&self, job: &Schedule) {
+        let (sid, name, url, volume) = (job.sid, &job.name, &job.record_url, job.volume);
+        let (format, outputs, emergency) = (&job.format, &job.outputs, job.emergency);
+        {
+            // the scheduler ticks twice a minute, only attempt once per minute.
+            let minute = local_now().format("%Y-%m-%d %H:%M").to_string();
+            let mut attempts = self.attempts.write().await;
+            if attempts.get(&sid) == Some(&minute) {
+                return;
+            }
+            attempts.insert(sid, minute);
+        }
         log::info!("Try to Play: {} {}", name, url);
-        if *self.block.read().await {
+        let multiplier = if emergency {
+            1.0
+        } else {
+            self.multiplier().await
+        };
+        let default_volume = self.config.read().await.volume;
+        let base_volume = volume.or(default_volume).unwrap_or(1.0);
+        // the logs hold the volume the record is played at.
+        let effective = Some(base_volume * multiplier);
+        let players = self.outputs.resolve(outputs).await;
+        if players.is_empty() {
+            log::error!("Can't play {} because the outputs are not available", name);
+            self.log_play(
+                sid,
+                url,
+                effective,
+                proto::OUTCOME_ERROR,
+                Some("output not available".to_string()),
+            );
+            return;
+        }
+        let blocked = {
+            let lock = self.blocked.read().await;
+            players.iter().any(|(key, _)| lock.contains(key))
+        };
+        if blocked {
             log::error!("Can't play {} because schedule is blocked", name);
+            self.log_play(
+                sid,
+                url,
+                effective,
+                proto::OUTCOME_SKIPPED,
+                Some("schedule is blocked".to_string()),
+            );
+            return;
+        }
+        if !emergency && self.is_quiet().await {
+            log::info!("Can't play {} because of quiet hours", name);
+            self.log_play(
+                sid,
+                url,
+                effective,
+                proto::OUTCOME_SKIPPED,
+                Some("quiet hours".to_string()),
+            );
+            return;
+        }
+        if multiplier <= 0.0 {
+            log::info!("Can't play {} because of the volume profile", name);
+            self.log_play(
+                sid,
+                url,
+                effective,
+                proto::OUTCOME_SKIPPED,
+                Some("muted".to_string()),
+            );
             return;
         }
-        if self.player.is_playing() {
+        if players.iter().any(|(_, player)| player.is_playing()) {
             log::error!("Can't play {} because other stream is playing", name);
+            self.log_play(
+                sid,
+                url,
+                effective,
+                proto::OUTCOME_SKIPPED,
+                Some("other stream is playing".to_string()),
+            );
             return;
         }
 
         let file_name = url.split('/').last().unwrap_or("");
-        if !file_name.ends_with(".mp3") {
-            log::error!("Can't play {} because it's not an mp3 file", name);
-            return;
-        }
+        let format = match AudioFormat::from_name(format) {
+            Some(format) => format,
+            None => {
+                log::error!("Can't play {} because {} is not supported", name, format);
+                self.log_play(
+                    sid,
+                    url,
+                    effective,
+                    proto::OUTCOME_ERROR,
+                    Some("unsupported format".to_string()),
+                );
+                return;
+            }
+        };
         if !self.assets.audio_exists(file_name) {
             log::error!("Can't play {} because it doesn't exist", name);
+            self.log_play(sid, url, effective, proto::OUTCOME_MISSING, None);
             return;
         }
         let path = self.assets.audio_path(file_name);
         log::debug!("Assigning player");
-        self.player.set_volume(volume.unwrap_or(1.0));
-        let f = match File::open(&path) {
-            Ok(f) => f,
-            Err(e) => {
-                log::error!("Error: {}", e);
-                return;
-            }
-        };
-        let reader = BufReader::new(f);
-        let decoder = match Decoder::new(reader) {
-            Ok(d) => d,
-            Err(e) => {
+        for (_, player) in players.iter() {
+            player.set_volume(base_volume * multiplier);
+            if let Err(e) = append_record(player, &path, format, false) {
                 log::error!("Error: {}", e);
+                for (_, player) in players.iter() {
+                    player.clear();
+                }
+                self.log_play(sid, url, effective, proto::OUTCOME_ERROR, Some(e));
                 return;
             }
-        };
-        log::info!("Play: {} {}", name, url);
-        self.player.append(decoder);
-        self.player.play();
+        }
+        log::info!("Play: {} {} on {:?}", name, url, outputs);
+        for (_, player) in players.iter() {
+            player.play();
+        }
+        self.now_playing.write().await.push(NowPlaying {
+            sid,
+            name: name.to_string(),
+            record_url: url.to_string(),
+            started_at: local_now(),
+            volume,
+            outputs: outputs.to_vec(),
+        });
+        let id = self.log_play(sid, url, effective, proto::OUTCOME_PLAYED, None);
+        self.finish_play(
+            id,
+            sid,
+            players.into_iter().map(|(_, p)| p).collect(),
+            base_volume,
+            emergency,
+        );
         log::debug!("Player assigned");
     }
 
@@ -156,55 +597,24 @@ impl ScheduleState {
 
             loop {
                 tick.tick().await;
-                let now = utils::time::time_24();
+                let at = local_now();
+                let now = at.format("%H:%M").to_string();
                 for job in jobs.iter() {
-                    let now_clone = now.clone();
-                    let job = job.clone();
-                    let state = state.clone();
-                    tokio::spawn(async move {
+                    if !job.times.iter().flatten().any(|t| *t == now) {
+                        log::debug!("Skipping job: {}", now);
+                        continue;
+                    }
+                    if !matches!(job.kind, 1 | 2) {
+                        log::warn!("Invalid job kind: {}", job.kind);
+                        continue;
+                    }
+                    if recurrence(job).fires_at(at) {
                         let job = job.clone();
-                        let now = now_clone;
-                        let times = job.times.into_iter().flatten().collect::<Vec<String>>();
-                        let weeks = job.weeks.into_iter().flatten().collect::<Vec<i32>>();
-                        let days = job.days.into_iter().flatten().collect::<Vec<i32>>();
-                        let dates = job.dates.into_iter().flatten().collect::<Vec<i32>>();
-
-                        if times.contains(&now) {
-                            match job.kind {
-                                1 => {
-                                    if weeks.contains(&(week_no() as i32))
-                                        && days.contains(&(day_of_week() as i32))
-                                    {
-                                        state.play(&job.name, &job.record_url, job.volume).await;
-                                    }
-
-                                    if dates.contains(&(day_no() as i32)) {
-                                        state.play(&job.name, &job.record_url, job.volume).await;
-                                    }
-                                }
-                                2 => {
-                                    let cm = current_month() as i32;
-                                    let cy = current_year() as i32;
-                                    if job.month.is_none() || job.year.is_none() {
-                                        return;
-                                    }
-                                    let month = job.month.unwrap();
-                                    let year = job.year.unwrap();
-                                    if month == cm
-                                        && year == cy
-                                        && dates.contains(&(day_no() as i32))
-                                    {
-                                        state.play(&job.name, &job.record_url, job.volume).await;
-                                    }
-                                }
-                                _ => {
-                                    log::warn!("Invalid job kind: {}", job.kind);
-                                }
-                            }
-                        } else {
-                            log::debug!("Skipping job: {}", now);
-                        }
-                    });
+                        let state = state.clone();
+                        tokio::spawn(async move {
+                            state.play(&job).await;
+                        });
+                    }
                 }
             }
         }));
@@ -222,21 +632,33 @@ impl ScheduleState {
         });
     }
 
-    /// Block scheduler.
-    pub async fn block(&self) {
-        self.player.clear();
-        *self.block.write().await = true;
+    /// Block scheduler on the outputs.
+    pub async fn block(&self, outputs: &[(String, Arc<AudioPlayer>)]) {
+        let mut lock = self.blocked.write().await;
+        for (key, player) in outputs {
+            player.clear();
+            lock.insert(key.to_owned());
+        }
     }
 
-    /// Unblock scheduler.
-    pub async fn unblock(&self) {
-        self.player.clear();
-        *self.block.write().await = false;
+    /// Check if the scheduler is blocked on the output.
+    pub async fn is_blocked(&self, key: &str) -> bool {
+        self.blocked.read().await.contains(key)
+    }
+
+    /// Unblock scheduler on the outputs.
+    pub async fn unblock(&self, outputs: &[(String, Arc<AudioPlayer>)]) {
+        let mut lock = self.blocked.write().await;
+        for (key, player) in outputs {
+            player.clear();
+            lock.remove(key);
+        }
     }
 
     /// Run scheduler.
     pub async fn run(&self) {
         log::info!("Starting scheduler");
+        self.queue_unsent_logs();
         if !self.runner.read().await.is_empty() {
             self.clear_runner().await;
         }
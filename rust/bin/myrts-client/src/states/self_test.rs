@@ -0,0 +1,184 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use super::schedule_state::verify_record;
+use audio::audio::AudioPlayer;
+use proto_db::ProtoDatabase;
+use std::{
+    path::Path,
+    time::{Duration, Instant},
+};
+use systemstat::{Platform, System};
+use types::proto::SelfTestCheck;
+use utils::media::AudioFormat;
+
+/// Minimum free space in bytes on the records disk.
+const MIN_DISK_FREE: u64 = 100 * 1024 * 1024;
+
+/// Outcome.
+/// The outcome of a check along with its detail.
+pub(super) enum Outcome {
+    Passed(String),
+    Failed(String),
+    Skipped(String),
+}
+
+impl From<Result<String, String>> for Outcome {
+    fn from(value: Result<String, String>) -> Self {
+        match value {
+            Ok(detail) => Self::Passed(detail),
+            Err(detail) => Self::Failed(detail),
+        }
+    }
+}
+
+/// Build the check report from the outcome.
+fn report(name: &str, started: Instant, outcome: Outcome) -> SelfTestCheck {
+    let (passed, detail) = match outcome {
+        Outcome::Passed(detail) => (Some(true), detail),
+        Outcome::Failed(detail) => (Some(false), detail),
+        Outcome::Skipped(detail) => (None, detail),
+    };
+    SelfTestCheck {
+        name: name.to_string(),
+        passed,
+        detail,
+        duration_ms: started.elapsed().as_millis() as u64,
+    }
+}
+
+/// Check that the output device is present and can be opened.
+/// The device is only reopened when nothing is playing.
+pub(super) fn audio_device(player: &AudioPlayer, busy: bool) -> SelfTestCheck {
+    let started = Instant::now();
+    let device = player.device();
+    let name = device.clone().unwrap_or_else(|| "default".to_string());
+    let outcome = if !player.is_available() {
+        Err(format!("output {} is not available", name))
+    } else if busy {
+        Ok(format!("output {} is available", name))
+    } else {
+        player
+            .set_device(device.as_deref())
+            .map(|_| format!("output {} opened", name))
+            .map_err(|e| format!("failed to open output {}: {}", name, e))
+    };
+    report("audio_device", started, outcome.into())
+}
+
+/// Wait until the test tone queued on the player finished.
+/// `played` is the outcome of queueing the tone.
+pub(super) async fn tone(player: &AudioPlayer, played: Outcome, seconds: u64) -> SelfTestCheck {
+    let started = Instant::now();
+    let outcome = match played {
+        Outcome::Passed(_) => {
+            let deadline = started + Duration::from_secs(seconds + 3);
+            while player.queued() > 0 && Instant::now() < deadline {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+            if player.queued() > 0 {
+                player.clear();
+                Outcome::Failed("the tone did not finish playing".to_string())
+            } else {
+                Outcome::Passed(format!("played a {} seconds tone", seconds))
+            }
+        }
+        other => other,
+    };
+    report("tone", started, outcome)
+}
+
+/// Decode the beginning of every cached record.
+pub(super) fn decoders(dir: &Path) -> SelfTestCheck {
+    let started = Instant::now();
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => return report("decoders", started, Outcome::Failed(e.to_string())),
+    };
+    let mut total = 0;
+    let mut failed = vec![];
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let format = match path
+            .extension()
+            .and_then(|e| e.to_str())
+            .and_then(AudioFormat::from_extension)
+        {
+            Some(format) => format,
+            None => continue,
+        };
+        total += 1;
+        if let Err(e) = verify_record(&path, format) {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            log::warn!("Record {} can't be decoded: {}", name, e);
+            failed.push(format!("{}: {}", name, e));
+        }
+    }
+    let outcome = if failed.is_empty() {
+        Ok(format!("{} records decoded", total))
+    } else {
+        Err(format!(
+            "{} of {} records failed\n{}",
+            failed.len(),
+            total,
+            failed.join("\n")
+        ))
+    };
+    report("decoders", started, outcome.into())
+}
+
+/// Check the free space of the disk holding the records.
+pub(super) fn disk(dir: &Path) -> SelfTestCheck {
+    let started = Instant::now();
+    let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+    let sys = System::new();
+    // the records are on the mount with the longest matching mount point.
+    let mount = sys.mounts().ok().and_then(|mounts| {
+        mounts
+            .into_iter()
+            .filter(|m| dir.starts_with(&m.fs_mounted_on))
+            .max_by_key(|m| m.fs_mounted_on.len())
+    });
+    let outcome = match mount {
+        Some(mount) => {
+            let avail = mount.avail.as_u64();
+            let detail = format!(
+                "{} MiB free on {}",
+                avail / 1024 / 1024,
+                mount.fs_mounted_on
+            );
+            if avail < MIN_DISK_FREE {
+                Err(detail)
+            } else {
+                Ok(detail)
+            }
+        }
+        None => Err(format!("no mount found for {}", dir.display())),
+    };
+    report("disk", started, outcome.into())
+}
+
+/// Run the integrity check of the local database.
+pub(super) fn database(db: &ProtoDatabase) -> SelfTestCheck {
+    let started = Instant::now();
+    let outcome = match proto_db::integrity_check(db) {
+        Ok(rows) if rows.len() == 1 && rows[0] == "ok" => Ok("ok".to_string()),
+        Ok(rows) => Err(rows.join("\n")),
+        Err(e) => Err(e.to_string()),
+    };
+    report("database", started, outcome.into())
+}
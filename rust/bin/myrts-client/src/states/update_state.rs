@@ -0,0 +1,318 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use proto::app::Stream;
+use proto_db::{models::NewPendingUpdate, repos::PendingUpdateRepo, ProtoDatabase};
+use std::{
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use types::proto::{UpdateAnnounce, UpdateStatus};
+use utils::crypto::{sha256, update_message, verify_ed25519};
+
+/// The running client version.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Report the update status to the server.
+async fn report(
+    stream: &Stream,
+    id: i32,
+    status: i32,
+    progress: Option<i32>,
+    message: Option<String>,
+) {
+    let data = UpdateStatus {
+        id,
+        status,
+        progress,
+        message,
+        version: VERSION.to_string(),
+    };
+    if let Err(e) = stream.write("update:status", data).await {
+        log::error!("Failed to report update status: {:?}", e);
+    }
+}
+
+/// Parse a `major.minor.patch` version, the pre-release and build suffixes are ignored.
+fn parse_version(version: &str) -> Option<Vec<u64>> {
+    version
+        .trim_start_matches('v')
+        .split(|c| c == '-' || c == '+')
+        .next()?
+        .split('.')
+        .map(|part| part.parse::<u64>().ok())
+        .collect()
+}
+
+/// Check whether the version is newer than the running one.
+/// An old signed announcement can be replayed, so a downgrade is never installed.
+fn is_newer(version: &str) -> bool {
+    match (parse_version(version), parse_version(VERSION)) {
+        (Some(version), Some(current)) => version > current,
+        _ => false,
+    }
+}
+
+/// Exit so the service manager restarts the client.
+/// The unit runs `myrts-client-guard` before every start, which counts the starts of a new version
+/// and restores the backup once it keeps failing, so the process is never replaced in place.
+fn restart() {
+    log::info!("Restarting");
+    std::process::exit(1);
+}
+
+/// Update state holds the state of the self-update.
+#[derive(Clone)]
+pub struct UpdateState {
+    db: ProtoDatabase,
+    public_key: Option<String>,
+    deadline: Duration,
+    exe: Option<PathBuf>,
+    busy: Arc<AtomicBool>,
+}
+
+impl UpdateState {
+    /// New update state.
+    /// `public_key` is the hex encoded ed25519 key trusted to sign updates, updates are refused without it.
+    /// `deadline` is the time the new version has to authenticate before it's rolled back.
+    pub fn new(db: ProtoDatabase, public_key: Option<String>, deadline: Duration) -> Self {
+        Self {
+            db,
+            public_key,
+            deadline,
+            // resolved now, after the swap the running binary is unlinked.
+            exe: std::env::current_exe().ok(),
+            busy: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Check the pending update on startup.
+    /// Starts the rollback deadline when running a freshly installed version.
+    /// Counting the starts is left to the service guard, it still runs when this binary can't.
+    pub fn startup(&self) {
+        let repo = self.db.repository::<PendingUpdateRepo>();
+        let pending = match repo.get() {
+            Ok(Some(pending)) => pending,
+            _ => return,
+        };
+        if pending.status != 4 {
+            return;
+        }
+        if pending.version != VERSION {
+            let message = format!(
+                "expected version {} but {} is running",
+                pending.version, VERSION
+            );
+            log::error!("Update failed: {}", message);
+            let _ = repo.set_status(6, Some(message));
+            return;
+        }
+        log::info!(
+            "Running updated version {}, waiting {}s for authentication",
+            VERSION,
+            self.deadline.as_secs()
+        );
+        let state = self.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(state.deadline).await;
+            let repo = state.db.repository::<PendingUpdateRepo>();
+            if let Ok(Some(pending)) = repo.get() {
+                if pending.status == 4 {
+                    state.rollback(&format!(
+                        "version {} didn't authenticate within {}s",
+                        VERSION,
+                        state.deadline.as_secs()
+                    ));
+                }
+            }
+        });
+    }
+
+    /// Restore the previous binary and restart.
+    fn rollback(&self, reason: &str) {
+        log::error!("Rolling back update: {}", reason);
+        let repo = self.db.repository::<PendingUpdateRepo>();
+        let exe = match &self.exe {
+            Some(exe) => exe,
+            None => {
+                let _ = repo.set_status(6, Some("can't locate the running binary".to_string()));
+                return;
+            }
+        };
+        if let Err(e) = std::fs::rename(exe.with_extension("bak"), exe) {
+            log::error!("Failed to restore the previous binary: {}", e);
+            let _ = repo.set_status(6, Some(format!("rollback failed: {}", e)));
+            return;
+        }
+        let _ = repo.set_status(7, Some(reason.to_string()));
+        restart();
+    }
+
+    /// Report the result of the pending update once authenticated.
+    pub async fn confirm(&self, stream: &Stream) {
+        let repo = self.db.repository::<PendingUpdateRepo>();
+        let pending = match repo.get() {
+            Ok(Some(pending)) => pending,
+            _ => return,
+        };
+        let status = if pending.status == 4 {
+            if pending.version != VERSION {
+                return;
+            }
+            log::info!("Update to {} completed", VERSION);
+            if let Some(exe) = &self.exe {
+                let _ = std::fs::remove_file(exe.with_extension("bak"));
+            }
+            5
+        } else {
+            pending.status
+        };
+        report(stream, pending.update_id, status, None, pending.message).await;
+        if let Err(e) = repo.clear() {
+            log::error!("Failed to clear pending update: {}", e);
+        }
+    }
+
+    /// Handle an update announcement.
+    pub async fn update(&self, stream: Stream, announce: UpdateAnnounce) {
+        if announce.version == VERSION {
+            report(&stream, announce.id, 5, None, None).await;
+            return;
+        }
+        if !is_newer(&announce.version) {
+            log::warn!(
+                "Ignoring update {}, not newer than {}",
+                announce.version,
+                VERSION
+            );
+            let message = format!("version {} is not newer than {}", announce.version, VERSION);
+            report(&stream, announce.id, 6, None, Some(message)).await;
+            return;
+        }
+        let public_key = match &self.public_key {
+            Some(key) => key.clone(),
+            None => {
+                log::warn!("Ignoring update {}, no update key", announce.version);
+                let message = "updates are not enabled on this device".to_string();
+                report(&stream, announce.id, 6, None, Some(message)).await;
+                return;
+            }
+        };
+        if self.busy.swap(true, Ordering::SeqCst) {
+            log::warn!("Ignoring update {}, already updating", announce.version);
+            return;
+        }
+        let state = self.clone();
+        tokio::spawn(async move {
+            if let Err(e) = state.install(&stream, &announce, &public_key).await {
+                log::error!("Update {} failed: {}", announce.version, e);
+                report(&stream, announce.id, 6, None, Some(e)).await;
+            }
+            state.busy.store(false, Ordering::SeqCst);
+        });
+    }
+
+    /// Download, verify and install the update, then restart.
+    async fn install(
+        &self,
+        stream: &Stream,
+        announce: &UpdateAnnounce,
+        public_key: &str,
+    ) -> Result<(), String> {
+        // check the signature first so untrusted announcements are never downloaded.
+        let message = update_message(&announce.version, announce.size, &announce.sha256);
+        if !verify_ed25519(public_key, message.as_bytes(), &announce.signature) {
+            return Err("invalid signature".to_string());
+        }
+        let exe = self
+            .exe
+            .clone()
+            .ok_or_else(|| "can't locate the running binary".to_string())?;
+
+        log::info!("Downloading update {}", announce.version);
+        report(stream, announce.id, 2, Some(0), None).await;
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<i32>();
+        let progress_stream = stream.clone();
+        let id = announce.id;
+        let progress = tokio::spawn(async move {
+            while let Some(percent) = rx.recv().await {
+                report(&progress_stream, id, 2, Some(percent), None).await;
+            }
+        });
+        let mut last = 0;
+        let size = announce.size.max(0) as u64;
+        let data = utils::download_file(&announce.url, size, |done, _| {
+            let percent = (done * 100 / size.max(1)).min(100) as i32;
+            if percent >= last + 10 {
+                last = percent;
+                let _ = tx.send(percent);
+            }
+        })
+        .await;
+        drop(tx);
+        let _ = progress.await;
+        let data = data.map_err(|e| format!("download failed: {}", e))?;
+
+        // a shorter file is only known once the download ends.
+        if data.len() as i64 != announce.size {
+            return Err(format!(
+                "size mismatch, expected {} got {}",
+                announce.size,
+                data.len()
+            ));
+        }
+        if sha256(&data) != announce.sha256.to_lowercase() {
+            return Err("sha256 mismatch".to_string());
+        }
+        report(stream, announce.id, 3, Some(100), None).await;
+
+        let new = exe.with_extension("new");
+        std::fs::write(&new, &data).map_err(|e| format!("failed to write update: {}", e))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&new, std::fs::Permissions::from_mode(0o755))
+                .map_err(|e| format!("failed to set permissions: {}", e))?;
+        }
+        std::fs::copy(&exe, exe.with_extension("bak"))
+            .map_err(|e| format!("failed to backup the current binary: {}", e))?;
+        let repo = self.db.repository::<PendingUpdateRepo>();
+        repo.set(NewPendingUpdate {
+            update_id: announce.id,
+            version: announce.version.clone(),
+            previous_version: VERSION.to_string(),
+            status: 4,
+        })
+        .map_err(|e| e.to_string())?;
+        // rename is atomic, the binary is either the old or the new one.
+        if let Err(e) = std::fs::rename(&new, &exe) {
+            let _ = repo.clear();
+            return Err(format!("failed to replace the binary: {}", e));
+        }
+
+        log::info!("Update {} installed", announce.version);
+        report(stream, announce.id, 4, None, None).await;
+        // give the report a moment to be flushed.
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        restart();
+        Ok(())
+    }
+}
@@ -122,7 +122,7 @@ rm_dir() {
 # download necessary packages
 download() {
     apt update -y && sudo apt upgrade -y
-    apt install -y curl pulseaudio libpulse-dev librust-libpulse-sys-dev alsa-utils libogg-dev libvorbis-dev ffmpeg libopus-dev libasound2-dev libudev-dev libopusfile-dev llvm-dev libclang-dev clang openssl libssl-dev libavutil-dev libavcodec-dev libavformat-dev
+    apt install -y curl sqlite3 pulseaudio libpulse-dev librust-libpulse-sys-dev alsa-utils libogg-dev libvorbis-dev ffmpeg libopus-dev libasound2-dev libudev-dev libopusfile-dev llvm-dev libclang-dev clang openssl libssl-dev libavutil-dev libavcodec-dev libavformat-dev
 
     success "Extra packages installed successfully"
     info "Clean up..."
@@ -172,6 +172,8 @@ main() {
     mkdir -p /var/lib/myrts
     curl -L "$base_url/myrts-client" -o /var/lib/myrts/myrts-client
     chmod +x /var/lib/myrts/myrts-client
+    curl -L "$base_url/myrts-client-guard.bash" -o /var/lib/myrts/myrts-client-guard
+    chmod +x /var/lib/myrts/myrts-client-guard
 
     info "Starting myrts-client..."
     systemctl start myrts-client
@@ -0,0 +1,44 @@
+#!/bin/bash
+
+# Rollback guard for myrts-client, run by systemd before every start.
+# A freshly installed version counts its starts in the pending_update table,
+# once it started MAX_ATTEMPTS times without authenticating the backup binary
+# is restored. This runs outside the client so a new version that panics or
+# fails to link before reaching its own startup checks is still rolled back.
+
+MAX_ATTEMPTS=3
+
+dir=$(dirname "$(readlink -f "$0")")
+exe="$dir/myrts-client"
+db="${DATABASE_URL:-$dir/myrts-client.db}"
+
+# nothing to check without the database or the sqlite3 cli
+if [ ! -f "$db" ] || ! command -v sqlite3 >/dev/null 2>&1; then
+    exit 0
+fi
+
+# status 4 is an installed update that didn't authenticate yet
+status=$(sqlite3 "$db" "select status from pending_update limit 1;" 2>/dev/null)
+if [ "$status" != "4" ]; then
+    exit 0
+fi
+
+attempts=$(sqlite3 "$db" "update pending_update set attempts = attempts + 1; select attempts from pending_update limit 1;" 2>/dev/null)
+if [ -z "$attempts" ] || [ "$attempts" -le "$MAX_ATTEMPTS" ]; then
+    exit 0
+fi
+
+version=$(sqlite3 "$db" "select version from pending_update limit 1;" 2>/dev/null)
+if [ ! -f "$exe.bak" ]; then
+    echo "Can't roll back $version, no backup binary"
+    sqlite3 "$db" "update pending_update set status = 6, message = 'rollback failed: no backup binary';"
+    exit 0
+fi
+
+echo "Rolling back $version, started $((attempts - 1)) times without authenticating"
+if mv "$exe.bak" "$exe"; then
+    sqlite3 "$db" "update pending_update set status = 7, message = 'version $version started $((attempts - 1)) times without authenticating';"
+else
+    sqlite3 "$db" "update pending_update set status = 6, message = 'rollback failed: can''t restore the backup binary';"
+fi
+exit 0
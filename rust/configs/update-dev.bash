@@ -1,7 +1,14 @@
 main() {
+    apt install -y sqlite3
     curl -L https://api.myrts.qcynaut.tech/install/myrts-client -o /tmp/myrts-client
     chmod +x /tmp/myrts-client
     mv /tmp/myrts-client /var/lib/myrts/
+    curl -L https://api.myrts.qcynaut.tech/install/myrts-client-guard.bash -o /var/lib/myrts/myrts-client-guard
+    chmod +x /var/lib/myrts/myrts-client-guard
+    # units installed before the guard existed don't run it
+    mkdir -p /etc/systemd/system/myrts-client.service.d
+    printf "[Service]\nExecStartPre=\nExecStartPre=-/var/lib/myrts/myrts-client-guard\nRestartSec=5\n" > /etc/systemd/system/myrts-client.service.d/guard.conf
+    systemctl daemon-reload
     systemctl restart myrts-client && systemctl enable myrts-client
 }
 
@@ -1,9 +1,15 @@
 main() {
     apt update -y && sudo apt upgrade -y
-    apt install -y curl pulseaudio libpulse-dev librust-libpulse-sys-dev alsa-utils libogg-dev libvorbis-dev ffmpeg libopus-dev libasound2-dev libudev-dev libopusfile-dev llvm-dev libclang-dev clang openssl libssl-dev libavutil-dev libavcodec-dev libavformat-dev libavfilter-dev libavdevice-dev ffmpeg
+    apt install -y curl sqlite3 pulseaudio libpulse-dev librust-libpulse-sys-dev alsa-utils libogg-dev libvorbis-dev ffmpeg libopus-dev libasound2-dev libudev-dev libopusfile-dev llvm-dev libclang-dev clang openssl libssl-dev libavutil-dev libavcodec-dev libavformat-dev libavfilter-dev libavdevice-dev ffmpeg
     curl -L https://api.myrts.id/install/myrts-client -o /tmp/myrts-client
     chmod +x /tmp/myrts-client
     mv /tmp/myrts-client /var/lib/myrts/
+    curl -L https://api.myrts.id/install/myrts-client-guard.bash -o /var/lib/myrts/myrts-client-guard
+    chmod +x /var/lib/myrts/myrts-client-guard
+    # units installed before the guard existed don't run it
+    mkdir -p /etc/systemd/system/myrts-client.service.d
+    printf "[Service]\nExecStartPre=\nExecStartPre=-/var/lib/myrts/myrts-client-guard\nRestartSec=5\n" > /etc/systemd/system/myrts-client.service.d/guard.conf
+    systemctl daemon-reload
     systemctl restart myrts-client && systemctl enable myrts-client
 }
 
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+
+drop TABLE play_log;
\ No newline at end of file
@@ -0,0 +1,20 @@
+-- Your SQL goes here
+
+create TABLE
+    play_log(
+        id SERIAL PRIMARY KEY NOT NULL,
+        avs_id INTEGER NOT NULL,
+        schedule_id INTEGER NOT NULL,
+        local_id INTEGER NOT NULL,
+        record_url TEXT NOT NULL,
+        started_at TIMESTAMP NOT NULL,
+        ended_at TIMESTAMP,
+        volume FLOAT8,
+        outcome INTEGER NOT NULL,
+        message TEXT,
+        created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        FOREIGN KEY (avs_id) REFERENCES avs(id) ON DELETE CASCADE,
+        UNIQUE (avs_id, local_id, started_at)
+    );
+
+create INDEX play_log_schedule_idx ON play_log (schedule_id, started_at);
@@ -0,0 +1,2 @@
+-- Your SQL goes here
+alter TABLE records ADD COLUMN format VARCHAR(16) NOT NULL DEFAULT 'mp3';
\ No newline at end of file
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+
+drop TABLE device_config;
\ No newline at end of file
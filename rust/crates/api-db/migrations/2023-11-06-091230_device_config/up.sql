@@ -0,0 +1,19 @@
+-- Your SQL goes here
+
+create TABLE
+    device_config(
+        id SERIAL PRIMARY KEY NOT NULL,
+        avs_id INTEGER UNIQUE,
+        user_group_id INTEGER UNIQUE,
+        volume FLOAT8,
+        log_level VARCHAR(16),
+        quiet_start VARCHAR(5),
+        quiet_end VARCHAR(5),
+        telemetry_interval INTEGER,
+        allowed_commands TEXT[],
+        audio_device VARCHAR(255),
+        updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        FOREIGN KEY (avs_id) REFERENCES avs(id) ON DELETE CASCADE,
+        FOREIGN KEY (user_group_id) REFERENCES user_group(id) ON DELETE CASCADE,
+        CHECK ((avs_id IS NULL) <> (user_group_id IS NULL))
+    );
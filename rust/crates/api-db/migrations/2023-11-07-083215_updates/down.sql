@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+
+drop TABLE avs_update;
+
+drop TABLE updates;
\ No newline at end of file
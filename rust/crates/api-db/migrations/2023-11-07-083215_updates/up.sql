@@ -0,0 +1,29 @@
+-- Your SQL goes here
+
+create TABLE
+    updates(
+        id SERIAL PRIMARY KEY NOT NULL,
+        version VARCHAR(64) NOT NULL UNIQUE,
+        file_name VARCHAR(255) NOT NULL,
+        size BIGINT NOT NULL,
+        sha256 VARCHAR(64) NOT NULL,
+        signature VARCHAR(128) NOT NULL,
+        user_id INTEGER NOT NULL,
+        created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        FOREIGN KEY (user_id) REFERENCES users(id)
+    );
+
+create TABLE
+    avs_update(
+        id SERIAL PRIMARY KEY NOT NULL,
+        avs_id INTEGER NOT NULL,
+        update_id INTEGER NOT NULL,
+        status INTEGER NOT NULL,
+        progress INTEGER,
+        message TEXT,
+        version VARCHAR(64),
+        updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        FOREIGN KEY (avs_id) REFERENCES avs(id) ON DELETE CASCADE,
+        FOREIGN KEY (update_id) REFERENCES updates(id) ON DELETE CASCADE,
+        UNIQUE (avs_id, update_id)
+    );
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+
+drop TABLE telemetry_network;
+
+drop TABLE telemetry;
\ No newline at end of file
@@ -0,0 +1,35 @@
+-- Your SQL goes here
+
+create TABLE
+    telemetry(
+        id SERIAL PRIMARY KEY NOT NULL,
+        avs_id INTEGER NOT NULL,
+        created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        uptime BIGINT,
+        cpu_load FLOAT8,
+        cpu_temp FLOAT8,
+        mem_total BIGINT,
+        mem_free BIGINT,
+        disk_total BIGINT,
+        disk_free BIGINT,
+        audio_output VARCHAR(255),
+        audio_ok BOOLEAN NOT NULL,
+        audio_playing BOOLEAN NOT NULL,
+        version VARCHAR(64) NOT NULL,
+        rss BIGINT,
+        FOREIGN KEY (avs_id) REFERENCES avs(id) ON DELETE CASCADE
+    );
+
+create INDEX telemetry_avs_idx ON telemetry (avs_id, created_at);
+
+create TABLE
+    telemetry_network(
+        id SERIAL PRIMARY KEY NOT NULL,
+        telemetry_id INTEGER NOT NULL,
+        name VARCHAR(255) NOT NULL,
+        rx_bytes BIGINT,
+        tx_bytes BIGINT,
+        FOREIGN KEY (telemetry_id) REFERENCES telemetry(id) ON DELETE CASCADE
+    );
+
+create INDEX telemetry_network_idx ON telemetry_network (telemetry_id);
@@ -0,0 +1,17 @@
+-- This file should undo anything in `up.sql`
+
+alter TABLE schedules DROP COLUMN zones;
+
+alter TABLE schedules DROP COLUMN port_ids;
+
+alter TABLE avs_port DROP CONSTRAINT avs_port_avs_id_fkey;
+
+alter TABLE avs_port ADD CONSTRAINT avs_port_avs_id_fkey FOREIGN KEY (avs_id) REFERENCES avs(id);
+
+alter TABLE avs_port DROP CONSTRAINT avs_port_device_key;
+
+alter TABLE avs_port DROP COLUMN available;
+
+alter TABLE avs_port DROP COLUMN zone;
+
+alter TABLE avs_port DROP COLUMN device;
\ No newline at end of file
@@ -0,0 +1,17 @@
+-- Your SQL goes here
+
+alter TABLE avs_port ADD COLUMN device VARCHAR(255) NOT NULL DEFAULT '';
+
+alter TABLE avs_port ADD COLUMN zone VARCHAR(255);
+
+alter TABLE avs_port ADD COLUMN available BOOLEAN NOT NULL DEFAULT true;
+
+alter TABLE avs_port ADD CONSTRAINT avs_port_device_key UNIQUE (avs_id, device);
+
+alter TABLE avs_port DROP CONSTRAINT avs_port_avs_id_fkey;
+
+alter TABLE avs_port ADD CONSTRAINT avs_port_avs_id_fkey FOREIGN KEY (avs_id) REFERENCES avs(id) ON DELETE CASCADE;
+
+alter TABLE schedules ADD COLUMN port_ids INTEGER[] NOT NULL DEFAULT '{}';
+
+alter TABLE schedules ADD COLUMN zones TEXT[] NOT NULL DEFAULT '{}';
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+
+alter TABLE schedules DROP COLUMN emergency;
+
+drop TABLE volume_profile;
\ No newline at end of file
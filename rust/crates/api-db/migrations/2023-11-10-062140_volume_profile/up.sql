@@ -0,0 +1,17 @@
+-- Your SQL goes here
+
+create TABLE
+    volume_profile(
+        id SERIAL PRIMARY KEY NOT NULL,
+        avs_id INTEGER,
+        user_group_id INTEGER,
+        position INTEGER NOT NULL,
+        start_time VARCHAR(5) NOT NULL,
+        end_time VARCHAR(5) NOT NULL,
+        multiplier FLOAT8 NOT NULL,
+        FOREIGN KEY (avs_id) REFERENCES avs(id) ON DELETE CASCADE,
+        FOREIGN KEY (user_group_id) REFERENCES user_group(id) ON DELETE CASCADE,
+        CHECK ((avs_id IS NULL) <> (user_group_id IS NULL))
+    );
+
+alter TABLE schedules ADD COLUMN emergency BOOLEAN NOT NULL DEFAULT false;
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+
+drop TABLE playlist_item;
+
+drop TABLE playlist;
\ No newline at end of file
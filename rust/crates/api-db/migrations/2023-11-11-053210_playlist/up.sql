@@ -0,0 +1,29 @@
+-- Your SQL goes here
+
+create TABLE
+    playlist(
+        id SERIAL PRIMARY KEY NOT NULL,
+        avs_id INTEGER UNIQUE,
+        user_group_id INTEGER UNIQUE,
+        shuffle BOOLEAN NOT NULL DEFAULT false,
+        repeat BOOLEAN NOT NULL DEFAULT true,
+        start_time VARCHAR(5),
+        end_time VARCHAR(5),
+        volume FLOAT8,
+        duck FLOAT8 NOT NULL DEFAULT 0.2,
+        FOREIGN KEY (avs_id) REFERENCES avs(id) ON DELETE CASCADE,
+        FOREIGN KEY (user_group_id) REFERENCES user_group(id) ON DELETE CASCADE,
+        CHECK ((avs_id IS NULL) <> (user_group_id IS NULL))
+    );
+
+create TABLE
+    playlist_item(
+        id SERIAL PRIMARY KEY NOT NULL,
+        playlist_id INTEGER NOT NULL,
+        records_id INTEGER NOT NULL,
+        position INTEGER NOT NULL,
+        FOREIGN KEY (playlist_id) REFERENCES playlist(id) ON DELETE CASCADE,
+        FOREIGN KEY (records_id) REFERENCES records(id) ON DELETE CASCADE
+    );
+
+create INDEX playlist_item_playlist_id_idx ON playlist_item(playlist_id);
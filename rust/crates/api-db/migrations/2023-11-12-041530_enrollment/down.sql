@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+
+drop TABLE enrollment_token;
+
+alter TABLE avs DROP COLUMN credential_hash;
\ No newline at end of file
@@ -0,0 +1,22 @@
+-- Your SQL goes here
+
+alter TABLE avs ADD COLUMN credential_hash VARCHAR(64);
+
+create TABLE
+    enrollment_token(
+        id SERIAL PRIMARY KEY NOT NULL,
+        token_hash VARCHAR(64) NOT NULL UNIQUE,
+        user_group_id INTEGER,
+        lat FLOAT8,
+        lng FLOAT8,
+        address VARCHAR(255),
+        description VARCHAR(255),
+        created_by INTEGER NOT NULL,
+        created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+        expires_at TIMESTAMP NOT NULL,
+        used_at TIMESTAMP,
+        avs_id INTEGER,
+        FOREIGN KEY (user_group_id) REFERENCES user_group(id) ON DELETE SET NULL,
+        FOREIGN KEY (created_by) REFERENCES users(id) ON DELETE CASCADE,
+        FOREIGN KEY (avs_id) REFERENCES avs(id) ON DELETE SET NULL
+    );
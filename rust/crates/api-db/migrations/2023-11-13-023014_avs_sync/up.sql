@@ -0,0 +1,11 @@
+-- Your SQL goes here
+
+create TABLE
+    avs_sync(
+        id SERIAL PRIMARY KEY NOT NULL,
+        avs_id INTEGER NOT NULL UNIQUE,
+        version BIGINT NOT NULL DEFAULT 0,
+        entries TEXT[] NOT NULL DEFAULT '{}',
+        updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+        FOREIGN KEY (avs_id) REFERENCES avs(id) ON DELETE CASCADE
+    );
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+
+drop TABLE self_test_check;
+
+drop TABLE self_test;
\ No newline at end of file
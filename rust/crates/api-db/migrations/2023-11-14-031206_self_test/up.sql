@@ -0,0 +1,27 @@
+-- Your SQL goes here
+
+create TABLE
+    self_test(
+        id SERIAL PRIMARY KEY NOT NULL,
+        avs_id INTEGER NOT NULL,
+        requested_by INTEGER,
+        requested_at TIMESTAMP NOT NULL DEFAULT NOW(),
+        finished_at TIMESTAMP,
+        passed BOOLEAN,
+        version VARCHAR(64),
+        FOREIGN KEY (avs_id) REFERENCES avs(id) ON DELETE CASCADE,
+        FOREIGN KEY (requested_by) REFERENCES users(id) ON DELETE SET NULL
+    );
+
+create INDEX self_test_avs_idx ON self_test (avs_id, requested_at);
+
+create TABLE
+    self_test_check(
+        id SERIAL PRIMARY KEY NOT NULL,
+        self_test_id INTEGER NOT NULL,
+        name VARCHAR(32) NOT NULL,
+        passed BOOLEAN,
+        detail TEXT NOT NULL,
+        duration_ms INTEGER NOT NULL,
+        FOREIGN KEY (self_test_id) REFERENCES self_test(id) ON DELETE CASCADE
+    );
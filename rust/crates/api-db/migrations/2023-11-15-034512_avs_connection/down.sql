@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+
+drop TABLE avs_connection;
\ No newline at end of file
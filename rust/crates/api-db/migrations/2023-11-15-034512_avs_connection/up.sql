@@ -0,0 +1,14 @@
+-- Your SQL goes here
+
+create TABLE
+    avs_connection(
+        id SERIAL PRIMARY KEY NOT NULL,
+        avs_id INTEGER NOT NULL,
+        connected_at TIMESTAMP NOT NULL DEFAULT NOW(),
+        disconnected_at TIMESTAMP,
+        remote_addr VARCHAR(255),
+        reason TEXT,
+        FOREIGN KEY (avs_id) REFERENCES avs(id) ON DELETE CASCADE
+    );
+
+create INDEX avs_connection_avs_idx ON avs_connection (avs_id, connected_at);
\ No newline at end of file
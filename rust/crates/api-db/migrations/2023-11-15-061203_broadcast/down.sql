@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+
+drop TABLE broadcast_target;
+
+drop TABLE broadcast;
\ No newline at end of file
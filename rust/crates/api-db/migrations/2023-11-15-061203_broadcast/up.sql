@@ -0,0 +1,29 @@
+-- Your SQL goes here
+
+create TABLE
+    broadcast(
+        id SERIAL PRIMARY KEY NOT NULL,
+        user_id INTEGER,
+        emergency BOOLEAN NOT NULL DEFAULT FALSE,
+        started_at TIMESTAMP NOT NULL DEFAULT NOW(),
+        ended_at TIMESTAMP,
+        airtime_seconds INTEGER,
+        FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE SET NULL
+    );
+
+create INDEX broadcast_started_idx ON broadcast (started_at);
+
+create TABLE
+    broadcast_target(
+        id SERIAL PRIMARY KEY NOT NULL,
+        broadcast_id INTEGER NOT NULL,
+        avs_id INTEGER NOT NULL,
+        joined_at TIMESTAMP,
+        left_at TIMESTAMP,
+        failed_at TIMESTAMP,
+        excluded VARCHAR(32),
+        FOREIGN KEY (broadcast_id) REFERENCES broadcast(id) ON DELETE CASCADE,
+        FOREIGN KEY (avs_id) REFERENCES avs(id) ON DELETE CASCADE
+    );
+
+create INDEX broadcast_target_avs_idx ON broadcast_target (avs_id, broadcast_id);
\ No newline at end of file
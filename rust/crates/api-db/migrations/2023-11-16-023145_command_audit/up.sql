@@ -0,0 +1,21 @@
+-- Your SQL goes here
+
+create TABLE
+    command_audit(
+        id SERIAL PRIMARY KEY NOT NULL,
+        user_id INTEGER,
+        avs_id INTEGER NOT NULL,
+        command TEXT NOT NULL,
+        delivered BOOLEAN NOT NULL DEFAULT FALSE,
+        requested_at TIMESTAMP NOT NULL DEFAULT NOW(),
+        responded_at TIMESTAMP,
+        exit_status INTEGER,
+        output TEXT,
+        truncated BOOLEAN NOT NULL DEFAULT FALSE,
+        FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE SET NULL,
+        FOREIGN KEY (avs_id) REFERENCES avs(id) ON DELETE CASCADE
+    );
+
+create INDEX command_audit_avs_idx ON command_audit (avs_id, requested_at);
+
+create INDEX command_audit_user_idx ON command_audit (user_id, requested_at);
\ No newline at end of file
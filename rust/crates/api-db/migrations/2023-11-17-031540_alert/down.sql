@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+
+drop TABLE alert;
+
+drop TABLE alert_rule;
\ No newline at end of file
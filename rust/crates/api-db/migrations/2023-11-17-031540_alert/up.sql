@@ -0,0 +1,39 @@
+-- Your SQL goes here
+
+create TABLE
+    alert_rule(
+        id SERIAL PRIMARY KEY NOT NULL,
+        user_group_id INTEGER NOT NULL,
+        offline_minutes INTEGER,
+        disk_free_mb BIGINT,
+        cpu_temp_max FLOAT8,
+        failed_plays INTEGER,
+        enabled BOOLEAN NOT NULL DEFAULT TRUE,
+        updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+        FOREIGN KEY (user_group_id) REFERENCES user_group(id) ON DELETE CASCADE
+    );
+
+create UNIQUE INDEX alert_rule_group_idx ON alert_rule (user_group_id);
+
+create TABLE
+    alert(
+        id SERIAL PRIMARY KEY NOT NULL,
+        avs_id INTEGER NOT NULL,
+        alert_rule_id INTEGER,
+        kind VARCHAR(32) NOT NULL,
+        status VARCHAR(16) NOT NULL DEFAULT 'open',
+        message TEXT NOT NULL,
+        value FLOAT8 NOT NULL,
+        opened_at TIMESTAMP NOT NULL DEFAULT NOW(),
+        acknowledged_at TIMESTAMP,
+        acknowledged_by INTEGER,
+        resolved_at TIMESTAMP,
+        notified_at TIMESTAMP,
+        FOREIGN KEY (avs_id) REFERENCES avs(id) ON DELETE CASCADE,
+        FOREIGN KEY (alert_rule_id) REFERENCES alert_rule(id) ON DELETE SET NULL,
+        FOREIGN KEY (acknowledged_by) REFERENCES users(id) ON DELETE SET NULL
+    );
+
+create UNIQUE INDEX alert_active_idx ON alert (avs_id, kind) WHERE status <> 'resolved';
+
+create INDEX alert_opened_idx ON alert (opened_at);
\ No newline at end of file
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+
+drop TABLE outbox_receipt;
\ No newline at end of file
@@ -0,0 +1,15 @@
+-- Your SQL goes here
+
+create TABLE
+    outbox_receipt(
+        id SERIAL PRIMARY KEY NOT NULL,
+        avs_id INTEGER NOT NULL,
+        outbox_id INTEGER NOT NULL,
+        created_at TIMESTAMP NOT NULL,
+        received_at TIMESTAMP NOT NULL DEFAULT NOW(),
+        FOREIGN KEY (avs_id) REFERENCES avs(id) ON DELETE CASCADE
+    );
+
+create UNIQUE INDEX outbox_receipt_entry_idx ON outbox_receipt (avs_id, outbox_id, created_at);
+
+create INDEX outbox_receipt_received_idx ON outbox_receipt (received_at);
\ No newline at end of file
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+
+alter TABLE play_log DROP COLUMN utc_offset;
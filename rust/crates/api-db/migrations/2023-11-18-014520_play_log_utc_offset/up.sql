@@ -0,0 +1,3 @@
+-- Your SQL goes here
+
+alter TABLE play_log ADD COLUMN utc_offset INTEGER;
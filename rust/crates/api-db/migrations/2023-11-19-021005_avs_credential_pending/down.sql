@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+
+alter TABLE avs DROP COLUMN credential_pending;
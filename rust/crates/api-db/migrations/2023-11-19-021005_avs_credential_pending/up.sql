@@ -0,0 +1,3 @@
+-- Your SQL goes here
+
+alter TABLE avs ADD COLUMN credential_pending VARCHAR(64);
@@ -0,0 +1,196 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use crate::ApiDatabase;
+use database::{DatabaseRepository, Result};
+use diesel::prelude::*;
+use types::api::{
+    alert::{Alert, NewAlert},
+    schema::*,
+};
+
+/// AlertFilter.
+/// The filter of the alerts.
+#[derive(Default)]
+pub struct AlertFilter {
+    /// Only the alerts of the avs, every avs when unset.
+    pub avs_ids: Option<Vec<i32>>,
+    pub status: Option<String>,
+    pub kind: Option<String>,
+}
+
+/// AlertRepo.
+/// Repository for `alert` table.
+#[derive(Clone)]
+pub struct AlertRepo {
+    db: ApiDatabase,
+}
+
+impl AlertRepo {
+    /// Get alert by id.
+    pub fn get(&self, id: i32) -> Result<Alert> {
+        self.db.run(|conn| {
+            alert::table
+                .find(id)
+                .first::<Alert>(conn)
+                .map_err(Into::into)
+        })
+    }
+
+    /// Get the unresolved alerts.
+    pub fn get_active(&self) -> Result<Vec<Alert>> {
+        self.db.run(|conn| {
+            alert::table
+                .filter(alert::status.ne("resolved"))
+                .load::<Alert>(conn)
+                .map_err(Into::into)
+        })
+    }
+
+    /// Open an alert.
+    /// An alert of the same avs and kind resolved after `reopen_after` is reopened instead,
+    /// and keeps its notification, so a flapping condition doesn't notify again.
+    pub fn open(&self, new: NewAlert, reopen_after: chrono::NaiveDateTime) -> Result<Alert> {
+        self.db.run_transaction(|conn| {
+            let recent = alert::table
+                .filter(alert::avs_id.eq(new.avs_id))
+                .filter(alert::kind.eq(&new.kind))
+                .filter(alert::status.eq("resolved"))
+                .filter(alert::resolved_at.ge(reopen_after))
+                .order(alert::resolved_at.desc())
+                .select(alert::id)
+                .first::<i32>(conn)
+                .optional()?;
+            if let Some(id) = recent {
+                return diesel::update(alert::table.find(id))
+                    .set((
+                        alert::status.eq("open"),
+                        alert::alert_rule_id.eq(new.alert_rule_id),
+                        alert::message.eq(&new.message),
+                        alert::value.eq(new.value),
+                        alert::acknowledged_at.eq(None::<chrono::NaiveDateTime>),
+                        alert::acknowledged_by.eq(None::<i32>),
+                        alert::resolved_at.eq(None::<chrono::NaiveDateTime>),
+                    ))
+                    .get_result::<Alert>(conn)
+                    .map_err(Into::into);
+            }
+            diesel::insert_into(alert::table)
+                .values(&new)
+                .get_result::<Alert>(conn)
+                .map_err(Into::into)
+        })
+    }
+
+    /// Update the measured value of an unresolved alert.
+    pub fn refresh(&self, id: i32, message: &str, value: f64) -> Result<()> {
+        self.db.run(|conn| {
+            diesel::update(
+                alert::table
+                    .filter(alert::id.eq(id))
+                    .filter(alert::status.ne("resolved")),
+            )
+            .set((alert::message.eq(message), alert::value.eq(value)))
+            .execute(conn)?;
+            Ok(())
+        })
+    }
+
+    /// Mark the group users as notified of the alert.
+    pub fn set_notified(&self, id: i32) -> Result<()> {
+        self.db.run(|conn| {
+            diesel::update(alert::table.find(id))
+                .set(alert::notified_at.eq(utils::time::now()))
+                .execute(conn)?;
+            Ok(())
+        })
+    }
+
+    /// Acknowledge an open alert.
+    pub fn acknowledge(&self, id: i32, user_id: i32) -> Result<Alert> {
+        self.db.run(|conn| {
+            diesel::update(
+                alert::table
+                    .filter(alert::id.eq(id))
+                    .filter(alert::status.eq("open")),
+            )
+            .set((
+                alert::status.eq("acknowledged"),
+                alert::acknowledged_at.eq(utils::time::now()),
+                alert::acknowledged_by.eq(user_id),
+            ))
+            .execute(conn)?;
+            alert::table
+                .find(id)
+                .first::<Alert>(conn)
+                .map_err(Into::into)
+        })
+    }
+
+    /// Resolve an unresolved alert.
+    pub fn resolve(&self, id: i32) -> Result<Alert> {
+        self.db.run(|conn| {
+            diesel::update(
+                alert::table
+                    .filter(alert::id.eq(id))
+                    .filter(alert::status.ne("resolved")),
+            )
+            .set((
+                alert::status.eq("resolved"),
+                alert::resolved_at.eq(utils::time::now()),
+            ))
+            .execute(conn)?;
+            alert::table
+                .find(id)
+                .first::<Alert>(conn)
+                .map_err(Into::into)
+        })
+    }
+
+    /// Get the alerts matching the filter, newest first.
+    pub fn get_paginated(&self, filter: AlertFilter, page: i64) -> Result<(Vec<Alert>, i64, i64)> {
+        self.db.run(|conn| {
+            let query = || {
+                let mut query = alert::table.into_boxed::<diesel::pg::Pg>();
+                if let Some(avs_ids) = &filter.avs_ids {
+                    query = query.filter(alert::avs_id.eq_any(avs_ids.clone()));
+                }
+                if let Some(status) = &filter.status {
+                    query = query.filter(alert::status.eq(status.clone()));
+                }
+                if let Some(kind) = &filter.kind {
+                    query = query.filter(alert::kind.eq(kind.clone()));
+                }
+                query
+            };
+            let alerts = query()
+                .order(alert::opened_at.desc())
+                .limit(10)
+                .offset((page - 1) * 10)
+                .load::<Alert>(conn)?;
+            let total = query().count().get_result::<i64>(conn)?;
+            let total_pages = (total - 1) / 10 + 1;
+            Ok((alerts, total, total_pages))
+        })
+    }
+}
+
+impl DatabaseRepository<PgConnection> for AlertRepo {
+    fn new(db: database::Database<PgConnection>) -> Self {
+        Self { db }
+    }
+}
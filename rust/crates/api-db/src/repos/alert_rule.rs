@@ -0,0 +1,105 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use crate::ApiDatabase;
+use database::{DatabaseRepository, Result};
+use diesel::{prelude::*, upsert::excluded};
+use types::api::{
+    alert::{AlertRule, NewAlertRule},
+    schema::*,
+    user_group::UserGroup,
+};
+
+/// AlertRuleRepo.
+/// Repository for `alert_rule` table.
+#[derive(Clone)]
+pub struct AlertRuleRepo {
+    db: ApiDatabase,
+}
+
+impl AlertRuleRepo {
+    /// Get all alert rules, ordered by the group id.
+    pub fn get_all(&self) -> Result<Vec<AlertRule>> {
+        self.db.run(|conn| {
+            alert_rule::table
+                .order(alert_rule::user_group_id.asc())
+                .load::<AlertRule>(conn)
+                .map_err(Into::into)
+        })
+    }
+
+    /// Get the enabled alert rules, ordered by the group id.
+    pub fn get_enabled(&self) -> Result<Vec<AlertRule>> {
+        self.db.run(|conn| {
+            alert_rule::table
+                .filter(alert_rule::enabled.eq(true))
+                .order(alert_rule::user_group_id.asc())
+                .load::<AlertRule>(conn)
+                .map_err(Into::into)
+        })
+    }
+
+    /// Get the alert rule of a group.
+    pub fn get_by_group(&self, group_id: i32) -> Result<Option<AlertRule>> {
+        self.db.run(|conn| {
+            alert_rule::table
+                .filter(alert_rule::user_group_id.eq(group_id))
+                .first::<AlertRule>(conn)
+                .optional()
+                .map_err(Into::into)
+        })
+    }
+
+    /// Create or replace the alert rule of a group.
+    /// The rule keeps its id so the alerts it opened stay linked.
+    pub fn set(&self, rule: NewAlertRule) -> Result<AlertRule> {
+        self.db.run_transaction(|conn| {
+            user_group::table
+                .find(rule.user_group_id)
+                .first::<UserGroup>(conn)?;
+            diesel::insert_into(alert_rule::table)
+                .values(&rule)
+                .on_conflict(alert_rule::user_group_id)
+                .do_update()
+                .set((
+                    alert_rule::offline_minutes.eq(excluded(alert_rule::offline_minutes)),
+                    alert_rule::disk_free_mb.eq(excluded(alert_rule::disk_free_mb)),
+                    alert_rule::cpu_temp_max.eq(excluded(alert_rule::cpu_temp_max)),
+                    alert_rule::failed_plays.eq(excluded(alert_rule::failed_plays)),
+                    alert_rule::enabled.eq(excluded(alert_rule::enabled)),
+                    alert_rule::updated_at.eq(excluded(alert_rule::updated_at)),
+                ))
+                .get_result(conn)
+                .map_err(Into::into)
+        })
+    }
+
+    /// Delete the alert rule of a group.
+    pub fn delete_by_group(&self, group_id: i32) -> Result<()> {
+        self.db.run(|conn| {
+            diesel::delete(alert_rule::table.filter(alert_rule::user_group_id.eq(group_id)))
+                .execute(conn)?;
+            Ok(())
+        })
+    }
+}
+
+impl DatabaseRepository<PgConnection> for AlertRuleRepo {
+    fn new(db: database::Database<PgConnection>) -> Self {
+        Self { db }
+    }
+}
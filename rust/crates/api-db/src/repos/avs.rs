@@ -37,6 +37,12 @@ impl AvsRepo {
             .run(|conn| avs::table.load::<Avs>(conn).map_err(Into::into))
     }
 
+    /// Get avs by id.
+    pub fn get(&self, id: i32) -> Result<Avs> {
+        self.db
+            .run(|conn| avs::table.find(id).first::<Avs>(conn).map_err(Into::into))
+    }
+
     /// Update slots.
     pub fn update_slots(&self, id: i32, slots: &str) -> Result<()> {
         self.db.run(|conn| {
@@ -57,6 +63,25 @@ impl AvsRepo {
         })
     }
 
+    /// Get avs owned by the users of a group.
+    pub fn get_by_group(&self, group_id: i32) -> Result<Vec<Avs>> {
+        self.db.run(|conn| {
+            let device_ids = users::table
+                .filter(users::user_group_ids.contains(vec![Some(group_id)]))
+                .select(users::device_ids)
+                .load::<Vec<Option<i32>>>(conn)?;
+            let ids = device_ids
+                .into_iter()
+                .flatten()
+                .flatten()
+                .collect::<Vec<i32>>();
+            avs::table
+                .filter(avs::id.eq_any(ids))
+                .load::<Avs>(conn)
+                .map_err(Into::into)
+        })
+    }
+
     /// Create new avs.
     pub fn create(&self, avs: NewAvs) -> Result<Avs> {
         self.db.run(|conn| {
@@ -108,6 +133,53 @@ impl AvsRepo {
         })
     }
 
+    /// Set the credential hash of an avs, dropping the credential waiting for acknowledgement.
+    /// With `None` the avs adopts a new credential on its next connection.
+    pub fn set_credential(&self, id: i32, credential_hash: Option<&str>) -> Result<()> {
+        self.db.run(|conn| {
+            diesel::update(avs::table.filter(avs::id.eq(id)))
+                .set((
+                    avs::credential_hash.eq(credential_hash),
+                    avs::credential_pending.eq(None::<String>),
+                ))
+                .execute(conn)?;
+            Ok(())
+        })
+    }
+
+    /// Issue a credential to an avs without one, it only replaces the credential hash
+    /// once the avs acknowledges it.
+    pub fn issue_credential(&self, id: i32, credential_hash: &str) -> Result<()> {
+        self.db.run(|conn| {
+            diesel::update(
+                avs::table
+                    .filter(avs::id.eq(id))
+                    .filter(avs::credential_hash.is_null()),
+            )
+            .set(avs::credential_pending.eq(credential_hash))
+            .execute(conn)?;
+            Ok(())
+        })
+    }
+
+    /// Make the issued credential of an avs its credential.
+    /// Returns `false` when no credential is waiting for acknowledgement.
+    pub fn confirm_credential(&self, id: i32) -> Result<bool> {
+        self.db.run(|conn| {
+            let updated = diesel::update(
+                avs::table
+                    .filter(avs::id.eq(id))
+                    .filter(avs::credential_pending.is_not_null()),
+            )
+            .set((
+                avs::credential_hash.eq(avs::credential_pending),
+                avs::credential_pending.eq(None::<String>),
+            ))
+            .execute(conn)?;
+            Ok(updated > 0)
+        })
+    }
+
     /// Accept pending avs.
     pub fn accept(&self, id: i32) -> Result<Avs> {
         self.db.run(|conn| {
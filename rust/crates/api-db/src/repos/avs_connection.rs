@@ -0,0 +1,135 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use crate::ApiDatabase;
+use database::{DatabaseRepository, Result};
+use diesel::prelude::*;
+use types::api::{
+    avs_connection::{AvsConnection, NewAvsConnection},
+    schema::*,
+};
+
+/// AvsConnectionRepo.
+/// Repository for `avs_connection` table.
+#[derive(Clone)]
+pub struct AvsConnectionRepo {
+    db: ApiDatabase,
+}
+
+impl AvsConnectionRepo {
+    /// Open a connection session of the avs.
+    /// Any session left open for the avs is closed as `superseded`.
+    pub fn open(&self, avs_id: i32, remote_addr: Option<String>) -> Result<AvsConnection> {
+        self.db.run_transaction(|conn| {
+            let now = utils::time::now();
+            diesel::update(
+                avs_connection::table
+                    .filter(avs_connection::avs_id.eq(avs_id))
+                    .filter(avs_connection::disconnected_at.is_null()),
+            )
+            .set((
+                avs_connection::disconnected_at.eq(now),
+                avs_connection::reason.eq("superseded"),
+            ))
+            .execute(conn)?;
+            diesel::insert_into(avs_connection::table)
+                .values(&NewAvsConnection {
+                    avs_id,
+                    connected_at: now,
+                    remote_addr,
+                })
+                .get_result(conn)
+                .map_err(Into::into)
+        })
+    }
+
+    /// Close the connection session, unless it was closed already.
+    pub fn close(&self, id: i32, reason: &str) -> Result<()> {
+        self.db.run(|conn| {
+            diesel::update(
+                avs_connection::table
+                    .filter(avs_connection::id.eq(id))
+                    .filter(avs_connection::disconnected_at.is_null()),
+            )
+            .set((
+                avs_connection::disconnected_at.eq(utils::time::now()),
+                avs_connection::reason.eq(reason),
+            ))
+            .execute(conn)?;
+            Ok(())
+        })
+    }
+
+    /// Close every open connection session.
+    pub fn close_all(&self, reason: &str) -> Result<()> {
+        self.db.run(|conn| {
+            diesel::update(avs_connection::table.filter(avs_connection::disconnected_at.is_null()))
+                .set((
+                    avs_connection::disconnected_at.eq(utils::time::now()),
+                    avs_connection::reason.eq(reason),
+                ))
+                .execute(conn)?;
+            Ok(())
+        })
+    }
+
+    /// Get the connection sessions of the avs overlapping the range, oldest first.
+    pub fn get_by_avs(
+        &self,
+        avs_ids: &[i32],
+        from: chrono::NaiveDateTime,
+        to: chrono::NaiveDateTime,
+    ) -> Result<Vec<AvsConnection>> {
+        self.db.run(|conn| {
+            avs_connection::table
+                .filter(avs_connection::avs_id.eq_any(avs_ids))
+                .filter(avs_connection::connected_at.lt(to))
+                .filter(
+                    avs_connection::disconnected_at
+                        .is_null()
+                        .or(avs_connection::disconnected_at.gt(from)),
+                )
+                .order(avs_connection::connected_at.asc())
+                .load::<AvsConnection>(conn)
+                .map_err(Into::into)
+        })
+    }
+
+    /// Get when each of the avs last disconnected, the avs that never connected are left out.
+    pub fn last_disconnected(
+        &self,
+        avs_ids: &[i32],
+    ) -> Result<Vec<(i32, Option<chrono::NaiveDateTime>)>> {
+        self.db.run(|conn| {
+            avs_connection::table
+                .filter(avs_connection::avs_id.eq_any(avs_ids))
+                .group_by(avs_connection::avs_id)
+                .select((
+                    avs_connection::avs_id,
+                    diesel::dsl::max(avs_connection::disconnected_at),
+                ))
+                .load::<(i32, Option<chrono::NaiveDateTime>)>(conn)
+                .map_err(Into::into)
+        })
+    }
+}
+
+impl DatabaseRepository<PgConnection> for AvsConnectionRepo {
+    fn new(db: database::Database<PgConnection>) -> Self {
+        Self { db }
+    }
+}
@@ -0,0 +1,120 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use crate::ApiDatabase;
+use database::{DatabaseRepository, Result};
+use diesel::prelude::*;
+use types::api::{
+    avs_port::{AvsPort, NewAvsPort},
+    schema::*,
+};
+
+/// AvsPortRepo.
+/// Repository for `avs_port` table.
+#[derive(Clone)]
+pub struct AvsPortRepo {
+    db: ApiDatabase,
+}
+
+impl AvsPortRepo {
+    /// Get port by id.
+    pub fn get(&self, id: i32) -> Result<AvsPort> {
+        self.db.run(|conn| {
+            avs_port::table
+                .find(id)
+                .first::<AvsPort>(conn)
+                .map_err(Into::into)
+        })
+    }
+
+    /// Get ports of an avs.
+    pub fn get_by_avs(&self, avs_id: i32) -> Result<Vec<AvsPort>> {
+        self.db.run(|conn| {
+            avs_port::table
+                .filter(avs_port::avs_id.eq(avs_id))
+                .order(avs_port::device.asc())
+                .load::<AvsPort>(conn)
+                .map_err(Into::into)
+        })
+    }
+
+    /// Store the output devices reported by an avs.
+    /// Known devices keep their name and zone, missing devices are marked as unavailable.
+    pub fn report(&self, avs_id: i32, ports: Vec<(i32, String)>) -> Result<()> {
+        self.db.run_transaction(|conn| {
+            diesel::update(avs_port::table.filter(avs_port::avs_id.eq(avs_id)))
+                .set(avs_port::available.eq(false))
+                .execute(conn)?;
+            for (speaker, device) in ports {
+                let port = NewAvsPort {
+                    avs_id,
+                    name: device.clone(),
+                    speaker,
+                    device,
+                    zone: None,
+                    available: true,
+                };
+                diesel::insert_into(avs_port::table)
+                    .values(&port)
+                    .on_conflict((avs_port::avs_id, avs_port::device))
+                    .do_update()
+                    .set((avs_port::speaker.eq(speaker), avs_port::available.eq(true)))
+                    .execute(conn)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Update the name and zone of a port.
+    pub fn update(&self, id: i32, name: &str, zone: Option<&str>) -> Result<AvsPort> {
+        self.db.run(|conn| {
+            diesel::update(avs_port::table.find(id))
+                .set((avs_port::name.eq(name), avs_port::zone.eq(zone)))
+                .get_result::<AvsPort>(conn)
+                .map_err(Into::into)
+        })
+    }
+
+    /// Resolve the ports and zones into the output devices of an avs.
+    /// Returns an empty list when nothing matches, meaning the default output.
+    pub fn resolve_outputs(
+        &self,
+        avs_id: i32,
+        port_ids: &[i32],
+        zones: &[String],
+    ) -> Result<Vec<String>> {
+        if port_ids.is_empty() && zones.is_empty() {
+            return Ok(vec![]);
+        }
+        let ports = self.get_by_avs(avs_id)?;
+        Ok(ports
+            .into_iter()
+            .filter(|p| {
+                p.available
+                    && (port_ids.contains(&p.id)
+                        || p.zone.as_ref().map(|z| zones.contains(z)).unwrap_or(false))
+            })
+            .map(|p| p.device)
+            .collect())
+    }
+}
+
+impl DatabaseRepository<PgConnection> for AvsPortRepo {
+    fn new(db: database::Database<PgConnection>) -> Self {
+        Self { db }
+    }
+}
@@ -0,0 +1,67 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use crate::ApiDatabase;
+use database::{DatabaseRepository, Result};
+use diesel::prelude::*;
+use types::api::{
+    avs_sync::{AvsSync, NewAvsSync},
+    schema::*,
+};
+
+/// AvsSyncRepo.
+/// Repository for `avs_sync` table.
+#[derive(Clone)]
+pub struct AvsSyncRepo {
+    db: ApiDatabase,
+}
+
+impl AvsSyncRepo {
+    /// Get the sync state of an avs.
+    pub fn get(&self, avs_id: i32) -> Result<Option<AvsSync>> {
+        self.db.run(|conn| {
+            avs_sync::table
+                .filter(avs_sync::avs_id.eq(avs_id))
+                .first::<AvsSync>(conn)
+                .optional()
+                .map_err(Into::into)
+        })
+    }
+
+    /// Store the sync state of an avs.
+    pub fn set(&self, new: NewAvsSync) -> Result<AvsSync> {
+        self.db.run(|conn| {
+            diesel::insert_into(avs_sync::table)
+                .values(&new)
+                .on_conflict(avs_sync::avs_id)
+                .do_update()
+                .set((
+                    avs_sync::version.eq(new.version),
+                    avs_sync::entries.eq(&new.entries),
+                    avs_sync::updated_at.eq(new.updated_at),
+                ))
+                .get_result(conn)
+                .map_err(Into::into)
+        })
+    }
+}
+
+impl DatabaseRepository<PgConnection> for AvsSyncRepo {
+    fn new(db: database::Database<PgConnection>) -> Self {
+        Self { db }
+    }
+}
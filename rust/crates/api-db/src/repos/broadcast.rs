@@ -0,0 +1,227 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use crate::ApiDatabase;
+use database::{DatabaseRepository, Result};
+use diesel::prelude::*;
+use types::api::{
+    broadcast::{Broadcast, BroadcastTarget, NewBroadcast, NewBroadcastTarget},
+    schema::*,
+};
+
+/// BroadcastFilter.
+/// The filter of the broadcast history.
+#[derive(Default)]
+pub struct BroadcastFilter {
+    pub user_ids: Option<Vec<i32>>,
+    pub avs_ids: Option<Vec<i32>>,
+    pub from: Option<chrono::NaiveDateTime>,
+    pub to: Option<chrono::NaiveDateTime>,
+}
+
+/// BroadcastRepo.
+/// Repository for `broadcast` table.
+#[derive(Clone)]
+pub struct BroadcastRepo {
+    db: ApiDatabase,
+}
+
+impl BroadcastRepo {
+    /// Create a broadcast along with its targets.
+    pub fn create(&self, new: NewBroadcast, targets: Vec<NewBroadcastTarget>) -> Result<Broadcast> {
+        self.db.run_transaction(|conn| {
+            let broadcast = diesel::insert_into(broadcast::table)
+                .values(&new)
+                .get_result::<Broadcast>(conn)?;
+            let targets = targets
+                .into_iter()
+                .map(|target| NewBroadcastTarget {
+                    broadcast_id: broadcast.id,
+                    ..target
+                })
+                .collect::<Vec<NewBroadcastTarget>>();
+            diesel::insert_into(broadcast_target::table)
+                .values(&targets)
+                .execute(conn)?;
+            Ok(broadcast)
+        })
+    }
+
+    /// Mark the target as joined, the first join time is kept.
+    pub fn joined(&self, broadcast_id: i32, avs_id: i32) -> Result<()> {
+        self.db.run(|conn| {
+            diesel::update(
+                broadcast_target::table
+                    .filter(broadcast_target::broadcast_id.eq(broadcast_id))
+                    .filter(broadcast_target::avs_id.eq(avs_id))
+                    .filter(broadcast_target::joined_at.is_null()),
+            )
+            .set(broadcast_target::joined_at.eq(utils::time::now()))
+            .execute(conn)?;
+            Ok(())
+        })
+    }
+
+    /// Mark the target as failed.
+    pub fn failed(&self, broadcast_id: i32, avs_id: i32) -> Result<()> {
+        self.db.run(|conn| {
+            diesel::update(
+                broadcast_target::table
+                    .filter(broadcast_target::broadcast_id.eq(broadcast_id))
+                    .filter(broadcast_target::avs_id.eq(avs_id)),
+            )
+            .set(broadcast_target::failed_at.eq(utils::time::now()))
+            .execute(conn)?;
+            Ok(())
+        })
+    }
+
+    /// Mark the target as left, `excluded` is set when the target couldn't be recovered.
+    pub fn left(&self, broadcast_id: i32, avs_id: i32, excluded: Option<&str>) -> Result<()> {
+        self.db.run(|conn| {
+            let target = broadcast_target::table
+                .filter(broadcast_target::broadcast_id.eq(broadcast_id))
+                .filter(broadcast_target::avs_id.eq(avs_id))
+                .filter(broadcast_target::left_at.is_null());
+            let now = utils::time::now();
+            match excluded {
+                Some(excluded) => diesel::update(target)
+                    .set((
+                        broadcast_target::left_at.eq(now),
+                        broadcast_target::excluded.eq(excluded),
+                    ))
+                    .execute(conn)?,
+                None => diesel::update(target)
+                    .set(broadcast_target::left_at.eq(now))
+                    .execute(conn)?,
+            };
+            Ok(())
+        })
+    }
+
+    /// End the broadcast, the targets that are still connected leave.
+    pub fn finish(&self, id: i32) -> Result<()> {
+        self.db.run_transaction(|conn| {
+            let now = utils::time::now();
+            let broadcast = diesel::update(
+                broadcast::table
+                    .filter(broadcast::id.eq(id))
+                    .filter(broadcast::ended_at.is_null()),
+            )
+            .set(broadcast::ended_at.eq(now))
+            .get_result::<Broadcast>(conn)
+            .optional()?;
+            if let Some(broadcast) = broadcast {
+                let airtime = (now - broadcast.started_at).num_seconds() as i32;
+                diesel::update(broadcast::table.filter(broadcast::id.eq(id)))
+                    .set(broadcast::airtime_seconds.eq(airtime))
+                    .execute(conn)?;
+            }
+            diesel::update(
+                broadcast_target::table
+                    .filter(broadcast_target::broadcast_id.eq(id))
+                    .filter(broadcast_target::joined_at.is_not_null())
+                    .filter(broadcast_target::left_at.is_null()),
+            )
+            .set(broadcast_target::left_at.eq(now))
+            .execute(conn)?;
+            Ok(())
+        })
+    }
+
+    /// End every ongoing broadcast.
+    pub fn finish_all(&self) -> Result<()> {
+        let ids = self.db.run(|conn| {
+            broadcast::table
+                .filter(broadcast::ended_at.is_null())
+                .select(broadcast::id)
+                .load::<i32>(conn)
+                .map_err(Into::into)
+        })?;
+        for id in ids {
+            self.finish(id)?;
+        }
+        Ok(())
+    }
+
+    /// Get a broadcast along with its targets.
+    pub fn get(&self, id: i32) -> Result<(Broadcast, Vec<BroadcastTarget>)> {
+        self.db.run(|conn| {
+            let broadcast = broadcast::table
+                .filter(broadcast::id.eq(id))
+                .first::<Broadcast>(conn)?;
+            let targets = BroadcastTarget::belonging_to(&broadcast)
+                .order(broadcast_target::id.asc())
+                .load::<BroadcastTarget>(conn)?;
+            Ok((broadcast, targets))
+        })
+    }
+
+    /// Get the broadcasts matching the filter along with their targets, newest first.
+    pub fn get_paginated(
+        &self,
+        filter: BroadcastFilter,
+        page: i64,
+    ) -> Result<(Vec<(Broadcast, Vec<BroadcastTarget>)>, i64, i64)> {
+        self.db.run(|conn| {
+            let query = || {
+                let mut query = broadcast::table.into_boxed::<diesel::pg::Pg>();
+                if let Some(user_ids) = &filter.user_ids {
+                    query = query.filter(broadcast::user_id.eq_any(user_ids.clone()));
+                }
+                if let Some(avs_ids) = &filter.avs_ids {
+                    query = query.filter(
+                        broadcast::id.eq_any(
+                            broadcast_target::table
+                                .filter(broadcast_target::avs_id.eq_any(avs_ids.clone()))
+                                .select(broadcast_target::broadcast_id),
+                        ),
+                    );
+                }
+                if let Some(from) = filter.from {
+                    query = query.filter(broadcast::started_at.ge(from));
+                }
+                if let Some(to) = filter.to {
+                    query = query.filter(broadcast::started_at.lt(to));
+                }
+                query
+            };
+            let broadcasts = query()
+                .order(broadcast::started_at.desc())
+                .limit(10)
+                .offset((page - 1) * 10)
+                .load::<Broadcast>(conn)?;
+            let total = query().count().get_result::<i64>(conn)?;
+            let total_pages = (total - 1) / 10 + 1;
+            let targets = BroadcastTarget::belonging_to(&broadcasts)
+                .order(broadcast_target::id.asc())
+                .load::<BroadcastTarget>(conn)?
+                .grouped_by(&broadcasts);
+            Ok((
+                broadcasts.into_iter().zip(targets).collect(),
+                total,
+                total_pages,
+            ))
+        })
+    }
+}
+
+impl DatabaseRepository<PgConnection> for BroadcastRepo {
+    fn new(db: database::Database<PgConnection>) -> Self {
+        Self { db }
+    }
+}
@@ -0,0 +1,172 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use crate::ApiDatabase;
+use database::{DatabaseRepository, Result};
+use diesel::prelude::*;
+use types::api::{
+    command_audit::{CommandAudit, NewCommandAudit},
+    schema::*,
+};
+
+/// CommandAuditFilter.
+/// The filter of the command audit.
+#[derive(Default)]
+pub struct CommandAuditFilter {
+    pub user_id: Option<i32>,
+    pub avs_id: Option<i32>,
+    pub q: Option<String>,
+    pub from: Option<chrono::NaiveDateTime>,
+    pub to: Option<chrono::NaiveDateTime>,
+}
+
+/// CommandAuditRepo.
+/// Repository for `command_audit` table.
+#[derive(Clone)]
+pub struct CommandAuditRepo {
+    db: ApiDatabase,
+}
+
+impl CommandAuditRepo {
+    /// Create a command audit.
+    pub fn create(&self, new: NewCommandAudit) -> Result<CommandAudit> {
+        self.db.run(|conn| {
+            diesel::insert_into(command_audit::table)
+                .values(&new)
+                .get_result::<CommandAudit>(conn)
+                .map_err(Into::into)
+        })
+    }
+
+    /// Get the oldest command of the user on the avs that has no response yet.
+    pub fn pending(&self, user_id: i32, avs_id: i32) -> Result<Option<CommandAudit>> {
+        self.db.run(|conn| {
+            command_audit::table
+                .filter(command_audit::user_id.eq(user_id))
+                .filter(command_audit::avs_id.eq(avs_id))
+                .filter(command_audit::delivered.eq(true))
+                .filter(command_audit::responded_at.is_null())
+                .order(command_audit::requested_at.asc())
+                .first::<CommandAudit>(conn)
+                .optional()
+                .map_err(Into::into)
+        })
+    }
+
+    /// Get a command requested on the avs.
+    pub fn get_by_avs(&self, id: i32, avs_id: i32) -> Result<Option<CommandAudit>> {
+        self.db.run(|conn| {
+            command_audit::table
+                .filter(command_audit::id.eq(id))
+                .filter(command_audit::avs_id.eq(avs_id))
+                .first::<CommandAudit>(conn)
+                .optional()
+                .map_err(Into::into)
+        })
+    }
+
+    /// Store the response of a command requested on the avs, only the first response is kept.
+    pub fn respond(
+        &self,
+        id: i32,
+        avs_id: i32,
+        exit_status: Option<i32>,
+        output: String,
+        truncated: bool,
+    ) -> Result<()> {
+        self.db
+            .run(|conn| Self::set_response(conn, id, avs_id, exit_status, output, truncated))
+    }
+
+    /// Store the response of a command on the connection, see [`CommandAuditRepo::respond`].
+    pub fn set_response(
+        conn: &mut PgConnection,
+        id: i32,
+        avs_id: i32,
+        exit_status: Option<i32>,
+        output: String,
+        truncated: bool,
+    ) -> Result<()> {
+        diesel::update(
+            command_audit::table
+                .filter(command_audit::id.eq(id))
+                .filter(command_audit::avs_id.eq(avs_id))
+                .filter(command_audit::responded_at.is_null()),
+        )
+        .set((
+            command_audit::responded_at.eq(utils::time::now()),
+            command_audit::exit_status.eq(exit_status),
+            command_audit::output.eq(output),
+            command_audit::truncated.eq(truncated),
+        ))
+        .execute(conn)?;
+        Ok(())
+    }
+
+    /// Get the commands matching the filter, newest first.
+    pub fn get_paginated(
+        &self,
+        filter: CommandAuditFilter,
+        page: i64,
+    ) -> Result<(Vec<CommandAudit>, i64, i64)> {
+        self.db.run(|conn| {
+            let query = || {
+                let mut query = command_audit::table.into_boxed::<diesel::pg::Pg>();
+                if let Some(user_id) = filter.user_id {
+                    query = query.filter(command_audit::user_id.eq(user_id));
+                }
+                if let Some(avs_id) = filter.avs_id {
+                    query = query.filter(command_audit::avs_id.eq(avs_id));
+                }
+                if let Some(q) = &filter.q {
+                    query = query.filter(command_audit::command.ilike(format!("%{}%", q)));
+                }
+                if let Some(from) = filter.from {
+                    query = query.filter(command_audit::requested_at.ge(from));
+                }
+                if let Some(to) = filter.to {
+                    query = query.filter(command_audit::requested_at.lt(to));
+                }
+                query
+            };
+            let commands = query()
+                .order(command_audit::requested_at.desc())
+                .limit(10)
+                .offset((page - 1) * 10)
+                .load::<CommandAudit>(conn)?;
+            let total = query().count().get_result::<i64>(conn)?;
+            let total_pages = (total - 1) / 10 + 1;
+            Ok((commands, total, total_pages))
+        })
+    }
+
+    /// Delete the commands requested more than `days` ago.
+    pub fn delete_older_than(&self, days: i64) -> Result<usize> {
+        let before = utils::time::now() - chrono::Duration::days(days);
+        self.db.run(|conn| {
+            diesel::delete(command_audit::table.filter(command_audit::requested_at.lt(before)))
+                .execute(conn)
+                .map_err(Into::into)
+        })
+    }
+}
+
+impl DatabaseRepository<PgConnection> for CommandAuditRepo {
+    fn new(db: database::Database<PgConnection>) -> Self {
+        Self { db }
+    }
+}
@@ -0,0 +1,152 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use crate::ApiDatabase;
+use database::{DatabaseRepository, Result};
+use diesel::prelude::*;
+use types::api::{
+    device_config::{DeviceConfig, NewDeviceConfig},
+    schema::*,
+};
+
+/// DeviceConfigRepo.
+/// Repository for `device_config` table.
+#[derive(Clone)]
+pub struct DeviceConfigRepo {
+    db: ApiDatabase,
+}
+
+impl DeviceConfigRepo {
+    /// Get the configuration of an avs.
+    pub fn get_by_avs(&self, avs_id: i32) -> Result<Option<DeviceConfig>> {
+        self.db.run(|conn| {
+            device_config::table
+                .filter(device_config::avs_id.eq(avs_id))
+                .first::<DeviceConfig>(conn)
+                .optional()
+                .map_err(Into::into)
+        })
+    }
+
+    /// Get the configuration of a group.
+    pub fn get_by_group(&self, group_id: i32) -> Result<Option<DeviceConfig>> {
+        self.db.run(|conn| {
+            device_config::table
+                .filter(device_config::user_group_id.eq(group_id))
+                .first::<DeviceConfig>(conn)
+                .optional()
+                .map_err(Into::into)
+        })
+    }
+
+    /// Create or replace the configuration of an avs or a group.
+    pub fn set(&self, config: NewDeviceConfig) -> Result<DeviceConfig> {
+        self.db.run_transaction(|conn| {
+            if let Some(avs_id) = config.avs_id {
+                diesel::delete(device_config::table.filter(device_config::avs_id.eq(avs_id)))
+                    .execute(conn)?;
+            }
+            if let Some(group_id) = config.user_group_id {
+                diesel::delete(
+                    device_config::table.filter(device_config::user_group_id.eq(group_id)),
+                )
+                .execute(conn)?;
+            }
+            diesel::insert_into(device_config::table)
+                .values(config)
+                .get_result(conn)
+                .map_err(Into::into)
+        })
+    }
+
+    /// Delete the configuration of an avs.
+    pub fn delete_by_avs(&self, avs_id: i32) -> Result<()> {
+        self.db.run(|conn| {
+            diesel::delete(device_config::table.filter(device_config::avs_id.eq(avs_id)))
+                .execute(conn)?;
+            Ok(())
+        })
+    }
+
+    /// Delete the configuration of a group.
+    pub fn delete_by_group(&self, group_id: i32) -> Result<()> {
+        self.db.run(|conn| {
+            diesel::delete(device_config::table.filter(device_config::user_group_id.eq(group_id)))
+                .execute(conn)?;
+            Ok(())
+        })
+    }
+
+    /// Resolve the effective configuration of an avs.
+    /// The avs configuration takes precedence, then the configuration of the groups
+    /// the avs owners belong to, ordered by the group id.
+    pub fn resolve(&self, avs_id: i32) -> Result<DeviceConfig> {
+        self.db.run(|conn| {
+            let group_ids = users::table
+                .filter(users::device_ids.contains(vec![Some(avs_id)]))
+                .select(users::user_group_ids)
+                .load::<Vec<Option<i32>>>(conn)?
+                .into_iter()
+                .flatten()
+                .flatten()
+                .collect::<Vec<i32>>();
+            let mut configs = device_config::table
+                .filter(device_config::avs_id.eq(avs_id))
+                .load::<DeviceConfig>(conn)?;
+            configs.extend(
+                device_config::table
+                    .filter(device_config::user_group_id.eq_any(group_ids))
+                    .order(device_config::user_group_id.asc())
+                    .load::<DeviceConfig>(conn)?,
+            );
+
+            let mut res = DeviceConfig {
+                id: 0,
+                avs_id: Some(avs_id),
+                user_group_id: None,
+                volume: None,
+                log_level: None,
+                quiet_start: None,
+                quiet_end: None,
+                telemetry_interval: None,
+                allowed_commands: None,
+                audio_device: None,
+                updated_at: chrono::NaiveDateTime::MIN,
+            };
+            for config in configs {
+                res.volume = res.volume.or(config.volume);
+                res.log_level = res.log_level.or(config.log_level);
+                // quiet hours are a single range, they're inherited together.
+                if res.quiet_start.is_none() && res.quiet_end.is_none() {
+                    res.quiet_start = config.quiet_start;
+                    res.quiet_end = config.quiet_end;
+                }
+                res.telemetry_interval = res.telemetry_interval.or(config.telemetry_interval);
+                res.allowed_commands = res.allowed_commands.or(config.allowed_commands);
+                res.audio_device = res.audio_device.or(config.audio_device);
+                res.updated_at = res.updated_at.max(config.updated_at);
+            }
+            Ok(res)
+        })
+    }
+}
+
+impl DatabaseRepository<PgConnection> for DeviceConfigRepo {
+    fn new(db: database::Database<PgConnection>) -> Self {
+        Self { db }
+    }
+}
@@ -0,0 +1,161 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use crate::ApiDatabase;
+use database::{DatabaseRepository, Result};
+use diesel::prelude::*;
+use types::api::{
+    avs::{Avs, NewAvs},
+    enrollment::{EnrollmentToken, NewEnrollmentToken},
+    schema::*,
+};
+
+/// EnrollmentRepo.
+/// Repository for `enrollment_token` table.
+#[derive(Clone)]
+pub struct EnrollmentRepo {
+    db: ApiDatabase,
+}
+
+impl EnrollmentRepo {
+    /// Get all enrollment tokens, newest first.
+    pub fn get_all(&self) -> Result<Vec<EnrollmentToken>> {
+        self.db.run(|conn| {
+            enrollment_token::table
+                .order(enrollment_token::id.desc())
+                .load::<EnrollmentToken>(conn)
+                .map_err(Into::into)
+        })
+    }
+
+    /// Get an enrollment token by id.
+    pub fn get(&self, id: i32) -> Result<EnrollmentToken> {
+        self.db.run(|conn| {
+            enrollment_token::table
+                .find(id)
+                .first::<EnrollmentToken>(conn)
+                .map_err(Into::into)
+        })
+    }
+
+    /// Create new enrollment token.
+    pub fn create(&self, new: NewEnrollmentToken) -> Result<EnrollmentToken> {
+        self.db.run(|conn| {
+            diesel::insert_into(enrollment_token::table)
+                .values(&new)
+                .get_result(conn)
+                .map_err(Into::into)
+        })
+    }
+
+    /// Delete an enrollment token.
+    pub fn delete(&self, id: i32) -> Result<()> {
+        self.db.run(|conn| {
+            diesel::delete(enrollment_token::table.filter(enrollment_token::id.eq(id)))
+                .execute(conn)?;
+            Ok(())
+        })
+    }
+
+    /// Enroll an avs with the token of the given hash.
+    /// The avs is created as accepted at the location of the token, assigned to the users
+    /// of the token group and stores the hash of its credential until the avs acknowledges it.
+    /// An existing avs without credential, registered before provisioning, is accepted
+    /// and gets the credential instead, its location is only replaced by the token location.
+    /// Returns `None` when the token is unknown, used or expired, or when the avs already
+    /// has a credential.
+    pub fn enroll(
+        &self,
+        token_hash: &str,
+        mut avs: NewAvs,
+        credential_hash: String,
+    ) -> Result<Option<Avs>> {
+        self.db.run_transaction(|conn| {
+            let now = chrono::Utc::now().naive_utc();
+            let token = enrollment_token::table
+                .filter(enrollment_token::token_hash.eq(token_hash))
+                .filter(enrollment_token::used_at.is_null())
+                .filter(enrollment_token::expires_at.gt(now))
+                .for_update()
+                .first::<EnrollmentToken>(conn)
+                .optional()?;
+            let token = match token {
+                Some(token) => token,
+                None => return Ok(None),
+            };
+            let existing = avs::table
+                .filter(avs::unique_id.eq(&avs.unique_id))
+                .for_update()
+                .first::<Avs>(conn)
+                .optional()?;
+            let avs = match existing {
+                Some(existing) if existing.credential_hash.is_some() => return Ok(None),
+                Some(existing) => diesel::update(avs::table.filter(avs::id.eq(existing.id)))
+                    .set((
+                        avs::pending.eq(0),
+                        avs::lat.eq(token.lat.or(existing.lat)),
+                        avs::lng.eq(token.lng.or(existing.lng)),
+                        avs::address.eq(token.address.or(existing.address)),
+                        avs::description.eq(token.description.or(existing.description)),
+                        avs::credential_pending.eq(credential_hash),
+                    ))
+                    .get_result::<Avs>(conn)?,
+                None => {
+                    avs.pending = 0;
+                    avs.lat = token.lat.or(avs.lat);
+                    avs.lng = token.lng.or(avs.lng);
+                    avs.address = token.address.or(avs.address);
+                    avs.description = token.description.or(avs.description);
+                    let avs = diesel::insert_into(avs::table)
+                        .values(avs)
+                        .get_result::<Avs>(conn)?;
+                    diesel::update(avs::table.filter(avs::id.eq(avs.id)))
+                        .set(avs::credential_pending.eq(credential_hash))
+                        .get_result::<Avs>(conn)?
+                }
+            };
+            if let Some(group_id) = token.user_group_id {
+                let users = users::table
+                    .filter(users::user_group_ids.contains(vec![Some(group_id)]))
+                    .select((users::id, users::device_ids))
+                    .load::<(i32, Vec<Option<i32>>)>(conn)?;
+                for (id, mut device_ids) in users {
+                    if device_ids.contains(&Some(avs.id)) {
+                        continue;
+                    }
+                    device_ids.push(Some(avs.id));
+                    diesel::update(users::table.filter(users::id.eq(id)))
+                        .set(users::device_ids.eq(device_ids))
+                        .execute(conn)?;
+                }
+            }
+            diesel::update(enrollment_token::table.filter(enrollment_token::id.eq(token.id)))
+                .set((
+                    enrollment_token::used_at.eq(now),
+                    enrollment_token::avs_id.eq(avs.id),
+                ))
+                .execute(conn)?;
+            Ok(Some(avs))
+        })
+    }
+}
+
+impl DatabaseRepository<PgConnection> for EnrollmentRepo {
+    fn new(db: database::Database<PgConnection>) -> Self {
+        Self { db }
+    }
+}
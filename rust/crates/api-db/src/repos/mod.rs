@@ -43,3 +43,35 @@ mod records;
 pub use records::*;
 mod schedules;
 pub use schedules::*;
+mod play_log;
+pub use play_log::*;
+mod device_config;
+pub use device_config::*;
+mod updates;
+pub use updates::*;
+mod telemetry;
+pub use telemetry::*;
+mod avs_port;
+pub use avs_port::*;
+mod volume_profile;
+pub use volume_profile::*;
+mod playlist;
+pub use playlist::*;
+mod enrollment;
+pub use enrollment::*;
+mod avs_sync;
+pub use avs_sync::*;
+mod self_test;
+pub use self_test::*;
+mod avs_connection;
+pub use avs_connection::*;
+mod broadcast;
+pub use broadcast::*;
+mod command_audit;
+pub use command_audit::*;
+mod alert_rule;
+pub use alert_rule::*;
+mod alert;
+pub use alert::*;
+mod outbox_receipt;
+pub use outbox_receipt::*;
@@ -0,0 +1,78 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use crate::ApiDatabase;
+use database::{DatabaseRepository, Result};
+use diesel::prelude::*;
+use types::api::schema::*;
+
+/// OutboxReceiptRepo.
+/// Repository for `outbox_receipt` table.
+/// The outbox entries received from the avs, so a batch sent again isn't stored twice.
+#[derive(Clone)]
+pub struct OutboxReceiptRepo {
+    db: ApiDatabase,
+}
+
+impl OutboxReceiptRepo {
+    /// Receive an outbox entry of the avs, `store` runs in the transaction of the claim
+    /// so the entry is either stored and claimed or neither.
+    /// Returns `false` without storing when the entry was already received.
+    /// The creation time tells apart the entries of an avs whose outbox was reset.
+    pub fn receive<F>(
+        &self,
+        avs_id: i32,
+        outbox_id: i32,
+        created_at: chrono::NaiveDateTime,
+        store: F,
+    ) -> Result<bool>
+    where
+        F: FnOnce(&mut PgConnection) -> Result<()>,
+    {
+        self.db.run_transaction(|conn| {
+            let count = diesel::insert_into(outbox_receipt::table)
+                .values((
+                    outbox_receipt::avs_id.eq(avs_id),
+                    outbox_receipt::outbox_id.eq(outbox_id),
+                    outbox_receipt::created_at.eq(created_at),
+                ))
+                .on_conflict_do_nothing()
+                .execute(conn)?;
+            if count == 0 {
+                return Ok(false);
+            }
+            store(conn)?;
+            Ok(true)
+        })
+    }
+
+    /// Delete the receipts received more than `days` ago.
+    pub fn delete_older_than(&self, days: i64) -> Result<usize> {
+        let before = utils::time::now() - chrono::Duration::days(days);
+        self.db.run(|conn| {
+            diesel::delete(outbox_receipt::table.filter(outbox_receipt::received_at.lt(before)))
+                .execute(conn)
+                .map_err(Into::into)
+        })
+    }
+}
+
+impl DatabaseRepository<PgConnection> for OutboxReceiptRepo {
+    fn new(db: database::Database<PgConnection>) -> Self {
+        Self { db }
+    }
+}
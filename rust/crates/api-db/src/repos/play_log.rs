@@ -0,0 +1,125 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use crate::ApiDatabase;
+use database::{DatabaseRepository, Result};
+use diesel::{prelude::*, upsert::excluded};
+use types::{
+    api::{
+        play_log::{NewPlayLog, PlayLog},
+        schema::*,
+    },
+    proto::{OUTCOME_ERROR, OUTCOME_MISSING},
+};
+
+/// PlayLogRepo.
+/// Repository for `play_log` table.
+#[derive(Clone)]
+pub struct PlayLogRepo {
+    db: ApiDatabase,
+}
+
+impl PlayLogRepo {
+    /// Insert many play logs on the connection,
+    /// the ones already stored only get their end time updated.
+    pub fn insert_many(conn: &mut PgConnection, logs: &[NewPlayLog]) -> Result<()> {
+        diesel::insert_into(play_log::table)
+            .values(logs)
+            .on_conflict((play_log::avs_id, play_log::local_id, play_log::started_at))
+            .do_update()
+            .set(play_log::ended_at.eq(excluded(play_log::ended_at)))
+            .execute(conn)?;
+        Ok(())
+    }
+
+    /// Get play logs of an avs between the given range.
+    pub fn get_by_avs(
+        &self,
+        avs_id: i32,
+        from: chrono::NaiveDateTime,
+        to: chrono::NaiveDateTime,
+    ) -> Result<Vec<PlayLog>> {
+        self.db.run(|conn| {
+            play_log::table
+                .filter(play_log::avs_id.eq(avs_id))
+                .filter(play_log::started_at.ge(from))
+                .filter(play_log::started_at.lt(to))
+                .order(play_log::started_at.asc())
+                .load::<PlayLog>(conn)
+                .map_err(Into::into)
+        })
+    }
+
+    /// Get play logs of a schedule between the given range.
+    pub fn get_by_schedule(
+        &self,
+        schedule_id: i32,
+        from: chrono::NaiveDateTime,
+        to: chrono::NaiveDateTime,
+    ) -> Result<Vec<PlayLog>> {
+        self.db.run(|conn| {
+            play_log::table
+                .filter(play_log::schedule_id.eq(schedule_id))
+                .filter(play_log::started_at.ge(from))
+                .filter(play_log::started_at.lt(to))
+                .order(play_log::started_at.asc())
+                .load::<PlayLog>(conn)
+                .map_err(Into::into)
+        })
+    }
+
+    /// Get the utc offset of the latest play log of each avs that reported one.
+    pub fn utc_offsets(&self, avs_ids: &[i32]) -> Result<Vec<(i32, i32)>> {
+        self.db.run(|conn| {
+            let res = play_log::table
+                .filter(play_log::avs_id.eq_any(avs_ids))
+                .filter(play_log::utc_offset.is_not_null())
+                .distinct_on(play_log::avs_id)
+                .order((play_log::avs_id, play_log::started_at.desc()))
+                .select((play_log::avs_id, play_log::utc_offset))
+                .load::<(i32, Option<i32>)>(conn)?;
+            Ok(res
+                .into_iter()
+                .filter_map(|(avs_id, offset)| Some((avs_id, offset?)))
+                .collect())
+        })
+    }
+
+    /// Count the failed plays (missing file or error) of each avs received since the given time.
+    pub fn count_failed(
+        &self,
+        avs_ids: &[i32],
+        since: chrono::NaiveDateTime,
+    ) -> Result<Vec<(i32, i64)>> {
+        self.db.run(|conn| {
+            play_log::table
+                .filter(play_log::avs_id.eq_any(avs_ids))
+                .filter(play_log::outcome.eq_any([OUTCOME_MISSING, OUTCOME_ERROR]))
+                .filter(play_log::created_at.ge(since))
+                .group_by(play_log::avs_id)
+                .select((play_log::avs_id, diesel::dsl::count(play_log::id)))
+                .load::<(i32, i64)>(conn)
+                .map_err(Into::into)
+        })
+    }
+}
+
+impl DatabaseRepository<PgConnection> for PlayLogRepo {
+    fn new(db: database::Database<PgConnection>) -> Self {
+        Self { db }
+    }
+}
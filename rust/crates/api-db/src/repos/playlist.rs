@@ -0,0 +1,172 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use crate::ApiDatabase;
+use database::{DatabaseRepository, Result};
+use diesel::prelude::*;
+use types::api::{
+    playlist::{NewPlaylist, NewPlaylistItem, Playlist, PlaylistItem},
+    records::Records,
+    schema::*,
+};
+
+/// PlaylistRepo.
+/// Repository for `playlist` table.
+#[derive(Clone)]
+pub struct PlaylistRepo {
+    db: ApiDatabase,
+}
+
+impl PlaylistRepo {
+    /// Load the items of the playlist in play order.
+    fn items(conn: &mut PgConnection, playlist: &Playlist) -> QueryResult<Vec<PlaylistItem>> {
+        PlaylistItem::belonging_to(playlist)
+            .order(playlist_item::position.asc())
+            .load::<PlaylistItem>(conn)
+    }
+
+    /// Get the playlist of an avs.
+    pub fn get_by_avs(&self, avs_id: i32) -> Result<Option<(Playlist, Vec<PlaylistItem>)>> {
+        self.db.run(|conn| {
+            let playlist = playlist::table
+                .filter(playlist::avs_id.eq(avs_id))
+                .first::<Playlist>(conn)
+                .optional()?;
+            match playlist {
+                Some(playlist) => {
+                    let items = Self::items(conn, &playlist)?;
+                    Ok(Some((playlist, items)))
+                }
+                None => Ok(None),
+            }
+        })
+    }
+
+    /// Get the playlist of a group.
+    pub fn get_by_group(&self, group_id: i32) -> Result<Option<(Playlist, Vec<PlaylistItem>)>> {
+        self.db.run(|conn| {
+            let playlist = playlist::table
+                .filter(playlist::user_group_id.eq(group_id))
+                .first::<Playlist>(conn)
+                .optional()?;
+            match playlist {
+                Some(playlist) => {
+                    let items = Self::items(conn, &playlist)?;
+                    Ok(Some((playlist, items)))
+                }
+                None => Ok(None),
+            }
+        })
+    }
+
+    /// Replace the playlist of the avs or the group of the new playlist.
+    pub fn set(
+        &self,
+        new: NewPlaylist,
+        records_ids: Vec<i32>,
+    ) -> Result<(Playlist, Vec<PlaylistItem>)> {
+        self.db.run_transaction(|conn| {
+            if let Some(avs_id) = new.avs_id {
+                diesel::delete(playlist::table.filter(playlist::avs_id.eq(avs_id)))
+                    .execute(conn)?;
+            }
+            if let Some(group_id) = new.user_group_id {
+                diesel::delete(playlist::table.filter(playlist::user_group_id.eq(group_id)))
+                    .execute(conn)?;
+            }
+            let playlist = diesel::insert_into(playlist::table)
+                .values(&new)
+                .get_result::<Playlist>(conn)?;
+            let items = records_ids
+                .into_iter()
+                .enumerate()
+                .map(|(position, records_id)| NewPlaylistItem {
+                    playlist_id: playlist.id,
+                    records_id,
+                    position: position as i32,
+                })
+                .collect::<Vec<NewPlaylistItem>>();
+            let mut items = diesel::insert_into(playlist_item::table)
+                .values(&items)
+                .get_results::<PlaylistItem>(conn)?;
+            items.sort_by_key(|i| i.position);
+            Ok((playlist, items))
+        })
+    }
+
+    /// Delete the playlist of an avs.
+    pub fn delete_by_avs(&self, avs_id: i32) -> Result<()> {
+        self.db.run(|conn| {
+            diesel::delete(playlist::table.filter(playlist::avs_id.eq(avs_id))).execute(conn)?;
+            Ok(())
+        })
+    }
+
+    /// Delete the playlist of a group.
+    pub fn delete_by_group(&self, group_id: i32) -> Result<()> {
+        self.db.run(|conn| {
+            diesel::delete(playlist::table.filter(playlist::user_group_id.eq(group_id)))
+                .execute(conn)?;
+            Ok(())
+        })
+    }
+
+    /// Resolve the effective playlist of an avs along with its active records in play order.
+    /// The avs playlist takes precedence, then the playlist of the first group
+    /// the avs owners belong to, ordered by the group id.
+    pub fn resolve(&self, avs_id: i32) -> Result<Option<(Playlist, Vec<Records>)>> {
+        self.db.run(|conn| {
+            let mut playlist = playlist::table
+                .filter(playlist::avs_id.eq(avs_id))
+                .first::<Playlist>(conn)
+                .optional()?;
+            if playlist.is_none() {
+                let group_ids = users::table
+                    .filter(users::device_ids.contains(vec![Some(avs_id)]))
+                    .select(users::user_group_ids)
+                    .load::<Vec<Option<i32>>>(conn)?
+                    .into_iter()
+                    .flatten()
+                    .flatten()
+                    .collect::<Vec<i32>>();
+                playlist = playlist::table
+                    .filter(playlist::user_group_id.eq_any(group_ids))
+                    .order(playlist::user_group_id.asc())
+                    .first::<Playlist>(conn)
+                    .optional()?;
+            }
+            let playlist = match playlist {
+                Some(playlist) => playlist,
+                None => return Ok(None),
+            };
+            let records = playlist_item::table
+                .inner_join(records::table)
+                .filter(playlist_item::playlist_id.eq(playlist.id))
+                .filter(records::status.eq(1))
+                .order(playlist_item::position.asc())
+                .select(records::all_columns)
+                .load::<Records>(conn)?;
+            Ok(Some((playlist, records)))
+        })
+    }
+}
+
+impl DatabaseRepository<PgConnection> for PlaylistRepo {
+    fn new(db: database::Database<PgConnection>) -> Self {
+        Self { db }
+    }
+}
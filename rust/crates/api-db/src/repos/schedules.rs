@@ -89,6 +89,24 @@ impl ScheduleRepo {
         })
     }
 
+    /// Set the ports and zones of a schedule.
+    pub fn set_outputs(
+        &self,
+        id: i32,
+        port_ids: Vec<i32>,
+        zones: Vec<String>,
+    ) -> Result<Schedules> {
+        self.db.run(|conn| {
+            diesel::update(schedules::table.filter(schedules::id.eq(id)))
+                .set((
+                    schedules::port_ids.eq(port_ids.into_iter().map(Some).collect::<Vec<_>>()),
+                    schedules::zones.eq(zones.into_iter().map(Some).collect::<Vec<_>>()),
+                ))
+                .get_result::<Schedules>(conn)
+                .map_err(Into::into)
+        })
+    }
+
     /// Delete schedule.
     pub fn delete(&self, id: i32) -> Result<Schedules> {
         self.db.run(|conn| {
@@ -0,0 +1,111 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use crate::ApiDatabase;
+use database::{DatabaseRepository, Result};
+use diesel::prelude::*;
+use types::api::{
+    schema::*,
+    self_test::{NewSelfTest, NewSelfTestCheck, SelfTest, SelfTestCheck},
+};
+
+/// SelfTestRepo.
+/// Repository for `self_test` table.
+#[derive(Clone)]
+pub struct SelfTestRepo {
+    db: ApiDatabase,
+}
+
+impl SelfTestRepo {
+    /// Create a pending self test.
+    pub fn create(&self, new: NewSelfTest) -> Result<SelfTest> {
+        self.db.run(|conn| {
+            diesel::insert_into(self_test::table)
+                .values(&new)
+                .get_result(conn)
+                .map_err(Into::into)
+        })
+    }
+
+    /// Store the report of a pending self test of the avs.
+    /// Returns `None` when the self test is unknown or already finished.
+    pub fn finish(
+        &self,
+        id: i32,
+        avs_id: i32,
+        version: String,
+        checks: Vec<NewSelfTestCheck>,
+    ) -> Result<Option<SelfTest>> {
+        self.db.run_transaction(|conn| {
+            let passed = checks.iter().all(|c| c.passed != Some(false));
+            let test = diesel::update(
+                self_test::table
+                    .filter(self_test::id.eq(id))
+                    .filter(self_test::avs_id.eq(avs_id))
+                    .filter(self_test::finished_at.is_null()),
+            )
+            .set((
+                self_test::finished_at.eq(utils::time::now()),
+                self_test::passed.eq(passed),
+                self_test::version.eq(version),
+            ))
+            .get_result::<SelfTest>(conn)
+            .optional()?;
+            let test = match test {
+                Some(test) => test,
+                None => return Ok(None),
+            };
+            let checks = checks
+                .into_iter()
+                .map(|check| NewSelfTestCheck {
+                    self_test_id: test.id,
+                    ..check
+                })
+                .collect::<Vec<NewSelfTestCheck>>();
+            diesel::insert_into(self_test_check::table)
+                .values(&checks)
+                .execute(conn)?;
+            Ok(Some(test))
+        })
+    }
+
+    /// Get the latest self tests of an avs along with their checks, newest first.
+    pub fn get_by_avs(
+        &self,
+        avs_id: i32,
+        limit: i64,
+    ) -> Result<Vec<(SelfTest, Vec<SelfTestCheck>)>> {
+        self.db.run(|conn| {
+            let tests = self_test::table
+                .filter(self_test::avs_id.eq(avs_id))
+                .order(self_test::requested_at.desc())
+                .limit(limit)
+                .load::<SelfTest>(conn)?;
+            let checks = SelfTestCheck::belonging_to(&tests)
+                .order(self_test_check::id.asc())
+                .load::<SelfTestCheck>(conn)?
+                .grouped_by(&tests);
+            Ok(tests.into_iter().zip(checks).collect())
+        })
+    }
+}
+
+impl DatabaseRepository<PgConnection> for SelfTestRepo {
+    fn new(db: database::Database<PgConnection>) -> Self {
+        Self { db }
+    }
+}
@@ -0,0 +1,103 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use crate::ApiDatabase;
+use database::{DatabaseRepository, Result};
+use diesel::prelude::*;
+use types::api::{
+    schema::*,
+    telemetry::{NewTelemetry, NewTelemetryNetwork, Telemetry, TelemetryNetwork},
+};
+
+/// TelemetryRepo.
+/// Repository for `telemetry` table.
+#[derive(Clone)]
+pub struct TelemetryRepo {
+    db: ApiDatabase,
+}
+
+impl TelemetryRepo {
+    /// Create a telemetry sample along with the network interfaces.
+    pub fn create(
+        &self,
+        telemetry: NewTelemetry,
+        networks: Vec<NewTelemetryNetwork>,
+    ) -> Result<Telemetry> {
+        self.db
+            .run_transaction(|conn| Self::insert(conn, telemetry, networks))
+    }
+
+    /// Insert a telemetry sample along with the network interfaces on the connection.
+    pub fn insert(
+        conn: &mut PgConnection,
+        telemetry: NewTelemetry,
+        networks: Vec<NewTelemetryNetwork>,
+    ) -> Result<Telemetry> {
+        let telemetry = diesel::insert_into(telemetry::table)
+            .values(&telemetry)
+            .get_result::<Telemetry>(conn)?;
+        let networks = networks
+            .into_iter()
+            .map(|network| NewTelemetryNetwork {
+                telemetry_id: telemetry.id,
+                ..network
+            })
+            .collect::<Vec<NewTelemetryNetwork>>();
+        diesel::insert_into(telemetry_network::table)
+            .values(&networks)
+            .execute(conn)?;
+        Ok(telemetry)
+    }
+
+    /// Get telemetry samples of an avs between the given range.
+    pub fn get_by_avs(
+        &self,
+        avs_id: i32,
+        from: chrono::NaiveDateTime,
+        to: chrono::NaiveDateTime,
+    ) -> Result<Vec<(Telemetry, Vec<TelemetryNetwork>)>> {
+        self.db.run(|conn| {
+            let samples = telemetry::table
+                .filter(telemetry::avs_id.eq(avs_id))
+                .filter(telemetry::created_at.ge(from))
+                .filter(telemetry::created_at.lt(to))
+                .order(telemetry::created_at.asc())
+                .load::<Telemetry>(conn)?;
+            let networks = TelemetryNetwork::belonging_to(&samples)
+                .order(telemetry_network::name.asc())
+                .load::<TelemetryNetwork>(conn)?
+                .grouped_by(&samples);
+            Ok(samples.into_iter().zip(networks).collect())
+        })
+    }
+
+    /// Delete telemetry samples older than the given days.
+    pub fn delete_older_than(&self, days: i64) -> Result<usize> {
+        let before = chrono::Utc::now().naive_utc() - chrono::Duration::days(days);
+        self.db.run(|conn| {
+            diesel::delete(telemetry::table.filter(telemetry::created_at.lt(before)))
+                .execute(conn)
+                .map_err(Into::into)
+        })
+    }
+}
+
+impl DatabaseRepository<PgConnection> for TelemetryRepo {
+    fn new(db: database::Database<PgConnection>) -> Self {
+        Self { db }
+    }
+}
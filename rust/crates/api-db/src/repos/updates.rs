@@ -0,0 +1,151 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use crate::ApiDatabase;
+use database::{DatabaseRepository, Result};
+use diesel::prelude::*;
+use types::api::{
+    schema::*,
+    updates::{AvsUpdate, NewAvsUpdate, NewUpdates, Updates},
+};
+
+/// UpdatesRepo.
+/// Repository for `updates` and `avs_update` table.
+#[derive(Clone)]
+pub struct UpdatesRepo {
+    db: ApiDatabase,
+}
+
+impl UpdatesRepo {
+    /// Create new update release.
+    pub fn create(&self, update: NewUpdates) -> Result<Updates> {
+        self.db.run(|conn| {
+            diesel::insert_into(updates::table)
+                .values(update)
+                .get_result(conn)
+                .map_err(Into::into)
+        })
+    }
+
+    /// Get update release by id.
+    pub fn get(&self, id: i32) -> Result<Updates> {
+        self.db
+            .run(|conn| updates::table.find(id).first(conn).map_err(Into::into))
+    }
+
+    /// Get all update releases, newest first.
+    pub fn get_all(&self) -> Result<Vec<Updates>> {
+        self.db.run(|conn| {
+            updates::table
+                .order(updates::created_at.desc())
+                .load::<Updates>(conn)
+                .map_err(Into::into)
+        })
+    }
+
+    /// Mark the update as announced to the avs, restarting any previous progress.
+    pub fn deploy(&self, update_id: i32, avs_ids: &[i32]) -> Result<Vec<AvsUpdate>> {
+        self.db.run_transaction(|conn| {
+            let now = chrono::Utc::now().naive_utc();
+            let mut res = vec![];
+            for avs_id in avs_ids {
+                let new = NewAvsUpdate {
+                    avs_id: *avs_id,
+                    update_id,
+                    status: 1,
+                    progress: None,
+                    message: None,
+                    version: None,
+                    updated_at: now,
+                };
+                let row = diesel::insert_into(avs_update::table)
+                    .values(&new)
+                    .on_conflict((avs_update::avs_id, avs_update::update_id))
+                    .do_update()
+                    .set((
+                        avs_update::status.eq(1),
+                        avs_update::progress.eq(None::<i32>),
+                        avs_update::message.eq(None::<String>),
+                        avs_update::updated_at.eq(now),
+                    ))
+                    .get_result::<AvsUpdate>(conn)?;
+                res.push(row);
+            }
+            Ok(res)
+        })
+    }
+
+    /// Set the update progress reported by the avs.
+    pub fn set_status(
+        &self,
+        avs_id: i32,
+        update_id: i32,
+        status: i32,
+        progress: Option<i32>,
+        message: Option<String>,
+        version: String,
+    ) -> Result<()> {
+        self.db.run(|conn| {
+            diesel::update(
+                avs_update::table
+                    .filter(avs_update::avs_id.eq(avs_id))
+                    .filter(avs_update::update_id.eq(update_id)),
+            )
+            .set((
+                avs_update::status.eq(status),
+                avs_update::progress.eq(progress),
+                avs_update::message.eq(message),
+                avs_update::version.eq(Some(version)),
+                avs_update::updated_at.eq(chrono::Utc::now().naive_utc()),
+            ))
+            .execute(conn)?;
+            Ok(())
+        })
+    }
+
+    /// Get the progress of an update release.
+    pub fn get_status(&self, update_id: i32) -> Result<Vec<AvsUpdate>> {
+        self.db.run(|conn| {
+            avs_update::table
+                .filter(avs_update::update_id.eq(update_id))
+                .order(avs_update::avs_id.asc())
+                .load::<AvsUpdate>(conn)
+                .map_err(Into::into)
+        })
+    }
+
+    /// Get the latest update the avs hasn't installed yet.
+    pub fn get_pending(&self, avs_id: i32) -> Result<Option<Updates>> {
+        self.db.run(|conn| {
+            avs_update::table
+                .inner_join(updates::table)
+                .filter(avs_update::avs_id.eq(avs_id))
+                .filter(avs_update::status.lt(4))
+                .order(avs_update::updated_at.desc())
+                .select(updates::all_columns)
+                .first::<Updates>(conn)
+                .optional()
+                .map_err(Into::into)
+        })
+    }
+}
+
+impl DatabaseRepository<PgConnection> for UpdatesRepo {
+    fn new(db: database::Database<PgConnection>) -> Self {
+        Self { db }
+    }
+}
@@ -131,6 +131,16 @@ impl UserRepo {
         })
     }
 
+    /// Get the users of a group.
+    pub fn get_by_group(&self, group_id: i32) -> Result<Vec<User>> {
+        self.db.run(|conn| {
+            users::table
+                .filter(users::user_group_ids.contains(vec![Some(group_id)]))
+                .load::<User>(conn)
+                .map_err(Into::into)
+        })
+    }
+
     /// Get user by email.
     pub fn get_by_email(&self, email: &str) -> Result<User> {
         self.db.run(|conn| {
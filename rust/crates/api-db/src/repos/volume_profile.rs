@@ -0,0 +1,140 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use crate::ApiDatabase;
+use database::{DatabaseRepository, Result};
+use diesel::prelude::*;
+use types::api::{
+    schema::*,
+    volume_profile::{NewVolumeProfile, VolumeProfile},
+};
+
+/// VolumeProfileRepo.
+/// Repository for `volume_profile` table.
+#[derive(Clone)]
+pub struct VolumeProfileRepo {
+    db: ApiDatabase,
+}
+
+impl VolumeProfileRepo {
+    /// Get the volume profile of an avs.
+    pub fn get_by_avs(&self, avs_id: i32) -> Result<Vec<VolumeProfile>> {
+        self.db.run(|conn| {
+            volume_profile::table
+                .filter(volume_profile::avs_id.eq(avs_id))
+                .order(volume_profile::position.asc())
+                .load::<VolumeProfile>(conn)
+                .map_err(Into::into)
+        })
+    }
+
+    /// Get the volume profile of a group.
+    pub fn get_by_group(&self, group_id: i32) -> Result<Vec<VolumeProfile>> {
+        self.db.run(|conn| {
+            volume_profile::table
+                .filter(volume_profile::user_group_id.eq(group_id))
+                .order(volume_profile::position.asc())
+                .load::<VolumeProfile>(conn)
+                .map_err(Into::into)
+        })
+    }
+
+    /// Replace the volume profile of an avs or a group.
+    /// The ranges must all belong to the given avs or group.
+    pub fn set(
+        &self,
+        avs_id: Option<i32>,
+        group_id: Option<i32>,
+        ranges: Vec<NewVolumeProfile>,
+    ) -> Result<Vec<VolumeProfile>> {
+        self.db.run_transaction(|conn| {
+            if let Some(avs_id) = avs_id {
+                diesel::delete(volume_profile::table.filter(volume_profile::avs_id.eq(avs_id)))
+                    .execute(conn)?;
+            }
+            if let Some(group_id) = group_id {
+                diesel::delete(
+                    volume_profile::table.filter(volume_profile::user_group_id.eq(group_id)),
+                )
+                .execute(conn)?;
+            }
+            let mut res = diesel::insert_into(volume_profile::table)
+                .values(&ranges)
+                .get_results::<VolumeProfile>(conn)?;
+            res.sort_by_key(|r| r.position);
+            Ok(res)
+        })
+    }
+
+    /// Delete the volume profile of an avs.
+    pub fn delete_by_avs(&self, avs_id: i32) -> Result<()> {
+        self.db.run(|conn| {
+            diesel::delete(volume_profile::table.filter(volume_profile::avs_id.eq(avs_id)))
+                .execute(conn)?;
+            Ok(())
+        })
+    }
+
+    /// Delete the volume profile of a group.
+    pub fn delete_by_group(&self, group_id: i32) -> Result<()> {
+        self.db.run(|conn| {
+            diesel::delete(
+                volume_profile::table.filter(volume_profile::user_group_id.eq(group_id)),
+            )
+            .execute(conn)?;
+            Ok(())
+        })
+    }
+
+    /// Resolve the effective volume profile of an avs.
+    /// The avs profile takes precedence, then the profile of the first group
+    /// the avs owners belong to, ordered by the group id.
+    pub fn resolve(&self, avs_id: i32) -> Result<Vec<VolumeProfile>> {
+        let res = self.get_by_avs(avs_id)?;
+        if !res.is_empty() {
+            return Ok(res);
+        }
+        self.db.run(|conn| {
+            let group_ids = users::table
+                .filter(users::device_ids.contains(vec![Some(avs_id)]))
+                .select(users::user_group_ids)
+                .load::<Vec<Option<i32>>>(conn)?
+                .into_iter()
+                .flatten()
+                .flatten()
+                .collect::<Vec<i32>>();
+            let ranges = volume_profile::table
+                .filter(volume_profile::user_group_id.eq_any(group_ids))
+                .order((
+                    volume_profile::user_group_id.asc(),
+                    volume_profile::position.asc(),
+                ))
+                .load::<VolumeProfile>(conn)?;
+            let first = ranges.first().and_then(|r| r.user_group_id);
+            Ok(ranges
+                .into_iter()
+                .filter(|r| r.user_group_id == first)
+                .collect())
+        })
+    }
+}
+
+impl DatabaseRepository<PgConnection> for VolumeProfileRepo {
+    fn new(db: database::Database<PgConnection>) -> Self {
+        Self { db }
+    }
+}
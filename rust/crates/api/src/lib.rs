@@ -118,17 +118,27 @@ fn doc(cfg: &mut actix_web::web::ServiceConfig) {
     /// # MyRTS api documentation.
     ///
     /// This api contains the following endpoints:
+    /// - `/alerts` - The device health alert endpoint `(stable)`.
     /// - `/assets` - The assets endpoint `(stable)`.
     /// - `/auth` - The authentication endpoint `(stable)`.
     /// - `/avs` - The avs endpoint `(stable)`.
+    /// - `/broadcasts` - The live broadcast history endpoint `(stable)`.
     /// - `/cities` - The cities endpoint `(stable)`.
+    /// - `/commands` - The remote command audit and action endpoint `(stable)`.
+    /// - `/connections` - The avs connection history endpoint `(stable)`.
+    /// - `/device_configs` - The device configuration endpoint `(stable)`.
+    /// - `/enrollments` - The device provisioning endpoint `(stable)`.
     /// - `/groups` - The groups endpoint `(stable)`.
     /// - `/packages` - The packages endpoint `(stable)`.
+    /// - `/play_logs` - The proof of play endpoint `(stable)`.
+    /// - `/playlists` - The background playlist endpoint `(stable)`.
     /// - `/provinces` - The provinces endpoint `(stable)`.
     /// - `/records` - The records endpoint `(stable)`.
     /// - `/roles` - The roles endpoint `(stable)`.
     /// - `/schedules` - The schedules endpoint `(stable)`.
+    /// - `/updates` - The client update endpoint `(stable)`.
     /// - `/users` - The users endpoint `(stable)`.
+    /// - `/volume_profiles` - The volume profile endpoint `(stable)`.
     ///
     /// ## Notes
     /// - Every generated timestamp is in UTC so you need to convert it to your local timezone.
@@ -147,12 +157,27 @@ fn doc(cfg: &mut actix_web::web::ServiceConfig) {
             routes::records,
             routes::statistics,
             routes::streaming,
+            routes::play_logs,
+            routes::playlists,
+            routes::device_configs,
+            routes::enrollments,
+            routes::updates,
+            routes::volume_profiles,
+            routes::connections,
+            routes::broadcasts,
+            routes::commands,
+            routes::alerts,
         ),
         types(
             self,
             types::api,
+            types::api::alert,
             types::api::avs,
+            types::api::avs_connection,
+            types::api::avs_port,
+            types::api::broadcast,
             types::api::city,
+            types::api::command_audit,
             types::api::province,
             types::api::role,
             types::api::user,
@@ -161,6 +186,14 @@ fn doc(cfg: &mut actix_web::web::ServiceConfig) {
             types::api::subscription,
             types::api::schedules,
             types::api::records,
+            types::api::play_log,
+            types::api::playlist,
+            types::api::device_config,
+            types::api::enrollment,
+            types::api::updates,
+            types::api::self_test,
+            types::api::telemetry,
+            types::api::volume_profile,
         ),
         mods(&ApiKey)
     )]
@@ -193,6 +226,16 @@ fn configure(cfg: &mut actix_web::web::ServiceConfig) {
         routes::statistics
         routes::install
         routes::streaming
+        routes::play_logs
+        routes::playlists
+        routes::device_configs
+        routes::enrollments
+        routes::updates
+        routes::volume_profiles
+        routes::connections
+        routes::broadcasts
+        routes::commands
+        routes::alerts
     }
 }
 
@@ -0,0 +1,460 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use crate::{middlewares::auth::Auth, ApiError, Config};
+use actix_web::{web, Responder};
+use api_db::{
+    repos::{AlertFilter, AlertRepo, AlertRuleRepo},
+    ApiDatabase,
+};
+use types::api::{
+    alert::{
+        AlertQuery, AlertResponse, AlertRuleReq, AlertRuleResponse, NewAlertRule,
+        PaginatedAlertResponse,
+    },
+    user::User,
+    Message,
+};
+
+/// Validate the alert rule request.
+fn validate(data: &AlertRuleReq) -> Result<(), ApiError> {
+    if data.offline_minutes.is_none()
+        && data.disk_free_mb.is_none()
+        && data.cpu_temp_max.is_none()
+        && data.failed_plays.is_none()
+    {
+        return Err(ApiError::new("At least one threshold is required").status(400));
+    }
+    if data.offline_minutes.map_or(false, |v| v < 1) {
+        return Err(ApiError::new("Offline minutes must be at least 1").status(400));
+    }
+    if data.disk_free_mb.map_or(false, |v| v < 1) {
+        return Err(ApiError::new("Free disk space must be at least 1 MB").status(400));
+    }
+    if data
+        .cpu_temp_max
+        .map_or(false, |v| !(0.0..=150.0).contains(&v))
+    {
+        return Err(ApiError::new("Cpu temperature must be between 0 and 150").status(400));
+    }
+    if data.failed_plays.map_or(false, |v| v < 1) {
+        return Err(ApiError::new("Failed plays must be at least 1").status(400));
+    }
+    Ok(())
+}
+
+/// Check whether the user can access the alerts of the avs.
+fn can_access(user: &User, avs_id: i32) -> bool {
+    user.role_id == 1 || user.role_id == 2 || user.device_ids.contains(&Some(avs_id))
+}
+
+/// # Get all alert rules.
+///
+/// This endpoint returns the alert rules of every group, ordered by the group id.
+/// Only user with role `1 (Root)` and `2 (SuperAdmin)` can access this endpoint.
+/// ---
+/// tags:
+///     - alerts
+/// responses:
+///     - status: 200
+///       content: !Vec AlertRuleResponse
+///       description: The alert rules.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// auth: api_key
+#[api_rt::route(get, "/alerts/rules", Auth)]
+async fn get_rules(
+    user: web::ReqData<User>,
+    db: web::Data<ApiDatabase>,
+) -> actix_web::Result<impl Responder> {
+    if user.role_id != 1 && user.role_id != 2 {
+        return Err(ApiError::new("Unauthorized").status(401).into());
+    }
+    let res = db
+        .repository::<AlertRuleRepo>()
+        .get_all()
+        .map_err(ApiError::from)?;
+    AlertRuleResponse::wrap_vec(res.into_iter().map(AlertRuleResponse::from).collect(), None)
+}
+
+/// # Get the alert rule of a group.
+///
+/// This endpoint returns the alert rule of the group.
+/// Only user with role `1 (Root)` and `2 (SuperAdmin)` can access this endpoint.
+/// ---
+/// tags:
+///     - alerts
+/// responses:
+///     - status: 200
+///       content: !T AlertRuleResponse
+///       description: The group alert rule.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 404
+///       content: !T ApiError
+///       description: The group has no alert rule.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// params:
+///     - name: id
+///       kind: !Path i32
+///       required: true
+///       description: The group id.
+/// auth: api_key
+#[api_rt::route(get, "/alerts/rules/groups/{id}", Auth)]
+async fn get_group_rule(
+    user: web::ReqData<User>,
+    id: web::Path<i32>,
+    db: web::Data<ApiDatabase>,
+) -> actix_web::Result<AlertRuleResponse> {
+    if user.role_id != 1 && user.role_id != 2 {
+        return Err(ApiError::new("Unauthorized").status(401).into());
+    }
+    let repo = db.repository::<AlertRuleRepo>();
+    match repo.get_by_group(id.into_inner()).map_err(ApiError::from)? {
+        Some(rule) => AlertRuleResponse::from(rule).wrap(),
+        None => Err(ApiError::new("Not found").status(404).into()),
+    }
+}
+
+/// # Set the alert rule of a group.
+///
+/// This endpoint replaces the alert rule of the group, the avs owned by the users of the group
+/// are evaluated against the thresholds on the next evaluation of the server.
+/// An alert is opened once per avs and kind, the users of the group are mailed when it opens
+/// and the alert is resolved when the avs is back under the threshold.
+/// Unset thresholds aren't evaluated.
+/// Only user with role `1 (Root)` and `2 (SuperAdmin)` can access this endpoint.
+/// ---
+/// tags:
+///     - alerts
+/// request:
+///     content: !T AlertRuleReq
+///     content_type: application/json
+///     description: The group alert rule.
+/// responses:
+///     - status: 200
+///       content: !T AlertRuleResponse
+///       description: The group alert rule.
+///     - status: 400
+///       content: !T ApiError
+///       description: The request is invalid.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 404
+///       content: !T ApiError
+///       description: The group is not found.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// params:
+///     - name: id
+///       kind: !Path i32
+///       required: true
+///       description: The group id.
+/// auth: api_key
+#[api_rt::route(put, "/alerts/rules/groups/{id}", Auth)]
+async fn put_group_rule(
+    user: web::ReqData<User>,
+    id: web::Path<i32>,
+    data: web::Json<AlertRuleReq>,
+    db: web::Data<ApiDatabase>,
+) -> actix_web::Result<AlertRuleResponse> {
+    if user.role_id != 1 && user.role_id != 2 {
+        return Err(ApiError::new("Unauthorized").status(401).into());
+    }
+    let data = data.into_inner();
+    validate(&data)?;
+    let rule = NewAlertRule {
+        user_group_id: id.into_inner(),
+        offline_minutes: data.offline_minutes,
+        disk_free_mb: data.disk_free_mb,
+        cpu_temp_max: data.cpu_temp_max,
+        failed_plays: data.failed_plays,
+        enabled: data.enabled.unwrap_or(true),
+        updated_at: utils::time::now(),
+    };
+    let res = db
+        .repository::<AlertRuleRepo>()
+        .set(rule)
+        .map_err(ApiError::from)?;
+    AlertRuleResponse::from(res).wrap()
+}
+
+/// # Delete the alert rule of a group.
+///
+/// This endpoint deletes the alert rule of the group.
+/// The alerts it opened are resolved on the next evaluation, unless another group of the avs covers them.
+/// Only user with role `1 (Root)` and `2 (SuperAdmin)` can access this endpoint.
+/// ---
+/// tags:
+///     - alerts
+/// responses:
+///     - status: 200
+///       content: !T Message
+///       description: The group alert rule is deleted.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// params:
+///     - name: id
+///       kind: !Path i32
+///       required: true
+///       description: The group id.
+/// auth: api_key
+#[api_rt::route(delete, "/alerts/rules/groups/{id}", Auth)]
+async fn delete_group_rule(
+    user: web::ReqData<User>,
+    id: web::Path<i32>,
+    db: web::Data<ApiDatabase>,
+) -> actix_web::Result<Message> {
+    if user.role_id != 1 && user.role_id != 2 {
+        return Err(ApiError::new("Unauthorized").status(401).into());
+    }
+    db.repository::<AlertRuleRepo>()
+        .delete_by_group(id.into_inner())
+        .map_err(ApiError::from)?;
+    Message::new("ok".to_owned()).wrap()
+}
+
+/// # Get alerts.
+///
+/// This endpoint returns the alerts matching the filter, newest first.
+/// ****Rules:****
+/// Root and SuperAdmin can access the alerts of every avs, other users can only access the alerts of their own avs.
+/// ---
+/// tags:
+///     - alerts
+/// responses:
+///     - status: 200
+///       content: !T PaginatedAlertResponse
+///       description: The alerts.
+///     - status: 400
+///       content: !T ApiError
+///       description: The request is invalid.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// params:
+///     - name: page
+///       kind: !Query i64
+///       required: false
+///       description: The page number, if not provided, it will default to 1.
+///     - name: status
+///       kind: !Query String
+///       required: false
+///       description: Only the alerts with the status, `open`, `acknowledged` or `resolved`.
+///     - name: kind
+///       kind: !Query String
+///       required: false
+///       description: Only the alerts of the kind, `offline`, `disk_free`, `cpu_temp` or `failed_plays`.
+///     - name: avs_id
+///       kind: !Query i32
+///       required: false
+///       description: Only the alerts of the avs.
+/// auth: api_key
+#[api_rt::route(get, "/alerts", Auth)]
+async fn get_alerts(
+    user: web::ReqData<User>,
+    query: web::Query<AlertQuery>,
+    db: web::Data<ApiDatabase>,
+    cfg: web::Data<Config>,
+) -> actix_web::Result<PaginatedAlertResponse> {
+    let query = query.into_inner();
+    let page = query.page.unwrap_or(1).max(1);
+    if let Some(status) = &query.status {
+        if !["open", "acknowledged", "resolved"].contains(&status.as_str()) {
+            return Err(
+                ApiError::new("Status must be one of open, acknowledged or resolved")
+                    .status(400)
+                    .into(),
+            );
+        }
+    }
+    if let Some(kind) = &query.kind {
+        if !["offline", "disk_free", "cpu_temp", "failed_plays"].contains(&kind.as_str()) {
+            return Err(ApiError::new(
+                "Kind must be one of offline, disk_free, cpu_temp or failed_plays",
+            )
+            .status(400)
+            .into());
+        }
+    }
+    let avs_ids = match query.avs_id {
+        Some(avs_id) if can_access(&user, avs_id) => Some(vec![avs_id]),
+        Some(_) => Some(vec![]),
+        None if user.role_id == 1 || user.role_id == 2 => None,
+        None => Some(user.device_ids.iter().flatten().copied().collect()),
+    };
+    let filter = AlertFilter {
+        avs_ids,
+        status: query.status.clone(),
+        kind: query.kind.clone(),
+    };
+    let (alerts, total, total_pages) = db
+        .repository::<AlertRepo>()
+        .get_paginated(filter, page)
+        .map_err(ApiError::from)?;
+    let mut params = String::new();
+    if let Some(status) = &query.status {
+        params.push_str(&format!("&status={}", status));
+    }
+    if let Some(kind) = &query.kind {
+        params.push_str(&format!("&kind={}", kind));
+    }
+    if let Some(avs_id) = query.avs_id {
+        params.push_str(&format!("&avs_id={}", avs_id));
+    }
+    let prev = if page > 1 {
+        Some(cfg.format(format!("/alerts?page={}{}", page - 1, params)))
+    } else {
+        None
+    };
+    let next = if total > page * 10 {
+        Some(cfg.format(format!("/alerts?page={}{}", page + 1, params)))
+    } else {
+        None
+    };
+    PaginatedAlertResponse::new(
+        alerts.into_iter().map(AlertResponse::from).collect(),
+        total,
+        page,
+        total_pages,
+        next,
+        prev,
+    )
+    .wrap()
+}
+
+/// # Acknowledge an alert.
+///
+/// This endpoint acknowledges an open alert, the alert stays unresolved until the avs is back under the threshold.
+/// Acknowledging an acknowledged alert does nothing.
+/// ****Rules:****
+/// Root and SuperAdmin can access the alerts of every avs, other users can only access the alerts of their own avs.
+/// ---
+/// tags:
+///     - alerts
+/// responses:
+///     - status: 200
+///       content: !T AlertResponse
+///       description: The alert.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 404
+///       content: !T ApiError
+///       description: The alert is not found.
+///     - status: 409
+///       content: !T ApiError
+///       description: The alert is already resolved.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// params:
+///     - name: id
+///       kind: !Path i32
+///       required: true
+///       description: The alert id.
+/// auth: api_key
+#[api_rt::route(put, "/alerts/{id}/acknowledge", Auth)]
+async fn acknowledge(
+    user: web::ReqData<User>,
+    id: web::Path<i32>,
+    db: web::Data<ApiDatabase>,
+) -> actix_web::Result<AlertResponse> {
+    let repo = db.repository::<AlertRepo>();
+    let alert = repo.get(id.into_inner()).map_err(ApiError::from)?;
+    if !can_access(&user, alert.avs_id) {
+        return Err(ApiError::new("Not found").status(404).into());
+    }
+    if alert.status == "resolved" {
+        return Err(ApiError::new("Alert is already resolved")
+            .status(409)
+            .into());
+    }
+    let res = repo
+        .acknowledge(alert.id, user.id)
+        .map_err(ApiError::from)?;
+    AlertResponse::from(res).wrap()
+}
+
+/// # Resolve an alert.
+///
+/// This endpoint resolves an alert, resolving a resolved alert does nothing.
+/// An alert resolved while the avs is still over the threshold is reopened on the next evaluation, without a new mail.
+/// ****Rules:****
+/// Root and SuperAdmin can access the alerts of every avs, other users can only access the alerts of their own avs.
+/// ---
+/// tags:
+///     - alerts
+/// responses:
+///     - status: 200
+///       content: !T AlertResponse
+///       description: The alert.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 404
+///       content: !T ApiError
+///       description: The alert is not found.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// params:
+///     - name: id
+///       kind: !Path i32
+///       required: true
+///       description: The alert id.
+/// auth: api_key
+#[api_rt::route(put, "/alerts/{id}/resolve", Auth)]
+async fn resolve(
+    user: web::ReqData<User>,
+    id: web::Path<i32>,
+    db: web::Data<ApiDatabase>,
+) -> actix_web::Result<AlertResponse> {
+    let repo = db.repository::<AlertRepo>();
+    let alert = repo.get(id.into_inner()).map_err(ApiError::from)?;
+    if !can_access(&user, alert.avs_id) {
+        return Err(ApiError::new("Not found").status(404).into());
+    }
+    let res = repo.resolve(alert.id).map_err(ApiError::from)?;
+    AlertResponse::from(res).wrap()
+}
+
+api_rt::routes! {
+    get_rules
+    get_group_rule
+    put_group_rule
+    delete_group_rule
+    get_alerts
+    acknowledge
+    resolve
+}
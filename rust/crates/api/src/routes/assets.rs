@@ -17,7 +17,7 @@ If MyRTS violates any of these terms, their license to use the software will aut
 
 use crate::ApiError;
 use actix_web::{http::StatusCode, web};
-use utils::files::ApiAssets;
+use utils::{files::ApiAssets, media::AudioFormat};
 
 /// # Get audio.
 /// Get the audio file.
@@ -27,8 +27,8 @@ use utils::files::ApiAssets;
 /// responses:
 ///     - status: 200
 ///       content: !T File
-///       content_type: audio/mp3
-///       description: The audio file.
+///       content_type: audio/mpeg
+///       description: The audio file, the content type follows the file format (mp3, ogg, wav or flac).
 ///     - status: 400
 ///       content: !T ApiError
 ///       description: The request is invalid.
@@ -46,8 +46,13 @@ async fn get_audio(
     assets: web::Data<ApiAssets>,
 ) -> actix_web::Result<impl actix_web::Responder> {
     let f = assets.read_audio(&name).map_err(ApiError::new)?;
+    let mime = name
+        .rsplit_once('.')
+        .and_then(|(_, ext)| AudioFormat::from_extension(ext))
+        .map(|f| f.mime())
+        .unwrap_or("audio/mpeg");
     Ok(actix_web::HttpResponse::build(StatusCode::OK)
-        .content_type("audio/mp3")
+        .content_type(mime)
         .body(f))
 }
 
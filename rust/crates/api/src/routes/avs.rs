@@ -15,17 +15,70 @@ MyRTS agrees to credit you as the developer of the software in all promotional m
 If MyRTS violates any of these terms, their license to use the software will automatically terminate.
 */
 
-use crate::{middlewares::auth::Auth, ApiError};
+use crate::{
+    middlewares::auth::Auth,
+    routes::{device_configs, playlists, schedules::refresh_schedules},
+    ApiError,
+};
 use actix_web::{web, Responder};
-use api_db::{repos::AvsRepo, ApiDatabase};
+use api_db::{
+    repos::{AvsPortRepo, AvsRepo, ScheduleRepo, SelfTestRepo, TelemetryRepo},
+    ApiDatabase,
+};
+use chrono::{Duration, NaiveDate, NaiveDateTime};
 use proto::WsState;
 use timeslots::TimeSlots;
 use types::api::{
     avs::{AvsResponse, PartialUpdateAvs},
+    avs_port::{AvsPortResponse, UpdateAvsPortReq},
+    self_test::{NewSelfTest, SelfTestReq, SelfTestResponse},
+    telemetry::{TelemetryQuery, TelemetryResponse},
     user::User,
     Message,
 };
 
+/// Maximum number of days of telemetry that can be requested at once.
+const MAX_TELEMETRY_DAYS: i64 = 31;
+
+/// Number of latest self tests returned per avs.
+const MAX_SELF_TESTS: i64 = 20;
+
+/// Parse the time range of the telemetry query, defaults to the last 24 hours.
+fn parse_telemetry_range(
+    query: TelemetryQuery,
+) -> Result<(NaiveDateTime, NaiveDateTime), ApiError> {
+    let parse = |value: &str| {
+        NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S")
+            .or_else(|_| {
+                NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                    .map(|d| d.and_hms_opt(0, 0, 0).unwrap())
+            })
+            .map_err(|_| {
+                ApiError::new("Invalid time, expected YYYY-MM-DDTHH:MM:SS or YYYY-MM-DD")
+                    .status(400)
+            })
+    };
+    let to = match query.to {
+        Some(to) => parse(&to)?,
+        None => chrono::Utc::now().naive_utc(),
+    };
+    let from = match query.from {
+        Some(from) => parse(&from)?,
+        None => to - Duration::days(1),
+    };
+    if from >= to {
+        return Err(ApiError::new("Invalid time range").status(400));
+    }
+    if to - from > Duration::days(MAX_TELEMETRY_DAYS) {
+        return Err(ApiError::new(format!(
+            "Time range can't be more than {} days",
+            MAX_TELEMETRY_DAYS
+        ))
+        .status(400));
+    }
+    Ok((from, to))
+}
+
 /// # Get all avs.
 ///
 /// This endpoint returns all avs with the following rules:
@@ -173,8 +226,10 @@ async fn patch_accept(
     if user.role_id == 1 || user.role_id == 2 {
         let repo = db.repository::<AvsRepo>();
         let avs = repo.accept(id.into_inner()).map_err(ApiError::from)?;
-        if let Some(stream) = ws_state.avs_by_id(avs.unique_id).await {
+        if let Some(stream) = ws_state.avs_by_id(avs.unique_id.clone()).await {
             let _ = stream.write("authenticated", "").await;
+            device_configs::push(&db, &ws_state, vec![avs.clone()]).await;
+            playlists::push(&db, &ws_state, vec![avs]).await;
         }
         Message::new("ok".to_owned()).wrap()
     } else {
@@ -182,6 +237,339 @@ async fn patch_accept(
     }
 }
 
+/// # Reset avs credential.
+///
+/// This endpoint clears the credential of an avs, e.g. after its storage was wiped.
+/// The avs adopts a new credential on its next connection, a connected avs is disconnected.
+/// ****Rules:****
+/// Only Root and SuperAdmin can access this endpoint.
+/// ---
+/// tags:
+///     - avs
+/// responses:
+///     - status: 200
+///       content: !T Message
+///       description: The credential is reset.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 404
+///       content: !T ApiError
+///       description: The avs is not found.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// params:
+///     - name: id
+///       kind: !Path i32
+///       required: true
+/// auth: api_key
+#[api_rt::route(post, "/avs/{id}/credential/reset", Auth)]
+async fn post_credential_reset(
+    user: web::ReqData<User>,
+    id: web::Path<i32>,
+    db: web::Data<ApiDatabase>,
+    ws_state: web::Data<WsState>,
+) -> actix_web::Result<Message> {
+    if user.role_id != 1 && user.role_id != 2 {
+        return Err(ApiError::new("Unauthorized").status(401).into());
+    }
+    let repo = db.repository::<AvsRepo>();
+    let avs = repo.get(id.into_inner()).map_err(ApiError::from)?;
+    repo.set_credential(avs.id, None).map_err(ApiError::from)?;
+    if let Some(stream) = ws_state.avs_by_id(avs.unique_id).await {
+        let _ = stream.disconnect().await;
+    }
+    Message::new("ok".to_owned()).wrap()
+}
+
+/// # Get avs telemetry.
+///
+/// This endpoint returns the telemetry history of an avs, ordered by time.
+/// Timestamps are in UTC, traffic counters are the total bytes since the avs booted.
+/// ****Rules:****
+/// Only Root and SuperAdmin can access this endpoint.
+/// ---
+/// tags:
+///     - avs
+/// responses:
+///     - status: 200
+///       content: !Vec TelemetryResponse
+///       description: The telemetry history.
+///     - status: 400
+///       content: !T ApiError
+///       description: The request is invalid.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// params:
+///     - name: id
+///       kind: !Path i32
+///       required: true
+///       description: The avs id.
+///     - name: from
+///       kind: !Query String
+///       required: false
+///       description: The start time (YYYY-MM-DDTHH:MM:SS), default to 24 hours before `to`.
+///     - name: to
+///       kind: !Query String
+///       required: false
+///       description: The end time (YYYY-MM-DDTHH:MM:SS), default to now.
+/// auth: api_key
+#[api_rt::route(get, "/avs/{id}/telemetry", Auth)]
+async fn get_telemetry(
+    user: web::ReqData<User>,
+    id: web::Path<i32>,
+    query: web::Query<TelemetryQuery>,
+    db: web::Data<ApiDatabase>,
+) -> actix_web::Result<impl Responder> {
+    if user.role_id != 1 && user.role_id != 2 {
+        return Err(ApiError::new("Unauthorized").status(401).into());
+    }
+    let id = id.into_inner();
+    let (from, to) = parse_telemetry_range(query.into_inner())?;
+    let repo = db.repository::<TelemetryRepo>();
+    let res = repo.get_by_avs(id, from, to).map_err(ApiError::from)?;
+    TelemetryResponse::wrap_vec(res.into_iter().map(TelemetryResponse::from).collect(), None)
+}
+
+/// # Run avs self test.
+///
+/// This endpoint asks a connected avs to test its audio output, decoders, disk and database.
+/// The avs reports the result on its own, poll the self tests of the avs for the checks.
+/// ****Rules:****
+/// Root and SuperAdmin can access every avs, Admin can only access their own avs.
+/// ---
+/// tags:
+///     - avs
+/// request:
+///     content: !T SelfTestReq
+///     content_type: application/json
+///     description: Whether to play a test tone.
+/// responses:
+///     - status: 200
+///       content: !T SelfTestResponse
+///       description: The pending self test.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 404
+///       content: !T ApiError
+///       description: The avs is not found or not connected.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// params:
+///     - name: id
+///       kind: !Path i32
+///       required: true
+///       description: The avs id.
+/// auth: api_key
+#[api_rt::route(post, "/avs/{id}/self_test", Auth)]
+async fn post_self_test(
+    user: web::ReqData<User>,
+    id: web::Path<i32>,
+    data: web::Json<SelfTestReq>,
+    db: web::Data<ApiDatabase>,
+    ws_state: web::Data<WsState>,
+) -> actix_web::Result<SelfTestResponse> {
+    let id = id.into_inner();
+    if user.role_id != 1 && user.role_id != 2 && !user.device_ids.contains(&Some(id)) {
+        return Err(ApiError::new("Not found").status(404).into());
+    }
+    let avs = db.repository::<AvsRepo>().get(id).map_err(ApiError::from)?;
+    let stream = match ws_state.avs_by_id(avs.unique_id).await {
+        Some(stream) => stream,
+        None => return Err(ApiError::new("Avs is not connected").status(404).into()),
+    };
+    let test = db
+        .repository::<SelfTestRepo>()
+        .create(NewSelfTest {
+            avs_id: avs.id,
+            requested_by: Some(user.id),
+        })
+        .map_err(ApiError::from)?;
+    let req = types::proto::SelfTestReq {
+        id: test.id,
+        tone: data.tone.unwrap_or(true),
+    };
+    if stream.write("self_test", req).await.is_err() {
+        return Err(ApiError::new("Failed to reach the avs").status(500).into());
+    }
+    SelfTestResponse::from((test, Vec::new())).wrap()
+}
+
+/// # Get avs self tests.
+///
+/// This endpoint returns the latest self tests of an avs along with their checks, newest first.
+/// ****Rules:****
+/// Root and SuperAdmin can access every avs, Admin can only access their own avs.
+/// ---
+/// tags:
+///     - avs
+/// responses:
+///     - status: 200
+///       content: !Vec SelfTestResponse
+///       description: The self tests.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 404
+///       content: !T ApiError
+///       description: The avs is not found.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// params:
+///     - name: id
+///       kind: !Path i32
+///       required: true
+///       description: The avs id.
+/// auth: api_key
+#[api_rt::route(get, "/avs/{id}/self_tests", Auth)]
+async fn get_self_tests(
+    user: web::ReqData<User>,
+    id: web::Path<i32>,
+    db: web::Data<ApiDatabase>,
+) -> actix_web::Result<impl Responder> {
+    let id = id.into_inner();
+    if user.role_id != 1 && user.role_id != 2 && !user.device_ids.contains(&Some(id)) {
+        return Err(ApiError::new("Not found").status(404).into());
+    }
+    let repo = db.repository::<SelfTestRepo>();
+    let res = repo
+        .get_by_avs(id, MAX_SELF_TESTS)
+        .map_err(ApiError::from)?;
+    SelfTestResponse::wrap_vec(res.into_iter().map(SelfTestResponse::from).collect(), None)
+}
+
+/// # Get avs ports.
+///
+/// This endpoint returns the audio outputs reported by an avs.
+/// Schedules and live streams can be played on specific ports or zones.
+/// ****Rules:****
+/// Root and SuperAdmin can access every avs, Admin can only access their own avs.
+/// ---
+/// tags:
+///     - avs
+/// responses:
+///     - status: 200
+///       content: !Vec AvsPortResponse
+///       description: The avs ports.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 404
+///       content: !T ApiError
+///       description: The avs is not found.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// params:
+///     - name: id
+///       kind: !Path i32
+///       required: true
+///       description: The avs id.
+/// auth: api_key
+#[api_rt::route(get, "/avs/{id}/ports", Auth)]
+async fn get_ports(
+    user: web::ReqData<User>,
+    id: web::Path<i32>,
+    db: web::Data<ApiDatabase>,
+) -> actix_web::Result<impl Responder> {
+    let id = id.into_inner();
+    if user.role_id != 1 && user.role_id != 2 && !user.device_ids.contains(&Some(id)) {
+        return Err(ApiError::new("Not found").status(404).into());
+    }
+    let repo = db.repository::<AvsPortRepo>();
+    let res = repo.get_by_avs(id).map_err(ApiError::from)?;
+    AvsPortResponse::wrap_vec(res.into_iter().map(AvsPortResponse::from).collect(), None)
+}
+
+/// # Update avs port.
+///
+/// This endpoint responsible for naming an avs port and assigning it to a zone.
+/// Schedules routed to the ports or zones of the avs are sent again.
+/// ****Rules:****
+/// Only Root and SuperAdmin can access this endpoint.
+/// ---
+/// tags:
+///     - avs
+/// request:
+///     content: !T UpdateAvsPortReq
+///     content_type: application/json
+///     description: The name and zone of the port.
+/// responses:
+///     - status: 200
+///       content: !T AvsPortResponse
+///       description: The port is updated.
+///     - status: 400
+///       content: !T ApiError
+///       description: The request is invalid.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 404
+///       content: !T ApiError
+///       description: The port is not found.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// params:
+///     - name: id
+///       kind: !Path i32
+///       required: true
+///       description: The port id.
+/// auth: api_key
+#[api_rt::route(put, "/avs/ports/{id}", Auth)]
+async fn put_port(
+    user: web::ReqData<User>,
+    id: web::Path<i32>,
+    data: web::Json<UpdateAvsPortReq>,
+    db: web::Data<ApiDatabase>,
+    ws_state: web::Data<WsState>,
+) -> actix_web::Result<AvsPortResponse> {
+    if user.role_id != 1 && user.role_id != 2 {
+        return Err(ApiError::new("Unauthorized").status(401).into());
+    }
+    let data = data.into_inner();
+    let name = data.name.trim();
+    if name.is_empty() || name.len() > 255 {
+        return Err(ApiError::new("Invalid port name").status(400).into());
+    }
+    let zone = data
+        .zone
+        .as_deref()
+        .map(str::trim)
+        .filter(|z| !z.is_empty());
+    if zone.map(|z| z.len() > 255).unwrap_or(false) {
+        return Err(ApiError::new("Invalid zone").status(400).into());
+    }
+    let repo = db.repository::<AvsPortRepo>();
+    let res = repo
+        .update(id.into_inner(), name, zone)
+        .map_err(ApiError::from)?;
+    let avs = db
+        .repository::<AvsRepo>()
+        .get(res.avs_id)
+        .map_err(ApiError::from)?;
+    let sids = db
+        .repository::<ScheduleRepo>()
+        .get_by_avs(avs.id)
+        .map_err(ApiError::from)?
+        .into_iter()
+        .filter(|s| s.port_ids.iter().any(Option::is_some) || s.zones.iter().any(Option::is_some))
+        .map(|s| s.id)
+        .collect::<Vec<i32>>();
+    tokio::spawn(async move {
+        refresh_schedules(&ws_state, vec![avs.unique_id], sids).await;
+    });
+    AvsPortResponse::from(res).wrap()
+}
+
 #[api_rt::route(get, "/avs/slot/{id}")]
 async fn get_slot(
     id: web::Path<i32>,
@@ -236,6 +624,12 @@ api_rt::routes! {
     get
     patch_id
     patch_accept
+    post_credential_reset
+    get_telemetry
+    post_self_test
+    get_self_tests
+    get_ports
+    put_port
     #[no_doc]
     get_slot
     delete
@@ -0,0 +1,364 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use crate::{middlewares::auth::Auth, ApiError, Config};
+use actix_web::web;
+use api_db::{
+    repos::{AvsRepo, BroadcastFilter, BroadcastRepo, RecordsRepo},
+    ApiDatabase,
+};
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use proto::WsState;
+use std::collections::HashMap;
+use types::{
+    api::{
+        broadcast::{
+            BroadcastData, BroadcastQuery, BusyTargetData, PaginatedBroadcastData, PlaybackData,
+            PlaybackReq,
+        },
+        user::User,
+        Message,
+    },
+    proto::{stream_priority, Conflict},
+};
+use utils::files::ApiAssets;
+
+/// Parse a date of the query, formatted as `YYYY-MM-DD`.
+fn parse_date(date: &str) -> Result<NaiveDateTime, ApiError> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map(|d| d.and_hms_opt(0, 0, 0).unwrap())
+        .map_err(|_| ApiError::new("Invalid date, expected YYYY-MM-DD").status(400))
+}
+
+/// # Get broadcasts.
+///
+/// This endpoint returns the history of the live broadcasts along with their targets,
+/// newest first.
+/// Every target records when it joined, left or failed, excluded targets record the reason
+/// they didn't receive the stream.
+/// ****Rules:****
+/// Root and SuperAdmin can access every broadcast, other users can only access their own broadcasts.
+/// ---
+/// tags:
+///     - broadcasts
+/// responses:
+///     - status: 200
+///       content: !T PaginatedBroadcastData
+///       description: The broadcast history.
+///     - status: 400
+///       content: !T ApiError
+///       description: The request is invalid.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// params:
+///     - name: page
+///       kind: !Query i64
+///       required: false
+///       description: The page number, if not provided, it will default to 1.
+///     - name: user_id
+///       kind: !Query i32
+///       required: false
+///       description: Only the broadcasts of the user.
+///     - name: avs_id
+///       kind: !Query i32
+///       required: false
+///       description: Only the broadcasts targeting the avs.
+///     - name: from
+///       kind: !Query String
+///       required: false
+///       description: The first date (YYYY-MM-DD) the broadcast started on.
+///     - name: to
+///       kind: !Query String
+///       required: false
+///       description: The last date (YYYY-MM-DD) the broadcast started on.
+/// auth: api_key
+#[api_rt::route(get, "/broadcasts", Auth)]
+async fn get(
+    user: web::ReqData<User>,
+    query: web::Query<BroadcastQuery>,
+    db: web::Data<ApiDatabase>,
+    cfg: web::Data<Config>,
+) -> actix_web::Result<PaginatedBroadcastData> {
+    let query = query.into_inner();
+    let page = query.page.unwrap_or(1).max(1);
+    let user_id = if user.role_id != 1 && user.role_id != 2 {
+        Some(user.id)
+    } else {
+        query.user_id
+    };
+    let filter = BroadcastFilter {
+        user_ids: user_id.map(|id| vec![id]),
+        avs_ids: query.avs_id.map(|id| vec![id]),
+        from: query.from.as_deref().map(parse_date).transpose()?,
+        to: query
+            .to
+            .as_deref()
+            .map(parse_date)
+            .transpose()?
+            .map(|to| to + Duration::days(1)),
+    };
+    let repo = db.repository::<BroadcastRepo>();
+    let (broadcasts, total, total_pages) =
+        repo.get_paginated(filter, page).map_err(ApiError::from)?;
+    let mut params = String::new();
+    if let Some(user_id) = query.user_id {
+        params.push_str(&format!("&user_id={}", user_id));
+    }
+    if let Some(avs_id) = query.avs_id {
+        params.push_str(&format!("&avs_id={}", avs_id));
+    }
+    if let Some(from) = &query.from {
+        params.push_str(&format!("&from={}", from));
+    }
+    if let Some(to) = &query.to {
+        params.push_str(&format!("&to={}", to));
+    }
+    let prev = if page > 1 {
+        Some(cfg.format(format!("/broadcasts?page={}{}", page - 1, params)))
+    } else {
+        None
+    };
+    let next = if total > page * 10 {
+        Some(cfg.format(format!("/broadcasts?page={}{}", page + 1, params)))
+    } else {
+        None
+    };
+    PaginatedBroadcastData::new(
+        broadcasts.into_iter().map(BroadcastData::from).collect(),
+        total,
+        page,
+        total_pages,
+        next,
+        prev,
+    )
+    .wrap()
+}
+
+/// # Get broadcast by id.
+///
+/// This endpoint returns a broadcast along with its targets.
+/// ****Rules:****
+/// Root and SuperAdmin can access every broadcast, other users can only access their own broadcasts.
+/// ---
+/// tags:
+///     - broadcasts
+/// responses:
+///     - status: 200
+///       content: !T BroadcastData
+///       description: The broadcast.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 404
+///       content: !T ApiError
+///       description: The broadcast is not found.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// params:
+///     - name: id
+///       kind: !Path i32
+///       required: true
+///       description: The broadcast id.
+/// auth: api_key
+#[api_rt::route(get, "/broadcasts/{id}", Auth)]
+async fn get_by_id(
+    user: web::ReqData<User>,
+    id: web::Path<i32>,
+    db: web::Data<ApiDatabase>,
+) -> actix_web::Result<BroadcastData> {
+    let repo = db.repository::<BroadcastRepo>();
+    let broadcast = repo.get(id.into_inner()).map_err(ApiError::from)?;
+    if user.role_id != 1 && user.role_id != 2 && broadcast.0.user_id != Some(user.id) {
+        return Err(ApiError::new("Not found").status(404).into());
+    }
+    BroadcastData::from(broadcast).wrap()
+}
+
+/// # Play a record now.
+///
+/// This endpoint plays a record right away on the connected avs and the avs of the groups,
+/// the server streams the record to the avs like a live broadcast.
+/// The returned broadcast lists the targets, the delivery status of every target is updated
+/// as they join, fail or leave and can be followed with `GET /broadcasts/{id}`.
+/// Targets that are offline are excluded, the targets busy with another broadcast are
+/// preempted, queued or excluded by the `conflict` policy and listed in `busy`, the user
+/// also receives them as the `offer:busy` event when connected to the stream server.
+/// A preempted broadcast gets its targets back when the playback ends.
+/// ****Rules:****
+/// Root and SuperAdmin can target every avs, other users can only play their own records
+/// on their own avs and groups, emergency playback is not allowed for Admin.
+/// ---
+/// tags:
+///     - broadcasts
+/// request:
+///     content: !T PlaybackReq
+///     content_type: application/json
+///     description: The record and the target avs.
+/// responses:
+///     - status: 200
+///       content: !T PlaybackData
+///       description: The broadcast of the playback and the busy targets.
+///     - status: 400
+///       content: !T ApiError
+///       description: The request is invalid or no target is available.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 404
+///       content: !T ApiError
+///       description: The record or the avs is not found.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// auth: api_key
+#[api_rt::route(post, "/broadcasts/playback", Auth)]
+async fn post_playback(
+    user: web::ReqData<User>,
+    data: web::Json<PlaybackReq>,
+    db: web::Data<ApiDatabase>,
+    assets: web::Data<ApiAssets>,
+    ws_state: web::Data<WsState>,
+) -> actix_web::Result<PlaybackData> {
+    let data = data.into_inner();
+    let privileged = user.role_id == 1 || user.role_id == 2;
+    let emergency = data.emergency.unwrap_or(false);
+    let conflict = data.conflict.unwrap_or_else(|| "reject".to_owned());
+    if let Err(e) = conflict.parse::<Conflict>() {
+        return Err(ApiError::new(e).status(400).into());
+    }
+    let priority = stream_priority(user.role_id, emergency, data.priority);
+    if emergency && user.role_id == 3 {
+        return Err(ApiError::new("Emergency playback is not allowed")
+            .status(401)
+            .into());
+    }
+    let record = db
+        .repository::<RecordsRepo>()
+        .get(data.record_id)
+        .map_err(ApiError::from)?;
+    if !privileged && record.user_id != user.id {
+        return Err(ApiError::new("Not found").status(404).into());
+    }
+    let name = record.file_url.rsplit('/').next().unwrap_or_default();
+    if !assets.audio_exists(name) {
+        return Err(ApiError::new("Record file is not found").status(404).into());
+    }
+
+    let avs_repo = db.repository::<AvsRepo>();
+    let mut avs_ids = data.avs_ids;
+    for group_id in data.group_ids {
+        if !privileged && !user.user_group_ids.contains(&Some(group_id)) {
+            return Err(ApiError::new("Group not found").status(404).into());
+        }
+        let avs = avs_repo.get_by_group(group_id).map_err(ApiError::from)?;
+        avs_ids.extend(avs.into_iter().map(|avs| avs.id));
+    }
+    avs_ids.sort();
+    avs_ids.dedup();
+    let mut target = vec![];
+    let mut unique_ids = HashMap::new();
+    for avs_id in avs_ids {
+        if !privileged && !user.device_ids.contains(&Some(avs_id)) {
+            continue;
+        }
+        let avs = avs_repo.get(avs_id).map_err(ApiError::from)?;
+        unique_ids.insert(avs.unique_id.clone(), avs.id);
+        target.push(avs.unique_id);
+    }
+    if target.is_empty() {
+        return Err(ApiError::new("Target avs is empty").status(400).into());
+    }
+
+    let (broadcast_id, busy) = ws_state
+        .start_playback(
+            user.id,
+            assets.audio_path(name),
+            target,
+            emergency,
+            priority,
+            conflict,
+        )
+        .await
+        .map_err(|e| ApiError::new(e).status(400))?;
+    let busy = busy
+        .into_iter()
+        .filter_map(|(unique_id, status)| {
+            let avs_id = unique_ids.get(&unique_id)?;
+            Some(BusyTargetData::new(*avs_id, status))
+        })
+        .collect();
+    let repo = db.repository::<BroadcastRepo>();
+    let broadcast = BroadcastData::from(repo.get(broadcast_id).map_err(ApiError::from)?);
+    PlaybackData::new(broadcast, busy).wrap()
+}
+
+/// # Stop a playback.
+///
+/// This endpoint stops the playback of a record started with `POST /broadcasts/playback`.
+/// ****Rules:****
+/// Root and SuperAdmin can stop every playback, other users can only stop their own playback.
+/// ---
+/// tags:
+///     - broadcasts
+/// responses:
+///     - status: 200
+///       content: !T Message
+///       description: The playback is stopped.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 404
+///       content: !T ApiError
+///       description: The playback is not found or already finished.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// params:
+///     - name: id
+///       kind: !Path i32
+///       required: true
+///       description: The broadcast id.
+/// auth: api_key
+#[api_rt::route(delete, "/broadcasts/{id}/playback", Auth)]
+async fn delete_playback(
+    user: web::ReqData<User>,
+    id: web::Path<i32>,
+    db: web::Data<ApiDatabase>,
+    ws_state: web::Data<WsState>,
+) -> actix_web::Result<Message> {
+    let repo = db.repository::<BroadcastRepo>();
+    let (broadcast, _) = repo.get(id.into_inner()).map_err(ApiError::from)?;
+    if user.role_id != 1 && user.role_id != 2 && broadcast.user_id != Some(user.id) {
+        return Err(ApiError::new("Not found").status(404).into());
+    }
+    if !ws_state.stop_playback(broadcast.id).await {
+        return Err(ApiError::new("Playback is not running").status(404).into());
+    }
+    Message::new("ok".to_owned()).wrap()
+}
+
+api_rt::routes! {
+    get
+    get_by_id
+    post_playback
+    delete_playback
+}
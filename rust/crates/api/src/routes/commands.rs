@@ -0,0 +1,291 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use crate::{middlewares::auth::Auth, ApiError, Config};
+use actix_web::web;
+use api_db::{
+    repos::{CommandAuditFilter, CommandAuditRepo},
+    ApiDatabase,
+};
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use types::{
+    api::{
+        command_audit::{
+            CommandAction, CommandActionParam, CommandAuditQuery, CommandAuditResponse,
+            PaginatedCommandAuditResponse,
+        },
+        user::User,
+    },
+    proto::action::{ParamKind, ACTIONS},
+};
+
+/// Parse a date of the query, formatted as `YYYY-MM-DD`.
+fn parse_date(date: &str) -> Result<NaiveDateTime, ApiError> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map(|d| d.and_hms_opt(0, 0, 0).unwrap())
+        .map_err(|_| ApiError::new("Invalid date, expected YYYY-MM-DD").status(400))
+}
+
+/// Get a page of the command audit matching the filter, `path` is the path of the page links.
+fn get_page(
+    db: &ApiDatabase,
+    cfg: &Config,
+    path: String,
+    mut filter: CommandAuditFilter,
+    query: CommandAuditQuery,
+) -> actix_web::Result<PaginatedCommandAuditResponse> {
+    let page = query.page.unwrap_or(1).max(1);
+    filter.q = query.q.clone().filter(|q| !q.is_empty());
+    filter.from = query.from.as_deref().map(parse_date).transpose()?;
+    filter.to = query
+        .to
+        .as_deref()
+        .map(parse_date)
+        .transpose()?
+        .map(|to| to + Duration::days(1));
+    let repo = db.repository::<CommandAuditRepo>();
+    let (commands, total, total_pages) =
+        repo.get_paginated(filter, page).map_err(ApiError::from)?;
+    let mut params = String::new();
+    if let Some(q) = &query.q {
+        params.push_str(&format!("&q={}", q));
+    }
+    if let Some(from) = &query.from {
+        params.push_str(&format!("&from={}", from));
+    }
+    if let Some(to) = &query.to {
+        params.push_str(&format!("&to={}", to));
+    }
+    let prev = if page > 1 {
+        Some(cfg.format(format!("{}?page={}{}", path, page - 1, params)))
+    } else {
+        None
+    };
+    let next = if total > page * 10 {
+        Some(cfg.format(format!("{}?page={}{}", path, page + 1, params)))
+    } else {
+        None
+    };
+    PaginatedCommandAuditResponse::new(
+        commands
+            .into_iter()
+            .map(CommandAuditResponse::from)
+            .collect(),
+        total,
+        page,
+        total_pages,
+        next,
+        prev,
+    )
+    .wrap()
+}
+
+/// # Get commands by avs.
+///
+/// This endpoint returns the audit of the remote commands sent to an avs, newest first.
+/// Every command records the user who sent it, whether the avs was online, the exit status
+/// and the output, the output is cut to the first 8 KiB.
+/// Commands are kept for the retention period of the server.
+/// ****Rules:****
+/// Root and SuperAdmin can access every avs, Admin can only access their own avs.
+/// ---
+/// tags:
+///     - commands
+/// responses:
+///     - status: 200
+///       content: !T PaginatedCommandAuditResponse
+///       description: The command audit.
+///     - status: 400
+///       content: !T ApiError
+///       description: The request is invalid.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 404
+///       content: !T ApiError
+///       description: The avs is not found.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// params:
+///     - name: id
+///       kind: !Path i32
+///       required: true
+///       description: The avs id.
+///     - name: page
+///       kind: !Query i64
+///       required: false
+///       description: The page number, if not provided, it will default to 1.
+///     - name: q
+///       kind: !Query String
+///       required: false
+///       description: Only the commands containing the text.
+///     - name: from
+///       kind: !Query String
+///       required: false
+///       description: The first date (YYYY-MM-DD) the command was sent on.
+///     - name: to
+///       kind: !Query String
+///       required: false
+///       description: The last date (YYYY-MM-DD) the command was sent on.
+/// auth: api_key
+#[api_rt::route(get, "/commands/avs/{id}", Auth)]
+async fn get_by_avs(
+    user: web::ReqData<User>,
+    id: web::Path<i32>,
+    query: web::Query<CommandAuditQuery>,
+    db: web::Data<ApiDatabase>,
+    cfg: web::Data<Config>,
+) -> actix_web::Result<PaginatedCommandAuditResponse> {
+    let id = id.into_inner();
+    if user.role_id != 1 && user.role_id != 2 && !user.device_ids.contains(&Some(id)) {
+        return Err(ApiError::new("Not found").status(404).into());
+    }
+    let filter = CommandAuditFilter {
+        avs_id: Some(id),
+        ..Default::default()
+    };
+    get_page(
+        &db,
+        &cfg,
+        format!("/commands/avs/{}", id),
+        filter,
+        query.into_inner(),
+    )
+}
+
+/// # Get commands by user.
+///
+/// This endpoint returns the audit of the remote commands sent by a user, newest first.
+/// ****Rules:****
+/// Root and SuperAdmin can access every user, other users can only access their own commands.
+/// ---
+/// tags:
+///     - commands
+/// responses:
+///     - status: 200
+///       content: !T PaginatedCommandAuditResponse
+///       description: The command audit.
+///     - status: 400
+///       content: !T ApiError
+///       description: The request is invalid.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 404
+///       content: !T ApiError
+///       description: The user is not found.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// params:
+///     - name: id
+///       kind: !Path i32
+///       required: true
+///       description: The user id.
+///     - name: page
+///       kind: !Query i64
+///       required: false
+///       description: The page number, if not provided, it will default to 1.
+///     - name: q
+///       kind: !Query String
+///       required: false
+///       description: Only the commands containing the text.
+///     - name: from
+///       kind: !Query String
+///       required: false
+///       description: The first date (YYYY-MM-DD) the command was sent on.
+///     - name: to
+///       kind: !Query String
+///       required: false
+///       description: The last date (YYYY-MM-DD) the command was sent on.
+/// auth: api_key
+#[api_rt::route(get, "/commands/users/{id}", Auth)]
+async fn get_by_user(
+    user: web::ReqData<User>,
+    id: web::Path<i32>,
+    query: web::Query<CommandAuditQuery>,
+    db: web::Data<ApiDatabase>,
+    cfg: web::Data<Config>,
+) -> actix_web::Result<PaginatedCommandAuditResponse> {
+    let id = id.into_inner();
+    if user.role_id != 1 && user.role_id != 2 && user.id != id {
+        return Err(ApiError::new("Not found").status(404).into());
+    }
+    let filter = CommandAuditFilter {
+        user_id: Some(id),
+        ..Default::default()
+    };
+    get_page(
+        &db,
+        &cfg,
+        format!("/commands/users/{}", id),
+        filter,
+        query.into_inner(),
+    )
+}
+
+/// # Get command actions.
+///
+/// This endpoint returns the predefined remote actions the user is allowed to run.
+/// An action is sent as the `action` of the `command` event along with its `params`,
+/// the avs only runs the actions of its catalog.
+/// ****Rules:****
+/// Raw shell commands are only allowed for Root, every command requires access to the avs.
+/// The avs only runs the raw commands whose program is listed in the `allowed_commands` of its config.
+/// ---
+/// tags:
+///     - commands
+/// responses:
+///     - status: 200
+///       content: !Vec CommandAction
+///       description: The allowed actions.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// auth: api_key
+#[api_rt::route(get, "/commands/actions", Auth)]
+async fn get_actions(user: web::ReqData<User>) -> actix_web::Result<impl actix_web::Responder> {
+    let actions = ACTIONS
+        .iter()
+        .filter(|a| a.allows(user.role_id))
+        .map(|a| {
+            let params = a
+                .params
+                .iter()
+                .map(|p| {
+                    let kind = match p.kind {
+                        ParamKind::Name => "name",
+                        ParamKind::Percent => "percent",
+                    };
+                    CommandActionParam::new(p.name.to_owned(), p.required, kind.to_owned())
+                })
+                .collect();
+            CommandAction::new(a.name.to_owned(), a.description.to_owned(), params)
+        })
+        .collect();
+    CommandAction::wrap_vec(actions, None)
+}
+
+api_rt::routes! {
+    get_by_avs
+    get_by_user
+    get_actions
+}
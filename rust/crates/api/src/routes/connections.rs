@@ -0,0 +1,449 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use crate::{middlewares::auth::Auth, ApiError};
+use actix_web::{web, Responder};
+use api_db::{
+    repos::{AvsConnectionRepo, AvsRepo},
+    ApiDatabase,
+};
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use types::api::{
+    avs_connection::{
+        AvsConnection, AvsConnectionResponse, GroupUptimeResponse, Outage, UptimeQuery,
+        UptimeResponse,
+    },
+    user::User,
+};
+
+/// Maximum number of days that can be requested at once.
+const MAX_RANGE_DAYS: i64 = 31;
+/// The window of the flapping detection.
+const FLAP_WINDOW_MINUTES: i64 = 60;
+/// The default number of disconnects within the window to consider the avs flapping.
+const FLAP_THRESHOLD: i64 = 5;
+
+/// Parse the date range of the query, defaults to the last 7 days.
+fn parse_range(query: &UptimeQuery) -> Result<(NaiveDateTime, NaiveDateTime), ApiError> {
+    let parse = |date: &str| {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map(|d| d.and_hms_opt(0, 0, 0).unwrap())
+            .map_err(|_| ApiError::new("Invalid date, expected YYYY-MM-DD").status(400))
+    };
+    let to = match &query.to {
+        Some(to) => parse(to)? + Duration::days(1),
+        None => utils::time::now().date().and_hms_opt(0, 0, 0).unwrap() + Duration::days(1),
+    };
+    let from = match &query.from {
+        Some(from) => parse(from)?,
+        None => to - Duration::days(7),
+    };
+    if from >= to {
+        return Err(ApiError::new("Invalid date range").status(400));
+    }
+    if to - from > Duration::days(MAX_RANGE_DAYS) {
+        return Err(ApiError::new(format!(
+            "Date range can't be more than {} days",
+            MAX_RANGE_DAYS
+        ))
+        .status(400));
+    }
+    Ok((from, to))
+}
+
+/// Compute the uptime report of an avs from its sessions ordered by `connected_at`.
+/// The range is clipped to the current time.
+fn report(
+    avs_id: i32,
+    sessions: &[&AvsConnection],
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+    threshold: i64,
+) -> UptimeResponse {
+    let now = utils::time::now();
+    let end = to.min(now).max(from);
+    let mut online = Duration::zero();
+    let mut outages = vec![];
+    let mut cursor = from;
+    let mut reason = None;
+    let mut disconnects = vec![];
+    for session in sessions {
+        let start = session.connected_at.max(from);
+        let stop = session.disconnected_at.unwrap_or(end).min(end);
+        if start > cursor && start < end {
+            outages.push(Outage::new(
+                cursor,
+                Some(start),
+                (start - cursor).num_seconds(),
+                reason.clone(),
+            ));
+        }
+        if stop > cursor.max(start) {
+            online = online + (stop - cursor.max(start));
+        }
+        cursor = cursor.max(stop);
+        if let Some(disconnected_at) = session.disconnected_at {
+            if disconnected_at >= from && disconnected_at < end {
+                disconnects.push(disconnected_at);
+            }
+            reason = session.reason.clone();
+        }
+    }
+    if cursor < end {
+        outages.push(Outage::new(
+            cursor,
+            if end < now { Some(end) } else { None },
+            (end - cursor).num_seconds(),
+            reason,
+        ));
+    }
+    disconnects.sort();
+    let window = Duration::minutes(FLAP_WINDOW_MINUTES);
+    let mut max_per_hour = 0;
+    let mut first = 0;
+    for (i, at) in disconnects.iter().enumerate() {
+        while *at - disconnects[first] >= window {
+            first += 1;
+        }
+        max_per_hour = max_per_hour.max((i - first + 1) as i64);
+    }
+    let total = (end - from).num_seconds();
+    let uptime = if total > 0 {
+        online.num_seconds() as f64 * 100.0 / total as f64
+    } else {
+        0.0
+    };
+    UptimeResponse::new(
+        avs_id,
+        uptime,
+        online.num_seconds(),
+        disconnects.len() as i64,
+        max_per_hour,
+        max_per_hour >= threshold,
+        outages,
+    )
+}
+
+/// # Get connections by avs.
+///
+/// This endpoint returns the connection sessions of an avs overlapping the date range.
+/// ****Rules:****
+/// Root and SuperAdmin can access every avs, Admin can only access their own avs.
+/// ---
+/// tags:
+///     - connections
+/// responses:
+///     - status: 200
+///       content: !Vec AvsConnectionResponse
+///       description: The connection sessions.
+///     - status: 400
+///       content: !T ApiError
+///       description: The request is invalid.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 404
+///       content: !T ApiError
+///       description: The avs is not found.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// params:
+///     - name: id
+///       kind: !Path i32
+///       required: true
+///       description: The avs id.
+///     - name: from
+///       kind: !Query String
+///       required: false
+///       description: The first date (YYYY-MM-DD), default to 7 days before `to`.
+///     - name: to
+///       kind: !Query String
+///       required: false
+///       description: The last date (YYYY-MM-DD), default to today.
+/// auth: api_key
+#[api_rt::route(get, "/connections/avs/{id}", Auth)]
+async fn get_by_avs(
+    user: web::ReqData<User>,
+    id: web::Path<i32>,
+    query: web::Query<UptimeQuery>,
+    db: web::Data<ApiDatabase>,
+) -> actix_web::Result<impl Responder> {
+    let id = id.into_inner();
+    if user.role_id != 1 && user.role_id != 2 && !user.device_ids.contains(&Some(id)) {
+        return Err(ApiError::new("Not found").status(404).into());
+    }
+    let (from, to) = parse_range(&query)?;
+    let repo = db.repository::<AvsConnectionRepo>();
+    let res = repo.get_by_avs(&[id], from, to).map_err(ApiError::from)?;
+    AvsConnectionResponse::wrap_vec(
+        res.into_iter().map(AvsConnectionResponse::from).collect(),
+        None,
+    )
+}
+
+/// # Get uptime by avs.
+///
+/// This endpoint returns the uptime percentage, the outages and the flapping state of an avs
+/// over the date range, the range is clipped to the current time.
+/// An avs is flapping when it disconnects `flap_threshold` times within an hour.
+/// ****Rules:****
+/// Root and SuperAdmin can access every avs, Admin can only access their own avs.
+/// ---
+/// tags:
+///     - connections
+/// responses:
+///     - status: 200
+///       content: !T UptimeResponse
+///       description: The uptime report.
+///     - status: 400
+///       content: !T ApiError
+///       description: The request is invalid.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 404
+///       content: !T ApiError
+///       description: The avs is not found.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// params:
+///     - name: id
+///       kind: !Path i32
+///       required: true
+///       description: The avs id.
+///     - name: from
+///       kind: !Query String
+///       required: false
+///       description: The first date (YYYY-MM-DD), default to 7 days before `to`.
+///     - name: to
+///       kind: !Query String
+///       required: false
+///       description: The last date (YYYY-MM-DD), default to today.
+///     - name: flap_threshold
+///       kind: !Query i64
+///       required: false
+///       description: The number of disconnects within an hour to consider the avs flapping, default to 5.
+/// auth: api_key
+#[api_rt::route(get, "/connections/avs/{id}/uptime", Auth)]
+async fn get_uptime_by_avs(
+    user: web::ReqData<User>,
+    id: web::Path<i32>,
+    query: web::Query<UptimeQuery>,
+    db: web::Data<ApiDatabase>,
+) -> actix_web::Result<UptimeResponse> {
+    let id = id.into_inner();
+    if user.role_id != 1 && user.role_id != 2 && !user.device_ids.contains(&Some(id)) {
+        return Err(ApiError::new("Not found").status(404).into());
+    }
+    let (from, to) = parse_range(&query)?;
+    let threshold = query.flap_threshold.unwrap_or(FLAP_THRESHOLD).max(1);
+    let repo = db.repository::<AvsConnectionRepo>();
+    let sessions = repo.get_by_avs(&[id], from, to).map_err(ApiError::from)?;
+    report(
+        id,
+        &sessions.iter().collect::<Vec<_>>(),
+        from,
+        to,
+        threshold,
+    )
+    .wrap()
+}
+
+/// # Get uptime by group.
+///
+/// This endpoint returns the uptime report of every avs of a group over the date range,
+/// along with the average uptime percentage and the avs that are flapping.
+/// ****Rules:****
+/// Root and SuperAdmin can access every group, other users can only access their own group
+/// and only get their own avs.
+/// ---
+/// tags:
+///     - connections
+/// responses:
+///     - status: 200
+///       content: !T GroupUptimeResponse
+///       description: The uptime report of the group.
+///     - status: 400
+///       content: !T ApiError
+///       description: The request is invalid.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 404
+///       content: !T ApiError
+///       description: The group is not found.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// params:
+///     - name: id
+///       kind: !Path i32
+///       required: true
+///       description: The group id.
+///     - name: from
+///       kind: !Query String
+///       required: false
+///       description: The first date (YYYY-MM-DD), default to 7 days before `to`.
+///     - name: to
+///       kind: !Query String
+///       required: false
+///       description: The last date (YYYY-MM-DD), default to today.
+///     - name: flap_threshold
+///       kind: !Query i64
+///       required: false
+///       description: The number of disconnects within an hour to consider the avs flapping, default to 5.
+/// auth: api_key
+#[api_rt::route(get, "/connections/groups/{id}/uptime", Auth)]
+async fn get_uptime_by_group(
+    user: web::ReqData<User>,
+    id: web::Path<i32>,
+    query: web::Query<UptimeQuery>,
+    db: web::Data<ApiDatabase>,
+) -> actix_web::Result<GroupUptimeResponse> {
+    let id = id.into_inner();
+    let privileged = user.role_id == 1 || user.role_id == 2;
+    if !privileged && !user.user_group_ids.contains(&Some(id)) {
+        return Err(ApiError::new("Not found").status(404).into());
+    }
+    let (from, to) = parse_range(&query)?;
+    let threshold = query.flap_threshold.unwrap_or(FLAP_THRESHOLD).max(1);
+    let avs_ids = db
+        .repository::<AvsRepo>()
+        .get_by_group(id)
+        .map_err(ApiError::from)?
+        .into_iter()
+        .map(|avs| avs.id)
+        .filter(|avs_id| privileged || user.device_ids.contains(&Some(*avs_id)))
+        .collect::<Vec<i32>>();
+    let repo = db.repository::<AvsConnectionRepo>();
+    let sessions = repo
+        .get_by_avs(&avs_ids, from, to)
+        .map_err(ApiError::from)?;
+    let devices = avs_ids
+        .iter()
+        .map(|avs_id| {
+            let sessions = sessions
+                .iter()
+                .filter(|s| s.avs_id == *avs_id)
+                .collect::<Vec<&AvsConnection>>();
+            report(*avs_id, &sessions, from, to, threshold)
+        })
+        .collect::<Vec<UptimeResponse>>();
+    let uptime = if devices.is_empty() {
+        0.0
+    } else {
+        devices.iter().map(|d| d.uptime).sum::<f64>() / devices.len() as f64
+    };
+    let flapping = devices
+        .iter()
+        .filter(|d| d.flapping)
+        .map(|d| d.avs_id)
+        .collect();
+    GroupUptimeResponse::new(id, uptime, flapping, devices).wrap()
+}
+
+api_rt::routes! {
+    get_by_avs
+    get_uptime_by_avs
+    get_uptime_by_group
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(hour: u32, min: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2023, 1, 1)
+            .unwrap()
+            .and_hms_opt(hour, min, 0)
+            .unwrap()
+    }
+
+    fn session(
+        connected_at: NaiveDateTime,
+        disconnected_at: Option<NaiveDateTime>,
+        reason: Option<&str>,
+    ) -> AvsConnection {
+        AvsConnection {
+            id: 0,
+            avs_id: 1,
+            connected_at,
+            disconnected_at,
+            remote_addr: None,
+            reason: reason.map(str::to_owned),
+        }
+    }
+
+    fn range() -> (NaiveDateTime, NaiveDateTime) {
+        (at(0, 0), at(0, 0) + Duration::days(1))
+    }
+
+    #[test]
+    fn reports_outages_between_sessions() {
+        let (from, to) = range();
+        let sessions = [
+            session(from - Duration::hours(2), Some(at(6, 0)), Some("timeout")),
+            session(at(8, 0), None, None),
+        ];
+        let res = report(1, &sessions.iter().collect::<Vec<_>>(), from, to, 5);
+        assert_eq!(res.online_seconds, 22 * 3600);
+        assert!((res.uptime - 22.0 * 100.0 / 24.0).abs() < 1e-9);
+        assert_eq!(res.disconnects, 1);
+        assert!(!res.flapping);
+        assert_eq!(res.outages.len(), 1);
+        let outage = &res.outages[0];
+        assert_eq!((outage.from, outage.to), (at(6, 0), Some(at(8, 0))));
+        assert_eq!(outage.duration_seconds, 2 * 3600);
+        assert_eq!(outage.reason.as_deref(), Some("timeout"));
+    }
+
+    #[test]
+    fn detects_flapping() {
+        let (from, to) = range();
+        let sessions = (0..5)
+            .map(|i| {
+                let reason = if i == 4 { "power" } else { "timeout" };
+                session(at(9, i * 10), Some(at(9, i * 10 + 5)), Some(reason))
+            })
+            .collect::<Vec<_>>();
+        let res = report(1, &sessions.iter().collect::<Vec<_>>(), from, to, 5);
+        assert_eq!(res.disconnects, 5);
+        assert_eq!(res.max_disconnects_per_hour, 5);
+        assert!(res.flapping);
+        assert_eq!(res.online_seconds, 5 * 5 * 60);
+        assert_eq!(res.outages.len(), 6);
+        let last = res.outages.last().unwrap();
+        assert_eq!((last.from, last.to), (at(9, 45), Some(to)));
+        assert_eq!(last.reason.as_deref(), Some("power"));
+
+        let res = report(1, &sessions.iter().collect::<Vec<_>>(), from, to, 6);
+        assert!(!res.flapping);
+    }
+
+    #[test]
+    fn reports_no_sessions_as_one_outage() {
+        let (from, to) = range();
+        let res = report(1, &[], from, to, 5);
+        assert_eq!(res.uptime, 0.0);
+        assert_eq!(res.online_seconds, 0);
+        assert_eq!(res.outages.len(), 1);
+        assert_eq!(res.outages[0].duration_seconds, 24 * 3600);
+        assert_eq!(res.outages[0].reason, None);
+    }
+}
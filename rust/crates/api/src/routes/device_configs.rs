@@ -0,0 +1,486 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use crate::{middlewares::auth::Auth, ApiError};
+use actix_web::web;
+use api_db::{
+    repos::{AvsRepo, DeviceConfigRepo, VolumeProfileRepo},
+    ApiDatabase,
+};
+use chrono::NaiveTime;
+use proto::WsState;
+use types::api::{
+    avs::Avs,
+    device_config::{
+        DeviceConfigReq, DeviceConfigResponse, EffectiveDeviceConfig, NewDeviceConfig,
+    },
+    user::User,
+    Message,
+};
+
+/// Validate the configuration request.
+fn validate(data: &DeviceConfigReq) -> Result<(), ApiError> {
+    if let Some(volume) = data.volume {
+        if !(0.0..=1.0).contains(&volume) {
+            return Err(ApiError::new("Volume must be between 0 and 1").status(400));
+        }
+    }
+    if let Some(level) = &data.log_level {
+        if !["debug", "info", "warn", "error"].contains(&level.as_str()) {
+            return Err(
+                ApiError::new("Log level must be one of debug, info, warn or error").status(400),
+            );
+        }
+    }
+    match (&data.quiet_start, &data.quiet_end) {
+        (Some(start), Some(end)) => {
+            if NaiveTime::parse_from_str(start, "%H:%M").is_err()
+                || NaiveTime::parse_from_str(end, "%H:%M").is_err()
+            {
+                return Err(ApiError::new("Invalid quiet hours, expected HH:MM").status(400));
+            }
+        }
+        (None, None) => {}
+        _ => {
+            return Err(ApiError::new("Quiet hours need both start and end").status(400));
+        }
+    }
+    if let Some(interval) = data.telemetry_interval {
+        if interval < 10 {
+            return Err(
+                ApiError::new("Telemetry interval must be at least 10 seconds").status(400),
+            );
+        }
+    }
+    if let Some(commands) = &data.allowed_commands {
+        if commands
+            .iter()
+            .any(|c| c.trim().is_empty() || c.contains(' '))
+        {
+            return Err(ApiError::new("Allowed commands must be program names").status(400));
+        }
+    }
+    if let Some(device) = &data.audio_device {
+        if device.trim().is_empty() {
+            return Err(ApiError::new("Audio device can't be empty").status(400));
+        }
+    }
+    Ok(())
+}
+
+/// Create the new configuration from the request.
+fn new_config(
+    data: DeviceConfigReq,
+    avs_id: Option<i32>,
+    user_group_id: Option<i32>,
+) -> NewDeviceConfig {
+    NewDeviceConfig {
+        avs_id,
+        user_group_id,
+        volume: data.volume,
+        log_level: data.log_level,
+        quiet_start: data.quiet_start,
+        quiet_end: data.quiet_end,
+        telemetry_interval: data.telemetry_interval,
+        allowed_commands: data
+            .allowed_commands
+            .map(|commands| commands.into_iter().map(Some).collect()),
+        audio_device: data.audio_device,
+        updated_at: chrono::Utc::now().naive_utc(),
+    }
+}
+
+/// Push the effective configuration and volume profile to the connected avs.
+pub(crate) async fn push(db: &ApiDatabase, ws_state: &WsState, avs: Vec<Avs>) {
+    let repo = db.repository::<DeviceConfigRepo>();
+    let profile_repo = db.repository::<VolumeProfileRepo>();
+    for avs in avs {
+        if avs.pending != 0 {
+            continue;
+        }
+        if let Some(stream) = ws_state.avs_by_id(avs.unique_id).await {
+            if let Ok(config) = repo.resolve(avs.id) {
+                let profile = profile_repo.resolve(avs.id).unwrap_or_default();
+                let _ = stream
+                    .write(
+                        "config",
+                        types::proto::DeviceConfig::from(config).with_volume_profile(profile),
+                    )
+                    .await;
+            }
+        }
+    }
+}
+
+/// # Get the configuration of an avs.
+///
+/// This endpoint returns the configuration stored for the avs itself, without the group configuration.
+/// Only user with role `1 (Root)` and `2 (SuperAdmin)` can access this endpoint.
+/// ---
+/// tags:
+///     - device_configs
+/// responses:
+///     - status: 200
+///       content: !T DeviceConfigResponse
+///       description: The avs configuration.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 404
+///       content: !T ApiError
+///       description: The avs has no configuration.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// params:
+///     - name: id
+///       kind: !Path i32
+///       required: true
+///       description: The avs id.
+/// auth: api_key
+#[api_rt::route(get, "/device_configs/avs/{id}", Auth)]
+async fn get_avs(
+    user: web::ReqData<User>,
+    id: web::Path<i32>,
+    db: web::Data<ApiDatabase>,
+) -> actix_web::Result<DeviceConfigResponse> {
+    if user.role_id != 1 && user.role_id != 2 {
+        return Err(ApiError::new("Unauthorized").status(401).into());
+    }
+    let repo = db.repository::<DeviceConfigRepo>();
+    match repo.get_by_avs(id.into_inner()).map_err(ApiError::from)? {
+        Some(config) => DeviceConfigResponse::from(config).wrap(),
+        None => Err(ApiError::new("Not found").status(404).into()),
+    }
+}
+
+/// # Get the effective configuration of an avs.
+///
+/// This endpoint returns the configuration pushed to the avs, after merging the group configuration.
+/// Only user with role `1 (Root)` and `2 (SuperAdmin)` can access this endpoint.
+/// ---
+/// tags:
+///     - device_configs
+/// responses:
+///     - status: 200
+///       content: !T EffectiveDeviceConfig
+///       description: The effective avs configuration.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 404
+///       content: !T ApiError
+///       description: The avs is not found.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// params:
+///     - name: id
+///       kind: !Path i32
+///       required: true
+///       description: The avs id.
+/// auth: api_key
+#[api_rt::route(get, "/device_configs/avs/{id}/effective", Auth)]
+async fn get_avs_effective(
+    user: web::ReqData<User>,
+    id: web::Path<i32>,
+    db: web::Data<ApiDatabase>,
+) -> actix_web::Result<EffectiveDeviceConfig> {
+    if user.role_id != 1 && user.role_id != 2 {
+        return Err(ApiError::new("Unauthorized").status(401).into());
+    }
+    let avs = db
+        .repository::<AvsRepo>()
+        .get(id.into_inner())
+        .map_err(ApiError::from)?;
+    let config = db
+        .repository::<DeviceConfigRepo>()
+        .resolve(avs.id)
+        .map_err(ApiError::from)?;
+    let config = types::proto::DeviceConfig::from(config);
+    EffectiveDeviceConfig::new(
+        avs.id,
+        config.volume.map(|v| v as f64),
+        config.log_level,
+        config.quiet_start,
+        config.quiet_end,
+        config.telemetry_interval,
+        config.allowed_commands,
+        config.audio_device,
+    )
+    .wrap()
+}
+
+/// # Set the configuration of an avs.
+///
+/// This endpoint replaces the configuration of the avs and pushes the result to the avs if it's connected.
+/// Unset fields are inherited from the group configuration.
+/// Only user with role `1 (Root)` and `2 (SuperAdmin)` can access this endpoint.
+/// ---
+/// tags:
+///     - device_configs
+/// request:
+///     content: !T DeviceConfigReq
+///     content_type: application/json
+///     description: The avs configuration.
+/// responses:
+///     - status: 200
+///       content: !T DeviceConfigResponse
+///       description: The avs configuration.
+///     - status: 400
+///       content: !T ApiError
+///       description: The request is invalid.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 404
+///       content: !T ApiError
+///       description: The avs is not found.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// params:
+///     - name: id
+///       kind: !Path i32
+///       required: true
+///       description: The avs id.
+/// auth: api_key
+#[api_rt::route(put, "/device_configs/avs/{id}", Auth)]
+async fn put_avs(
+    user: web::ReqData<User>,
+    id: web::Path<i32>,
+    data: web::Json<DeviceConfigReq>,
+    db: web::Data<ApiDatabase>,
+    ws_state: web::Data<WsState>,
+) -> actix_web::Result<DeviceConfigResponse> {
+    if user.role_id != 1 && user.role_id != 2 {
+        return Err(ApiError::new("Unauthorized").status(401).into());
+    }
+    let data = data.into_inner();
+    validate(&data)?;
+    let avs = db
+        .repository::<AvsRepo>()
+        .get(id.into_inner())
+        .map_err(ApiError::from)?;
+    let repo = db.repository::<DeviceConfigRepo>();
+    let res = repo
+        .set(new_config(data, Some(avs.id), None))
+        .map_err(ApiError::from)?;
+    push(&db, &ws_state, vec![avs]).await;
+    DeviceConfigResponse::from(res).wrap()
+}
+
+/// # Delete the configuration of an avs.
+///
+/// This endpoint deletes the configuration of the avs, the avs falls back to the group configuration.
+/// Only user with role `1 (Root)` and `2 (SuperAdmin)` can access this endpoint.
+/// ---
+/// tags:
+///     - device_configs
+/// responses:
+///     - status: 200
+///       content: !T Message
+///       description: The avs configuration is deleted.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 404
+///       content: !T ApiError
+///       description: The avs is not found.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// params:
+///     - name: id
+///       kind: !Path i32
+///       required: true
+///       description: The avs id.
+/// auth: api_key
+#[api_rt::route(delete, "/device_configs/avs/{id}", Auth)]
+async fn delete_avs(
+    user: web::ReqData<User>,
+    id: web::Path<i32>,
+    db: web::Data<ApiDatabase>,
+    ws_state: web::Data<WsState>,
+) -> actix_web::Result<Message> {
+    if user.role_id != 1 && user.role_id != 2 {
+        return Err(ApiError::new("Unauthorized").status(401).into());
+    }
+    let avs = db
+        .repository::<AvsRepo>()
+        .get(id.into_inner())
+        .map_err(ApiError::from)?;
+    let repo = db.repository::<DeviceConfigRepo>();
+    repo.delete_by_avs(avs.id).map_err(ApiError::from)?;
+    push(&db, &ws_state, vec![avs]).await;
+    Message::new("ok".to_owned()).wrap()
+}
+
+/// # Get the configuration of a group.
+///
+/// This endpoint returns the configuration of the group.
+/// Only user with role `1 (Root)` and `2 (SuperAdmin)` can access this endpoint.
+/// ---
+/// tags:
+///     - device_configs
+/// responses:
+///     - status: 200
+///       content: !T DeviceConfigResponse
+///       description: The group configuration.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 404
+///       content: !T ApiError
+///       description: The group has no configuration.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// params:
+///     - name: id
+///       kind: !Path i32
+///       required: true
+///       description: The group id.
+/// auth: api_key
+#[api_rt::route(get, "/device_configs/groups/{id}", Auth)]
+async fn get_group(
+    user: web::ReqData<User>,
+    id: web::Path<i32>,
+    db: web::Data<ApiDatabase>,
+) -> actix_web::Result<DeviceConfigResponse> {
+    if user.role_id != 1 && user.role_id != 2 {
+        return Err(ApiError::new("Unauthorized").status(401).into());
+    }
+    let repo = db.repository::<DeviceConfigRepo>();
+    match repo.get_by_group(id.into_inner()).map_err(ApiError::from)? {
+        Some(config) => DeviceConfigResponse::from(config).wrap(),
+        None => Err(ApiError::new("Not found").status(404).into()),
+    }
+}
+
+/// # Set the configuration of a group.
+///
+/// This endpoint replaces the configuration of the group and pushes the result to the connected avs of the group.
+/// Only user with role `1 (Root)` and `2 (SuperAdmin)` can access this endpoint.
+/// ---
+/// tags:
+///     - device_configs
+/// request:
+///     content: !T DeviceConfigReq
+///     content_type: application/json
+///     description: The group configuration.
+/// responses:
+///     - status: 200
+///       content: !T DeviceConfigResponse
+///       description: The group configuration.
+///     - status: 400
+///       content: !T ApiError
+///       description: The request is invalid.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 404
+///       content: !T ApiError
+///       description: The group is not found.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// params:
+///     - name: id
+///       kind: !Path i32
+///       required: true
+///       description: The group id.
+/// auth: api_key
+#[api_rt::route(put, "/device_configs/groups/{id}", Auth)]
+async fn put_group(
+    user: web::ReqData<User>,
+    id: web::Path<i32>,
+    data: web::Json<DeviceConfigReq>,
+    db: web::Data<ApiDatabase>,
+    ws_state: web::Data<WsState>,
+) -> actix_web::Result<DeviceConfigResponse> {
+    if user.role_id != 1 && user.role_id != 2 {
+        return Err(ApiError::new("Unauthorized").status(401).into());
+    }
+    let data = data.into_inner();
+    validate(&data)?;
+    let group_id = id.into_inner();
+    let repo = db.repository::<DeviceConfigRepo>();
+    let res = repo
+        .set(new_config(data, None, Some(group_id)))
+        .map_err(ApiError::from)?;
+    let avs = db
+        .repository::<AvsRepo>()
+        .get_by_group(group_id)
+        .map_err(ApiError::from)?;
+    push(&db, &ws_state, avs).await;
+    DeviceConfigResponse::from(res).wrap()
+}
+
+/// # Delete the configuration of a group.
+///
+/// This endpoint deletes the configuration of the group and pushes the result to the connected avs of the group.
+/// Only user with role `1 (Root)` and `2 (SuperAdmin)` can access this endpoint.
+/// ---
+/// tags:
+///     - device_configs
+/// responses:
+///     - status: 200
+///       content: !T Message
+///       description: The group configuration is deleted.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// params:
+///     - name: id
+///       kind: !Path i32
+///       required: true
+///       description: The group id.
+/// auth: api_key
+#[api_rt::route(delete, "/device_configs/groups/{id}", Auth)]
+async fn delete_group(
+    user: web::ReqData<User>,
+    id: web::Path<i32>,
+    db: web::Data<ApiDatabase>,
+    ws_state: web::Data<WsState>,
+) -> actix_web::Result<Message> {
+    if user.role_id != 1 && user.role_id != 2 {
+        return Err(ApiError::new("Unauthorized").status(401).into());
+    }
+    let group_id = id.into_inner();
+    let repo = db.repository::<DeviceConfigRepo>();
+    repo.delete_by_group(group_id).map_err(ApiError::from)?;
+    let avs = db
+        .repository::<AvsRepo>()
+        .get_by_group(group_id)
+        .map_err(ApiError::from)?;
+    push(&db, &ws_state, avs).await;
+    Message::new("ok".to_owned()).wrap()
+}
+
+api_rt::routes! {
+    get_avs
+    get_avs_effective
+    put_avs
+    delete_avs
+    get_group
+    put_group
+    delete_group
+}
@@ -0,0 +1,180 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use crate::{middlewares::auth::Auth, ApiError};
+use actix_web::{web, Responder};
+use api_db::{repos::EnrollmentRepo, ApiDatabase};
+use types::api::{
+    enrollment::{EnrollmentTokenReq, EnrollmentTokenResponse, NewEnrollmentToken},
+    user::User,
+    Message,
+};
+
+/// Default lifetime of an enrollment token in hours.
+const DEFAULT_EXPIRES_IN_HOURS: i64 = 24;
+
+/// Maximum lifetime of an enrollment token in hours.
+const MAX_EXPIRES_IN_HOURS: i64 = 24 * 30;
+
+/// # Create an enrollment token.
+///
+/// This endpoint creates a one-time token to provision a new avs.
+/// The device presents the token on its first connect and receives a long-lived credential,
+/// unknown devices without a token are rejected.
+/// Existing avs without a credential, registered before provisioning, also need a token to connect.
+/// The enrolled avs is accepted right away, assigned to the users of the group and placed at the given location.
+/// The plain token is only returned by this endpoint.
+/// Only user with role `1 (Root)` and `2 (SuperAdmin)` can access this endpoint.
+/// ---
+/// tags:
+///     - enrollments
+/// request:
+///     content: !T EnrollmentTokenReq
+///     content_type: application/json
+///     description: The enrollment token data.
+/// responses:
+///     - status: 200
+///       content: !T EnrollmentTokenResponse
+///       description: The enrollment token along with the plain token.
+///     - status: 400
+///       content: !T ApiError
+///       description: The request is invalid.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// auth: api_key
+#[api_rt::route(post, "/enrollments", Auth)]
+async fn create(
+    user: web::ReqData<User>,
+    data: web::Json<EnrollmentTokenReq>,
+    db: web::Data<ApiDatabase>,
+) -> actix_web::Result<impl Responder> {
+    if user.role_id != 1 && user.role_id != 2 {
+        return Err(ApiError::new("Unauthorized").status(401).into());
+    }
+    let data = data.into_inner();
+    let hours = data.expires_in_hours.unwrap_or(DEFAULT_EXPIRES_IN_HOURS);
+    if hours <= 0 || hours > MAX_EXPIRES_IN_HOURS {
+        return Err(ApiError::new(format!(
+            "Expiration must be between 1 and {} hours",
+            MAX_EXPIRES_IN_HOURS
+        ))
+        .status(400)
+        .into());
+    }
+    let token = utils::crypto::random_string(16);
+    let res = db
+        .repository::<EnrollmentRepo>()
+        .create(NewEnrollmentToken {
+            token_hash: utils::crypto::hash(token.as_bytes()),
+            user_group_id: data.user_group_id,
+            lat: data.lat,
+            lng: data.lng,
+            address: data.address,
+            description: data.description,
+            created_by: user.id,
+            expires_at: chrono::Utc::now().naive_utc() + chrono::Duration::hours(hours),
+        })
+        .map_err(ApiError::from)?;
+    EnrollmentTokenResponse::from(res).with_token(token).wrap()
+}
+
+/// # Get all enrollment tokens.
+///
+/// This endpoint returns the enrollment tokens, newest first, without the plain tokens.
+/// Only user with role `1 (Root)` and `2 (SuperAdmin)` can access this endpoint.
+/// ---
+/// tags:
+///     - enrollments
+/// responses:
+///     - status: 200
+///       content: !Vec EnrollmentTokenResponse
+///       description: The enrollment tokens.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// auth: api_key
+#[api_rt::route(get, "/enrollments", Auth)]
+async fn get_all(
+    user: web::ReqData<User>,
+    db: web::Data<ApiDatabase>,
+) -> actix_web::Result<impl Responder> {
+    if user.role_id != 1 && user.role_id != 2 {
+        return Err(ApiError::new("Unauthorized").status(401).into());
+    }
+    let res = db
+        .repository::<EnrollmentRepo>()
+        .get_all()
+        .map_err(ApiError::from)?;
+    EnrollmentTokenResponse::wrap_vec(
+        res.into_iter().map(EnrollmentTokenResponse::from).collect(),
+        None,
+    )
+}
+
+/// # Revoke an enrollment token.
+///
+/// This endpoint deletes the enrollment token, avs enrolled with it keep their credential.
+/// Only user with role `1 (Root)` and `2 (SuperAdmin)` can access this endpoint.
+/// ---
+/// tags:
+///     - enrollments
+/// responses:
+///     - status: 200
+///       content: !T Message
+///       description: The enrollment token is revoked.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 404
+///       content: !T ApiError
+///       description: The enrollment token is not found.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// params:
+///     - name: id
+///       kind: !Path i32
+///       required: true
+///       description: The enrollment token id.
+/// auth: api_key
+#[api_rt::route(delete, "/enrollments/{id}", Auth)]
+async fn delete(
+    user: web::ReqData<User>,
+    id: web::Path<i32>,
+    db: web::Data<ApiDatabase>,
+) -> actix_web::Result<Message> {
+    if user.role_id != 1 && user.role_id != 2 {
+        return Err(ApiError::new("Unauthorized").status(401).into());
+    }
+    let repo = db.repository::<EnrollmentRepo>();
+    let token = repo.get(id.into_inner()).map_err(ApiError::from)?;
+    repo.delete(token.id).map_err(ApiError::from)?;
+    Message::new("ok".to_owned()).wrap()
+}
+
+api_rt::routes! {
+    create
+    get_all
+    delete
+}
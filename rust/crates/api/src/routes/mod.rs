@@ -15,17 +15,27 @@ MyRTS agrees to credit you as the developer of the software in all promotional m
 If MyRTS violates any of these terms, their license to use the software will automatically terminate.
 */
 
+pub mod alerts;
 pub mod assets;
 pub mod auth;
 pub mod avs;
+pub mod broadcasts;
 pub mod cities;
+pub mod commands;
+pub mod connections;
+pub mod device_configs;
+pub mod enrollments;
 pub mod groups;
 pub mod install;
 pub mod packages;
+pub mod play_logs;
+pub mod playlists;
 pub mod provinces;
 pub mod records;
 pub mod roles;
 pub mod schedules;
 pub mod statistics;
 pub mod streaming;
+pub mod updates;
 pub mod users;
+pub mod volume_profiles;
@@ -0,0 +1,306 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use crate::{middlewares::auth::Auth, ApiError};
+use actix_web::web;
+use api_db::{
+    repos::{PlayLogRepo, ScheduleRepo},
+    ApiDatabase,
+};
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
+use types::{
+    api::{
+        play_log::{MissedPlay, PlayLog, PlayLogHistory, PlayLogQuery, PlayLogResponse},
+        schedules::Schedules,
+        user::User,
+    },
+    proto::OUTCOME_PLAYED,
+};
+use utils::time::Recurrence;
+
+/// Maximum number of days that can be requested at once.
+const MAX_RANGE_DAYS: i64 = 31;
+
+/// Parse the date range of the query, defaults to the last 7 days.
+fn parse_range(query: PlayLogQuery) -> Result<(NaiveDateTime, NaiveDateTime), ApiError> {
+    let parse = |date: &str| {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map(|d| d.and_hms_opt(0, 0, 0).unwrap())
+            .map_err(|_| ApiError::new("Invalid date, expected YYYY-MM-DD").status(400))
+    };
+    let to = match query.to {
+        Some(to) => parse(&to)? + Duration::days(1),
+        None => {
+            utils::time::local_now()
+                .date()
+                .and_hms_opt(0, 0, 0)
+                .unwrap()
+                + Duration::days(1)
+        }
+    };
+    let from = match query.from {
+        Some(from) => parse(&from)?,
+        None => to - Duration::days(7),
+    };
+    if from >= to {
+        return Err(ApiError::new("Invalid date range").status(400));
+    }
+    if to - from > Duration::days(MAX_RANGE_DAYS) {
+        return Err(ApiError::new(format!(
+            "Date range can't be more than {} days",
+            MAX_RANGE_DAYS
+        ))
+        .status(400));
+    }
+    Ok((from, to))
+}
+
+/// Get the recurrence of the schedule.
+fn recurrence(schedule: &Schedules) -> Recurrence<'_> {
+    Recurrence {
+        kind: schedule.kind,
+        weeks: &schedule.weeks,
+        days: &schedule.days,
+        dates: &schedule.dates,
+        month: schedule.month,
+        year: schedule.year,
+    }
+}
+
+/// Get the local time of the avs from the utc offset of its latest play log,
+/// the server local time is used for the avs that never reported one.
+fn avs_now(avs_id: i32, offsets: &[(i32, i32)]) -> NaiveDateTime {
+    match offsets.iter().find(|(id, _)| *id == avs_id) {
+        Some((_, offset)) => utils::time::now() + Duration::seconds(*offset as i64),
+        None => utils::time::local_now(),
+    }
+}
+
+/// Get the expected occurrences of the schedule between the given range, up to `now`.
+fn expected_occurrences(
+    schedule: &Schedules,
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+    now: NaiveDateTime,
+) -> Vec<NaiveDateTime> {
+    let times = schedule
+        .times
+        .iter()
+        .flatten()
+        .filter_map(|t| NaiveTime::parse_from_str(t, "%H:%M").ok())
+        .collect::<Vec<NaiveTime>>();
+    let mut res = vec![];
+    let mut date = from.date();
+    while date <= to.date() {
+        for time in times.iter() {
+            let at = date.and_time(*time);
+            if at < from || at >= to || at > now {
+                continue;
+            }
+            if recurrence(schedule).fires_at(at) {
+                res.push(at);
+            }
+        }
+        date = date + Duration::days(1);
+    }
+    res
+}
+
+/// Find the expected occurrences that were not played.
+fn find_missed(
+    schedule: &Schedules,
+    avs_ids: &[i32],
+    logs: &[PlayLog],
+    offsets: &[(i32, i32)],
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+) -> Vec<MissedPlay> {
+    let mut missed = vec![];
+    for avs_id in avs_ids {
+        let now = avs_now(*avs_id, offsets);
+        for expected_at in expected_occurrences(schedule, from, to, now) {
+            let reported = logs
+                .iter()
+                .filter(|log| {
+                    log.avs_id == *avs_id
+                        && log.schedule_id == schedule.id
+                        && log.started_at >= expected_at
+                        && log.started_at < expected_at + Duration::minutes(1)
+                })
+                .collect::<Vec<&PlayLog>>();
+            if reported.iter().any(|log| log.outcome == OUTCOME_PLAYED) {
+                continue;
+            }
+            missed.push(MissedPlay::new(
+                *avs_id,
+                schedule.id,
+                expected_at,
+                reported.first().map(|log| log.outcome),
+            ));
+        }
+    }
+    missed.sort_by_key(|m| m.expected_at);
+    missed
+}
+
+/// # Get play logs by avs.
+///
+/// This endpoint returns the proof of play history of an avs, along with the expected
+/// schedule occurrences that were not played.
+/// Timestamps are in the avs local time, occurrences are expected up to the current local
+/// time of the avs, derived from the utc offset it reports.
+/// ****Rules:****
+/// Root and SuperAdmin can access every avs, Admin can only access their own avs.
+/// ---
+/// tags:
+///     - play_logs
+/// responses:
+///     - status: 200
+///       content: !T PlayLogHistory
+///       description: The play log history.
+///     - status: 400
+///       content: !T ApiError
+///       description: The request is invalid.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 404
+///       content: !T ApiError
+///       description: The avs is not found.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// params:
+///     - name: id
+///       kind: !Path i32
+///       required: true
+///       description: The avs id.
+///     - name: from
+///       kind: !Query String
+///       required: false
+///       description: The first date (YYYY-MM-DD), default to 7 days before `to`.
+///     - name: to
+///       kind: !Query String
+///       required: false
+///       description: The last date (YYYY-MM-DD), default to today.
+/// auth: api_key
+#[api_rt::route(get, "/play_logs/avs/{id}", Auth)]
+async fn get_by_avs(
+    user: web::ReqData<User>,
+    id: web::Path<i32>,
+    query: web::Query<PlayLogQuery>,
+    db: web::Data<ApiDatabase>,
+) -> actix_web::Result<PlayLogHistory> {
+    let id = id.into_inner();
+    if user.role_id != 1 && user.role_id != 2 && !user.device_ids.contains(&Some(id)) {
+        return Err(ApiError::new("Not found").status(404).into());
+    }
+    let (from, to) = parse_range(query.into_inner())?;
+    let repo = db.repository::<PlayLogRepo>();
+    let schedule_repo = db.repository::<ScheduleRepo>();
+    let logs = repo.get_by_avs(id, from, to).map_err(ApiError::from)?;
+    let schedules = schedule_repo.get_by_avs(id).map_err(ApiError::from)?;
+    let offsets = repo.utc_offsets(&[id]).map_err(ApiError::from)?;
+    let mut missed = vec![];
+    for schedule in schedules.iter() {
+        missed.extend(find_missed(schedule, &[id], &logs, &offsets, from, to));
+    }
+    missed.sort_by_key(|m| m.expected_at);
+    PlayLogHistory::new(
+        logs.into_iter().map(PlayLogResponse::from).collect(),
+        missed,
+    )
+    .wrap()
+}
+
+/// # Get play logs by schedule.
+///
+/// This endpoint returns the proof of play history of a schedule on every assigned avs,
+/// along with the expected occurrences that were not played.
+/// Timestamps are in the avs local time, occurrences are expected up to the current local
+/// time of the avs, derived from the utc offset it reports.
+/// ****Rules:****
+/// Root and SuperAdmin can access every schedule, otherwise only the schedule owner can access it.
+/// ---
+/// tags:
+///     - play_logs
+/// responses:
+///     - status: 200
+///       content: !T PlayLogHistory
+///       description: The play log history.
+///     - status: 400
+///       content: !T ApiError
+///       description: The request is invalid.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 404
+///       content: !T ApiError
+///       description: The schedule is not found.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// params:
+///     - name: id
+///       kind: !Path i32
+///       required: true
+///       description: The schedule id.
+///     - name: from
+///       kind: !Query String
+///       required: false
+///       description: The first date (YYYY-MM-DD), default to 7 days before `to`.
+///     - name: to
+///       kind: !Query String
+///       required: false
+///       description: The last date (YYYY-MM-DD), default to today.
+/// auth: api_key
+#[api_rt::route(get, "/play_logs/schedules/{id}", Auth)]
+async fn get_by_schedule(
+    user: web::ReqData<User>,
+    id: web::Path<i32>,
+    query: web::Query<PlayLogQuery>,
+    db: web::Data<ApiDatabase>,
+) -> actix_web::Result<PlayLogHistory> {
+    let schedule_repo = db.repository::<ScheduleRepo>();
+    let schedule = schedule_repo.get(id.into_inner()).map_err(ApiError::from)?;
+    if user.role_id != 1 && user.role_id != 2 && schedule.user_id != user.id {
+        return Err(ApiError::new("Not found").status(404).into());
+    }
+    let (from, to) = parse_range(query.into_inner())?;
+    let repo = db.repository::<PlayLogRepo>();
+    let logs = repo
+        .get_by_schedule(schedule.id, from, to)
+        .map_err(ApiError::from)?;
+    let avs_ids = schedule
+        .device_ids
+        .iter()
+        .flatten()
+        .copied()
+        .collect::<Vec<i32>>();
+    let offsets = repo.utc_offsets(&avs_ids).map_err(ApiError::from)?;
+    let missed = find_missed(&schedule, &avs_ids, &logs, &offsets, from, to);
+    PlayLogHistory::new(
+        logs.into_iter().map(PlayLogResponse::from).collect(),
+        missed,
+    )
+    .wrap()
+}
+
+api_rt::routes! {
+    get_by_avs
+    get_by_schedule
+}
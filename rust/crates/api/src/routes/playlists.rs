@@ -0,0 +1,410 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use crate::{middlewares::auth::Auth, ApiError};
+use actix_web::web;
+use api_db::{
+    repos::{AvsRepo, PlaylistRepo, RecordsRepo},
+    ApiDatabase,
+};
+use chrono::NaiveTime;
+use proto::WsState;
+use types::api::{
+    avs::Avs,
+    playlist::{NewPlaylist, PlaylistReq, PlaylistResponse},
+    user::User,
+    Message,
+};
+
+/// Maximum number of records in a playlist.
+const MAX_RECORDS: usize = 200;
+
+/// Validate the playlist request and create the new playlist.
+fn new_playlist(
+    db: &ApiDatabase,
+    data: &PlaylistReq,
+    avs_id: Option<i32>,
+    user_group_id: Option<i32>,
+) -> Result<NewPlaylist, ApiError> {
+    if data.records_ids.is_empty() {
+        return Err(ApiError::new("Records cannot be empty").status(400));
+    }
+    if data.records_ids.len() > MAX_RECORDS {
+        return Err(
+            ApiError::new(format!("Records can't be more than {}", MAX_RECORDS)).status(400),
+        );
+    }
+    match (&data.start_time, &data.end_time) {
+        (Some(start), Some(end)) => {
+            if NaiveTime::parse_from_str(start, "%H:%M").is_err()
+                || NaiveTime::parse_from_str(end, "%H:%M").is_err()
+            {
+                return Err(ApiError::new("Invalid time window, expected HH:MM").status(400));
+            }
+            if start == end {
+                return Err(ApiError::new("Time window start and end can't be equal").status(400));
+            }
+        }
+        (None, None) => {}
+        _ => {
+            return Err(ApiError::new("Time window needs both start and end").status(400));
+        }
+    }
+    if let Some(volume) = data.volume {
+        if !(0.0..=1.0).contains(&volume) {
+            return Err(ApiError::new("Volume must be between 0 and 1").status(400));
+        }
+    }
+    if let Some(duck) = data.duck {
+        if !(0.0..=1.0).contains(&duck) {
+            return Err(ApiError::new("Duck must be between 0 and 1").status(400));
+        }
+    }
+    let repo = db.repository::<RecordsRepo>();
+    for id in data.records_ids.iter() {
+        match repo.get(*id) {
+            Ok(record) if record.status == 1 => {}
+            _ => return Err(ApiError::new("Record not found").status(404)),
+        }
+    }
+    Ok(NewPlaylist {
+        avs_id,
+        user_group_id,
+        shuffle: data.shuffle,
+        repeat: data.repeat,
+        start_time: data.start_time.clone(),
+        end_time: data.end_time.clone(),
+        volume: data.volume,
+        duck: data.duck.unwrap_or(0.2),
+    })
+}
+
+/// Push the effective playlist to the connected avs, an empty playlist stops the playback.
+pub(crate) async fn push(db: &ApiDatabase, ws_state: &WsState, avs: Vec<Avs>) {
+    let repo = db.repository::<PlaylistRepo>();
+    for avs in avs {
+        if avs.pending != 0 {
+            continue;
+        }
+        if let Some(stream) = ws_state.avs_by_id(avs.unique_id).await {
+            if let Ok(playlist) = repo.resolve(avs.id) {
+                let playlist = playlist
+                    .map(types::proto::Playlist::from)
+                    .unwrap_or_default();
+                let _ = stream.write("playlist", playlist).await;
+            }
+        }
+    }
+}
+
+/// # Get the playlist of an avs.
+///
+/// This endpoint returns the background playlist stored for the avs itself, without the group playlist.
+/// Only user with role `1 (Root)` and `2 (SuperAdmin)` can access this endpoint.
+/// ---
+/// tags:
+///     - playlists
+/// responses:
+///     - status: 200
+///       content: !T PlaylistResponse
+///       description: The avs playlist.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 404
+///       content: !T ApiError
+///       description: The avs has no playlist.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// params:
+///     - name: id
+///       kind: !Path i32
+///       required: true
+///       description: The avs id.
+/// auth: api_key
+#[api_rt::route(get, "/playlists/avs/{id}", Auth)]
+async fn get_avs(
+    user: web::ReqData<User>,
+    id: web::Path<i32>,
+    db: web::Data<ApiDatabase>,
+) -> actix_web::Result<PlaylistResponse> {
+    if user.role_id != 1 && user.role_id != 2 {
+        return Err(ApiError::new("Unauthorized").status(401).into());
+    }
+    let repo = db.repository::<PlaylistRepo>();
+    match repo.get_by_avs(id.into_inner()).map_err(ApiError::from)? {
+        Some(playlist) => PlaylistResponse::from(playlist).wrap(),
+        None => Err(ApiError::new("Not found").status(404).into()),
+    }
+}
+
+/// # Set the playlist of an avs.
+///
+/// This endpoint replaces the background playlist of the avs and pushes it to the avs if it's connected.
+/// The playlist plays on the default output whenever the avs is idle, it is ducked by the schedules and paused by the live streams.
+/// The avs playlist replaces the group playlist as a whole.
+/// Only user with role `1 (Root)` and `2 (SuperAdmin)` can access this endpoint.
+/// ---
+/// tags:
+///     - playlists
+/// request:
+///     content: !T PlaylistReq
+///     content_type: application/json
+///     description: The avs playlist.
+/// responses:
+///     - status: 200
+///       content: !T PlaylistResponse
+///       description: The avs playlist.
+///     - status: 400
+///       content: !T ApiError
+///       description: The request is invalid.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 404
+///       content: !T ApiError
+///       description: The avs or a record is not found.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// params:
+///     - name: id
+///       kind: !Path i32
+///       required: true
+///       description: The avs id.
+/// auth: api_key
+#[api_rt::route(put, "/playlists/avs/{id}", Auth)]
+async fn put_avs(
+    user: web::ReqData<User>,
+    id: web::Path<i32>,
+    data: web::Json<PlaylistReq>,
+    db: web::Data<ApiDatabase>,
+    ws_state: web::Data<WsState>,
+) -> actix_web::Result<PlaylistResponse> {
+    if user.role_id != 1 && user.role_id != 2 {
+        return Err(ApiError::new("Unauthorized").status(401).into());
+    }
+    let avs = db
+        .repository::<AvsRepo>()
+        .get(id.into_inner())
+        .map_err(ApiError::from)?;
+    let data = data.into_inner();
+    let new = new_playlist(&db, &data, Some(avs.id), None)?;
+    let repo = db.repository::<PlaylistRepo>();
+    let res = repo.set(new, data.records_ids).map_err(ApiError::from)?;
+    push(&db, &ws_state, vec![avs]).await;
+    PlaylistResponse::from(res).wrap()
+}
+
+/// # Delete the playlist of an avs.
+///
+/// This endpoint deletes the background playlist of the avs, the avs falls back to the group playlist.
+/// Only user with role `1 (Root)` and `2 (SuperAdmin)` can access this endpoint.
+/// ---
+/// tags:
+///     - playlists
+/// responses:
+///     - status: 200
+///       content: !T Message
+///       description: The avs playlist is deleted.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 404
+///       content: !T ApiError
+///       description: The avs is not found.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// params:
+///     - name: id
+///       kind: !Path i32
+///       required: true
+///       description: The avs id.
+/// auth: api_key
+#[api_rt::route(delete, "/playlists/avs/{id}", Auth)]
+async fn delete_avs(
+    user: web::ReqData<User>,
+    id: web::Path<i32>,
+    db: web::Data<ApiDatabase>,
+    ws_state: web::Data<WsState>,
+) -> actix_web::Result<Message> {
+    if user.role_id != 1 && user.role_id != 2 {
+        return Err(ApiError::new("Unauthorized").status(401).into());
+    }
+    let avs = db
+        .repository::<AvsRepo>()
+        .get(id.into_inner())
+        .map_err(ApiError::from)?;
+    let repo = db.repository::<PlaylistRepo>();
+    repo.delete_by_avs(avs.id).map_err(ApiError::from)?;
+    push(&db, &ws_state, vec![avs]).await;
+    Message::new("ok".to_owned()).wrap()
+}
+
+/// # Get the playlist of a group.
+///
+/// This endpoint returns the background playlist of the group.
+/// Only user with role `1 (Root)` and `2 (SuperAdmin)` can access this endpoint.
+/// ---
+/// tags:
+///     - playlists
+/// responses:
+///     - status: 200
+///       content: !T PlaylistResponse
+///       description: The group playlist.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 404
+///       content: !T ApiError
+///       description: The group has no playlist.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// params:
+///     - name: id
+///       kind: !Path i32
+///       required: true
+///       description: The group id.
+/// auth: api_key
+#[api_rt::route(get, "/playlists/groups/{id}", Auth)]
+async fn get_group(
+    user: web::ReqData<User>,
+    id: web::Path<i32>,
+    db: web::Data<ApiDatabase>,
+) -> actix_web::Result<PlaylistResponse> {
+    if user.role_id != 1 && user.role_id != 2 {
+        return Err(ApiError::new("Unauthorized").status(401).into());
+    }
+    let repo = db.repository::<PlaylistRepo>();
+    match repo.get_by_group(id.into_inner()).map_err(ApiError::from)? {
+        Some(playlist) => PlaylistResponse::from(playlist).wrap(),
+        None => Err(ApiError::new("Not found").status(404).into()),
+    }
+}
+
+/// # Set the playlist of a group.
+///
+/// This endpoint replaces the background playlist of the group and pushes the result to the connected avs of the group.
+/// Only user with role `1 (Root)` and `2 (SuperAdmin)` can access this endpoint.
+/// ---
+/// tags:
+///     - playlists
+/// request:
+///     content: !T PlaylistReq
+///     content_type: application/json
+///     description: The group playlist.
+/// responses:
+///     - status: 200
+///       content: !T PlaylistResponse
+///       description: The group playlist.
+///     - status: 400
+///       content: !T ApiError
+///       description: The request is invalid.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 404
+///       content: !T ApiError
+///       description: A record is not found.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// params:
+///     - name: id
+///       kind: !Path i32
+///       required: true
+///       description: The group id.
+/// auth: api_key
+#[api_rt::route(put, "/playlists/groups/{id}", Auth)]
+async fn put_group(
+    user: web::ReqData<User>,
+    id: web::Path<i32>,
+    data: web::Json<PlaylistReq>,
+    db: web::Data<ApiDatabase>,
+    ws_state: web::Data<WsState>,
+) -> actix_web::Result<PlaylistResponse> {
+    if user.role_id != 1 && user.role_id != 2 {
+        return Err(ApiError::new("Unauthorized").status(401).into());
+    }
+    let group_id = id.into_inner();
+    let data = data.into_inner();
+    let new = new_playlist(&db, &data, None, Some(group_id))?;
+    let repo = db.repository::<PlaylistRepo>();
+    let res = repo.set(new, data.records_ids).map_err(ApiError::from)?;
+    let avs = db
+        .repository::<AvsRepo>()
+        .get_by_group(group_id)
+        .map_err(ApiError::from)?;
+    push(&db, &ws_state, avs).await;
+    PlaylistResponse::from(res).wrap()
+}
+
+/// # Delete the playlist of a group.
+///
+/// This endpoint deletes the background playlist of the group and pushes the result to the connected avs of the group.
+/// Only user with role `1 (Root)` and `2 (SuperAdmin)` can access this endpoint.
+/// ---
+/// tags:
+///     - playlists
+/// responses:
+///     - status: 200
+///       content: !T Message
+///       description: The group playlist is deleted.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// params:
+///     - name: id
+///       kind: !Path i32
+///       required: true
+///       description: The group id.
+/// auth: api_key
+#[api_rt::route(delete, "/playlists/groups/{id}", Auth)]
+async fn delete_group(
+    user: web::ReqData<User>,
+    id: web::Path<i32>,
+    db: web::Data<ApiDatabase>,
+    ws_state: web::Data<WsState>,
+) -> actix_web::Result<Message> {
+    if user.role_id != 1 && user.role_id != 2 {
+        return Err(ApiError::new("Unauthorized").status(401).into());
+    }
+    let group_id = id.into_inner();
+    let repo = db.repository::<PlaylistRepo>();
+    repo.delete_by_group(group_id).map_err(ApiError::from)?;
+    let avs = db
+        .repository::<AvsRepo>()
+        .get_by_group(group_id)
+        .map_err(ApiError::from)?;
+    push(&db, &ws_state, avs).await;
+    Message::new("ok".to_owned()).wrap()
+}
+
+api_rt::routes! {
+    get_avs
+    put_avs
+    delete_avs
+    get_group
+    put_group
+    delete_group
+}
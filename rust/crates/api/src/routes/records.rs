@@ -27,7 +27,11 @@ use types::api::{
     user::User,
     RecordsReq, ValIntReq,
 };
-use utils::{audio::decode_audio, files::ApiAssets};
+use utils::{
+    audio::{probe_audio, transcode_opus},
+    files::ApiAssets,
+    media::AudioFormat,
+};
 
 /// # Get all records.
 ///
@@ -78,6 +82,8 @@ async fn get(
 /// # Create new record.
 ///
 /// This endpoint creates a new record, user can either create record only or also share it with other users.
+/// Supported formats are `mp3`, `ogg (opus)`, `wav` and `flac`, the format is probed from the file content.
+/// The record is stored as `opus` in an ogg container.
 /// When the user_ids filled (send to another user), the record will be available on these user with status `0 (Pending)`.
 ///  ****Notes:****
 /// Only user below the caller can receive these.
@@ -117,38 +123,53 @@ async fn post(
     let data = data.into_inner();
     let repo: RecordsRepo = db.repository();
 
-    if let Some(fname) = data.file.file_name {
-        if !fname.ends_with(".mp3") {
-            return Err(ApiError::new("Only mp3 files are allowed.")
-                .status(400)
-                .into());
+    let declared = match &data.file.file_name {
+        Some(fname) => fname
+            .rsplit_once('.')
+            .and_then(|(_, ext)| AudioFormat::from_extension(ext)),
+        None => return Err(ApiError::new("File invalid").status(400).into()),
+    };
+    let declared = match declared {
+        Some(declared) => declared,
+        None => {
+            return Err(
+                ApiError::new("Only mp3, ogg (opus), wav and flac files are allowed.")
+                    .status(400)
+                    .into(),
+            )
         }
-    } else {
-        return Err(ApiError::new("File invalid").status(400).into());
-    }
+    };
 
     let mut f = data.file.file.into_file();
     let mut buf = Vec::new();
     f.read_to_end(&mut buf)?;
 
-    // this was to make sure if the file is valid audio.
-    let buf = decode_audio(buf);
+    // probe the actual format instead of trusting the file name.
+    let format = probe_audio(&buf, declared.name())
+        .map_err(|e| ApiError::new(format!("File is not a supported audio: {}", e)).status(400))?;
 
     let hash = utils::crypto::hash(&buf);
     let file_url: String;
     let duration: u64;
-    match repo.get_hash(&hash).map_err(ApiError::from)? {
+    let format = match repo.get_hash(&hash).map_err(ApiError::from)? {
         Some(x) => {
             file_url = x.file_url;
             duration = x.duration.parse().map_err(ApiError::new)?;
+            x.format
         }
         None => {
-            let uuid = format!("{}.mp3", utils::crypto::uuid());
+            // every record is delivered to the avs as opus, whatever was uploaded.
+            let buf = transcode_opus(&buf, format.name()).map_err(|e| {
+                ApiError::new(format!("Failed to transcode audio: {}", e)).status(400)
+            })?;
+            let format = AudioFormat::Opus;
+            let uuid = format!("{}.{}", utils::crypto::uuid(), format.name());
             assets.write_audio(&uuid, buf).map_err(ApiError::new)?;
             duration = assets.duration_audio(&uuid).map_err(ApiError::new)?;
             file_url = cfg.format(format!("/assets/audio/{}", uuid));
+            format.name().to_string()
         }
-    }
+    };
 
     let description = data.description.map(|d| d.clone());
     let name = data.name.clone();
@@ -164,6 +185,7 @@ async fn post(
             status: 1,
             duration: duration.to_string(),
             sender: None,
+            format: format.clone(),
         })
         .map_err(ApiError::from)?;
     for other in data.user_ids {
@@ -178,6 +200,7 @@ async fn post(
                 status: 0,
                 duration: duration.to_string(),
                 sender: Some(user.id),
+                format: format.clone(),
             })
             .map_err(ApiError::from)?;
     }
@@ -18,16 +18,40 @@ If MyRTS violates any of these terms, their license to use the software will aut
 use crate::{middlewares::auth::Auth, ApiError};
 use actix_web::web;
 use api_db::{
-    repos::{AvsRepo, RecordsRepo, ScheduleRepo},
+    repos::{AvsPortRepo, AvsRepo, RecordsRepo, ScheduleRepo},
     ApiDatabase,
 };
 use proto::WsState;
 use timeslots::TimeSlots;
 use types::api::{
-    schedules::{NewSchedule, NewScheduleReq, SchedulesResponse},
+    schedules::{NewSchedule, NewScheduleReq, ScheduleOutputsReq, SchedulesResponse},
     user::User,
 };
 
+/// Check that the ports belong to the avs.
+fn check_ports(db: &ApiDatabase, port_ids: &[i32], avs_ids: &[i32]) -> Result<(), ApiError> {
+    let repo = db.repository::<AvsPortRepo>();
+    for port_id in port_ids {
+        let port = repo.get(*port_id).map_err(ApiError::from)?;
+        if !avs_ids.contains(&port.avs_id) {
+            return Err(ApiError::new("Port not found.").status(404));
+        }
+    }
+    Ok(())
+}
+
+/// Make the avs sync again, the schedules with changed outputs are sent as upserts.
+pub(crate) async fn refresh_schedules(ws_state: &WsState, avs_ids: Vec<String>, sids: Vec<i32>) {
+    if sids.is_empty() {
+        return;
+    }
+    for id in avs_ids {
+        if let Some(av) = ws_state.avs_by_id(id).await {
+            let _ = av.write("resync", "").await;
+        }
+    }
+}
+
 /// # Get all schedules.
 ///
 /// This endpoint responsible for getting all schedules. If the user role `1 (Root)` or `2 (SuperAdmin)` it will return all schedules
@@ -189,8 +213,14 @@ async fn get_by_avs(
 /// If you want to set the custom volume for device with id 1 = 50%, and 3 = 30%, then the request body must be like this:
 /// `volumes`: ["1:50", "3:30"]
 ///
+/// The `port_ids` and `zones` fields select the avs ports to play on, see `/avs/{id}/ports`.
+/// An avs without any matching port plays the schedule on its default output.
+///
+/// The `emergency` field marks the schedule as emergency content, it bypasses the quiet hours and the volume profile of the avs.
+///
 /// ****Rules:****
 /// - The record must be in active `1 (Active)` status.
+/// - Only Root and SuperAdmin can create emergency schedule.
 /// - The record used must be owned by the user.
 /// - The fields used must be match with it's kind `1 (Repetition)` or `2 (Calendar)`.
 ///
@@ -269,6 +299,9 @@ async fn post(
             .status(400)
             .into());
     }
+    if data.emergency.unwrap_or(false) && user.role_id != 1 && user.role_id != 2 {
+        return Err(ApiError::new("Unauthorized").status(401).into());
+    }
 
     // Check records.
     let record_repo = db.repository::<RecordsRepo>();
@@ -290,6 +323,7 @@ async fn post(
     if avses.is_empty() || avses.len() != avs_ids.len() {
         return Err(ApiError::new("Avs not found.").status(404).into());
     }
+    check_ports(&db, data.port_ids.as_deref().unwrap_or_default(), &avs_ids)?;
 
     // Check schedule.
     let schedule_repo = db.repository::<ScheduleRepo>();
@@ -440,6 +474,19 @@ async fn post(
             month: data.month,
             year: data.year,
             volumes: data.volumes,
+            port_ids: data
+                .port_ids
+                .unwrap_or_default()
+                .into_iter()
+                .map(Some)
+                .collect(),
+            zones: data
+                .zones
+                .unwrap_or_default()
+                .into_iter()
+                .map(Some)
+                .collect(),
+            emergency: data.emergency.unwrap_or(false),
         })
         .map_err(ApiError::from)?;
     tokio::spawn(async move {
@@ -548,10 +595,89 @@ async fn delete(
     }
 }
 
+/// # Set schedule outputs.
+///
+/// This endpoint responsible for setting the avs ports and zones a schedule plays on.
+/// An avs without any matching port plays the schedule on its default output,
+/// empty `port_ids` and `zones` play the schedule on the default output of every avs.
+/// Root and SuperAdmin can update any schedule, while Admin can only update their own schedule.
+/// ---
+/// tags:
+///     - schedules
+/// request:
+///     content: !T ScheduleOutputsReq
+///     content_type: application/json
+///     description: The ports and zones of the schedule.
+/// responses:
+///     - status: 200
+///       content: !T SchedulesResponse
+///       content_type: application/json
+///       description: Updated schedule.
+///     - status: 400
+///       content: !T ApiError
+///       content_type: application/json
+///       description: Bad request.
+///     - status: 401
+///       content: !T ApiError
+///       content_type: application/json
+///       description: Unauthorized.
+///     - status: 404
+///       content: !T ApiError
+///       content_type: application/json
+///       description: Not found.
+///     - status: 500
+///       content: !T ApiError
+///       content_type: application/json
+///       description: Server encountered an error.
+/// params:
+///     - name: id
+///       kind: !Path i32
+///       required: true
+///       description: Schedule id
+/// auth: api_key
+#[api_rt::route(put, "/schedules/{id}/outputs", Auth)]
+async fn put_outputs(
+    user: web::ReqData<User>,
+    id: web::Path<i32>,
+    data: web::Json<ScheduleOutputsReq>,
+    db: web::Data<ApiDatabase>,
+    ws_state: web::Data<WsState>,
+) -> actix_web::Result<SchedulesResponse> {
+    let repo = db.repository::<ScheduleRepo>();
+    let avs_repo = db.repository::<AvsRepo>();
+    let id = id.into_inner();
+    let current = repo.get(id).map_err(ApiError::from)?;
+    if user.role_id != 1 && user.role_id != 2 && current.user_id != user.id {
+        return Err(ApiError::new("Not found.").status(404).into());
+    }
+    let avs_ids = current
+        .device_ids
+        .iter()
+        .flatten()
+        .copied()
+        .collect::<Vec<i32>>();
+    let data = data.into_inner();
+    check_ports(&db, &data.port_ids, &avs_ids)?;
+    let res = repo
+        .set_outputs(id, data.port_ids, data.zones)
+        .map_err(ApiError::from)?;
+    let avs_ids = avs_repo
+        .get_many(&avs_ids)
+        .map_err(ApiError::from)?
+        .into_iter()
+        .map(|avs| avs.unique_id)
+        .collect::<Vec<String>>();
+    tokio::spawn(async move {
+        refresh_schedules(&ws_state, avs_ids, vec![id]).await;
+    });
+    SchedulesResponse::from(res).wrap()
+}
+
 api_rt::routes! {
     get
     get_by_id
     get_by_avs
     post
+    put_outputs
     delete
 }
@@ -0,0 +1,259 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use crate::{middlewares::auth::Auth, ApiError, Config};
+use actix_web::web;
+use api_db::{
+    repos::{AvsRepo, UpdatesRepo},
+    ApiDatabase,
+};
+use proto::WsState;
+use types::{
+    api::{
+        updates::{AvsUpdateResponse, DeployReq, NewUpdates, UpdatesReq, UpdatesResponse},
+        user::User,
+    },
+    proto::UpdateAnnounce,
+};
+use utils::{crypto::sha256, files::ApiAssets};
+
+/// # Get all client updates.
+///
+/// This endpoint returns all client update releases, newest first.
+/// Only user with role `1 (Root)` and `2 (SuperAdmin)` can access this endpoint.
+/// ---
+/// tags:
+///     - updates
+/// responses:
+///     - status: 200
+///       content: !Vec UpdatesResponse
+///       description: List of client update releases.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// auth: api_key
+#[api_rt::route(get, "/updates", Auth)]
+async fn get(
+    user: web::ReqData<User>,
+    db: web::Data<ApiDatabase>,
+) -> actix_web::Result<impl actix_web::Responder> {
+    if user.role_id != 1 && user.role_id != 2 {
+        return Err(ApiError::new("Unauthorized").status(401).into());
+    }
+    let repo = db.repository::<UpdatesRepo>();
+    let res = repo.get_all().map_err(ApiError::from)?;
+    UpdatesResponse::wrap_vec(res.into_iter().map(UpdatesResponse::from).collect(), None)
+}
+
+/// # Create a client update.
+///
+/// This endpoint creates a client update release from a binary uploaded through `/install`.
+/// The size and sha256 are computed from the uploaded file, the signature must be the hex encoded
+/// ed25519 signature of `myrts-client:{version}:{size}:{sha256}` made with the key trusted by the devices.
+/// Only user with role `1 (Root)` can access this endpoint.
+/// ---
+/// tags:
+///     - updates
+/// request:
+///     content: !T UpdatesReq
+///     content_type: application/json
+///     description: The client update release.
+/// responses:
+///     - status: 200
+///       content: !T UpdatesResponse
+///       description: The client update release.
+///     - status: 400
+///       content: !T ApiError
+///       description: The request is invalid.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 404
+///       content: !T ApiError
+///       description: The file is not found.
+///     - status: 409
+///       content: !T ApiError
+///       description: The version already exists.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// auth: api_key
+#[api_rt::route(post, "/updates", Auth)]
+async fn post(
+    user: web::ReqData<User>,
+    data: web::Json<UpdatesReq>,
+    db: web::Data<ApiDatabase>,
+    assets: web::Data<ApiAssets>,
+) -> actix_web::Result<UpdatesResponse> {
+    if user.role_id != 1 {
+        return Err(ApiError::new("Unauthorized").status(401).into());
+    }
+    let data = data.into_inner();
+    if data.version.trim().is_empty() {
+        return Err(ApiError::new("Version can't be empty").status(400).into());
+    }
+    let signature = data.signature.trim().to_lowercase();
+    if signature.len() != 128 || !signature.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(ApiError::new("Signature must be 64 hex encoded bytes")
+            .status(400)
+            .into());
+    }
+    if !assets.other_exists(&data.file_name) {
+        return Err(ApiError::new("File not found").status(404).into());
+    }
+    let file = assets.read_other(&data.file_name).map_err(ApiError::new)?;
+    let repo = db.repository::<UpdatesRepo>();
+    let res = repo
+        .create(NewUpdates {
+            version: data.version.trim().to_string(),
+            file_name: data.file_name,
+            size: file.len() as i64,
+            sha256: sha256(&file),
+            signature,
+            user_id: user.id,
+        })
+        .map_err(ApiError::from)?;
+    UpdatesResponse::from(res).wrap()
+}
+
+/// # Deploy a client update.
+///
+/// This endpoint announces the client update to the given avs, the connected avs start updating immediately
+/// and the others when they reconnect.
+/// Only user with role `1 (Root)` can access this endpoint.
+/// ---
+/// tags:
+///     - updates
+/// request:
+///     content: !T DeployReq
+///     content_type: application/json
+///     description: The avs to update.
+/// responses:
+///     - status: 200
+///       content: !Vec AvsUpdateResponse
+///       description: The update progress of the avs.
+///     - status: 400
+///       content: !T ApiError
+///       description: The request is invalid.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 404
+///       content: !T ApiError
+///       description: The update is not found.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// params:
+///     - name: id
+///       kind: !Path i32
+///       required: true
+///       description: The update id.
+/// auth: api_key
+#[api_rt::route(post, "/updates/{id}/deploy", Auth)]
+async fn post_deploy(
+    user: web::ReqData<User>,
+    id: web::Path<i32>,
+    data: web::Json<DeployReq>,
+    db: web::Data<ApiDatabase>,
+    ws_state: web::Data<WsState>,
+    cfg: web::Data<Config>,
+) -> actix_web::Result<impl actix_web::Responder> {
+    if user.role_id != 1 {
+        return Err(ApiError::new("Unauthorized").status(401).into());
+    }
+    let repo = db.repository::<UpdatesRepo>();
+    let update = repo.get(id.into_inner()).map_err(ApiError::from)?;
+    let avs = db
+        .repository::<AvsRepo>()
+        .get_many(&data.avs_ids)
+        .map_err(ApiError::from)?;
+    if avs.is_empty() {
+        return Err(ApiError::new("No avs to update").status(400).into());
+    }
+    let ids = avs.iter().map(|a| a.id).collect::<Vec<i32>>();
+    let res = repo.deploy(update.id, &ids).map_err(ApiError::from)?;
+    let announce = UpdateAnnounce {
+        id: update.id,
+        version: update.version,
+        url: cfg.format(format!("/install/{}", update.file_name)),
+        size: update.size,
+        sha256: update.sha256,
+        signature: update.signature,
+    };
+    for avs in avs {
+        if avs.pending != 0 {
+            continue;
+        }
+        if let Some(stream) = ws_state.avs_by_id(avs.unique_id).await {
+            let _ = stream.write("update", announce.clone()).await;
+        }
+    }
+    AvsUpdateResponse::wrap_vec(res.into_iter().map(AvsUpdateResponse::from).collect(), None)
+}
+
+/// # Get the progress of a client update.
+///
+/// This endpoint returns the update progress of every avs the update was deployed to.
+/// `status`: `1 (announced)`, `2 (downloading)`, `3 (verified)`, `4 (installed)`, `5 (completed)`, `6 (failed)` and `7 (rolled back)`.
+/// Only user with role `1 (Root)` and `2 (SuperAdmin)` can access this endpoint.
+/// ---
+/// tags:
+///     - updates
+/// responses:
+///     - status: 200
+///       content: !Vec AvsUpdateResponse
+///       description: The update progress of the avs.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 404
+///       content: !T ApiError
+///       description: The update is not found.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// params:
+///     - name: id
+///       kind: !Path i32
+///       required: true
+///       description: The update id.
+/// auth: api_key
+#[api_rt::route(get, "/updates/{id}/status", Auth)]
+async fn get_status(
+    user: web::ReqData<User>,
+    id: web::Path<i32>,
+    db: web::Data<ApiDatabase>,
+) -> actix_web::Result<impl actix_web::Responder> {
+    if user.role_id != 1 && user.role_id != 2 {
+        return Err(ApiError::new("Unauthorized").status(401).into());
+    }
+    let repo = db.repository::<UpdatesRepo>();
+    let update = repo.get(id.into_inner()).map_err(ApiError::from)?;
+    let res = repo.get_status(update.id).map_err(ApiError::from)?;
+    AvsUpdateResponse::wrap_vec(res.into_iter().map(AvsUpdateResponse::from).collect(), None)
+}
+
+api_rt::routes! {
+    get
+    post
+    post_deploy
+    get_status
+}
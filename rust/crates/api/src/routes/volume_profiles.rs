@@ -0,0 +1,420 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use crate::{middlewares::auth::Auth, routes::device_configs::push, ApiError};
+use actix_web::{web, Responder};
+use api_db::{
+    repos::{AvsRepo, VolumeProfileRepo},
+    ApiDatabase,
+};
+use chrono::NaiveTime;
+use proto::WsState;
+use types::api::{
+    user::User,
+    volume_profile::{NewVolumeProfile, VolumeProfileReq, VolumeProfileResponse},
+    Message,
+};
+
+/// Maximum number of ranges in a profile.
+const MAX_RANGES: usize = 48;
+
+/// Validate the profile request and create the new ranges.
+fn new_ranges(
+    data: VolumeProfileReq,
+    avs_id: Option<i32>,
+    user_group_id: Option<i32>,
+) -> Result<Vec<NewVolumeProfile>, ApiError> {
+    if data.ranges.is_empty() {
+        return Err(ApiError::new("Ranges cannot be empty").status(400));
+    }
+    if data.ranges.len() > MAX_RANGES {
+        return Err(ApiError::new(format!("Ranges can't be more than {}", MAX_RANGES)).status(400));
+    }
+    let mut res = vec![];
+    for (position, range) in data.ranges.into_iter().enumerate() {
+        if NaiveTime::parse_from_str(&range.start_time, "%H:%M").is_err()
+            || NaiveTime::parse_from_str(&range.end_time, "%H:%M").is_err()
+        {
+            return Err(ApiError::new("Invalid range, expected HH:MM").status(400));
+        }
+        if range.start_time == range.end_time {
+            return Err(ApiError::new("Range start and end can't be equal").status(400));
+        }
+        if !(0.0..=1.0).contains(&range.multiplier) {
+            return Err(ApiError::new("Multiplier must be between 0 and 1").status(400));
+        }
+        res.push(NewVolumeProfile {
+            avs_id,
+            user_group_id,
+            position: position as i32,
+            start_time: range.start_time,
+            end_time: range.end_time,
+            multiplier: range.multiplier,
+        });
+    }
+    Ok(res)
+}
+
+/// # Get the volume profile of an avs.
+///
+/// This endpoint returns the volume profile stored for the avs itself, without the group profile.
+/// Only user with role `1 (Root)` and `2 (SuperAdmin)` can access this endpoint.
+/// ---
+/// tags:
+///     - volume_profiles
+/// responses:
+///     - status: 200
+///       content: !Vec VolumeProfileResponse
+///       description: The avs volume profile.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// params:
+///     - name: id
+///       kind: !Path i32
+///       required: true
+///       description: The avs id.
+/// auth: api_key
+#[api_rt::route(get, "/volume_profiles/avs/{id}", Auth)]
+async fn get_avs(
+    user: web::ReqData<User>,
+    id: web::Path<i32>,
+    db: web::Data<ApiDatabase>,
+) -> actix_web::Result<impl Responder> {
+    if user.role_id != 1 && user.role_id != 2 {
+        return Err(ApiError::new("Unauthorized").status(401).into());
+    }
+    let repo = db.repository::<VolumeProfileRepo>();
+    let res = repo.get_by_avs(id.into_inner()).map_err(ApiError::from)?;
+    VolumeProfileResponse::wrap_vec(
+        res.into_iter().map(VolumeProfileResponse::from).collect(),
+        None,
+    )
+}
+
+/// # Get the effective volume profile of an avs.
+///
+/// This endpoint returns the volume profile pushed to the avs, the avs profile or else the group profile.
+/// Only user with role `1 (Root)` and `2 (SuperAdmin)` can access this endpoint.
+/// ---
+/// tags:
+///     - volume_profiles
+/// responses:
+///     - status: 200
+///       content: !Vec VolumeProfileResponse
+///       description: The effective volume profile.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 404
+///       content: !T ApiError
+///       description: The avs is not found.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// params:
+///     - name: id
+///       kind: !Path i32
+///       required: true
+///       description: The avs id.
+/// auth: api_key
+#[api_rt::route(get, "/volume_profiles/avs/{id}/effective", Auth)]
+async fn get_avs_effective(
+    user: web::ReqData<User>,
+    id: web::Path<i32>,
+    db: web::Data<ApiDatabase>,
+) -> actix_web::Result<impl Responder> {
+    if user.role_id != 1 && user.role_id != 2 {
+        return Err(ApiError::new("Unauthorized").status(401).into());
+    }
+    let avs = db
+        .repository::<AvsRepo>()
+        .get(id.into_inner())
+        .map_err(ApiError::from)?;
+    let repo = db.repository::<VolumeProfileRepo>();
+    let res = repo.resolve(avs.id).map_err(ApiError::from)?;
+    VolumeProfileResponse::wrap_vec(
+        res.into_iter().map(VolumeProfileResponse::from).collect(),
+        None,
+    )
+}
+
+/// # Set the volume profile of an avs.
+///
+/// This endpoint replaces the volume profile of the avs and pushes it to the avs if it's connected.
+/// The avs profile replaces the group profile as a whole, use a `0` multiplier for mute windows.
+/// Emergency schedules and streams bypass the profile.
+/// Only user with role `1 (Root)` and `2 (SuperAdmin)` can access this endpoint.
+/// ---
+/// tags:
+///     - volume_profiles
+/// request:
+///     content: !T VolumeProfileReq
+///     content_type: application/json
+///     description: The avs volume profile.
+/// responses:
+///     - status: 200
+///       content: !Vec VolumeProfileResponse
+///       description: The avs volume profile.
+///     - status: 400
+///       content: !T ApiError
+///       description: The request is invalid.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 404
+///       content: !T ApiError
+///       description: The avs is not found.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// params:
+///     - name: id
+///       kind: !Path i32
+///       required: true
+///       description: The avs id.
+/// auth: api_key
+#[api_rt::route(put, "/volume_profiles/avs/{id}", Auth)]
+async fn put_avs(
+    user: web::ReqData<User>,
+    id: web::Path<i32>,
+    data: web::Json<VolumeProfileReq>,
+    db: web::Data<ApiDatabase>,
+    ws_state: web::Data<WsState>,
+) -> actix_web::Result<impl Responder> {
+    if user.role_id != 1 && user.role_id != 2 {
+        return Err(ApiError::new("Unauthorized").status(401).into());
+    }
+    let avs = db
+        .repository::<AvsRepo>()
+        .get(id.into_inner())
+        .map_err(ApiError::from)?;
+    let ranges = new_ranges(data.into_inner(), Some(avs.id), None)?;
+    let repo = db.repository::<VolumeProfileRepo>();
+    let res = repo
+        .set(Some(avs.id), None, ranges)
+        .map_err(ApiError::from)?;
+    push(&db, &ws_state, vec![avs]).await;
+    VolumeProfileResponse::wrap_vec(
+        res.into_iter().map(VolumeProfileResponse::from).collect(),
+        None,
+    )
+}
+
+/// # Delete the volume profile of an avs.
+///
+/// This endpoint deletes the volume profile of the avs, the avs falls back to the group profile.
+/// Only user with role `1 (Root)` and `2 (SuperAdmin)` can access this endpoint.
+/// ---
+/// tags:
+///     - volume_profiles
+/// responses:
+///     - status: 200
+///       content: !T Message
+///       description: The avs volume profile is deleted.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 404
+///       content: !T ApiError
+///       description: The avs is not found.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// params:
+///     - name: id
+///       kind: !Path i32
+///       required: true
+///       description: The avs id.
+/// auth: api_key
+#[api_rt::route(delete, "/volume_profiles/avs/{id}", Auth)]
+async fn delete_avs(
+    user: web::ReqData<User>,
+    id: web::Path<i32>,
+    db: web::Data<ApiDatabase>,
+    ws_state: web::Data<WsState>,
+) -> actix_web::Result<Message> {
+    if user.role_id != 1 && user.role_id != 2 {
+        return Err(ApiError::new("Unauthorized").status(401).into());
+    }
+    let avs = db
+        .repository::<AvsRepo>()
+        .get(id.into_inner())
+        .map_err(ApiError::from)?;
+    let repo = db.repository::<VolumeProfileRepo>();
+    repo.delete_by_avs(avs.id).map_err(ApiError::from)?;
+    push(&db, &ws_state, vec![avs]).await;
+    Message::new("ok".to_owned()).wrap()
+}
+
+/// # Get the volume profile of a group.
+///
+/// This endpoint returns the volume profile of the group.
+/// Only user with role `1 (Root)` and `2 (SuperAdmin)` can access this endpoint.
+/// ---
+/// tags:
+///     - volume_profiles
+/// responses:
+///     - status: 200
+///       content: !Vec VolumeProfileResponse
+///       description: The group volume profile.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// params:
+///     - name: id
+///       kind: !Path i32
+///       required: true
+///       description: The group id.
+/// auth: api_key
+#[api_rt::route(get, "/volume_profiles/groups/{id}", Auth)]
+async fn get_group(
+    user: web::ReqData<User>,
+    id: web::Path<i32>,
+    db: web::Data<ApiDatabase>,
+) -> actix_web::Result<impl Responder> {
+    if user.role_id != 1 && user.role_id != 2 {
+        return Err(ApiError::new("Unauthorized").status(401).into());
+    }
+    let repo = db.repository::<VolumeProfileRepo>();
+    let res = repo.get_by_group(id.into_inner()).map_err(ApiError::from)?;
+    VolumeProfileResponse::wrap_vec(
+        res.into_iter().map(VolumeProfileResponse::from).collect(),
+        None,
+    )
+}
+
+/// # Set the volume profile of a group.
+///
+/// This endpoint replaces the volume profile of the group and pushes it to the connected avs of the group.
+/// Only user with role `1 (Root)` and `2 (SuperAdmin)` can access this endpoint.
+/// ---
+/// tags:
+///     - volume_profiles
+/// request:
+///     content: !T VolumeProfileReq
+///     content_type: application/json
+///     description: The group volume profile.
+/// responses:
+///     - status: 200
+///       content: !Vec VolumeProfileResponse
+///       description: The group volume profile.
+///     - status: 400
+///       content: !T ApiError
+///       description: The request is invalid.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 404
+///       content: !T ApiError
+///       description: The group is not found.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// params:
+///     - name: id
+///       kind: !Path i32
+///       required: true
+///       description: The group id.
+/// auth: api_key
+#[api_rt::route(put, "/volume_profiles/groups/{id}", Auth)]
+async fn put_group(
+    user: web::ReqData<User>,
+    id: web::Path<i32>,
+    data: web::Json<VolumeProfileReq>,
+    db: web::Data<ApiDatabase>,
+    ws_state: web::Data<WsState>,
+) -> actix_web::Result<impl Responder> {
+    if user.role_id != 1 && user.role_id != 2 {
+        return Err(ApiError::new("Unauthorized").status(401).into());
+    }
+    let group_id = id.into_inner();
+    let ranges = new_ranges(data.into_inner(), None, Some(group_id))?;
+    let repo = db.repository::<VolumeProfileRepo>();
+    let res = repo
+        .set(None, Some(group_id), ranges)
+        .map_err(ApiError::from)?;
+    let avs = db
+        .repository::<AvsRepo>()
+        .get_by_group(group_id)
+        .map_err(ApiError::from)?;
+    push(&db, &ws_state, avs).await;
+    VolumeProfileResponse::wrap_vec(
+        res.into_iter().map(VolumeProfileResponse::from).collect(),
+        None,
+    )
+}
+
+/// # Delete the volume profile of a group.
+///
+/// This endpoint deletes the volume profile of the group and pushes the result to the connected avs of the group.
+/// Only user with role `1 (Root)` and `2 (SuperAdmin)` can access this endpoint.
+/// ---
+/// tags:
+///     - volume_profiles
+/// responses:
+///     - status: 200
+///       content: !T Message
+///       description: The group volume profile is deleted.
+///     - status: 401
+///       content: !T ApiError
+///       description: The user is not authenticated.
+///     - status: 500
+///       content: !T ApiError
+///       description: Server encountered an error.
+/// params:
+///     - name: id
+///       kind: !Path i32
+///       required: true
+///       description: The group id.
+/// auth: api_key
+#[api_rt::route(delete, "/volume_profiles/groups/{id}", Auth)]
+async fn delete_group(
+    user: web::ReqData<User>,
+    id: web::Path<i32>,
+    db: web::Data<ApiDatabase>,
+    ws_state: web::Data<WsState>,
+) -> actix_web::Result<Message> {
+    if user.role_id != 1 && user.role_id != 2 {
+        return Err(ApiError::new("Unauthorized").status(401).into());
+    }
+    let group_id = id.into_inner();
+    let repo = db.repository::<VolumeProfileRepo>();
+    repo.delete_by_group(group_id).map_err(ApiError::from)?;
+    let avs = db
+        .repository::<AvsRepo>()
+        .get_by_group(group_id)
+        .map_err(ApiError::from)?;
+    push(&db, &ws_state, avs).await;
+    Message::new("ok".to_owned()).wrap()
+}
+
+api_rt::routes! {
+    get_avs
+    get_avs_effective
+    put_avs
+    delete_avs
+    get_group
+    put_group
+    delete_group
+}
@@ -15,40 +15,130 @@ MyRTS agrees to credit you as the developer of the software in all promotional m
 If MyRTS violates any of these terms, their license to use the software will automatically terminate.
 */
 
-use rodio::{cpal::FromSample, OutputStream, Sample, Sink, Source};
+use anyhow::{anyhow, Result};
+use rodio::{
+    cpal::{
+        traits::{DeviceTrait, HostTrait},
+        FromSample,
+    },
+    OutputStream, Sample, Sink, Source,
+};
+use std::sync::RwLock;
 
-/// Audio player.
-pub struct AudioPlayer {
+/// Audio output.
+/// The background sink is mixed under the main sink on the same device.
+struct Output {
     _s: OutputStream,
     sink: Sink,
+    background: Sink,
+    device: Option<String>,
+}
+
+impl Output {
+    /// Open the output device by its name, or the default device.
+    fn open(device: Option<&str>) -> Result<Self> {
+        let (o, h) = match device {
+            Some(name) => {
+                let device = rodio::cpal::default_host()
+                    .output_devices()?
+                    .find(|d| d.name().map(|n| n == name).unwrap_or(false))
+                    .ok_or_else(|| anyhow!("Output device {} not found", name))?;
+                OutputStream::try_from_device(&device)?
+            }
+            None => OutputStream::try_default()?,
+        };
+        let sink = Sink::try_new(&h)?;
+        let background = Sink::try_new(&h)?;
+        Ok(Self {
+            _s: o,
+            sink,
+            background,
+            device: device.map(ToOwned::to_owned),
+        })
+    }
+}
+
+/// Audio player.
+pub struct AudioPlayer {
+    output: RwLock<Output>,
 }
 
 impl AudioPlayer {
     /// Create audio player.
     pub fn new() -> Self {
-        let (o, h) = OutputStream::try_default().unwrap();
-        let sink = Sink::try_new(&h).unwrap();
-        Self { _s: o, sink }
+        Self {
+            output: RwLock::new(Output::open(None).unwrap()),
+        }
+    }
+
+    /// Create audio player on the output device by its name, `None` for the default device.
+    pub fn open(device: Option<&str>) -> Result<Self> {
+        Ok(Self {
+            output: RwLock::new(Output::open(device)?),
+        })
+    }
+
+    /// Get the name of the default output device.
+    pub fn default_device() -> Option<String> {
+        rodio::cpal::default_host()
+            .default_output_device()
+            .and_then(|d| d.name().ok())
+    }
+
+    /// Get the names of the available output devices.
+    pub fn devices() -> Vec<String> {
+        rodio::cpal::default_host()
+            .output_devices()
+            .map(|devices| devices.filter_map(|d| d.name().ok()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Get the name of the current output device, `None` for the default device.
+    pub fn device(&self) -> Option<String> {
+        self.output.read().unwrap().device.clone()
+    }
+
+    /// Check if the current output device is still available.
+    pub fn is_available(&self) -> bool {
+        match self.device() {
+            Some(name) => Self::devices().contains(&name),
+            None => rodio::cpal::default_host()
+                .default_output_device()
+                .is_some(),
+        }
+    }
+
+    /// Switch the output device, `None` for the default device.
+    /// The current playback is stopped.
+    pub fn set_device(&self, device: Option<&str>) -> Result<()> {
+        let output = Output::open(device)?;
+        let mut lock = self.output.write().unwrap();
+        output.sink.set_volume(lock.sink.volume());
+        output.background.set_volume(lock.background.volume());
+        lock.sink.stop();
+        lock.background.stop();
+        *lock = output;
+        Ok(())
     }
 
     /// Play.
     pub fn play(&self) {
-        self.sink.play();
+        self.output.read().unwrap().sink.play();
     }
 
     /// Pause.
     pub fn pause(&self) {
-        self.sink.pause();
+        self.output.read().unwrap().sink.pause();
     }
 
     /// Stop.
     pub fn stop(&self) {
-        self.sink.stop();
+        self.output.read().unwrap().sink.stop();
     }
 
     /// Clear.
     pub fn clear(&self) {
-        self.sink.clear();
+        self.output.read().unwrap().sink.clear();
     }
 
     /// Append.
@@ -58,17 +148,57 @@ impl AudioPlayer {
         f32: FromSample<S::Item>,
         S::Item: Sample + Send,
     {
-        self.sink.append(s)
+        self.output.read().unwrap().sink.append(s)
     }
 
     /// Set volume.
     pub fn set_volume(&self, volume: f32) {
-        self.sink.set_volume(volume);
+        self.output.read().unwrap().sink.set_volume(volume);
     }
 
     /// Check if playing.
     pub fn is_playing(&self) -> bool {
-        !self.sink.empty()
+        !self.output.read().unwrap().sink.empty()
+    }
+
+    /// Get the number of sources queued to play.
+    pub fn queued(&self) -> usize {
+        self.output.read().unwrap().sink.len()
+    }
+
+    /// Append to the background.
+    pub fn append_background<S>(&self, s: S)
+    where
+        S: Source + Send + 'static,
+        f32: FromSample<S::Item>,
+        S::Item: Sample + Send,
+    {
+        self.output.read().unwrap().background.append(s)
+    }
+
+    /// Play the background.
+    pub fn play_background(&self) {
+        self.output.read().unwrap().background.play();
+    }
+
+    /// Pause the background, it resumes where it left off.
+    pub fn pause_background(&self) {
+        self.output.read().unwrap().background.pause();
+    }
+
+    /// Clear the background.
+    pub fn clear_background(&self) {
+        self.output.read().unwrap().background.clear();
+    }
+
+    /// Set the background volume.
+    pub fn set_background_volume(&self, volume: f32) {
+        self.output.read().unwrap().background.set_volume(volume);
+    }
+
+    /// Check if the background has anything to play.
+    pub fn has_background(&self) -> bool {
+        !self.output.read().unwrap().background.empty()
     }
 }
 
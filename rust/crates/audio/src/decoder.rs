@@ -99,6 +99,7 @@ pub fn packetize(packet: &[u8], granule_pos: i64, packetno: i64) -> *mut c::ogg_
 
 /// Sample
 /// Represents an pcm sample
+#[derive(Clone)]
 pub struct Sample {
     data: Vec<i16>,
     pos: usize,
@@ -318,3 +319,186 @@ impl Drop for Decoder {
 
 unsafe impl Send for Decoder {}
 unsafe impl Sync for Decoder {}
+
+/// Maximum samples per channel of an opus packet, 120ms at 48kHz.
+const MAX_FRAME_SAMPLES: usize = 5760;
+
+/// RtpDecoder
+/// Decodes rtp opus packets one by one, the lost packets are recovered
+/// from the in-band fec of the following packet or concealed by the opus plc.
+pub struct RtpDecoder {
+    st: *mut c::OpusDecoder,
+    recovered: u64,
+    concealed: u64,
+}
+
+impl RtpDecoder {
+    /// new
+    /// Create a new stereo 48kHz decoder.
+    pub fn new() -> Result<Self> {
+        unsafe {
+            let mut err = 0;
+            let st = c::opus_decoder_create(48000, 2, &mut err);
+            if err != 0 || st.is_null() {
+                bail!("Cannot create opus decoder: {}", err);
+            }
+            Ok(Self {
+                st,
+                recovered: 0,
+                concealed: 0,
+            })
+        }
+    }
+
+    /// run
+    /// Run the opus decoder, `None` packet runs the plc.
+    fn run(&mut self, packet: Option<&[u8]>, samples: usize, fec: bool) -> Option<Sample> {
+        let samples = samples.clamp(1, MAX_FRAME_SAMPLES);
+        let mut pcm = vec![0i16; samples * 2];
+        let (data, len) = match packet {
+            Some(packet) => (packet.as_ptr(), packet.len() as i32),
+            None => (std::ptr::null(), 0),
+        };
+        let ret = unsafe {
+            c::opus_decode(
+                self.st,
+                data,
+                len,
+                pcm.as_mut_ptr(),
+                samples as i32,
+                fec as i32,
+            )
+        };
+        if ret <= 0 {
+            return None;
+        }
+        Some(Sample::new(&pcm[..(ret * 2) as usize]))
+    }
+
+    /// decode
+    /// Decode the packet.
+    pub fn decode(&mut self, packet: &[u8]) -> Option<Sample> {
+        self.run(Some(packet), MAX_FRAME_SAMPLES, false)
+    }
+
+    /// decode_lost
+    /// Recover `samples` of the lost packet from the fec of the following packet,
+    /// or conceal them when the following packet is unavailable.
+    pub fn decode_lost(&mut self, fec: Option<&[u8]>, samples: usize) -> Option<Sample> {
+        if let Some(fec) = fec {
+            if let Some(sample) = self.run(Some(fec), samples, true) {
+                self.recovered += 1;
+                return Some(sample);
+            }
+        }
+        self.concealed += 1;
+        self.run(None, samples, false)
+    }
+
+    /// recovered
+    /// Number of the lost packets recovered by the fec.
+    pub fn recovered(&self) -> u64 {
+        self.recovered
+    }
+
+    /// concealed
+    /// Number of the lost packets concealed by the plc.
+    pub fn concealed(&self) -> u64 {
+        self.concealed
+    }
+}
+
+impl Drop for RtpDecoder {
+    fn drop(&mut self) {
+        unsafe {
+            c::opus_decoder_destroy(self.st);
+        }
+    }
+}
+
+unsafe impl Send for RtpDecoder {}
+unsafe impl Sync for RtpDecoder {}
+
+/// OpusFile
+/// Playable source of an in-memory ogg opus file.
+pub struct OpusFile {
+    of: *mut c::OggOpusFile,
+    _data: Vec<u8>,
+    buf: Vec<i16>,
+    pos: usize,
+}
+
+impl OpusFile {
+    /// new
+    /// Open the given ogg opus file content.
+    pub fn new(data: Vec<u8>) -> Result<Self> {
+        unsafe {
+            let mut err = 0;
+            let of = c::op_open_memory(data.as_ptr() as *const u8, data.len(), &mut err);
+            if err != 0 || of.is_null() {
+                bail!("Cannot open opus file: {}", err);
+            }
+            Ok(Self {
+                of,
+                _data: data,
+                buf: Vec::new(),
+                pos: 0,
+            })
+        }
+    }
+
+    /// fill
+    /// Read the next pcm chunk, returns false at the end of the stream.
+    fn fill(&mut self) -> bool {
+        let mut pcm = [0i16; 11520];
+        let ret = unsafe { c::op_read_stereo(self.of, pcm.as_mut_ptr(), pcm.len() as i32) };
+        if ret <= 0 {
+            return false;
+        }
+        self.buf = pcm[..(ret * 2) as usize].to_vec();
+        self.pos = 0;
+        true
+    }
+}
+
+impl Iterator for OpusFile {
+    type Item = i16;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pos >= self.buf.len() && !self.fill() {
+            return None;
+        }
+        let ret = self.buf[self.pos];
+        self.pos += 1;
+        Some(ret)
+    }
+}
+
+impl Source for OpusFile {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        2
+    }
+
+    fn sample_rate(&self) -> u32 {
+        48000
+    }
+
+    fn total_duration(&self) -> Option<std::time::Duration> {
+        None
+    }
+}
+
+impl Drop for OpusFile {
+    fn drop(&mut self) {
+        unsafe {
+            c::op_free(self.of);
+        }
+    }
+}
+
+unsafe impl Send for OpusFile {}
+unsafe impl Sync for OpusFile {}
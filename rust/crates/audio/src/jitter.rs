@@ -0,0 +1,338 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use std::{collections::BTreeMap, time::Instant};
+
+/// Clock rate of the opus rtp timestamps.
+const CLOCK_RATE: f32 = 48000.0;
+/// Samples per frame until it can be derived from the timestamps, 20ms.
+const DEFAULT_FRAME: u32 = 960;
+/// Longest gap concealed frame by frame, longer gaps are skipped.
+const MAX_CONCEAL: u64 = 5;
+
+/// Frame
+/// The next frame to play out of the jitter buffer.
+pub enum Frame {
+    /// The packet arrived in time.
+    Packet(Vec<u8>),
+    /// The packet is lost, `fec` is the following packet carrying the in-band fec of the lost one.
+    Lost {
+        fec: Option<Vec<u8>>,
+        samples: usize,
+    },
+}
+
+/// JitterStats
+/// The jitter buffer statistics, counters are since the buffer was created.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JitterStats {
+    /// Packets received.
+    pub received: u64,
+    /// Packets that never arrived in time to play.
+    pub lost: u64,
+    /// Packets arrived after their play out time.
+    pub late: u64,
+    /// Packets received more than once.
+    pub duplicate: u64,
+    /// Packets dropped to catch up with the sender.
+    pub dropped: u64,
+    /// Times the buffer ran empty while playing.
+    pub underruns: u64,
+    /// Interarrival jitter in milliseconds.
+    pub jitter_ms: f32,
+    /// Audio currently buffered in milliseconds.
+    pub delay_ms: f32,
+    /// Adaptive target of the buffered audio in milliseconds.
+    pub target_ms: f32,
+}
+
+/// A buffered packet.
+struct Packet {
+    timestamp: u32,
+    payload: Vec<u8>,
+}
+
+/// JitterBuffer
+/// Adaptive jitter buffer keyed on the rtp sequence number and timestamp.
+/// Packets are reordered by sequence number and played out once the buffered
+/// audio reaches the target delay, which follows the interarrival jitter.
+pub struct JitterBuffer {
+    packets: BTreeMap<u64, Packet>,
+    /// Extended sequence number of the next packet to play.
+    next: Option<u64>,
+    /// Highest extended sequence number received.
+    highest: Option<u64>,
+    buffering: bool,
+    min_ms: f32,
+    max_ms: f32,
+    target_ms: f32,
+    /// Interarrival jitter in samples.
+    jitter: f32,
+    /// Arrival time in samples and timestamp of the last in order packet.
+    last: Option<(f32, u32)>,
+    created_at: Instant,
+    /// Samples per frame.
+    frame: u32,
+    stats: JitterStats,
+}
+
+impl Default for JitterBuffer {
+    fn default() -> Self {
+        Self::new(40, 400)
+    }
+}
+
+impl JitterBuffer {
+    /// new
+    /// Create a jitter buffer with the target delay bounds in milliseconds.
+    pub fn new(min_ms: u32, max_ms: u32) -> Self {
+        Self {
+            packets: BTreeMap::new(),
+            next: None,
+            highest: None,
+            buffering: true,
+            min_ms: min_ms as f32,
+            max_ms: max_ms.max(min_ms) as f32,
+            target_ms: min_ms as f32,
+            jitter: 0.0,
+            last: None,
+            created_at: Instant::now(),
+            frame: DEFAULT_FRAME,
+            stats: JitterStats::default(),
+        }
+    }
+
+    /// extend
+    /// Extend the 16 bit sequence number with the rollover count closest to the highest one.
+    fn extend(&self, seq: u16) -> u64 {
+        let highest = match self.highest {
+            Some(highest) => highest,
+            None => return seq as u64 + (1 << 16),
+        };
+        let candidate = (highest & !0xFFFF) | seq as u64;
+        if candidate + (1 << 15) < highest {
+            candidate + (1 << 16)
+        } else if candidate > highest + (1 << 15) && candidate >= (1 << 16) {
+            candidate - (1 << 16)
+        } else {
+            candidate
+        }
+    }
+
+    /// frame_ms
+    /// Duration of a frame in milliseconds.
+    fn frame_ms(&self) -> f32 {
+        self.frame as f32 * 1000.0 / CLOCK_RATE
+    }
+
+    /// depth_ms
+    /// Audio currently buffered in milliseconds.
+    fn depth_ms(&self) -> f32 {
+        match (
+            self.packets.first_key_value(),
+            self.packets.last_key_value(),
+        ) {
+            (Some((_, first)), Some((_, last))) => {
+                let samples = last.timestamp.wrapping_sub(first.timestamp);
+                // reordered timestamps would wrap around.
+                let samples = if samples > u32::MAX / 2 { 0 } else { samples };
+                (samples + self.frame) as f32 * 1000.0 / CLOCK_RATE
+            }
+            _ => 0.0,
+        }
+    }
+
+    /// push
+    /// Add a received packet.
+    pub fn push(&mut self, seq: u16, timestamp: u32, payload: Vec<u8>) {
+        let ext = self.extend(seq);
+        self.stats.received += 1;
+
+        // RFC 3550 interarrival jitter over the in order packets.
+        let arrival = self.created_at.elapsed().as_secs_f32() * CLOCK_RATE;
+        if self.highest.map_or(true, |h| ext > h) {
+            // across a gap the timestamps don't tell the duration of a frame.
+            let consecutive = self.highest.map_or(false, |h| ext == h + 1);
+            if let (Some((last_arrival, last_timestamp)), true) = (self.last, consecutive) {
+                let elapsed = timestamp.wrapping_sub(last_timestamp);
+                if (120..=5760).contains(&elapsed) {
+                    self.frame = elapsed;
+                }
+                let d = (arrival - last_arrival) - elapsed as i32 as f32;
+                self.jitter += (d.abs() - self.jitter) / 16.0;
+            }
+            self.last = Some((arrival, timestamp));
+        }
+
+        if self.next.map_or(false, |next| ext < next) {
+            self.stats.late += 1;
+            return;
+        }
+        if self.packets.contains_key(&ext) {
+            self.stats.duplicate += 1;
+            return;
+        }
+        self.packets.insert(ext, Packet { timestamp, payload });
+        self.highest = Some(self.highest.map_or(ext, |h| h.max(ext)));
+
+        // follow the jitter quickly upward and slowly downward.
+        let jitter_ms = self.jitter * 1000.0 / CLOCK_RATE;
+        let wanted = (self.frame_ms() + 3.0 * jitter_ms).clamp(self.min_ms, self.max_ms);
+        if wanted > self.target_ms {
+            self.target_ms = wanted;
+        } else {
+            self.target_ms += (wanted - self.target_ms) / 64.0;
+        }
+
+        // never hold more than twice the maximum delay.
+        while self.depth_ms() > self.max_ms * 2.0 {
+            if let Some((key, _)) = self.packets.pop_first() {
+                self.stats.dropped += 1;
+                if self.next.map_or(false, |next| next <= key) {
+                    self.next = Some(key + 1);
+                }
+            }
+        }
+    }
+
+    /// pop
+    /// Get the next frame to play, `None` while buffering.
+    pub fn pop(&mut self) -> Option<Frame> {
+        if self.buffering {
+            if self.packets.is_empty() || self.depth_ms() < self.target_ms {
+                return None;
+            }
+            self.buffering = false;
+            let first = *self.packets.keys().next()?;
+            if self.next.map_or(true, |next| next < first) {
+                self.next = Some(first);
+            }
+        }
+        let first = match self.packets.keys().next() {
+            Some(first) => *first,
+            None => {
+                self.buffering = true;
+                self.stats.underruns += 1;
+                // give the next buffering some more room.
+                self.target_ms = (self.target_ms + self.frame_ms()).min(self.max_ms);
+                return None;
+            }
+        };
+        let mut next = self.next.unwrap_or(first);
+
+        // the buffer is well beyond the target, drop a frame to catch up.
+        let depth = self.depth_ms();
+        if depth > self.target_ms * 2.0 && depth > self.target_ms + 100.0 && first == next {
+            self.packets.remove(&next);
+            self.stats.dropped += 1;
+            next += 1;
+        }
+
+        if next < first && first - next > MAX_CONCEAL {
+            self.stats.lost += first - next;
+            next = first;
+        }
+        if let Some(packet) = self.packets.remove(&next) {
+            self.next = Some(next + 1);
+            return Some(Frame::Packet(packet.payload));
+        }
+        self.stats.lost += 1;
+        self.next = Some(next + 1);
+        Some(Frame::Lost {
+            fec: self.packets.get(&(next + 1)).map(|p| p.payload.clone()),
+            samples: self.frame as usize,
+        })
+    }
+
+    /// stats
+    /// Get the jitter buffer statistics.
+    pub fn stats(&self) -> JitterStats {
+        JitterStats {
+            jitter_ms: self.jitter * 1000.0 / CLOCK_RATE,
+            delay_ms: self.depth_ms(),
+            target_ms: self.target_ms,
+            ..self.stats
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Push the packets, the payload is the low byte of the sequence number.
+    /// The timestamps run on across the sequence wraparound.
+    fn push(buffer: &mut JitterBuffer, seqs: &[u16]) {
+        for seq in seqs {
+            let timestamp = seq.wrapping_sub(65000) as u32 * DEFAULT_FRAME;
+            buffer.push(*seq, timestamp, vec![*seq as u8]);
+        }
+    }
+
+    /// Pop the next frame, the payload of a packet or `None` for a lost one.
+    fn pop(buffer: &mut JitterBuffer) -> Option<u8> {
+        match buffer.pop().expect("frame to play") {
+            Frame::Packet(payload) => Some(payload[0]),
+            Frame::Lost { .. } => None,
+        }
+    }
+
+    #[test]
+    fn reorders_by_sequence() {
+        let mut buffer = JitterBuffer::new(20, 1000);
+        push(&mut buffer, &[1, 3, 2, 4]);
+        let played = (0..4).map(|_| pop(&mut buffer)).collect::<Vec<_>>();
+        assert_eq!(played, vec![Some(1), Some(2), Some(3), Some(4)]);
+        let stats = buffer.stats();
+        assert_eq!(stats.received, 4);
+        assert_eq!(stats.lost, 0);
+        assert_eq!(stats.late, 0);
+    }
+
+    #[test]
+    fn reorders_across_sequence_wraparound() {
+        let mut buffer = JitterBuffer::new(20, 1000);
+        push(&mut buffer, &[65534, 0, 65535, 1]);
+        let played = (0..4).map(|_| pop(&mut buffer)).collect::<Vec<_>>();
+        assert_eq!(played, vec![Some(254), Some(255), Some(0), Some(1)]);
+        assert_eq!(buffer.stats().lost, 0);
+    }
+
+    #[test]
+    fn counts_lost_late_and_duplicate_packets() {
+        let mut buffer = JitterBuffer::new(20, 1000);
+        push(&mut buffer, &[1, 2, 2, 4, 5]);
+        assert_eq!(pop(&mut buffer), Some(1));
+        assert_eq!(pop(&mut buffer), Some(2));
+        match buffer.pop().expect("frame to play") {
+            Frame::Lost { fec, samples } => {
+                assert_eq!(fec, Some(vec![4]));
+                assert_eq!(samples, DEFAULT_FRAME as usize);
+            }
+            Frame::Packet(_) => panic!("packet 3 was never sent"),
+        }
+        // packet 3 arriving after its play out time is late.
+        push(&mut buffer, &[3]);
+        assert_eq!(pop(&mut buffer), Some(4));
+        assert_eq!(pop(&mut buffer), Some(5));
+        let stats = buffer.stats();
+        assert_eq!(stats.received, 6);
+        assert_eq!(stats.duplicate, 1);
+        assert_eq!(stats.lost, 1);
+        assert_eq!(stats.late, 1);
+    }
+}
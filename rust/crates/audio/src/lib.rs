@@ -17,3 +17,5 @@ If MyRTS violates any of these terms, their license to use the software will aut
 
 pub mod audio;
 pub mod decoder;
+pub mod jitter;
+pub mod recorder;
@@ -0,0 +1,172 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use crate::decoder::{c, opus_head, opus_tags};
+use anyhow::{bail, Result};
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+/// OggWriter
+/// Writes opus packets received over rtp into an ogg opus file.
+pub struct OggWriter {
+    os: *mut c::ogg_stream_state,
+    file: BufWriter<File>,
+    /// The last packet and its granule position.
+    pending: Option<(Vec<u8>, i64)>,
+    packetno: i64,
+    granule_pos: i64,
+    /// The sequence number and timestamp of the last written rtp packet.
+    last: Option<(u16, u32)>,
+    /// The timestamp of the last written rtp packet in samples since the first.
+    position: i64,
+}
+
+impl OggWriter {
+    /// create
+    /// Create the file at the given path and write the opus headers.
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = BufWriter::new(File::create(path)?);
+        unsafe {
+            let os: *mut c::ogg_stream_state =
+                libc::malloc(std::mem::size_of::<c::ogg_stream_state>())
+                    as *mut c::ogg_stream_state;
+            if os.is_null() {
+                bail!("Cannot allocate ogg_stream_state");
+            }
+            let serialno = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.subsec_nanos() as i32)
+                .unwrap_or(0);
+            let ret = c::ogg_stream_init(os, serialno);
+            if ret != 0 {
+                libc::free(os as *mut libc::c_void);
+                bail!("Cannot initialize ogg_stream_state");
+            }
+            let mut writer = Self {
+                os,
+                file,
+                pending: None,
+                packetno: 2,
+                granule_pos: 0,
+                last: None,
+                position: 0,
+            };
+            let head = opus_head()?;
+            let tags = opus_tags()?;
+            // the headers must be on their own pages.
+            c::ogg_stream_packetin(writer.os, head);
+            libc::free(head as *mut libc::c_void);
+            writer.pages(true)?;
+            c::ogg_stream_packetin(writer.os, tags);
+            libc::free(tags as *mut libc::c_void);
+            writer.pages(true)?;
+            Ok(writer)
+        }
+    }
+
+    /// pages
+    /// Write the completed pages to the file, `flush` forces the pending packets out.
+    fn pages(&mut self, flush: bool) -> Result<()> {
+        unsafe {
+            let mut page: c::ogg_page = std::mem::zeroed();
+            loop {
+                let ret = if flush {
+                    c::ogg_stream_flush(self.os, &mut page)
+                } else {
+                    c::ogg_stream_pageout(self.os, &mut page)
+                };
+                if ret == 0 {
+                    break;
+                }
+                let head = std::slice::from_raw_parts(page.header, page.header_len as usize);
+                self.file.write_all(head)?;
+                let body = std::slice::from_raw_parts(page.body, page.body_len as usize);
+                self.file.write_all(body)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// packetin
+    /// Submit a packet ending at the granule position to the ogg stream.
+    fn packetin(&mut self, mut packet: Vec<u8>, granule_pos: i64, e_o_s: bool) -> Result<()> {
+        // the granule position never goes backward.
+        self.granule_pos = self.granule_pos.max(granule_pos);
+        let mut op = c::ogg_packet {
+            packet: packet.as_mut_ptr(),
+            bytes: packet.len() as _,
+            b_o_s: 0,
+            e_o_s: e_o_s as _,
+            granulepos: self.granule_pos,
+            packetno: self.packetno,
+        };
+        self.packetno += 1;
+        unsafe {
+            c::ogg_stream_packetin(self.os, &mut op);
+        }
+        self.pages(e_o_s)
+    }
+
+    /// write
+    /// Write the payload of an rtp packet, empty payloads are skipped.
+    /// Duplicate and late packets are dropped, lost packets leave a gap in the granule positions.
+    pub fn write(&mut self, seq: u16, timestamp: u32, packet: &[u8]) -> Result<()> {
+        if packet.is_empty() {
+            return Ok(());
+        }
+        if let Some((last_seq, last_timestamp)) = self.last {
+            if seq.wrapping_sub(last_seq) as i16 <= 0 {
+                return Ok(());
+            }
+            // the opus rtp clock runs at 48 kHz like the granule position.
+            self.position += (timestamp.wrapping_sub(last_timestamp) as i32).max(0) as i64;
+        }
+        self.last = Some((seq, timestamp));
+        let samples =
+            unsafe { c::opus_packet_get_nb_samples(packet.as_ptr(), packet.len() as i32, 48000) };
+        let granule_pos = self.position + samples.max(0) as i64;
+        // the last packet is held back to mark the end of the stream on it.
+        match self.pending.replace((packet.to_vec(), granule_pos)) {
+            Some((pending, granule_pos)) => self.packetin(pending, granule_pos, false),
+            None => Ok(()),
+        }
+    }
+
+    /// finish
+    /// End the stream and flush the file, returns the duration in seconds.
+    pub fn finish(mut self) -> Result<u64> {
+        if let Some((pending, granule_pos)) = self.pending.take() {
+            self.packetin(pending, granule_pos, true)?;
+        }
+        self.file.flush()?;
+        Ok((self.granule_pos / 48000) as u64)
+    }
+}
+
+impl Drop for OggWriter {
+    fn drop(&mut self) {
+        unsafe {
+            c::ogg_stream_clear(self.os);
+            libc::free(self.os as *mut libc::c_void);
+        }
+    }
+}
+
+unsafe impl Send for OggWriter {}
@@ -45,8 +45,15 @@ extern int ogg_stream_init(ogg_stream_state *os, int serialno);
 extern int ogg_stream_packetin(ogg_stream_state *os, ogg_packet *op);
 extern int ogg_stream_pageout(ogg_stream_state *os, ogg_page *og);
 extern int ogg_stream_flush(ogg_stream_state *os, ogg_page *og);
+extern int ogg_stream_clear(ogg_stream_state *os);
 extern OggOpusFile *op_open_memory(const unsigned char *_data,
                                    size_t _size, int *_error);
 extern int op_read_stereo(OggOpusFile *_of,
                           opus_int16 *_pcm, int _buf_size);
-extern int opus_packet_get_nb_samples(const unsigned char packet[], int len, int Fs);
\ No newline at end of file
+extern void op_free(OggOpusFile *_of);
+extern int opus_packet_get_nb_samples(const unsigned char packet[], int len, int Fs);
+typedef struct OpusDecoder OpusDecoder;
+extern OpusDecoder *opus_decoder_create(int Fs, int channels, int *error);
+extern int opus_decode(OpusDecoder *st, const unsigned char *data, int len,
+                       opus_int16 *pcm, int frame_size, int decode_fec);
+extern void opus_decoder_destroy(OpusDecoder *st);
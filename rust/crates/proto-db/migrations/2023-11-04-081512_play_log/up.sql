@@ -0,0 +1,12 @@
+-- Your SQL goes here
+create TABLE play_log (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    sid INTEGER NOT NULL,
+    record_url TEXT NOT NULL,
+    started_at TIMESTAMP NOT NULL,
+    ended_at TIMESTAMP,
+    volume DOUBLE,
+    outcome INTEGER NOT NULL,
+    message TEXT,
+    sent INTEGER NOT NULL DEFAULT 0
+);
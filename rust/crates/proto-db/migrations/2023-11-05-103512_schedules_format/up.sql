@@ -0,0 +1,2 @@
+-- Your SQL goes here
+alter TABLE schedules ADD COLUMN format TEXT NOT NULL DEFAULT 'mp3';
\ No newline at end of file
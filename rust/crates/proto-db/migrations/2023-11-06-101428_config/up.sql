@@ -0,0 +1,11 @@
+-- Your SQL goes here
+create TABLE config (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    volume DOUBLE,
+    log_level TEXT,
+    quiet_start TEXT,
+    quiet_end TEXT,
+    telemetry_interval INTEGER,
+    allowed_commands TEXT,
+    audio_device TEXT
+);
@@ -0,0 +1,10 @@
+-- Your SQL goes here
+create TABLE pending_update (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    update_id INTEGER NOT NULL,
+    version TEXT NOT NULL,
+    previous_version TEXT NOT NULL,
+    status INTEGER NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    message TEXT
+);
@@ -0,0 +1,2 @@
+-- This file should undo anything in `up.sql`
+alter TABLE schedules DROP COLUMN outputs;
\ No newline at end of file
@@ -0,0 +1,2 @@
+-- Your SQL goes here
+alter TABLE schedules ADD COLUMN outputs TEXT NOT NULL DEFAULT '[]';
\ No newline at end of file
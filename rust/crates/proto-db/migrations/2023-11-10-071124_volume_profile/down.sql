@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+alter TABLE schedules DROP COLUMN emergency;
+alter TABLE config DROP COLUMN volume_profile;
\ No newline at end of file
@@ -0,0 +1,3 @@
+-- Your SQL goes here
+alter TABLE schedules ADD COLUMN emergency INTEGER NOT NULL DEFAULT 0;
+alter TABLE config ADD COLUMN volume_profile TEXT;
\ No newline at end of file
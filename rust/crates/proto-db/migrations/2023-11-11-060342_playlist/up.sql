@@ -0,0 +1,12 @@
+-- Your SQL goes here
+create TABLE playlist (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    tracks TEXT NOT NULL,
+    shuffle INTEGER NOT NULL,
+    repeat INTEGER NOT NULL,
+    start_time TEXT,
+    end_time TEXT,
+    volume DOUBLE,
+    duck DOUBLE NOT NULL,
+    position INTEGER NOT NULL DEFAULT 0
+);
@@ -0,0 +1,2 @@
+-- This file should undo anything in `up.sql`
+alter TABLE device DROP COLUMN credential;
\ No newline at end of file
@@ -0,0 +1,2 @@
+-- Your SQL goes here
+alter TABLE device ADD COLUMN credential TEXT;
\ No newline at end of file
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+drop TABLE sync_state;
+alter TABLE schedules DROP COLUMN rev;
\ No newline at end of file
@@ -0,0 +1,6 @@
+-- Your SQL goes here
+alter TABLE schedules ADD COLUMN rev TEXT NOT NULL DEFAULT '';
+create TABLE sync_state (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    version BIGINT NOT NULL
+);
\ No newline at end of file
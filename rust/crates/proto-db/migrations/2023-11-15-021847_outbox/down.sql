@@ -0,0 +1,2 @@
+-- This file should undo anything in `up.sql`
+drop TABLE outbox;
\ No newline at end of file
@@ -47,3 +47,20 @@ pub fn new_proto_database(db_url: &str) -> Result<ProtoDatabase> {
         .map_err(|e| DatabaseError::Other(e.to_string()))?;
     Database::new(db_url)
 }
+
+/// IntegrityCheck.
+/// A row of `PRAGMA integrity_check`.
+#[derive(diesel::QueryableByName)]
+struct IntegrityCheck {
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    integrity_check: String,
+}
+
+/// Run the sqlite integrity check, a healthy database returns `["ok"]`.
+pub fn integrity_check(db: &ProtoDatabase) -> Result<Vec<String>> {
+    use diesel::RunQueryDsl;
+    db.run(|conn| {
+        let rows = diesel::sql_query("PRAGMA integrity_check").load::<IntegrityCheck>(conn)?;
+        Ok(rows.into_iter().map(|r| r.integrity_check).collect())
+    })
+}
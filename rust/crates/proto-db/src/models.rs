@@ -27,6 +27,8 @@ pub struct Device {
     pub uid: String,
     pub description: String,
     pub address: String,
+    /// The credential issued by the server on enrollment.
+    pub credential: Option<String>,
 }
 
 /// Schedule.
@@ -50,6 +52,14 @@ pub struct Schedule {
     pub month: Option<i32>,
     pub year: Option<i32>,
     pub volume: Option<f64>,
+    /// mp3, opus, wav or flac.
+    pub format: String,
+    /// JSON array of the output devices.
+    pub outputs: String,
+    /// 0 = normal, 1 = emergency.
+    pub emergency: i32,
+    /// The revision of the schedule sent by the server.
+    pub rev: String,
 }
 
 /// NewSchedule.
@@ -72,4 +82,168 @@ pub struct NewSchedule {
     pub month: Option<i32>,
     pub year: Option<i32>,
     pub volume: Option<f64>,
+    /// mp3, opus, wav or flac.
+    pub format: String,
+    /// JSON array of the output devices.
+    pub outputs: String,
+    /// 0 = normal, 1 = emergency.
+    pub emergency: i32,
+    /// The revision of the schedule sent by the server.
+    pub rev: String,
+}
+
+/// PlayLog.
+/// The play log type.
+#[derive(Clone, Selectable, Queryable, Identifiable)]
+#[diesel(table_name = play_log)]
+pub struct PlayLog {
+    pub id: i32,
+    pub sid: i32,
+    pub record_url: String,
+    pub started_at: chrono::NaiveDateTime,
+    pub ended_at: Option<chrono::NaiveDateTime>,
+    pub volume: Option<f64>,
+    /// One of the `types::proto::OUTCOME_*` constants.
+    pub outcome: i32,
+    pub message: Option<String>,
+    /// 0 = pending upload, 1 = queued in the outbox.
+    pub sent: i32,
+}
+
+/// NewPlayLog.
+/// The new play log type.
+#[derive(Clone, Insertable)]
+#[diesel(table_name = play_log)]
+pub struct NewPlayLog {
+    pub sid: i32,
+    pub record_url: String,
+    pub started_at: chrono::NaiveDateTime,
+    pub ended_at: Option<chrono::NaiveDateTime>,
+    pub volume: Option<f64>,
+    /// One of the `types::proto::OUTCOME_*` constants.
+    pub outcome: i32,
+    pub message: Option<String>,
+}
+
+/// Config.
+/// The device configuration pushed by the server.
+#[derive(Clone, Selectable, Queryable, Identifiable)]
+#[diesel(table_name = config)]
+pub struct Config {
+    pub id: i32,
+    pub volume: Option<f64>,
+    pub log_level: Option<String>,
+    pub quiet_start: Option<String>,
+    pub quiet_end: Option<String>,
+    pub telemetry_interval: Option<i32>,
+    /// Option<Vec<String>>
+    pub allowed_commands: Option<String>,
+    pub audio_device: Option<String>,
+    /// Option<Vec<VolumeRange>>
+    pub volume_profile: Option<String>,
+}
+
+/// NewConfig.
+/// The new device configuration type.
+#[derive(Clone, Insertable)]
+#[diesel(table_name = config)]
+pub struct NewConfig {
+    pub volume: Option<f64>,
+    pub log_level: Option<String>,
+    pub quiet_start: Option<String>,
+    pub quiet_end: Option<String>,
+    pub telemetry_interval: Option<i32>,
+    /// Option<Vec<String>>
+    pub allowed_commands: Option<String>,
+    pub audio_device: Option<String>,
+    /// Option<Vec<VolumeRange>>
+    pub volume_profile: Option<String>,
+}
+
+/// PendingUpdate.
+/// The client update waiting to be confirmed.
+#[derive(Clone, Selectable, Queryable, Identifiable)]
+#[diesel(table_name = pending_update)]
+pub struct PendingUpdate {
+    pub id: i32,
+    pub update_id: i32,
+    pub version: String,
+    pub previous_version: String,
+    /// 4 = installed, 6 = failed, 7 = rolled back.
+    pub status: i32,
+    /// Number of times the new version has been started.
+    pub attempts: i32,
+    pub message: Option<String>,
+}
+
+/// NewPendingUpdate.
+/// The new pending update type.
+#[derive(Clone, Insertable)]
+#[diesel(table_name = pending_update)]
+pub struct NewPendingUpdate {
+    pub update_id: i32,
+    pub version: String,
+    pub previous_version: String,
+    pub status: i32,
+}
+
+/// Playlist.
+/// The background playlist pushed by the server.
+#[derive(Clone, Selectable, Queryable, Identifiable)]
+#[diesel(table_name = playlist)]
+pub struct Playlist {
+    pub id: i32,
+    /// Vec<PlaylistTrack>
+    pub tracks: String,
+    /// 0 = in order, 1 = shuffled.
+    pub shuffle: i32,
+    /// 0 = play once, 1 = repeat.
+    pub repeat: i32,
+    pub start_time: Option<String>,
+    pub end_time: Option<String>,
+    pub volume: Option<f64>,
+    pub duck: f64,
+    /// Index of the track to resume from.
+    pub position: i32,
+}
+
+/// NewPlaylist.
+/// The new background playlist type.
+#[derive(Clone, Insertable)]
+#[diesel(table_name = playlist)]
+pub struct NewPlaylist {
+    pub tracks: String,
+    pub shuffle: i32,
+    pub repeat: i32,
+    pub start_time: Option<String>,
+    pub end_time: Option<String>,
+    pub volume: Option<f64>,
+    pub duck: f64,
+}
+
+/// OutboxEntry.
+/// An event waiting to be delivered to the server.
+#[derive(Clone, Selectable, Queryable, Identifiable)]
+#[diesel(table_name = outbox)]
+pub struct OutboxEntry {
+    pub id: i32,
+    /// `telemetry`, `play_log` or `command`.
+    pub kind: String,
+    /// The json encoded event.
+    pub payload: String,
+    /// The length of the payload in bytes.
+    pub size: i32,
+    /// When the event happened, in UTC.
+    pub created_at: chrono::NaiveDateTime,
+}
+
+/// NewOutboxEntry.
+/// The new outbox entry type.
+#[derive(Clone, Insertable)]
+#[diesel(table_name = outbox)]
+pub struct NewOutboxEntry {
+    pub kind: String,
+    pub payload: String,
+    pub size: i32,
+    pub created_at: chrono::NaiveDateTime,
 }
@@ -0,0 +1,60 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use crate::{
+    models::{Config, NewConfig},
+    schema::*,
+    ProtoDatabase,
+};
+use database::{DatabaseRepository, Result};
+use diesel::prelude::*;
+
+/// ConfigRepo.
+/// Repository for `config` table.
+#[derive(Clone)]
+pub struct ConfigRepo {
+    db: ProtoDatabase,
+}
+
+impl ConfigRepo {
+    /// Get the stored config.
+    pub fn get(&self) -> Result<Option<Config>> {
+        self.db.run(|conn| {
+            config::table
+                .first::<Config>(conn)
+                .optional()
+                .map_err(Into::into)
+        })
+    }
+
+    /// Replace the stored config.
+    pub fn set(&self, new: NewConfig) -> Result<()> {
+        self.db.run_transaction(|conn| {
+            diesel::delete(config::table).execute(conn)?;
+            diesel::insert_into(config::table)
+                .values(&new)
+                .execute(conn)?;
+            Ok(())
+        })
+    }
+}
+
+impl DatabaseRepository<SqliteConnection> for ConfigRepo {
+    fn new(db: database::Database<SqliteConnection>) -> Self {
+        Self { db }
+    }
+}
@@ -52,6 +52,16 @@ impl DeviceRepo {
         })
     }
 
+    /// Set the credential issued by the server.
+    pub fn set_credential(&self, credential: &str) -> Result<()> {
+        self.db.run(|conn| {
+            diesel::update(device::table)
+                .set(device::credential.eq(credential))
+                .execute(conn)?;
+            Ok(())
+        })
+    }
+
     /// Get device.
     pub fn get(&self) -> Result<Device> {
         self.db.run(|conn| {
@@ -19,3 +19,13 @@ mod device;
 pub use device::*;
 mod schedule;
 pub use schedule::*;
+mod play_log;
+pub use play_log::*;
+mod config;
+pub use config::*;
+mod pending_update;
+pub use pending_update::*;
+mod playlist;
+pub use playlist::*;
+mod outbox;
+pub use outbox::*;
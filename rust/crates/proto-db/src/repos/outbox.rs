@@ -0,0 +1,99 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use crate::{
+    models::{NewOutboxEntry, OutboxEntry},
+    schema::*,
+    ProtoDatabase,
+};
+use database::{DatabaseRepository, Result};
+use diesel::{dsl::count_star, prelude::*};
+
+/// The kind of outbox entries evicted first when the outbox is full.
+const EVICT_FIRST: &str = "telemetry";
+
+/// OutboxRepo.
+/// Repository for `outbox` table.
+#[derive(Clone)]
+pub struct OutboxRepo {
+    db: ProtoDatabase,
+}
+
+impl OutboxRepo {
+    /// Queue an entry, then evict the oldest entries beyond `max_entries` or `max_bytes`.
+    /// Telemetry is evicted before play logs and command results.
+    /// Returns the number of evicted entries.
+    pub fn enqueue(&self, new: NewOutboxEntry, max_entries: i64, max_bytes: i64) -> Result<usize> {
+        self.db.run_transaction(|conn| {
+            diesel::insert_into(outbox::table)
+                .values(&new)
+                .execute(conn)?;
+            let (count, bytes) = outbox::table
+                .select((count_star(), diesel::dsl::sum(outbox::size)))
+                .first::<(i64, Option<i64>)>(conn)?;
+            let bytes = bytes.unwrap_or(0);
+            if count <= max_entries && bytes <= max_bytes {
+                return Ok(0);
+            }
+            let entries = outbox::table
+                .select((outbox::id, outbox::kind, outbox::size))
+                .order(outbox::id.asc())
+                .load::<(i32, String, i32)>(conn)?;
+            let (first, rest): (Vec<_>, Vec<_>) = entries
+                .into_iter()
+                .partition(|(_, kind, _)| kind == EVICT_FIRST);
+            let mut count = count;
+            let mut bytes = bytes;
+            let mut evicted = vec![];
+            for (id, _, size) in first.into_iter().chain(rest) {
+                if count <= max_entries && bytes <= max_bytes {
+                    break;
+                }
+                count -= 1;
+                bytes -= size as i64;
+                evicted.push(id);
+            }
+            diesel::delete(outbox::table.filter(outbox::id.eq_any(&evicted))).execute(conn)?;
+            Ok(evicted.len())
+        })
+    }
+
+    /// Get the oldest entries.
+    pub fn get_pending(&self, limit: i64) -> Result<Vec<OutboxEntry>> {
+        self.db.run(|conn| {
+            outbox::table
+                .order(outbox::id.asc())
+                .limit(limit)
+                .load::<OutboxEntry>(conn)
+                .map_err(Into::into)
+        })
+    }
+
+    /// Remove the entries acknowledged by the server.
+    pub fn ack(&self, ids: Vec<i32>) -> Result<()> {
+        self.db.run(|conn| {
+            diesel::delete(outbox::table.filter(outbox::id.eq_any(ids))).execute(conn)?;
+            Ok(())
+        })
+    }
+}
+
+impl DatabaseRepository<SqliteConnection> for OutboxRepo {
+    fn new(db: database::Database<SqliteConnection>) -> Self {
+        Self { db }
+    }
+}
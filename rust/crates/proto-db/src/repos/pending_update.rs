@@ -0,0 +1,81 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use crate::{
+    models::{NewPendingUpdate, PendingUpdate},
+    schema::*,
+    ProtoDatabase,
+};
+use database::{DatabaseRepository, Result};
+use diesel::prelude::*;
+
+/// PendingUpdateRepo.
+/// Repository for `pending_update` table.
+#[derive(Clone)]
+pub struct PendingUpdateRepo {
+    db: ProtoDatabase,
+}
+
+impl PendingUpdateRepo {
+    /// Get the pending update.
+    pub fn get(&self) -> Result<Option<PendingUpdate>> {
+        self.db.run(|conn| {
+            pending_update::table
+                .first::<PendingUpdate>(conn)
+                .optional()
+                .map_err(Into::into)
+        })
+    }
+
+    /// Replace the pending update.
+    pub fn set(&self, new: NewPendingUpdate) -> Result<()> {
+        self.db.run_transaction(|conn| {
+            diesel::delete(pending_update::table).execute(conn)?;
+            diesel::insert_into(pending_update::table)
+                .values(&new)
+                .execute(conn)?;
+            Ok(())
+        })
+    }
+
+    /// Set the status of the pending update.
+    pub fn set_status(&self, status: i32, message: Option<String>) -> Result<()> {
+        self.db.run(|conn| {
+            diesel::update(pending_update::table)
+                .set((
+                    pending_update::status.eq(status),
+                    pending_update::message.eq(message),
+                ))
+                .execute(conn)?;
+            Ok(())
+        })
+    }
+
+    /// Remove the pending update.
+    pub fn clear(&self) -> Result<()> {
+        self.db.run(|conn| {
+            diesel::delete(pending_update::table).execute(conn)?;
+            Ok(())
+        })
+    }
+}
+
+impl DatabaseRepository<SqliteConnection> for PendingUpdateRepo {
+    fn new(db: database::Database<SqliteConnection>) -> Self {
+        Self { db }
+    }
+}
@@ -0,0 +1,96 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use crate::{
+    models::{NewPlayLog, PlayLog},
+    schema::*,
+    ProtoDatabase,
+};
+use database::{DatabaseRepository, Result};
+use diesel::prelude::*;
+
+/// PlayLogRepo.
+/// Repository for `play_log` table.
+#[derive(Clone)]
+pub struct PlayLogRepo {
+    db: ProtoDatabase,
+}
+
+impl PlayLogRepo {
+    /// Create new play log, returning its id.
+    pub fn create(&self, log: NewPlayLog) -> Result<i32> {
+        self.db.run_transaction(|conn| {
+            diesel::insert_into(play_log::table)
+                .values(&log)
+                .execute(conn)?;
+            play_log::table
+                .select(play_log::id)
+                .order(play_log::id.desc())
+                .first::<i32>(conn)
+                .map_err(Into::into)
+        })
+    }
+
+    /// Set the end time of a play log.
+    pub fn finish(&self, id: i32, ended_at: chrono::NaiveDateTime) -> Result<()> {
+        self.db.run(|conn| {
+            diesel::update(play_log::table.filter(play_log::id.eq(id)))
+                .set(play_log::ended_at.eq(Some(ended_at)))
+                .execute(conn)?;
+            Ok(())
+        })
+    }
+
+    /// Get a play log.
+    pub fn get(&self, id: i32) -> Result<Option<PlayLog>> {
+        self.db.run(|conn| {
+            play_log::table
+                .filter(play_log::id.eq(id))
+                .first::<PlayLog>(conn)
+                .optional()
+                .map_err(Into::into)
+        })
+    }
+
+    /// Get the oldest unsent play logs.
+    pub fn get_unsent(&self, limit: i64) -> Result<Vec<PlayLog>> {
+        self.db.run(|conn| {
+            play_log::table
+                .filter(play_log::sent.eq(0))
+                .order(play_log::id.asc())
+                .limit(limit)
+                .load::<PlayLog>(conn)
+                .map_err(Into::into)
+        })
+    }
+
+    /// Mark play logs as queued in the outbox.
+    pub fn mark_sent(&self, ids: Vec<i32>) -> Result<()> {
+        self.db.run(|conn| {
+            diesel::update(play_log::table.filter(play_log::id.eq_any(ids)))
+                .set(play_log::sent.eq(1))
+                .execute(conn)?;
+            Ok(())
+        })
+    }
+}
+
+impl DatabaseRepository<SqliteConnection> for PlayLogRepo {
+    fn new(db: database::Database<SqliteConnection>) -> Self {
+        Self { db }
+    }
+}
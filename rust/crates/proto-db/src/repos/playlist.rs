@@ -0,0 +1,72 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use crate::{
+    models::{NewPlaylist, Playlist},
+    schema::*,
+    ProtoDatabase,
+};
+use database::{DatabaseRepository, Result};
+use diesel::prelude::*;
+
+/// PlaylistRepo.
+/// Repository for `playlist` table.
+#[derive(Clone)]
+pub struct PlaylistRepo {
+    db: ProtoDatabase,
+}
+
+impl PlaylistRepo {
+    /// Get the stored playlist.
+    pub fn get(&self) -> Result<Option<Playlist>> {
+        self.db.run(|conn| {
+            playlist::table
+                .first::<Playlist>(conn)
+                .optional()
+                .map_err(Into::into)
+        })
+    }
+
+    /// Replace the stored playlist, `None` removes it.
+    pub fn set(&self, new: Option<NewPlaylist>) -> Result<()> {
+        self.db.run_transaction(|conn| {
+            diesel::delete(playlist::table).execute(conn)?;
+            if let Some(new) = new {
+                diesel::insert_into(playlist::table)
+                    .values(&new)
+                    .execute(conn)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Set the index of the track to resume from.
+    pub fn set_position(&self, position: i32) -> Result<()> {
+        self.db.run(|conn| {
+            diesel::update(playlist::table)
+                .set(playlist::position.eq(position))
+                .execute(conn)?;
+            Ok(())
+        })
+    }
+}
+
+impl DatabaseRepository<SqliteConnection> for PlaylistRepo {
+    fn new(db: database::Database<SqliteConnection>) -> Self {
+        Self { db }
+    }
+}
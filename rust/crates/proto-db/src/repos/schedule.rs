@@ -47,6 +47,61 @@ impl ScheduleRepo {
         })
     }
 
+    /// Get the `(sid, rev)` pairs of the schedules.
+    pub fn get_revs(&self) -> Result<Vec<(i32, String)>> {
+        self.db.run(|conn| {
+            schedules::table
+                .select((schedules::sid, schedules::rev))
+                .load::<(i32, String)>(conn)
+                .map_err(Into::into)
+        })
+    }
+
+    /// Get the last applied sync version, `0` when never synced.
+    pub fn get_version(&self) -> Result<i64> {
+        self.db.run(|conn| {
+            let version = sync_state::table
+                .select(sync_state::version)
+                .first::<i64>(conn)
+                .optional()?;
+            Ok(version.unwrap_or(0))
+        })
+    }
+
+    /// Apply a versioned sync, `full` drops every schedule first.
+    /// Added schedules replace the schedules with the same sid.
+    pub fn apply(
+        &self,
+        full: bool,
+        add: Vec<NewSchedule>,
+        remove: Vec<i32>,
+        version: i64,
+    ) -> Result<()> {
+        self.db.run_transaction(|conn| {
+            if full {
+                diesel::delete(schedules::table).execute(conn)?;
+            } else {
+                let sids = remove
+                    .into_iter()
+                    .chain(add.iter().map(|s| s.sid))
+                    .collect::<Vec<i32>>();
+                diesel::delete(schedules::table)
+                    .filter(schedules::sid.eq_any(sids))
+                    .execute(conn)?;
+            }
+            if !add.is_empty() {
+                diesel::insert_into(schedules::table)
+                    .values(&add)
+                    .execute(conn)?;
+            }
+            diesel::delete(sync_state::table).execute(conn)?;
+            diesel::insert_into(sync_state::table)
+                .values(sync_state::version.eq(version))
+                .execute(conn)?;
+            Ok(())
+        })
+    }
+
     /// Create new schedule.
     pub fn create(&self, schedule: NewSchedule) -> Result<()> {
         self.db.run(|conn| {
@@ -1,11 +1,76 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    config (id) {
+        id -> Integer,
+        volume -> Nullable<Double>,
+        log_level -> Nullable<Text>,
+        quiet_start -> Nullable<Text>,
+        quiet_end -> Nullable<Text>,
+        telemetry_interval -> Nullable<Integer>,
+        allowed_commands -> Nullable<Text>,
+        audio_device -> Nullable<Text>,
+        volume_profile -> Nullable<Text>,
+    }
+}
+
 diesel::table! {
     device (id) {
         id -> Integer,
         uid -> Text,
         description -> Text,
         address -> Text,
+        credential -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    outbox (id) {
+        id -> Integer,
+        kind -> Text,
+        payload -> Text,
+        size -> Integer,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    pending_update (id) {
+        id -> Integer,
+        update_id -> Integer,
+        version -> Text,
+        previous_version -> Text,
+        status -> Integer,
+        attempts -> Integer,
+        message -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    play_log (id) {
+        id -> Integer,
+        sid -> Integer,
+        record_url -> Text,
+        started_at -> Timestamp,
+        ended_at -> Nullable<Timestamp>,
+        volume -> Nullable<Double>,
+        outcome -> Integer,
+        message -> Nullable<Text>,
+        sent -> Integer,
+    }
+}
+
+diesel::table! {
+    playlist (id) {
+        id -> Integer,
+        tracks -> Text,
+        shuffle -> Integer,
+        repeat -> Integer,
+        start_time -> Nullable<Text>,
+        end_time -> Nullable<Text>,
+        volume -> Nullable<Double>,
+        duck -> Double,
+        position -> Integer,
     }
 }
 
@@ -23,10 +88,27 @@ diesel::table! {
         month -> Nullable<Integer>,
         year -> Nullable<Integer>,
         volume -> Nullable<Double>,
+        format -> Text,
+        outputs -> Text,
+        emergency -> Integer,
+        rev -> Text,
+    }
+}
+
+diesel::table! {
+    sync_state (id) {
+        id -> Integer,
+        version -> BigInt,
     }
 }
 
 diesel::allow_tables_to_appear_in_same_query!(
+    config,
     device,
+    outbox,
+    pending_update,
+    play_log,
+    playlist,
     schedules,
+    sync_state,
 );
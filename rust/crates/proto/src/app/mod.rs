@@ -145,11 +145,12 @@ impl Service {
                     Err(e) => match e {
                         crate::error::Error::Connection(e) => {
                             log::error!("{}", e);
+                            // the end service receives the reason as a json string.
                             match services.handle(
                                 "end".to_owned(),
                                 Session::new(
                                     state.clone(),
-                                    String::new(),
+                                    serde_json::to_string(&e).unwrap_or_default(),
                                     stream.clone(),
                                     streams.clone(),
                                 ),
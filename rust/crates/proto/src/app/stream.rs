@@ -190,6 +190,7 @@ pub struct Stream {
     id: String,
     reader: Arc<Box<dyn ReaderExt>>,
     writer: Arc<Box<dyn WriterExt>>,
+    remote_addr: Option<String>,
 }
 
 impl std::fmt::Debug for Stream {
@@ -204,6 +205,7 @@ impl Clone for Stream {
             id: self.id.clone(),
             reader: self.reader.clone(),
             writer: self.writer.clone(),
+            remote_addr: self.remote_addr.clone(),
         }
     }
 }
@@ -237,9 +239,16 @@ impl Stream {
             id: hex::encode(id),
             reader: Arc::new(Box::new(reader)),
             writer: Arc::new(Box::new(writer)),
+            remote_addr: None,
         }
     }
 
+    /// Set the address of the remote peer.
+    pub(crate) fn with_remote_addr(mut self, addr: Option<String>) -> Self {
+        self.remote_addr = addr;
+        self
+    }
+
     /// Read a message.
     pub(crate) async fn read(&self) -> Result<StreamMessage> {
         let res = self.reader.read().await?;
@@ -264,6 +273,11 @@ impl Stream {
     pub fn id(&self) -> &str {
         &self.id
     }
+
+    /// Get the address of the remote peer, only known on the server.
+    pub fn remote_addr(&self) -> Option<&str> {
+        self.remote_addr.as_deref()
+    }
 }
 
 /// Collection of streams.
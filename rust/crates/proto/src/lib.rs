@@ -23,13 +23,37 @@ If MyRTS violates any of these terms, their license to use the software will aut
 use app::Stream;
 pub use proto_macro::service;
 use std::{collections::HashMap, sync::Arc};
-use tokio::sync::RwLock;
+use tokio::sync::{mpsc, oneshot, RwLock};
 
 pub mod app;
 pub mod client;
 pub mod error;
 pub mod server;
 
+/// PlaybackRequest.
+/// A request to the streaming server to play an audio file on the avs.
+#[derive(Debug)]
+pub enum PlaybackRequest {
+    /// Play the file at `path` on the avs identified by their unique ids.
+    /// Replies with the id of the broadcast and the busy avs along with their status,
+    /// `preempted`, `queued` or `rejected`.
+    /// `conflict` is the policy for the busy avs: `preempt`, `queue` or `reject`.
+    Start {
+        user_id: i32,
+        path: String,
+        target: Vec<String>,
+        emergency: bool,
+        priority: u8,
+        conflict: String,
+        reply: oneshot::Sender<Result<(i32, Vec<(String, String)>), String>>,
+    },
+    /// Stop the playback of the broadcast, replies whether it was playing.
+    Stop {
+        broadcast_id: i32,
+        reply: oneshot::Sender<bool>,
+    },
+}
+
 /// WsState.
 /// The state of the websocket.
 #[derive(Clone, Debug)]
@@ -39,6 +63,8 @@ pub struct WsState {
     avs_map: Arc<RwLock<HashMap<String, String>>>,
     user_map: Arc<RwLock<HashMap<String, i32>>>,
     streaming: Arc<RwLock<HashMap<i32, Vec<String>>>>,
+    playback: Arc<RwLock<Option<mpsc::UnboundedSender<PlaybackRequest>>>>,
+    sessions: Arc<RwLock<HashMap<String, i32>>>,
 }
 
 impl Default for WsState {
@@ -49,6 +75,8 @@ impl Default for WsState {
             avs_map: Arc::new(RwLock::new(HashMap::new())),
             user_map: Arc::new(RwLock::new(HashMap::new())),
             streaming: Arc::new(RwLock::new(HashMap::new())),
+            playback: Arc::new(RwLock::new(None)),
+            sessions: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 }
@@ -117,6 +145,16 @@ impl WsState {
         }
     }
 
+    /// Set the connection session opened for the stream of an avs.
+    pub async fn set_session(&self, stream_id: String, session_id: i32) {
+        self.sessions.write().await.insert(stream_id, session_id);
+    }
+
+    /// Take the connection session opened for the stream of an avs.
+    pub async fn take_session(&self, stream_id: &str) -> Option<i32> {
+        self.sessions.write().await.remove(stream_id)
+    }
+
     /// Remove user.
     pub async fn remove_user(&self, stream_id: String) {
         if let Some(user_id) = self.user_map.write().await.remove(&stream_id) {
@@ -147,6 +185,53 @@ impl WsState {
         res
     }
 
+    /// Set the handler of the playback requests.
+    pub async fn set_playback(&self, tx: mpsc::UnboundedSender<PlaybackRequest>) {
+        *self.playback.write().await = Some(tx);
+    }
+
+    /// Start playing the file at `path` on the target avs.
+    /// Returns the id of the broadcast and the busy avs along with their status.
+    pub async fn start_playback(
+        &self,
+        user_id: i32,
+        path: String,
+        target: Vec<String>,
+        emergency: bool,
+        priority: u8,
+        conflict: String,
+    ) -> Result<(i32, Vec<(String, String)>), String> {
+        let (reply, rx) = oneshot::channel();
+        let req = PlaybackRequest::Start {
+            user_id,
+            path,
+            target,
+            emergency,
+            priority,
+            conflict,
+            reply,
+        };
+        match self.playback.read().await.as_ref() {
+            Some(tx) if tx.send(req).is_ok() => {}
+            _ => return Err("playback is not available".to_owned()),
+        }
+        rx.await
+            .unwrap_or_else(|_| Err("playback is not available".to_owned()))
+    }
+
+    /// Stop the playback of the broadcast, returns whether it was playing.
+    pub async fn stop_playback(&self, broadcast_id: i32) -> bool {
+        let (reply, rx) = oneshot::channel();
+        let req = PlaybackRequest::Stop {
+            broadcast_id,
+            reply,
+        };
+        match self.playback.read().await.as_ref() {
+            Some(tx) if tx.send(req).is_ok() => rx.await.unwrap_or(false),
+            _ => false,
+        }
+    }
+
     /// Get all on going streaming.
     pub async fn get_ongoing(&self) -> HashMap<i32, Vec<String>> {
         let lock = self.streaming.read().await;
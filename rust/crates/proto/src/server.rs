@@ -19,14 +19,18 @@ use crate::{
     app::{App, Stream},
     error::{Error, Result},
 };
-use std::sync::Arc;
+use std::{net::IpAddr, sync::Arc};
 use tokio::{net::TcpListener, task::JoinHandle};
-use tokio_tungstenite::accept_async;
+use tokio_tungstenite::{
+    accept_hdr_async,
+    tungstenite::handshake::server::{ErrorResponse, Request, Response},
+};
 
 /// MyRTS server builder.
 pub struct Server {
     app: App,
     port: u16,
+    trusted_proxies: Vec<IpAddr>,
     handle: Vec<JoinHandle<()>>,
 }
 
@@ -36,20 +40,39 @@ impl Server {
         Self {
             app,
             port,
+            trusted_proxies: vec![],
             handle: vec![],
         }
     }
 
+    /// Set the reverse proxies whose forwarded client address is trusted.
+    pub fn with_trusted_proxies(mut self, proxies: Vec<IpAddr>) -> Self {
+        self.trusted_proxies = proxies;
+        self
+    }
+
     /// Run the application.
     pub async fn run(mut self) -> Result<()> {
         let listener = TcpListener::bind(format!("0.0.0.0:{}", self.port))
             .await
             .map_err(|e| Error::Connection(e.to_string()))?;
         let service = self.app.build();
-        while let Ok((stream, _)) = listener.accept().await {
-            match accept_async(stream).await {
+        while let Ok((stream, addr)) = listener.accept().await {
+            // behind a reverse proxy the peer is the proxy, prefer the forwarded client address.
+            let mut remote_addr = addr.ip();
+            let trusted_proxies = &self.trusted_proxies;
+            // the signature is imposed by tungstenite.
+            #[allow(clippy::result_large_err)]
+            let callback =
+                |req: &Request, res: Response| -> std::result::Result<Response, ErrorResponse> {
+                    remote_addr = client_addr(req, addr.ip(), trusted_proxies);
+                    Ok(res)
+                };
+            match accept_hdr_async(stream, callback).await {
                 Ok(stream) => {
-                    let stream = Arc::new(Stream::new(stream));
+                    let stream = Arc::new(
+                        Stream::new(stream).with_remote_addr(Some(remote_addr.to_string())),
+                    );
                     self.handle.push(service.handle(stream).await);
                 }
                 Err(e) => {
@@ -61,3 +84,31 @@ impl Server {
         Ok(())
     }
 }
+
+/// Resolve the address of the client.
+/// The forwarded headers are only honoured from a trusted proxy, the nearest
+/// untrusted hop of `X-Forwarded-For` is the client.
+fn client_addr(req: &Request, peer: IpAddr, trusted_proxies: &[IpAddr]) -> IpAddr {
+    if !trusted_proxies.contains(&peer) {
+        return peer;
+    }
+    let header = |name: &str| req.headers().get(name).and_then(|v| v.to_str().ok());
+    if let Some(forwarded) = header("x-forwarded-for") {
+        let mut client = peer;
+        for hop in forwarded.rsplit(',') {
+            match hop.trim().parse::<IpAddr>() {
+                Ok(hop) => {
+                    client = hop;
+                    if !trusted_proxies.contains(&hop) {
+                        break;
+                    }
+                }
+                Err(_) => break,
+            }
+        }
+        return client;
+    }
+    header("x-real-ip")
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(peer)
+}
@@ -15,10 +15,18 @@ MyRTS agrees to credit you as the developer of the software in all promotional m
 If MyRTS violates any of these terms, their license to use the software will automatically terminate.
 */
 
+use audio::recorder::OggWriter;
 use proto::app::Stream;
-use std::{future::Future, pin::Pin, sync::Arc};
-use tokio::sync::RwLock;
-use types::proto::{Answer, Ices, Offer};
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+use tokio::sync::{Mutex, RwLock};
+use types::proto::{Answer, Ices, Offer, Turn};
 use webrtc::{
     api::{
         interceptor_registry::register_default_interceptors,
@@ -34,13 +42,13 @@ use webrtc::{
         configuration::RTCConfiguration, peer_connection_state::RTCPeerConnectionState,
         sdp::session_description::RTCSessionDescription, RTCPeerConnection,
     },
+    rtp::{header::Header, packet::Packet},
     rtp_transceiver::rtp_codec::{RTCRtpCodecCapability, RTCRtpCodecParameters, RTPCodecType},
-    track::{
-        track_local::{track_local_static_rtp::TrackLocalStaticRTP, TrackLocalWriter},
-        track_remote::TrackRemote,
-    },
+    track::{track_local::TrackLocalWriter, track_remote::TrackRemote},
 };
 
+pub use webrtc::track::track_local::track_local_static_rtp::TrackLocalStaticRTP;
+
 /// RTCError.
 #[derive(Debug, thiserror::Error)]
 pub enum RTCError {
@@ -52,17 +60,57 @@ pub enum RTCError {
 
 type Result<T> = std::result::Result<T, RTCError>;
 
+/// Callback of the connection state of the forwarder.
+type StateHandler = Arc<dyn Fn() -> Pin<Box<dyn Future<Output = ()> + Send>> + Send + Sync>;
+
+/// The registered connection state callbacks of the forwarder.
+#[derive(Default)]
+struct StateHandlers {
+    connected: Option<StateHandler>,
+    failed: Option<StateHandler>,
+}
+
 fn capability() -> RTCRtpCodecCapability {
     RTCRtpCodecCapability {
         mime_type: MIME_TYPE_OPUS.to_owned(),
         clock_rate: 48000,
         channels: 2,
-        sdp_fmtp_line: "".to_owned(),
+        // ask the sender for in-band fec, the consumer recovers lost packets from it.
+        sdp_fmtp_line: "minptime=10;useinbandfec=1".to_owned(),
         rtcp_feedback: vec![],
     }
 }
 
-async fn new_rtc(receive_audio: bool) -> Result<RTCPeerConnection> {
+/// Convert the turn data into ice servers.
+/// Stun urls don't take credentials, `url` is used when `urls` is empty.
+fn ice_servers(turn: &Turn) -> Vec<RTCIceServer> {
+    let urls = if turn.urls.is_empty() && !turn.url.is_empty() {
+        vec![turn.url.clone()]
+    } else {
+        turn.urls.clone()
+    };
+    let (stun, turn_urls): (Vec<String>, Vec<String>) = urls
+        .into_iter()
+        .partition(|u| u.starts_with("stun:") || u.starts_with("stuns:"));
+    let mut servers = vec![];
+    if !stun.is_empty() {
+        servers.push(RTCIceServer {
+            urls: stun,
+            ..Default::default()
+        });
+    }
+    if !turn_urls.is_empty() {
+        servers.push(RTCIceServer {
+            urls: turn_urls,
+            username: turn.username.clone(),
+            credential: turn.password.clone(),
+            credential_type: RTCIceCredentialType::Password,
+        });
+    }
+    servers
+}
+
+async fn new_rtc(receive_audio: bool, turn: &Turn) -> Result<RTCPeerConnection> {
     let mut m = MediaEngine::default();
     m.register_codec(
         RTCRtpCodecParameters {
@@ -80,12 +128,7 @@ async fn new_rtc(receive_audio: bool) -> Result<RTCPeerConnection> {
         .build();
     let peer = api
         .new_peer_connection(RTCConfiguration {
-            ice_servers: vec![RTCIceServer {
-                urls: vec!["turn:api.brandio.id:3478".to_owned()],
-                username: "brandio".to_owned(),
-                credential: "brandio".to_owned(),
-                credential_type: RTCIceCredentialType::Password,
-            }],
+            ice_servers: ice_servers(turn),
             ..Default::default()
         })
         .await?;
@@ -103,12 +146,13 @@ pub struct RTCProvider {
     stream: Stream,
     local_track: Arc<TrackLocalStaticRTP>,
     peer: Arc<RTCPeerConnection>,
+    recorder: Arc<Mutex<Option<OggWriter>>>,
 }
 
 impl RTCProvider {
     /// Create new RTCProvider.
-    pub async fn new(stream: Stream) -> Result<Self> {
-        let peer = Arc::new(new_rtc(true).await?);
+    pub async fn new(stream: Stream, turn: &Turn) -> Result<Self> {
+        let peer = Arc::new(new_rtc(true, turn).await?);
         let ices: Arc<RwLock<Vec<RTCIceCandidateInit>>> = Arc::new(RwLock::new(vec![]));
         let ices_clone = ices.clone();
         peer.on_ice_candidate(Box::new(move |c| {
@@ -141,16 +185,28 @@ impl RTCProvider {
             "audio".to_owned(),
             "webrtc-rs".to_owned(),
         ));
+        let recorder: Arc<Mutex<Option<OggWriter>>> = Arc::default();
         let peer_clone = peer.clone();
         let local_track_clone = local_track.clone();
+        let recorder_clone = recorder.clone();
         peer.on_track(Box::new(move |track, _, _| {
             let local_track = local_track_clone.clone();
             let peer = peer_clone.clone();
+            let recorder = recorder_clone.clone();
 
             Box::pin(async move {
                 loop {
                     if let Ok((p, _)) = track.read_rtp().await {
                         let _ = local_track.write_rtp(&p).await;
+                        if let Some(writer) = recorder.lock().await.as_mut() {
+                            let header = &p.header;
+                            let payload = &p.payload;
+                            if let Err(e) =
+                                writer.write(header.sequence_number, header.timestamp, payload)
+                            {
+                                log::error!("failed to record packet: {:?}", e);
+                            }
+                        }
                     } else {
                         if peer.connection_state() != RTCPeerConnectionState::Connected {
                             log::debug!("peer connection state: {:?}", peer.connection_state());
@@ -165,9 +221,20 @@ impl RTCProvider {
             stream,
             local_track,
             peer,
+            recorder,
         })
     }
 
+    /// Start recording the incoming stream with the writer.
+    pub async fn start_recording(&self, writer: OggWriter) {
+        *self.recorder.lock().await = Some(writer);
+    }
+
+    /// Stop recording, returns the writer to finish the file.
+    pub async fn stop_recording(&self) -> Option<OggWriter> {
+        self.recorder.lock().await.take()
+    }
+
     /// Add offer.
     pub async fn add_offer(&self, offer: String) -> Result<()> {
         let offer: RTCSessionDescription = serde_json::from_str(&offer)?;
@@ -205,6 +272,11 @@ impl RTCProvider {
         self.local_track.clone()
     }
 
+    /// Get the stream of the user.
+    pub fn stream(&self) -> Stream {
+        self.stream.clone()
+    }
+
     /// Disconnect.
     pub async fn disconnect(&self) {
         if self.peer.connection_state() == RTCPeerConnectionState::Connected {
@@ -213,18 +285,110 @@ impl RTCProvider {
     }
 }
 
+/// RTCSource.
+/// This kind of rtc used on the server for playing server provided opus packets to the consumer.
+#[derive(Clone)]
+pub struct RTCSource {
+    local_track: Arc<TrackLocalStaticRTP>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl RTCSource {
+    /// Create new RTCSource.
+    pub fn new() -> Self {
+        Self {
+            local_track: Arc::new(TrackLocalStaticRTP::new(
+                capability(),
+                "audio".to_owned(),
+                "webrtc-rs".to_owned(),
+            )),
+            stopped: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Get local track.
+    pub fn local_track(&self) -> Arc<TrackLocalStaticRTP> {
+        self.local_track.clone()
+    }
+
+    /// Play the opus packets in real time, each packet lasts `frame_samples` at 48kHz.
+    /// Returns false when stopped before the end.
+    pub async fn play(&self, packets: Vec<Vec<u8>>, frame_samples: u32) -> bool {
+        let mut tick = tokio::time::interval(std::time::Duration::from_micros(
+            frame_samples as u64 * 1_000_000 / 48000,
+        ));
+        for (i, payload) in packets.into_iter().enumerate() {
+            tick.tick().await;
+            if self.stopped.load(Ordering::Relaxed) {
+                return false;
+            }
+            let packet = Packet {
+                header: Header {
+                    version: 2,
+                    marker: i == 0,
+                    payload_type: 111,
+                    sequence_number: i as u16,
+                    timestamp: (i as u32).wrapping_mul(frame_samples),
+                    ..Default::default()
+                },
+                payload: payload.into(),
+            };
+            let _ = self.local_track.write_rtp(&packet).await;
+        }
+        !self.stopped.load(Ordering::Relaxed)
+    }
+
+    /// Stop playing.
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Default for RTCSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// RTCForwader.
 /// This kind of rtc used on the server for handling user provided stream to the consumer.
 #[derive(Clone)]
 pub struct RTCForwader {
     stream: Stream,
     peer: Arc<RTCPeerConnection>,
+    outputs: Vec<String>,
+    emergency: bool,
+    turn: Turn,
+    handlers: Arc<std::sync::RwLock<StateHandlers>>,
+    prefix: &'static str,
 }
 
 impl RTCForwader {
     /// Create new RTCForwader.
-    pub async fn new(stream: Stream, track: Arc<TrackLocalStaticRTP>) -> Result<Self> {
-        let peer = Arc::new(new_rtc(false).await?);
+    /// The turn data is also sent to the consumer along with the offer.
+    pub async fn new(stream: Stream, track: Arc<TrackLocalStaticRTP>, turn: Turn) -> Result<Self> {
+        Self::with_prefix(stream, track, turn, "").await
+    }
+
+    /// Create new RTCForwader for a listen-only user.
+    /// The signaling events are prefixed with `listen:` to keep them apart from the
+    /// events of the stream of the user.
+    pub async fn listener(
+        stream: Stream,
+        track: Arc<TrackLocalStaticRTP>,
+        turn: Turn,
+    ) -> Result<Self> {
+        Self::with_prefix(stream, track, turn, "listen:").await
+    }
+
+    /// Create new RTCForwader with the prefix of the signaling events.
+    async fn with_prefix(
+        stream: Stream,
+        track: Arc<TrackLocalStaticRTP>,
+        turn: Turn,
+        prefix: &'static str,
+    ) -> Result<Self> {
+        let peer = Arc::new(new_rtc(false, &turn).await?);
         let ices: Arc<RwLock<Vec<RTCIceCandidateInit>>> = Arc::new(RwLock::new(vec![]));
         let ices_clone = ices.clone();
         peer.on_ice_candidate(Box::new(move |c| {
@@ -248,12 +412,63 @@ impl RTCForwader {
                     let lock = ices.read().await;
                     let ices = lock.clone();
                     let json = serde_json::to_string(&ices).unwrap();
-                    let _ = stream.write("ices", Ices { ices: json }).await;
+                    let _ = stream
+                        .write(&format!("{}ices", prefix), Ices { ices: json })
+                        .await;
+                }
+            })
+        }));
+        let handlers: Arc<std::sync::RwLock<StateHandlers>> = Arc::default();
+        let handlers_clone = handlers.clone();
+        peer.on_peer_connection_state_change(Box::new(move |state| {
+            let handler = handlers_clone.read().ok().and_then(|h| match state {
+                RTCPeerConnectionState::Connected => h.connected.clone(),
+                RTCPeerConnectionState::Failed => h.failed.clone(),
+                _ => None,
+            });
+            Box::pin(async move {
+                if let Some(f) = handler {
+                    f().await;
                 }
             })
         }));
         peer.add_track(track).await?;
-        Ok(Self { stream, peer })
+        Ok(Self {
+            stream,
+            peer,
+            outputs: vec![],
+            emergency: false,
+            turn,
+            handlers,
+            prefix,
+        })
+    }
+
+    /// Set the output devices the target plays the stream on.
+    pub fn with_outputs(mut self, outputs: Vec<String>) -> Self {
+        self.outputs = outputs;
+        self
+    }
+
+    /// Get the output devices the target plays the stream on.
+    pub fn outputs(&self) -> Vec<String> {
+        self.outputs.clone()
+    }
+
+    /// Mark the stream as emergency content, bypassing the volume profile of the target.
+    pub fn with_emergency(mut self, emergency: bool) -> Self {
+        self.emergency = emergency;
+        self
+    }
+
+    /// Check whether the stream is emergency content.
+    pub fn emergency(&self) -> bool {
+        self.emergency
+    }
+
+    /// Check whether the consumer is connected.
+    pub fn connected(&self) -> bool {
+        self.peer.connection_state() == RTCPeerConnectionState::Connected
     }
 
     /// On failed.
@@ -261,18 +476,19 @@ impl RTCForwader {
         &self,
         f: Box<dyn Fn() -> Pin<Box<dyn Future<Output = ()> + Send>> + Send + Sync>,
     ) {
-        let f = Arc::new(f);
-        self.peer
-            .on_peer_connection_state_change(Box::new(move |state| {
-                let f = f.clone();
-                if state == RTCPeerConnectionState::Failed {
-                    Box::pin(async move {
-                        f().await;
-                    })
-                } else {
-                    Box::pin(async move {})
-                }
-            }));
+        if let Ok(mut handlers) = self.handlers.write() {
+            handlers.failed = Some(Arc::from(f));
+        }
+    }
+
+    /// On connected.
+    pub fn on_connected(
+        &self,
+        f: Box<dyn Fn() -> Pin<Box<dyn Future<Output = ()> + Send>> + Send + Sync>,
+    ) {
+        if let Ok(mut handlers) = self.handlers.write() {
+            handlers.connected = Some(Arc::from(f));
+        }
     }
 
     /// Create offer.
@@ -283,10 +499,18 @@ impl RTCForwader {
         let _ = self
             .stream
             .write(
-                "offer",
+                &format!("{}offer", self.prefix),
                 Offer {
                     offer,
                     target: vec![],
+                    port_ids: vec![],
+                    zones: vec![],
+                    outputs: self.outputs.clone(),
+                    emergency: self.emergency,
+                    turn: Some(self.turn.clone()),
+                    record: false,
+                    priority: None,
+                    conflict: Default::default(),
                 },
             )
             .await;
@@ -324,7 +548,10 @@ impl RTCForwader {
     /// Disconnect.
     pub async fn disconnect(&self) {
         if self.peer.connection_state() == RTCPeerConnectionState::Connected {
-            let _ = self.stream.write("stream:close", "").await;
+            let _ = self
+                .stream
+                .write(&format!("{}stream:close", self.prefix), "")
+                .await;
             let _ = self.peer.close().await;
         }
     }
@@ -340,8 +567,8 @@ pub struct RTCConsumer {
 
 impl RTCConsumer {
     /// Create new RTCConsumer.
-    pub async fn new(stream: Stream) -> Result<Self> {
-        let peer = Arc::new(new_rtc(true).await?);
+    pub async fn new(stream: Stream, turn: &Turn) -> Result<Self> {
+        let peer = Arc::new(new_rtc(true, turn).await?);
         let ices: Arc<RwLock<Vec<RTCIceCandidateInit>>> = Arc::new(RwLock::new(vec![]));
         let ices_clone = ices.clone();
         peer.on_ice_candidate(Box::new(move |c| {
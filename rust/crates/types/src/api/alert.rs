@@ -0,0 +1,122 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use super::avs::Avs;
+#[cfg(feature = "db")]
+use super::schema::*;
+#[cfg(feature = "db")]
+use diesel::prelude::*;
+use types_rt::ty;
+
+/// AlertRule.
+/// The health thresholds of the avs of a group, unset thresholds aren't evaluated.
+#[derive(Clone)]
+#[ty(db(kind: Query, table: alert_rule), web(Response))]
+pub struct AlertRule {
+    pub id: i32,
+    pub user_group_id: i32,
+    /// Alert when the avs is offline for longer than the minutes.
+    pub offline_minutes: Option<i32>,
+    /// Alert when the free disk space drops below the megabytes.
+    pub disk_free_mb: Option<i64>,
+    /// Alert when the cpu temperature rises above the degrees celsius.
+    pub cpu_temp_max: Option<f64>,
+    /// Alert when the failed plays of the last 24 hours reach the count.
+    pub failed_plays: Option<i32>,
+    pub enabled: bool,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+/// NewAlertRule.
+/// The data to create or replace the alert rule of a group.
+#[ty(db(kind: Insert, table: alert_rule))]
+pub struct NewAlertRule {
+    pub user_group_id: i32,
+    pub offline_minutes: Option<i32>,
+    pub disk_free_mb: Option<i64>,
+    pub cpu_temp_max: Option<f64>,
+    pub failed_plays: Option<i32>,
+    pub enabled: bool,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+/// AlertRuleReq.
+/// The alert rule request data, at least one threshold must be set.
+/// `enabled` defaults to `true`.
+#[ty(web(Request))]
+pub struct AlertRuleReq {
+    pub offline_minutes: Option<i32>,
+    pub disk_free_mb: Option<i64>,
+    pub cpu_temp_max: Option<f64>,
+    pub failed_plays: Option<i32>,
+    pub enabled: Option<bool>,
+}
+
+/// Alert.
+/// A health alert of an avs, there is at most one unresolved alert per avs and kind.
+#[derive(Clone)]
+#[ty(db(kind: Query, table: alert, relations: [Avs]), web(Response(pagination: true)))]
+pub struct Alert {
+    pub id: i32,
+    pub avs_id: i32,
+    /// The rule that opened the alert, `null` when the rule is deleted.
+    pub alert_rule_id: Option<i32>,
+    /// `offline`, `disk_free`, `cpu_temp` or `failed_plays`.
+    pub kind: String,
+    /// `open`, `acknowledged` or `resolved`.
+    pub status: String,
+    pub message: String,
+    /// The measured value: minutes offline, free megabytes, degrees celsius or failed plays.
+    pub value: f64,
+    pub opened_at: chrono::NaiveDateTime,
+    pub acknowledged_at: Option<chrono::NaiveDateTime>,
+    pub acknowledged_by: Option<i32>,
+    pub resolved_at: Option<chrono::NaiveDateTime>,
+    /// When the group users were notified, `null` until the mail is sent.
+    pub notified_at: Option<chrono::NaiveDateTime>,
+}
+
+/// NewAlert.
+/// The data to open an alert.
+#[ty(db(kind: Insert, table: alert))]
+pub struct NewAlert {
+    pub avs_id: i32,
+    pub alert_rule_id: Option<i32>,
+    pub kind: String,
+    pub message: String,
+    pub value: f64,
+}
+
+/// AlertQuery.
+/// The filter of the alerts.
+#[ty(web(Request))]
+pub struct AlertQuery {
+    pub page: Option<i64>,
+    /// `open`, `acknowledged` or `resolved`.
+    pub status: Option<String>,
+    /// `offline`, `disk_free`, `cpu_temp` or `failed_plays`.
+    pub kind: Option<String>,
+    pub avs_id: Option<i32>,
+}
+
+api_rt::schemas! {
+    AlertRuleResponse
+    AlertRuleReq
+    AlertResponse
+    PaginatedAlertResponse
+    AlertQuery
+}
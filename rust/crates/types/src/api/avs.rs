@@ -23,6 +23,7 @@ use types_rt::ty;
 
 /// Avs.
 /// The avs data.
+#[derive(Clone)]
 #[ty(db(kind: Query, table: avs))]
 pub struct Avs {
     pub id: i32,
@@ -43,6 +44,10 @@ pub struct Avs {
     pub disk_total: Option<String>,
     pub disk_free: Option<String>,
     pub cpu_temp: Option<String>,
+    /// The sha3 hash of the device credential.
+    pub credential_hash: Option<String>,
+    /// The sha3 hash of a credential issued but not yet acknowledged by the device.
+    pub credential_pending: Option<String>,
 }
 
 /// Avs.
@@ -0,0 +1,105 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use super::avs::Avs;
+#[cfg(feature = "db")]
+use super::schema::*;
+#[cfg(feature = "db")]
+use diesel::prelude::*;
+use types_rt::ty;
+
+/// AvsConnection.
+/// A connection session of an avs, `disconnected_at` is unset while the avs is connected.
+/// Timestamps are in UTC.
+#[derive(Clone)]
+#[ty(db(kind: Query, table: avs_connection, relations: [Avs]), web(Response))]
+pub struct AvsConnection {
+    pub id: i32,
+    pub avs_id: i32,
+    pub connected_at: chrono::NaiveDateTime,
+    pub disconnected_at: Option<chrono::NaiveDateTime>,
+    /// The address the avs connected from.
+    pub remote_addr: Option<String>,
+    /// The reason of the disconnection.
+    pub reason: Option<String>,
+}
+
+/// NewAvsConnection.
+/// The data to open a connection session.
+#[ty(db(kind: Insert, table: avs_connection))]
+pub struct NewAvsConnection {
+    pub avs_id: i32,
+    pub connected_at: chrono::NaiveDateTime,
+    pub remote_addr: Option<String>,
+}
+
+/// UptimeQuery.
+/// The date range query of the uptime report, formatted as `YYYY-MM-DD` (UTC).
+#[ty(web(Request))]
+pub struct UptimeQuery {
+    pub from: Option<String>,
+    pub to: Option<String>,
+    /// The number of disconnects within an hour to consider the avs flapping, default `5`.
+    pub flap_threshold: Option<i64>,
+}
+
+/// Outage.
+/// A period the avs was not connected.
+#[ty(web(Response))]
+pub struct Outage {
+    pub from: chrono::NaiveDateTime,
+    /// `null` when the avs is still offline.
+    pub to: Option<chrono::NaiveDateTime>,
+    pub duration_seconds: i64,
+    /// The reason of the disconnection that started the outage.
+    pub reason: Option<String>,
+}
+
+/// UptimeResponse.
+/// The uptime report of an avs over the date range.
+#[ty(web(Response))]
+pub struct UptimeResponse {
+    pub avs_id: i32,
+    /// The uptime percentage over the range.
+    pub uptime: f64,
+    pub online_seconds: i64,
+    pub disconnects: i64,
+    /// The highest number of disconnects within an hour.
+    pub max_disconnects_per_hour: i64,
+    pub flapping: bool,
+    pub outages: Vec<Outage>,
+}
+
+/// GroupUptimeResponse.
+/// The uptime report of the avs of a group over the date range.
+#[ty(web(Response))]
+pub struct GroupUptimeResponse {
+    pub user_group_id: i32,
+    /// The average uptime percentage of the avs.
+    pub uptime: f64,
+    /// The avs that are flapping.
+    pub flapping: Vec<i32>,
+    pub devices: Vec<UptimeResponse>,
+}
+
+api_rt::schemas! {
+    AvsConnectionResponse
+    UptimeQuery
+    Outage
+    UptimeResponse
+    GroupUptimeResponse
+}
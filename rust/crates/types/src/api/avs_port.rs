@@ -0,0 +1,67 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use super::avs::Avs;
+#[cfg(feature = "db")]
+use super::schema::*;
+#[cfg(feature = "db")]
+use diesel::prelude::*;
+use types_rt::ty;
+
+/// AvsPort.
+/// An audio output of an avs, reported by the avs itself.
+#[derive(Clone)]
+#[ty(db(kind: Query, table: avs_port, relations: [Avs]), web(Response))]
+pub struct AvsPort {
+    pub id: i32,
+    pub avs_id: i32,
+    /// The name given by the operator.
+    pub name: String,
+    /// A stable number derived from the name of the output device on the avs.
+    pub speaker: i32,
+    /// The name of the output device on the avs.
+    pub device: String,
+    /// The zone of the port, ports can be targeted by zone.
+    pub zone: Option<String>,
+    /// Whether the output device was reported on the last connection.
+    pub available: bool,
+}
+
+/// NewAvsPort.
+/// The data to create an avs port.
+#[ty(db(kind: Insert, table: avs_port))]
+pub struct NewAvsPort {
+    pub avs_id: i32,
+    pub name: String,
+    pub speaker: i32,
+    pub device: String,
+    pub zone: Option<String>,
+    pub available: bool,
+}
+
+/// UpdateAvsPortReq.
+/// The operator data of an avs port.
+#[ty(web(Request))]
+pub struct UpdateAvsPortReq {
+    pub name: String,
+    pub zone: Option<String>,
+}
+
+api_rt::schemas! {
+    AvsPortResponse
+    UpdateAvsPortReq
+}
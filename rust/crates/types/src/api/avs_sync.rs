@@ -0,0 +1,61 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use super::avs::Avs;
+#[cfg(feature = "db")]
+use super::schema::*;
+#[cfg(feature = "db")]
+use diesel::prelude::*;
+use types_rt::ty;
+
+/// AvsSync.
+/// The schedules last sent to an avs, `entries` are formatted as `sid:rev`.
+/// `version` increases every sync that changes the entries, the `rev` of an entry is the
+/// content digest of the schedule, so only the last of several edits between syncs is sent.
+#[derive(Clone)]
+#[ty(db(kind: Query, table: avs_sync, relations: [Avs]))]
+pub struct AvsSync {
+    pub id: i32,
+    pub avs_id: i32,
+    pub version: i64,
+    pub entries: Vec<Option<String>>,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+/// NewAvsSync.
+/// The data to store the schedules sent to an avs.
+#[ty(db(kind: Insert, table: avs_sync))]
+pub struct NewAvsSync {
+    pub avs_id: i32,
+    pub version: i64,
+    pub entries: Vec<Option<String>>,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+impl AvsSync {
+    /// Parse the entries into `(sid, rev)` pairs.
+    pub fn revs(&self) -> Vec<(i32, String)> {
+        self.entries
+            .iter()
+            .flatten()
+            .filter_map(|e| {
+                let (sid, rev) = e.split_once(':')?;
+                Some((sid.parse::<i32>().ok()?, rev.to_owned()))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,163 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+#[cfg(feature = "db")]
+use super::schema::*;
+use super::user::User;
+#[cfg(feature = "db")]
+use diesel::prelude::*;
+use types_rt::ty;
+
+/// Broadcast.
+/// A live broadcast session of a user, `ended_at` is unset while the user is streaming.
+#[derive(Clone)]
+#[ty(db(kind: Query, table: broadcast, relations: [User]))]
+pub struct Broadcast {
+    pub id: i32,
+    pub user_id: Option<i32>,
+    pub emergency: bool,
+    pub started_at: chrono::NaiveDateTime,
+    pub ended_at: Option<chrono::NaiveDateTime>,
+    pub airtime_seconds: Option<i32>,
+}
+
+/// NewBroadcast.
+/// The data to create a broadcast.
+#[ty(db(kind: Insert, table: broadcast))]
+pub struct NewBroadcast {
+    pub user_id: Option<i32>,
+    pub emergency: bool,
+}
+
+/// BroadcastTarget.
+/// A target avs of a broadcast.
+#[derive(Clone)]
+#[ty(db(kind: Query, table: broadcast_target, relations: [Broadcast]), web(Response))]
+pub struct BroadcastTarget {
+    pub id: i32,
+    pub broadcast_id: i32,
+    pub avs_id: i32,
+    /// When the avs connected to the stream.
+    pub joined_at: Option<chrono::NaiveDateTime>,
+    /// When the avs left the stream.
+    pub left_at: Option<chrono::NaiveDateTime>,
+    /// When the connection to the avs last failed.
+    pub failed_at: Option<chrono::NaiveDateTime>,
+    /// Why the avs didn't receive the stream: `busy`, `offline`, `failed` or `preempted`.
+    pub excluded: Option<String>,
+}
+
+/// NewBroadcastTarget.
+/// The data to create a target of the broadcast.
+/// `broadcast_id` is filled by the repository.
+#[ty(db(kind: Insert, table: broadcast_target))]
+pub struct NewBroadcastTarget {
+    pub broadcast_id: i32,
+    pub avs_id: i32,
+    pub excluded: Option<String>,
+}
+
+/// BroadcastQuery.
+/// The filter of the broadcast history, dates are formatted as `YYYY-MM-DD`.
+#[ty(web(Request))]
+pub struct BroadcastQuery {
+    pub page: Option<i64>,
+    pub user_id: Option<i32>,
+    pub avs_id: Option<i32>,
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+/// PlaybackReq.
+/// The request to play a record on the avs right away.
+#[ty(web(Request))]
+pub struct PlaybackReq {
+    pub record_id: i32,
+    #[serde(default)]
+    pub avs_ids: Vec<i32>,
+    /// Every avs of the groups is targeted as well.
+    #[serde(default)]
+    pub group_ids: Vec<i32>,
+    /// Bypass the volume profile of the avs, default `false`.
+    pub emergency: Option<bool>,
+    /// The priority of the playback, capped to the priority of the role of the user.
+    pub priority: Option<u8>,
+    /// The policy for the avs busy with another broadcast: `preempt`, `queue` or `reject`, default `reject`.
+    pub conflict: Option<String>,
+}
+
+/// BroadcastData.
+/// A broadcast along with its targets.
+#[ty(web(Response(pagination: true)))]
+pub struct BroadcastData {
+    pub id: i32,
+    /// `null` when the user is deleted.
+    pub user_id: Option<i32>,
+    pub emergency: bool,
+    pub started_at: chrono::NaiveDateTime,
+    /// `null` while the broadcast is ongoing.
+    pub ended_at: Option<chrono::NaiveDateTime>,
+    /// The duration of the broadcast.
+    pub airtime_seconds: Option<i32>,
+    pub targets: Vec<BroadcastTargetResponse>,
+}
+
+#[cfg(feature = "web")]
+impl From<(Broadcast, Vec<BroadcastTarget>)> for BroadcastData {
+    fn from((value, targets): (Broadcast, Vec<BroadcastTarget>)) -> Self {
+        Self {
+            id: value.id,
+            user_id: value.user_id,
+            emergency: value.emergency,
+            started_at: value.started_at,
+            ended_at: value.ended_at,
+            airtime_seconds: value.airtime_seconds,
+            targets: targets
+                .into_iter()
+                .map(BroadcastTargetResponse::from)
+                .collect(),
+            __status: 200,
+        }
+    }
+}
+
+/// BusyTargetData.
+/// A target avs of a playback that was busy with another broadcast.
+#[ty(web(Response))]
+pub struct BusyTargetData {
+    pub avs_id: i32,
+    /// How the conflict policy resolved it: `preempted`, `queued` or `rejected`.
+    pub status: String,
+}
+
+/// PlaybackData.
+/// The broadcast of a playback along with the targets that were busy with another broadcast.
+#[ty(web(Response))]
+pub struct PlaybackData {
+    pub broadcast: BroadcastData,
+    pub busy: Vec<BusyTargetData>,
+}
+
+api_rt::schemas! {
+    BroadcastTargetResponse
+    BroadcastQuery
+    PlaybackReq
+    BroadcastData
+    PaginatedBroadcastData
+    BusyTargetData
+    PlaybackData
+}
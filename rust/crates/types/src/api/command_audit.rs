@@ -0,0 +1,92 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use super::avs::Avs;
+#[cfg(feature = "db")]
+use super::schema::*;
+#[cfg(feature = "db")]
+use diesel::prelude::*;
+use types_rt::ty;
+
+/// CommandAudit.
+/// A remote command requested on an avs, `responded_at` is unset until the avs responds.
+#[derive(Clone)]
+#[ty(db(kind: Query, table: command_audit, relations: [Avs]), web(Response(pagination: true)))]
+pub struct CommandAudit {
+    pub id: i32,
+    /// `null` when the user is deleted.
+    pub user_id: Option<i32>,
+    pub avs_id: i32,
+    pub command: String,
+    /// `false` when the avs was offline.
+    pub delivered: bool,
+    pub requested_at: chrono::NaiveDateTime,
+    pub responded_at: Option<chrono::NaiveDateTime>,
+    /// The exit status of the command, `null` when the avs didn't report it.
+    pub exit_status: Option<i32>,
+    pub output: Option<String>,
+    /// `true` when the output is cut to the first bytes.
+    pub truncated: bool,
+}
+
+/// NewCommandAudit.
+/// The data to create a command audit.
+#[ty(db(kind: Insert, table: command_audit))]
+pub struct NewCommandAudit {
+    pub user_id: Option<i32>,
+    pub avs_id: i32,
+    pub command: String,
+    pub delivered: bool,
+}
+
+/// CommandAuditQuery.
+/// The filter of the command audit, dates are formatted as `YYYY-MM-DD`.
+#[ty(web(Request))]
+pub struct CommandAuditQuery {
+    pub page: Option<i64>,
+    /// Only the commands containing the text.
+    pub q: Option<String>,
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+/// CommandActionParam.
+/// A parameter of a remote action.
+#[ty(web(Response))]
+pub struct CommandActionParam {
+    pub name: String,
+    pub required: bool,
+    /// `name` for letters, digits, `-`, `_`, `.` and `@`, `percent` for 0 to 100.
+    pub kind: String,
+}
+
+/// CommandAction.
+/// A predefined remote action, sent as the `action` of the `command` event along with its `params`.
+#[ty(web(Response))]
+pub struct CommandAction {
+    pub name: String,
+    pub description: String,
+    pub params: Vec<CommandActionParam>,
+}
+
+api_rt::schemas! {
+    CommandAuditResponse
+    PaginatedCommandAuditResponse
+    CommandAuditQuery
+    CommandActionParam
+    CommandAction
+}
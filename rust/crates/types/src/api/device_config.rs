@@ -0,0 +1,111 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+#[cfg(feature = "db")]
+use super::schema::*;
+#[cfg(feature = "db")]
+use diesel::prelude::*;
+use types_rt::ty;
+
+/// DeviceConfig.
+/// The device configuration of an avs or a group.
+/// The avs configuration overrides the group configuration field by field.
+#[derive(Clone)]
+#[ty(db(kind: Query, table: device_config), web(Response))]
+pub struct DeviceConfig {
+    pub id: i32,
+    pub avs_id: Option<i32>,
+    pub user_group_id: Option<i32>,
+    pub volume: Option<f64>,
+    pub log_level: Option<String>,
+    pub quiet_start: Option<String>,
+    pub quiet_end: Option<String>,
+    pub telemetry_interval: Option<i32>,
+    pub allowed_commands: Option<Vec<Option<String>>>,
+    pub audio_device: Option<String>,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+/// NewDeviceConfig.
+/// The data to create or replace a device configuration.
+#[ty(db(kind: Insert, table: device_config))]
+pub struct NewDeviceConfig {
+    pub avs_id: Option<i32>,
+    pub user_group_id: Option<i32>,
+    pub volume: Option<f64>,
+    pub log_level: Option<String>,
+    pub quiet_start: Option<String>,
+    pub quiet_end: Option<String>,
+    pub telemetry_interval: Option<i32>,
+    pub allowed_commands: Option<Vec<Option<String>>>,
+    pub audio_device: Option<String>,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+/// DeviceConfigReq.
+/// The device configuration request data, unset fields are inherited.
+/// `log_level`: `debug`, `info`, `warn` or `error`.
+/// `quiet_start` and `quiet_end` are formatted as `HH:MM`.
+/// `telemetry_interval` is in seconds.
+#[ty(web(Request))]
+pub struct DeviceConfigReq {
+    pub volume: Option<f64>,
+    pub log_level: Option<String>,
+    pub quiet_start: Option<String>,
+    pub quiet_end: Option<String>,
+    pub telemetry_interval: Option<i32>,
+    pub allowed_commands: Option<Vec<String>>,
+    pub audio_device: Option<String>,
+}
+
+/// EffectiveDeviceConfig.
+/// The configuration pushed to an avs after merging its group configuration.
+#[ty(web(Response))]
+pub struct EffectiveDeviceConfig {
+    pub avs_id: i32,
+    pub volume: Option<f64>,
+    pub log_level: Option<String>,
+    pub quiet_start: Option<String>,
+    pub quiet_end: Option<String>,
+    pub telemetry_interval: Option<i32>,
+    pub allowed_commands: Option<Vec<String>>,
+    pub audio_device: Option<String>,
+}
+
+#[cfg(feature = "proto")]
+impl From<DeviceConfig> for crate::proto::DeviceConfig {
+    fn from(config: DeviceConfig) -> Self {
+        Self {
+            volume: config.volume.map(|v| v as f32),
+            log_level: config.log_level,
+            quiet_start: config.quiet_start,
+            quiet_end: config.quiet_end,
+            telemetry_interval: config.telemetry_interval,
+            allowed_commands: config
+                .allowed_commands
+                .map(|commands| commands.into_iter().flatten().collect()),
+            audio_device: config.audio_device,
+            volume_profile: None,
+        }
+    }
+}
+
+api_rt::schemas! {
+    DeviceConfigResponse
+    DeviceConfigReq
+    EffectiveDeviceConfig
+}
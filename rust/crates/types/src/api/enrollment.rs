@@ -0,0 +1,123 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+#[cfg(feature = "db")]
+use super::schema::*;
+#[cfg(feature = "db")]
+use diesel::prelude::*;
+use types_rt::ty;
+
+/// EnrollmentToken.
+/// A one-time token to provision a new avs, only the hash of the token is stored.
+#[derive(Clone)]
+#[ty(db(kind: Query, table: enrollment_token))]
+pub struct EnrollmentToken {
+    pub id: i32,
+    pub token_hash: String,
+    pub user_group_id: Option<i32>,
+    pub lat: Option<f64>,
+    pub lng: Option<f64>,
+    pub address: Option<String>,
+    pub description: Option<String>,
+    pub created_by: i32,
+    pub created_at: chrono::NaiveDateTime,
+    pub expires_at: chrono::NaiveDateTime,
+    pub used_at: Option<chrono::NaiveDateTime>,
+    pub avs_id: Option<i32>,
+}
+
+/// NewEnrollmentToken.
+/// The data to create an enrollment token.
+#[ty(db(kind: Insert, table: enrollment_token))]
+pub struct NewEnrollmentToken {
+    pub token_hash: String,
+    pub user_group_id: Option<i32>,
+    pub lat: Option<f64>,
+    pub lng: Option<f64>,
+    pub address: Option<String>,
+    pub description: Option<String>,
+    pub created_by: i32,
+    pub expires_at: chrono::NaiveDateTime,
+}
+
+/// EnrollmentTokenReq.
+/// The enrollment token request data.
+/// The enrolled avs is assigned to the users of `user_group_id` and placed at the given location.
+#[ty(web(Request))]
+pub struct EnrollmentTokenReq {
+    pub user_group_id: Option<i32>,
+    pub lat: Option<f64>,
+    pub lng: Option<f64>,
+    pub address: Option<String>,
+    pub description: Option<String>,
+    /// Lifetime of the token in hours, default `24`.
+    pub expires_in_hours: Option<i64>,
+}
+
+/// EnrollmentTokenResponse.
+/// An enrollment token, `token` is only returned once on creation.
+#[ty(web(Response))]
+pub struct EnrollmentTokenResponse {
+    pub id: i32,
+    pub token: Option<String>,
+    pub user_group_id: Option<i32>,
+    pub lat: Option<f64>,
+    pub lng: Option<f64>,
+    pub address: Option<String>,
+    pub description: Option<String>,
+    pub created_by: i32,
+    pub created_at: chrono::NaiveDateTime,
+    pub expires_at: chrono::NaiveDateTime,
+    pub used_at: Option<chrono::NaiveDateTime>,
+    /// The avs enrolled with the token.
+    pub avs_id: Option<i32>,
+}
+
+#[cfg(feature = "web")]
+impl From<EnrollmentToken> for EnrollmentTokenResponse {
+    fn from(value: EnrollmentToken) -> Self {
+        Self {
+            id: value.id,
+            token: None,
+            user_group_id: value.user_group_id,
+            lat: value.lat,
+            lng: value.lng,
+            address: value.address,
+            description: value.description,
+            created_by: value.created_by,
+            created_at: value.created_at,
+            expires_at: value.expires_at,
+            used_at: value.used_at,
+            avs_id: value.avs_id,
+            __status: 200,
+        }
+    }
+}
+
+#[cfg(feature = "web")]
+impl EnrollmentTokenResponse {
+    /// Attach the plain token.
+    pub fn with_token(mut self, token: String) -> Self {
+        self.token = Some(token);
+        self
+    }
+}
+
+api_rt::schemas! {
+    EnrollmentTokenReq
+    EnrollmentTokenResponse
+}
@@ -21,23 +21,37 @@ use actix_multipart::form;
 #[cfg(feature = "web")]
 pub use self::web::*;
 
+pub mod alert;
 pub mod avs;
+pub mod avs_connection;
+pub mod avs_port;
+pub mod avs_sync;
 pub mod blacklist_token;
+pub mod broadcast;
 pub mod city;
+pub mod command_audit;
+pub mod device_config;
 pub mod docs_credential;
+pub mod enrollment;
 pub mod forgot_password;
 pub mod package;
+pub mod play_log;
+pub mod playlist;
 pub mod province;
 pub mod records;
 pub mod role;
 pub mod schedules;
 #[cfg(feature = "db")]
 pub mod schema;
+pub mod self_test;
 pub mod session;
 pub mod subscription;
+pub mod telemetry;
+pub mod updates;
 pub mod user;
 pub mod user_group;
 pub mod verify;
+pub mod volume_profile;
 
 #[cfg(feature = "web")]
 mod web {
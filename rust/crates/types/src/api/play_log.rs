@@ -0,0 +1,97 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use super::avs::Avs;
+#[cfg(feature = "db")]
+use super::schema::*;
+#[cfg(feature = "db")]
+use diesel::prelude::*;
+use types_rt::ty;
+
+/// PlayLog.
+/// The proof of play data reported by the avs.
+/// `started_at` and `ended_at` are in the avs local time.
+#[derive(Clone)]
+#[ty(db(kind: Query, table: play_log, relations: [Avs]), web(Response))]
+pub struct PlayLog {
+    pub id: i32,
+    pub avs_id: i32,
+    pub schedule_id: i32,
+    /// The id of the log on the avs.
+    pub local_id: i32,
+    pub record_url: String,
+    pub started_at: chrono::NaiveDateTime,
+    pub ended_at: Option<chrono::NaiveDateTime>,
+    /// The effective volume, with the multiplier of the volume profile applied.
+    pub volume: Option<f64>,
+    /// The outcome, one of the `types::proto::OUTCOME_*` constants.
+    pub outcome: i32,
+    pub message: Option<String>,
+    pub created_at: chrono::NaiveDateTime,
+    /// The offset of the avs local time from UTC in seconds, unset for older avs.
+    pub utc_offset: Option<i32>,
+}
+
+/// NewPlayLog.
+/// The data to create a play log.
+#[ty(db(kind: Insert, table: play_log))]
+pub struct NewPlayLog {
+    pub avs_id: i32,
+    pub schedule_id: i32,
+    pub local_id: i32,
+    pub record_url: String,
+    pub started_at: chrono::NaiveDateTime,
+    pub ended_at: Option<chrono::NaiveDateTime>,
+    pub volume: Option<f64>,
+    pub outcome: i32,
+    pub message: Option<String>,
+    pub utc_offset: Option<i32>,
+}
+
+/// PlayLogQuery.
+/// The date range query of the play log history, formatted as `YYYY-MM-DD`.
+#[ty(web(Request))]
+pub struct PlayLogQuery {
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+/// MissedPlay.
+/// An expected schedule occurrence that was not played.
+#[ty(web(Response))]
+pub struct MissedPlay {
+    pub avs_id: i32,
+    pub schedule_id: i32,
+    pub expected_at: chrono::NaiveDateTime,
+    /// The outcome reported by the avs, `null` if the avs didn't report anything.
+    pub outcome: Option<i32>,
+}
+
+/// PlayLogHistory.
+/// The play log history along with the missed occurrences.
+#[ty(web(Response))]
+pub struct PlayLogHistory {
+    pub logs: Vec<PlayLogResponse>,
+    pub missed: Vec<MissedPlay>,
+}
+
+api_rt::schemas! {
+    PlayLogResponse
+    PlayLogQuery
+    MissedPlay
+    PlayLogHistory
+}
@@ -0,0 +1,156 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+#[cfg(feature = "db")]
+use super::schema::*;
+#[cfg(feature = "db")]
+use diesel::prelude::*;
+use types_rt::ty;
+
+/// Playlist.
+/// The background playlist of an avs or a group, played whenever the avs is idle.
+/// The avs playlist replaces the group playlist as a whole.
+#[derive(Clone)]
+#[ty(db(kind: Query, table: playlist))]
+pub struct Playlist {
+    pub id: i32,
+    pub avs_id: Option<i32>,
+    pub user_group_id: Option<i32>,
+    pub shuffle: bool,
+    pub repeat: bool,
+    pub start_time: Option<String>,
+    pub end_time: Option<String>,
+    pub volume: Option<f64>,
+    pub duck: f64,
+}
+
+/// NewPlaylist.
+/// The data to create a playlist.
+#[ty(db(kind: Insert, table: playlist))]
+pub struct NewPlaylist {
+    pub avs_id: Option<i32>,
+    pub user_group_id: Option<i32>,
+    pub shuffle: bool,
+    pub repeat: bool,
+    pub start_time: Option<String>,
+    pub end_time: Option<String>,
+    pub volume: Option<f64>,
+    pub duck: f64,
+}
+
+/// PlaylistItem.
+/// A record of the playlist.
+#[derive(Clone)]
+#[ty(db(kind: Query, table: playlist_item, relations: [Playlist]))]
+pub struct PlaylistItem {
+    pub id: i32,
+    pub playlist_id: i32,
+    pub records_id: i32,
+    pub position: i32,
+}
+
+/// NewPlaylistItem.
+/// The data to create a record of the playlist.
+/// `playlist_id` is filled by the repository.
+#[ty(db(kind: Insert, table: playlist_item))]
+pub struct NewPlaylistItem {
+    pub playlist_id: i32,
+    pub records_id: i32,
+    pub position: i32,
+}
+
+/// PlaylistResponse.
+/// A playlist along with its records in play order.
+#[ty(web(Response))]
+pub struct PlaylistResponse {
+    pub id: i32,
+    pub avs_id: Option<i32>,
+    pub user_group_id: Option<i32>,
+    pub shuffle: bool,
+    pub repeat: bool,
+    /// Start of the time window, formatted as `HH:MM`.
+    pub start_time: Option<String>,
+    /// End of the time window, formatted as `HH:MM`.
+    pub end_time: Option<String>,
+    /// Volume of the playlist, the avs volume when `null`.
+    pub volume: Option<f64>,
+    /// Volume multiplier while a schedule is playing, `0` pauses the playlist.
+    pub duck: f64,
+    pub records_ids: Vec<i32>,
+}
+
+#[cfg(feature = "web")]
+impl From<(Playlist, Vec<PlaylistItem>)> for PlaylistResponse {
+    fn from((value, items): (Playlist, Vec<PlaylistItem>)) -> Self {
+        Self {
+            id: value.id,
+            avs_id: value.avs_id,
+            user_group_id: value.user_group_id,
+            shuffle: value.shuffle,
+            repeat: value.repeat,
+            start_time: value.start_time,
+            end_time: value.end_time,
+            volume: value.volume,
+            duck: value.duck,
+            records_ids: items.into_iter().map(|i| i.records_id).collect(),
+            __status: 200,
+        }
+    }
+}
+
+/// PlaylistReq.
+/// The playlist request data, `start_time` and `end_time` are formatted as `HH:MM`.
+/// The time window may pass midnight, the playlist always plays when both are unset.
+#[ty(web(Request))]
+pub struct PlaylistReq {
+    /// The active records to play, in order.
+    pub records_ids: Vec<i32>,
+    pub shuffle: bool,
+    pub repeat: bool,
+    pub start_time: Option<String>,
+    pub end_time: Option<String>,
+    /// Volume between `0` and `1`.
+    pub volume: Option<f64>,
+    /// Volume multiplier while a schedule is playing, between `0` (pause) and `1`, default `0.2`.
+    pub duck: Option<f64>,
+}
+
+#[cfg(feature = "proto")]
+impl From<(Playlist, Vec<super::records::Records>)> for crate::proto::Playlist {
+    fn from((value, records): (Playlist, Vec<super::records::Records>)) -> Self {
+        Self {
+            tracks: records
+                .into_iter()
+                .map(|r| crate::proto::PlaylistTrack {
+                    record_url: r.file_url,
+                    format: r.format,
+                })
+                .collect(),
+            shuffle: value.shuffle,
+            repeat: value.repeat,
+            start: value.start_time,
+            end: value.end_time,
+            volume: value.volume.map(|v| v as f32),
+            duck: value.duck as f32,
+        }
+    }
+}
+
+api_rt::schemas! {
+    PlaylistResponse
+    PlaylistReq
+}
@@ -37,6 +37,8 @@ pub struct Records {
     pub status: i32,
     pub duration: String,
     pub sender: Option<i32>,
+    /// The delivery format: `mp3`, `opus`, `wav` or `flac`.
+    pub format: String,
 }
 
 /// RecordsResponse.
@@ -53,6 +55,8 @@ pub struct RecordsResponse {
     pub status: i32,
     pub duration: String,
     pub sender: Option<UserResponse>,
+    /// The delivery format: `mp3`, `opus`, `wav` or `flac`.
+    pub format: String,
 }
 
 #[cfg(feature = "web")]
@@ -70,6 +74,7 @@ impl From<Records> for RecordsResponse {
             status: r.status,
             duration: r.duration,
             sender: None,
+            format: r.format,
         }
     }
 }
@@ -86,6 +91,8 @@ pub struct NewRecords {
     pub status: i32,
     pub duration: String,
     pub sender: Option<i32>,
+    /// The delivery format: `mp3`, `opus`, `wav` or `flac`.
+    pub format: String,
 }
 
 api_rt::schemas! {
@@ -40,6 +40,12 @@ pub struct Schedules {
     pub month: Option<i32>,
     pub year: Option<i32>,
     pub volumes: Vec<Option<String>>,
+    /// The avs ports to play on, see `AvsPort`.
+    pub port_ids: Vec<Option<i32>>,
+    /// The zones to play on, see `AvsPort`.
+    pub zones: Vec<Option<String>>,
+    /// Emergency schedules bypass the quiet hours and volume profiles.
+    pub emergency: bool,
 }
 
 /// Create new Schedule.
@@ -57,6 +63,9 @@ pub struct NewSchedule {
     pub month: Option<i32>,
     pub year: Option<i32>,
     pub volumes: Vec<Option<String>>,
+    pub port_ids: Vec<Option<i32>>,
+    pub zones: Vec<Option<String>>,
+    pub emergency: bool,
 }
 
 /// NewScheduleRequest.
@@ -73,9 +82,25 @@ pub struct NewScheduleReq {
     pub month: Option<i32>,
     pub year: Option<i32>,
     pub volumes: Vec<Option<String>>,
+    /// The avs ports to play on, empty or omitted to play on the default output.
+    pub port_ids: Option<Vec<i32>>,
+    /// The zones to play on, empty or omitted to play on the default output.
+    pub zones: Option<Vec<String>>,
+    /// Bypass the quiet hours and volume profiles, only for Root and SuperAdmin.
+    pub emergency: Option<bool>,
+}
+
+/// ScheduleOutputsReq.
+/// The ports and zones to play a schedule on.
+/// An avs without any matching port plays the schedule on its default output.
+#[ty(web(Request))]
+pub struct ScheduleOutputsReq {
+    pub port_ids: Vec<i32>,
+    pub zones: Vec<String>,
 }
 
 api_rt::schemas! {
     SchedulesResponse
     NewScheduleReq
+    ScheduleOutputsReq
 }
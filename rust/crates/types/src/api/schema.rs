@@ -1,5 +1,37 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    alert (id) {
+        id -> Int4,
+        avs_id -> Int4,
+        alert_rule_id -> Nullable<Int4>,
+        #[max_length = 32]
+        kind -> Varchar,
+        #[max_length = 16]
+        status -> Varchar,
+        message -> Text,
+        value -> Float8,
+        opened_at -> Timestamp,
+        acknowledged_at -> Nullable<Timestamp>,
+        acknowledged_by -> Nullable<Int4>,
+        resolved_at -> Nullable<Timestamp>,
+        notified_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    alert_rule (id) {
+        id -> Int4,
+        user_group_id -> Int4,
+        offline_minutes -> Nullable<Int4>,
+        disk_free_mb -> Nullable<Int8>,
+        cpu_temp_max -> Nullable<Float8>,
+        failed_plays -> Nullable<Int4>,
+        enabled -> Bool,
+        updated_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     avs (id) {
         id -> Int4,
@@ -21,6 +53,22 @@ diesel::table! {
         disk_total -> Nullable<Text>,
         disk_free -> Nullable<Text>,
         cpu_temp -> Nullable<Text>,
+        #[max_length = 64]
+        credential_hash -> Nullable<Varchar>,
+        #[max_length = 64]
+        credential_pending -> Nullable<Varchar>,
+    }
+}
+
+diesel::table! {
+    avs_connection (id) {
+        id -> Int4,
+        avs_id -> Int4,
+        connected_at -> Timestamp,
+        disconnected_at -> Nullable<Timestamp>,
+        #[max_length = 255]
+        remote_addr -> Nullable<Varchar>,
+        reason -> Nullable<Text>,
     }
 }
 
@@ -31,6 +79,35 @@ diesel::table! {
         #[max_length = 255]
         name -> Varchar,
         speaker -> Int4,
+        #[max_length = 255]
+        device -> Varchar,
+        #[max_length = 255]
+        zone -> Nullable<Varchar>,
+        available -> Bool,
+    }
+}
+
+diesel::table! {
+    avs_sync (id) {
+        id -> Int4,
+        avs_id -> Int4,
+        version -> Int8,
+        entries -> Array<Nullable<Text>>,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    avs_update (id) {
+        id -> Int4,
+        avs_id -> Int4,
+        update_id -> Int4,
+        status -> Int4,
+        progress -> Nullable<Int4>,
+        message -> Nullable<Text>,
+        #[max_length = 64]
+        version -> Nullable<Varchar>,
+        updated_at -> Timestamp,
     }
 }
 
@@ -41,6 +118,30 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    broadcast (id) {
+        id -> Int4,
+        user_id -> Nullable<Int4>,
+        emergency -> Bool,
+        started_at -> Timestamp,
+        ended_at -> Nullable<Timestamp>,
+        airtime_seconds -> Nullable<Int4>,
+    }
+}
+
+diesel::table! {
+    broadcast_target (id) {
+        id -> Int4,
+        broadcast_id -> Int4,
+        avs_id -> Int4,
+        joined_at -> Nullable<Timestamp>,
+        left_at -> Nullable<Timestamp>,
+        failed_at -> Nullable<Timestamp>,
+        #[max_length = 32]
+        excluded -> Nullable<Varchar>,
+    }
+}
+
 diesel::table! {
     city (id) {
         id -> Int4,
@@ -50,6 +151,41 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    command_audit (id) {
+        id -> Int4,
+        user_id -> Nullable<Int4>,
+        avs_id -> Int4,
+        command -> Text,
+        delivered -> Bool,
+        requested_at -> Timestamp,
+        responded_at -> Nullable<Timestamp>,
+        exit_status -> Nullable<Int4>,
+        output -> Nullable<Text>,
+        truncated -> Bool,
+    }
+}
+
+diesel::table! {
+    device_config (id) {
+        id -> Int4,
+        avs_id -> Nullable<Int4>,
+        user_group_id -> Nullable<Int4>,
+        volume -> Nullable<Float8>,
+        #[max_length = 16]
+        log_level -> Nullable<Varchar>,
+        #[max_length = 5]
+        quiet_start -> Nullable<Varchar>,
+        #[max_length = 5]
+        quiet_end -> Nullable<Varchar>,
+        telemetry_interval -> Nullable<Int4>,
+        allowed_commands -> Nullable<Array<Nullable<Text>>>,
+        #[max_length = 255]
+        audio_device -> Nullable<Varchar>,
+        updated_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     docs_credentials (id) {
         id -> Int4,
@@ -60,6 +196,26 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    enrollment_token (id) {
+        id -> Int4,
+        #[max_length = 64]
+        token_hash -> Varchar,
+        user_group_id -> Nullable<Int4>,
+        lat -> Nullable<Float8>,
+        lng -> Nullable<Float8>,
+        #[max_length = 255]
+        address -> Nullable<Varchar>,
+        #[max_length = 255]
+        description -> Nullable<Varchar>,
+        created_by -> Int4,
+        created_at -> Timestamp,
+        expires_at -> Timestamp,
+        used_at -> Nullable<Timestamp>,
+        avs_id -> Nullable<Int4>,
+    }
+}
+
 diesel::table! {
     forgot_password (id) {
         id -> Int4,
@@ -86,6 +242,16 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    outbox_receipt (id) {
+        id -> Int4,
+        avs_id -> Int4,
+        outbox_id -> Int4,
+        created_at -> Timestamp,
+        received_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     package (id) {
         id -> Int4,
@@ -95,6 +261,48 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    play_log (id) {
+        id -> Int4,
+        avs_id -> Int4,
+        schedule_id -> Int4,
+        local_id -> Int4,
+        record_url -> Text,
+        started_at -> Timestamp,
+        ended_at -> Nullable<Timestamp>,
+        volume -> Nullable<Float8>,
+        outcome -> Int4,
+        message -> Nullable<Text>,
+        created_at -> Timestamp,
+        utc_offset -> Nullable<Int4>,
+    }
+}
+
+diesel::table! {
+    playlist (id) {
+        id -> Int4,
+        avs_id -> Nullable<Int4>,
+        user_group_id -> Nullable<Int4>,
+        shuffle -> Bool,
+        repeat -> Bool,
+        #[max_length = 5]
+        start_time -> Nullable<Varchar>,
+        #[max_length = 5]
+        end_time -> Nullable<Varchar>,
+        volume -> Nullable<Float8>,
+        duck -> Float8,
+    }
+}
+
+diesel::table! {
+    playlist_item (id) {
+        id -> Int4,
+        playlist_id -> Int4,
+        records_id -> Int4,
+        position -> Int4,
+    }
+}
+
 diesel::table! {
     province (id) {
         id -> Int4,
@@ -116,6 +324,8 @@ diesel::table! {
         status -> Int4,
         duration -> Text,
         sender -> Nullable<Int4>,
+        #[max_length = 16]
+        format -> Varchar,
     }
 }
 
@@ -142,6 +352,34 @@ diesel::table! {
         month -> Nullable<Int4>,
         year -> Nullable<Int4>,
         volumes -> Array<Nullable<Text>>,
+        port_ids -> Array<Nullable<Int4>>,
+        zones -> Array<Nullable<Text>>,
+        emergency -> Bool,
+    }
+}
+
+diesel::table! {
+    self_test (id) {
+        id -> Int4,
+        avs_id -> Int4,
+        requested_by -> Nullable<Int4>,
+        requested_at -> Timestamp,
+        finished_at -> Nullable<Timestamp>,
+        passed -> Nullable<Bool>,
+        #[max_length = 64]
+        version -> Nullable<Varchar>,
+    }
+}
+
+diesel::table! {
+    self_test_check (id) {
+        id -> Int4,
+        self_test_id -> Int4,
+        #[max_length = 32]
+        name -> Varchar,
+        passed -> Nullable<Bool>,
+        detail -> Text,
+        duration_ms -> Int4,
     }
 }
 
@@ -155,6 +393,56 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    telemetry (id) {
+        id -> Int4,
+        avs_id -> Int4,
+        created_at -> Timestamp,
+        uptime -> Nullable<Int8>,
+        cpu_load -> Nullable<Float8>,
+        cpu_temp -> Nullable<Float8>,
+        mem_total -> Nullable<Int8>,
+        mem_free -> Nullable<Int8>,
+        disk_total -> Nullable<Int8>,
+        disk_free -> Nullable<Int8>,
+        #[max_length = 255]
+        audio_output -> Nullable<Varchar>,
+        audio_ok -> Bool,
+        audio_playing -> Bool,
+        #[max_length = 64]
+        version -> Varchar,
+        rss -> Nullable<Int8>,
+    }
+}
+
+diesel::table! {
+    telemetry_network (id) {
+        id -> Int4,
+        telemetry_id -> Int4,
+        #[max_length = 255]
+        name -> Varchar,
+        rx_bytes -> Nullable<Int8>,
+        tx_bytes -> Nullable<Int8>,
+    }
+}
+
+diesel::table! {
+    updates (id) {
+        id -> Int4,
+        #[max_length = 64]
+        version -> Varchar,
+        #[max_length = 255]
+        file_name -> Varchar,
+        size -> Int8,
+        #[max_length = 64]
+        sha256 -> Varchar,
+        #[max_length = 128]
+        signature -> Varchar,
+        user_id -> Int4,
+        created_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     user_group (id) {
         id -> Int4,
@@ -194,6 +482,20 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    volume_profile (id) {
+        id -> Int4,
+        avs_id -> Nullable<Int4>,
+        user_group_id -> Nullable<Int4>,
+        position -> Int4,
+        #[max_length = 5]
+        start_time -> Varchar,
+        #[max_length = 5]
+        end_time -> Varchar,
+        multiplier -> Float8,
+    }
+}
+
 diesel::table! {
     web_session (id) {
         id -> Int4,
@@ -210,34 +512,86 @@ diesel::table! {
     }
 }
 
+diesel::joinable!(alert -> alert_rule (alert_rule_id));
+diesel::joinable!(alert -> avs (avs_id));
+diesel::joinable!(alert -> users (acknowledged_by));
+diesel::joinable!(alert_rule -> user_group (user_group_id));
+diesel::joinable!(avs_connection -> avs (avs_id));
 diesel::joinable!(avs_port -> avs (avs_id));
+diesel::joinable!(avs_sync -> avs (avs_id));
+diesel::joinable!(avs_update -> avs (avs_id));
+diesel::joinable!(avs_update -> updates (update_id));
+diesel::joinable!(broadcast -> users (user_id));
+diesel::joinable!(broadcast_target -> avs (avs_id));
+diesel::joinable!(broadcast_target -> broadcast (broadcast_id));
 diesel::joinable!(city -> province (province_id));
+diesel::joinable!(command_audit -> avs (avs_id));
+diesel::joinable!(command_audit -> users (user_id));
+diesel::joinable!(device_config -> avs (avs_id));
+diesel::joinable!(device_config -> user_group (user_group_id));
+diesel::joinable!(enrollment_token -> avs (avs_id));
+diesel::joinable!(enrollment_token -> user_group (user_group_id));
+diesel::joinable!(enrollment_token -> users (created_by));
+diesel::joinable!(outbox_receipt -> avs (avs_id));
+diesel::joinable!(play_log -> avs (avs_id));
+diesel::joinable!(playlist -> avs (avs_id));
+diesel::joinable!(playlist -> user_group (user_group_id));
+diesel::joinable!(playlist_item -> playlist (playlist_id));
+diesel::joinable!(playlist_item -> records (records_id));
 diesel::joinable!(records -> users (user_id));
 diesel::joinable!(schedules -> records (records_id));
 diesel::joinable!(schedules -> users (user_id));
+diesel::joinable!(self_test -> avs (avs_id));
+diesel::joinable!(self_test -> users (requested_by));
+diesel::joinable!(self_test_check -> self_test (self_test_id));
 diesel::joinable!(subscription -> package (package_id));
 diesel::joinable!(subscription -> users (user_id));
+diesel::joinable!(telemetry -> avs (avs_id));
+diesel::joinable!(telemetry_network -> telemetry (telemetry_id));
+diesel::joinable!(updates -> users (user_id));
 diesel::joinable!(users -> city (city_id));
 diesel::joinable!(users -> role (role_id));
+diesel::joinable!(volume_profile -> avs (avs_id));
+diesel::joinable!(volume_profile -> user_group (user_group_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    alert,
+    alert_rule,
     avs,
+    avs_connection,
     avs_port,
+    avs_sync,
+    avs_update,
     blacklist_token,
+    broadcast,
+    broadcast_target,
     city,
+    command_audit,
+    device_config,
     docs_credentials,
+    enrollment_token,
     forgot_password,
     mobile_session,
     mobile_session_pending,
+    outbox_receipt,
     package,
+    play_log,
+    playlist,
+    playlist_item,
     province,
     records,
     role,
     schedules,
+    self_test,
+    self_test_check,
     subscription,
+    telemetry,
+    telemetry_network,
+    updates,
     user_group,
     users,
     verify,
+    volume_profile,
     web_session,
     web_session_pending,
 );
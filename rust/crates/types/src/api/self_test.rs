@@ -0,0 +1,136 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use super::avs::Avs;
+#[cfg(feature = "db")]
+use super::schema::*;
+#[cfg(feature = "db")]
+use diesel::prelude::*;
+use types_rt::ty;
+
+/// SelfTest.
+/// A self test requested on an avs, `passed` is unset until the avs reports.
+#[derive(Clone)]
+#[ty(db(kind: Query, table: self_test, relations: [Avs]))]
+pub struct SelfTest {
+    pub id: i32,
+    pub avs_id: i32,
+    pub requested_by: Option<i32>,
+    pub requested_at: chrono::NaiveDateTime,
+    pub finished_at: Option<chrono::NaiveDateTime>,
+    pub passed: Option<bool>,
+    pub version: Option<String>,
+}
+
+/// NewSelfTest.
+/// The data to create a self test.
+#[ty(db(kind: Insert, table: self_test))]
+pub struct NewSelfTest {
+    pub avs_id: i32,
+    pub requested_by: Option<i32>,
+}
+
+/// SelfTestCheck.
+/// The result of a single check of the self test.
+#[derive(Clone)]
+#[ty(db(kind: Query, table: self_test_check, relations: [SelfTest]), web(Response))]
+pub struct SelfTestCheck {
+    pub id: i32,
+    pub self_test_id: i32,
+    /// `audio_device`, `tone`, `decoders`, `disk` or `database`.
+    pub name: String,
+    /// `null` when the check is skipped.
+    pub passed: Option<bool>,
+    pub detail: String,
+    pub duration_ms: i32,
+}
+
+/// NewSelfTestCheck.
+/// The data to create a check of the self test.
+/// `self_test_id` is filled by the repository.
+#[ty(db(kind: Insert, table: self_test_check))]
+pub struct NewSelfTestCheck {
+    pub self_test_id: i32,
+    pub name: String,
+    pub passed: Option<bool>,
+    pub detail: String,
+    pub duration_ms: i32,
+}
+
+/// SelfTestReq.
+/// The self test request data.
+#[ty(web(Request))]
+pub struct SelfTestReq {
+    /// Play a test tone on the default output, default `true`.
+    pub tone: Option<bool>,
+}
+
+/// SelfTestResponse.
+/// A self test along with its checks.
+#[ty(web(Response))]
+pub struct SelfTestResponse {
+    pub id: i32,
+    pub avs_id: i32,
+    pub requested_by: Option<i32>,
+    pub requested_at: chrono::NaiveDateTime,
+    /// `null` while the avs has not reported.
+    pub finished_at: Option<chrono::NaiveDateTime>,
+    /// `true` when no check failed.
+    pub passed: Option<bool>,
+    /// The client version.
+    pub version: Option<String>,
+    pub checks: Vec<SelfTestCheckResponse>,
+}
+
+#[cfg(feature = "web")]
+impl From<(SelfTest, Vec<SelfTestCheck>)> for SelfTestResponse {
+    fn from((value, checks): (SelfTest, Vec<SelfTestCheck>)) -> Self {
+        Self {
+            id: value.id,
+            avs_id: value.avs_id,
+            requested_by: value.requested_by,
+            requested_at: value.requested_at,
+            finished_at: value.finished_at,
+            passed: value.passed,
+            version: value.version,
+            checks: checks
+                .into_iter()
+                .map(SelfTestCheckResponse::from)
+                .collect(),
+            __status: 200,
+        }
+    }
+}
+
+#[cfg(feature = "proto")]
+impl From<crate::proto::SelfTestCheck> for NewSelfTestCheck {
+    fn from(value: crate::proto::SelfTestCheck) -> Self {
+        Self {
+            self_test_id: 0,
+            name: value.name,
+            passed: value.passed,
+            detail: value.detail,
+            duration_ms: value.duration_ms.min(i32::MAX as u64) as i32,
+        }
+    }
+}
+
+api_rt::schemas! {
+    SelfTestCheckResponse
+    SelfTestReq
+    SelfTestResponse
+}
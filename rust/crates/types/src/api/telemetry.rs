@@ -0,0 +1,162 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use super::avs::Avs;
+#[cfg(feature = "db")]
+use super::schema::*;
+#[cfg(feature = "db")]
+use diesel::prelude::*;
+use types_rt::ty;
+
+/// Telemetry.
+/// A telemetry sample reported by the avs, sizes are in bytes.
+#[derive(Clone)]
+#[ty(db(kind: Query, table: telemetry, relations: [Avs]))]
+pub struct Telemetry {
+    pub id: i32,
+    pub avs_id: i32,
+    pub created_at: chrono::NaiveDateTime,
+    pub uptime: Option<i64>,
+    pub cpu_load: Option<f64>,
+    pub cpu_temp: Option<f64>,
+    pub mem_total: Option<i64>,
+    pub mem_free: Option<i64>,
+    pub disk_total: Option<i64>,
+    pub disk_free: Option<i64>,
+    pub audio_output: Option<String>,
+    pub audio_ok: bool,
+    pub audio_playing: bool,
+    pub version: String,
+    pub rss: Option<i64>,
+}
+
+/// NewTelemetry.
+/// The data to create a telemetry sample.
+#[ty(db(kind: Insert, table: telemetry))]
+pub struct NewTelemetry {
+    pub avs_id: i32,
+    pub uptime: Option<i64>,
+    pub cpu_load: Option<f64>,
+    pub cpu_temp: Option<f64>,
+    pub mem_total: Option<i64>,
+    pub mem_free: Option<i64>,
+    pub disk_total: Option<i64>,
+    pub disk_free: Option<i64>,
+    pub audio_output: Option<String>,
+    pub audio_ok: bool,
+    pub audio_playing: bool,
+    pub version: String,
+    pub rss: Option<i64>,
+    /// When the sample was taken, defaults to now.
+    pub created_at: Option<chrono::NaiveDateTime>,
+}
+
+/// TelemetryNetwork.
+/// The traffic counters of a network interface in a telemetry sample.
+#[derive(Clone)]
+#[ty(db(kind: Query, table: telemetry_network, relations: [Telemetry]), web(Response))]
+pub struct TelemetryNetwork {
+    pub id: i32,
+    pub telemetry_id: i32,
+    pub name: String,
+    /// Received bytes since boot.
+    pub rx_bytes: Option<i64>,
+    /// Transmitted bytes since boot.
+    pub tx_bytes: Option<i64>,
+}
+
+/// NewTelemetryNetwork.
+/// The data to create the traffic counters of a network interface.
+/// `telemetry_id` is filled by the repository.
+#[ty(db(kind: Insert, table: telemetry_network))]
+pub struct NewTelemetryNetwork {
+    pub telemetry_id: i32,
+    pub name: String,
+    pub rx_bytes: Option<i64>,
+    pub tx_bytes: Option<i64>,
+}
+
+/// TelemetryResponse.
+/// A telemetry sample along with the network interfaces.
+#[ty(web(Response))]
+pub struct TelemetryResponse {
+    pub id: i32,
+    pub avs_id: i32,
+    pub created_at: chrono::NaiveDateTime,
+    /// Uptime of the avs in seconds.
+    pub uptime: Option<i64>,
+    /// One minute load average.
+    pub cpu_load: Option<f64>,
+    /// Cpu temperature in celsius.
+    pub cpu_temp: Option<f64>,
+    pub mem_total: Option<i64>,
+    pub mem_free: Option<i64>,
+    pub disk_total: Option<i64>,
+    pub disk_free: Option<i64>,
+    /// Name of the audio output device, `null` for the system default.
+    pub audio_output: Option<String>,
+    /// Whether the audio output device is available.
+    pub audio_ok: bool,
+    pub audio_playing: bool,
+    /// The client version.
+    pub version: String,
+    /// Resident set size of the client process.
+    pub rss: Option<i64>,
+    pub networks: Vec<TelemetryNetworkResponse>,
+}
+
+#[cfg(feature = "web")]
+impl From<(Telemetry, Vec<TelemetryNetwork>)> for TelemetryResponse {
+    fn from((value, networks): (Telemetry, Vec<TelemetryNetwork>)) -> Self {
+        Self {
+            id: value.id,
+            avs_id: value.avs_id,
+            created_at: value.created_at,
+            uptime: value.uptime,
+            cpu_load: value.cpu_load,
+            cpu_temp: value.cpu_temp,
+            mem_total: value.mem_total,
+            mem_free: value.mem_free,
+            disk_total: value.disk_total,
+            disk_free: value.disk_free,
+            audio_output: value.audio_output,
+            audio_ok: value.audio_ok,
+            audio_playing: value.audio_playing,
+            version: value.version,
+            rss: value.rss,
+            networks: networks
+                .into_iter()
+                .map(TelemetryNetworkResponse::from)
+                .collect(),
+            __status: 200,
+        }
+    }
+}
+
+/// TelemetryQuery.
+/// The time range query of the telemetry history, formatted as `YYYY-MM-DDTHH:MM:SS` in UTC.
+#[ty(web(Request))]
+pub struct TelemetryQuery {
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+api_rt::schemas! {
+    TelemetryNetworkResponse
+    TelemetryResponse
+    TelemetryQuery
+}
@@ -0,0 +1,108 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use super::avs::Avs;
+#[cfg(feature = "db")]
+use super::schema::*;
+use super::user::User;
+#[cfg(feature = "db")]
+use diesel::prelude::*;
+use types_rt::ty;
+
+/// Updates.
+/// The client update release data.
+/// `signature` is the hex encoded ed25519 signature of `myrts-client:{version}:{size}:{sha256}`.
+#[derive(Clone)]
+#[ty(db(kind: Query, table: updates, relations: [User]), web(Response))]
+pub struct Updates {
+    pub id: i32,
+    pub version: String,
+    /// The file name uploaded through `/install`.
+    pub file_name: String,
+    pub size: i64,
+    pub sha256: String,
+    pub signature: String,
+    pub user_id: i32,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+/// NewUpdates.
+/// The data to create a client update release.
+#[ty(db(kind: Insert, table: updates))]
+pub struct NewUpdates {
+    pub version: String,
+    pub file_name: String,
+    pub size: i64,
+    pub sha256: String,
+    pub signature: String,
+    pub user_id: i32,
+}
+
+/// UpdatesReq.
+/// The client update release request data.
+/// `file_name` is the name returned by `/install`, `signature` is hex encoded.
+#[ty(web(Request))]
+pub struct UpdatesReq {
+    pub version: String,
+    pub file_name: String,
+    pub signature: String,
+}
+
+/// DeployReq.
+/// The avs to deploy a client update to.
+#[ty(web(Request))]
+pub struct DeployReq {
+    pub avs_ids: Vec<i32>,
+}
+
+/// AvsUpdate.
+/// The update progress of an avs.
+#[derive(Clone)]
+#[ty(db(kind: Query, table: avs_update, relations: [Avs]), web(Response))]
+pub struct AvsUpdate {
+    pub id: i32,
+    pub avs_id: i32,
+    pub update_id: i32,
+    /// 1 = announced, 2 = downloading, 3 = verified, 4 = installed, 5 = completed, 6 = failed, 7 = rolled back.
+    pub status: i32,
+    /// Download progress in percent.
+    pub progress: Option<i32>,
+    pub message: Option<String>,
+    /// The version reported by the avs.
+    pub version: Option<String>,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+/// NewAvsUpdate.
+/// The data to create an avs update progress.
+#[ty(db(kind: Insert, table: avs_update))]
+pub struct NewAvsUpdate {
+    pub avs_id: i32,
+    pub update_id: i32,
+    pub status: i32,
+    pub progress: Option<i32>,
+    pub message: Option<String>,
+    pub version: Option<String>,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+api_rt::schemas! {
+    UpdatesResponse
+    UpdatesReq
+    DeployReq
+    AvsUpdateResponse
+}
@@ -0,0 +1,98 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+#[cfg(feature = "db")]
+use super::schema::*;
+#[cfg(feature = "db")]
+use diesel::prelude::*;
+use types_rt::ty;
+
+/// VolumeProfile.
+/// A time range of the volume profile of an avs or a group.
+/// The avs profile replaces the group profile as a whole.
+#[derive(Clone)]
+#[ty(db(kind: Query, table: volume_profile), web(Response))]
+pub struct VolumeProfile {
+    pub id: i32,
+    pub avs_id: Option<i32>,
+    pub user_group_id: Option<i32>,
+    /// The order of the range, the first matching range applies.
+    pub position: i32,
+    pub start_time: String,
+    pub end_time: String,
+    /// Multiplier of the volume, `0` mutes the avs.
+    pub multiplier: f64,
+}
+
+/// NewVolumeProfile.
+/// The data to create a time range of the volume profile.
+#[ty(db(kind: Insert, table: volume_profile))]
+pub struct NewVolumeProfile {
+    pub avs_id: Option<i32>,
+    pub user_group_id: Option<i32>,
+    pub position: i32,
+    pub start_time: String,
+    pub end_time: String,
+    pub multiplier: f64,
+}
+
+/// VolumeRangeReq.
+/// A time range of the volume profile, `start_time` and `end_time` are formatted as `HH:MM`.
+/// The range may pass midnight, `multiplier` is between `0` (mute) and `1`.
+#[ty(web(Request))]
+pub struct VolumeRangeReq {
+    pub start_time: String,
+    pub end_time: String,
+    pub multiplier: f64,
+}
+
+/// VolumeProfileReq.
+/// The volume profile request data, the first matching range applies.
+#[ty(web(Request))]
+pub struct VolumeProfileReq {
+    pub ranges: Vec<VolumeRangeReq>,
+}
+
+#[cfg(feature = "proto")]
+impl From<VolumeProfile> for crate::proto::VolumeRange {
+    fn from(profile: VolumeProfile) -> Self {
+        Self {
+            start: profile.start_time,
+            end: profile.end_time,
+            multiplier: profile.multiplier as f32,
+        }
+    }
+}
+
+#[cfg(feature = "proto")]
+impl crate::proto::DeviceConfig {
+    /// Set the volume profile, an empty profile is left unset.
+    pub fn with_volume_profile(mut self, profile: Vec<VolumeProfile>) -> Self {
+        self.volume_profile = if profile.is_empty() {
+            None
+        } else {
+            Some(profile.into_iter().map(Into::into).collect())
+        };
+        self
+    }
+}
+
+api_rt::schemas! {
+    VolumeProfileResponse
+    VolumeRangeReq
+    VolumeProfileReq
+}
@@ -0,0 +1,212 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+use std::collections::HashMap;
+
+/// Role allowed to send raw shell commands.
+pub const RAW_SHELL_ROLE: i32 = 1;
+
+/// ParamKind.
+/// The kind of value of an action parameter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamKind {
+    /// Letters, digits, `-`, `_`, `.` and `@`, not starting with `-`
+    /// so the value can't be taken as an option.
+    Name,
+    /// A whole number from 0 to 100.
+    Percent,
+}
+
+impl ParamKind {
+    /// Check whether the value is of the kind.
+    pub fn check(&self, value: &str) -> bool {
+        match self {
+            Self::Name => {
+                !value.is_empty()
+                    && !value.starts_with('-')
+                    && value
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || "-_.@".contains(c))
+            }
+            Self::Percent => value.parse::<u8>().map(|v| v <= 100).unwrap_or(false),
+        }
+    }
+}
+
+/// ActionParam.
+/// A parameter of an action.
+#[derive(Debug, Clone, Copy)]
+pub struct ActionParam {
+    pub name: &'static str,
+    pub required: bool,
+    pub kind: ParamKind,
+}
+
+/// Action.
+/// A predefined remote command, the avs maps it to the programs to run.
+#[derive(Debug, Clone, Copy)]
+pub struct Action {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// The roles allowed to run the action.
+    pub roles: &'static [i32],
+    pub params: &'static [ActionParam],
+}
+
+/// The catalog of the actions.
+pub const ACTIONS: &[Action] = &[
+    Action {
+        name: "restart_service",
+        description: "Restart a system service",
+        roles: &[1, 2],
+        params: &[ActionParam {
+            name: "service",
+            required: true,
+            kind: ParamKind::Name,
+        }],
+    },
+    Action {
+        name: "reboot",
+        description: "Reboot the avs",
+        roles: &[1, 2],
+        params: &[],
+    },
+    Action {
+        name: "set_volume",
+        description: "Set the volume of an alsa mixer control, `Master` by default",
+        roles: &[1, 2, 3],
+        params: &[
+            ActionParam {
+                name: "volume",
+                required: true,
+                kind: ParamKind::Percent,
+            },
+            ActionParam {
+                name: "control",
+                required: false,
+                kind: ParamKind::Name,
+            },
+        ],
+    },
+    Action {
+        name: "diagnostics",
+        description: "Collect the uptime, disk, memory, audio devices and client logs",
+        roles: &[1, 2, 3],
+        params: &[],
+    },
+];
+
+/// Get the action of the catalog by its name.
+pub fn action(name: &str) -> Option<&'static Action> {
+    ACTIONS.iter().find(|a| a.name == name)
+}
+
+impl Action {
+    /// Check whether the role is allowed to run the action.
+    pub fn allows(&self, role_id: i32) -> bool {
+        self.roles.contains(&role_id)
+    }
+
+    /// Validate the parameters of a request of the action.
+    pub fn validate(&self, params: &HashMap<String, String>) -> Result<(), String> {
+        for name in params.keys() {
+            if !self.params.iter().any(|p| p.name == name) {
+                return Err(format!("unknown parameter {}", name));
+            }
+        }
+        for param in self.params {
+            match params.get(param.name) {
+                Some(value) if !param.kind.check(value) => {
+                    return Err(format!("invalid parameter {}", param.name));
+                }
+                None if param.required => {
+                    return Err(format!("missing parameter {}", param.name));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Describe a request of the action, the parameters are sorted by name.
+    pub fn describe(&self, params: &HashMap<String, String>) -> String {
+        let mut params = params
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect::<Vec<String>>();
+        params.sort();
+        std::iter::once(self.name.to_owned())
+            .chain(params)
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn validates_parameters() {
+        let set_volume = action("set_volume").unwrap();
+        assert_eq!(set_volume.validate(&params(&[("volume", "80")])), Ok(()));
+        assert_eq!(
+            set_volume.validate(&params(&[("volume", "0"), ("control", "PCM")])),
+            Ok(())
+        );
+        assert_eq!(
+            set_volume.validate(&params(&[])),
+            Err("missing parameter volume".to_owned())
+        );
+        assert_eq!(
+            set_volume.validate(&params(&[("volume", "101")])),
+            Err("invalid parameter volume".to_owned())
+        );
+        assert_eq!(
+            set_volume.validate(&params(&[("volume", "-1")])),
+            Err("invalid parameter volume".to_owned())
+        );
+        assert_eq!(
+            set_volume.validate(&params(&[("volume", "50"), ("card", "0")])),
+            Err("unknown parameter card".to_owned())
+        );
+    }
+
+    #[test]
+    fn rejects_option_like_names() {
+        let restart = action("restart_service").unwrap();
+        assert_eq!(
+            restart.validate(&params(&[("service", "myrts-client.service")])),
+            Ok(())
+        );
+        for service in ["", "--help", "-r", "a b", "a;reboot", "../x/"] {
+            assert_eq!(
+                restart.validate(&params(&[("service", service)])),
+                Err("invalid parameter service".to_owned()),
+                "{:?}",
+                service
+            );
+        }
+    }
+}
@@ -16,6 +16,9 @@ If MyRTS violates any of these terms, their license to use the software will aut
 */
 
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+pub mod action;
 
 /// Authenticate.
 /// This is the `authenticate` data sent from the client to the server.
@@ -25,6 +28,21 @@ pub struct Authenticate {
     pub client_type: u8,
     pub client_description: String,
     pub client_address: String,
+    /// The one-time enrollment token, presented by an unknown avs on first connect.
+    #[serde(default)]
+    pub token: Option<String>,
+    /// The device credential issued by the server on enrollment.
+    #[serde(default)]
+    pub credential: Option<String>,
+}
+
+/// Credential.
+/// This is the `credential` data sent from the server to the client.
+/// The client must persist it, send it back as `credential:ack` and present it on every following connect.
+/// The server only makes the credential final once acknowledged or presented.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Credential {
+    pub credential: String,
 }
 
 /// Scedule.
@@ -41,39 +59,86 @@ pub struct Schedule {
     pub month: Option<i32>,
     pub year: Option<i32>,
     pub volume: Option<f32>,
+    /// The record format: `mp3`, `opus`, `wav` or `flac`.
+    #[serde(default = "default_format")]
+    pub format: String,
+    /// The output devices to play on, empty for the default output.
+    #[serde(default)]
+    pub outputs: Vec<String>,
+    /// Emergency schedules bypass the quiet hours and volume profile.
+    #[serde(default)]
+    pub emergency: bool,
+    /// The content digest of the schedule as sent to the avs, not a counter.
+    /// It is computed at sync time, so several edits between two syncs yield a single change.
+    #[serde(default)]
+    pub rev: String,
+}
+
+/// Default record format, used by servers that only deliver mp3.
+fn default_format() -> String {
+    "mp3".to_string()
 }
 
 /// Sync.
 /// This is the `sync` data sent from the server to the client.
+/// `add` holds the new and changed schedules, which replace the local ones with the same sid.
+/// When `full` is set the client drops every local schedule before applying `add`.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Sync {
     pub add: Vec<Schedule>,
     pub remove: Vec<i32>,
+    /// The version to store once applied, unset by servers without versioned sync.
+    #[serde(default)]
+    pub version: Option<i64>,
+    #[serde(default)]
+    pub full: bool,
 }
 
 /// SyncReq.
 /// This is the `sync` data sent from the client to the server.
+/// `digest` is the `utils::crypto::sync_digest` of the local schedules,
+/// a mismatch with the server falls back to a full resync.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SyncReq {
     pub local: Vec<i32>,
+    /// The last applied version, unset by clients without versioned sync.
+    #[serde(default)]
+    pub version: Option<i64>,
+    #[serde(default)]
+    pub digest: Option<String>,
 }
 
 /// Turn.
 /// This is the `turn` data sent from the server to the client.
-#[derive(Debug, Serialize, Deserialize)]
+/// The credentials are only valid for the session they were issued for.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Turn {
+    /// The first turn url, kept for older clients.
     pub url: String,
     pub username: String,
     pub password: String,
+    /// Every stun and turn url, the credentials apply to the turn urls.
+    #[serde(default)]
+    pub urls: Vec<String>,
 }
 
 /// CmdRequest.
 /// This is the `cmd_request` data sent from the client to the server.
+/// Either `action` names an action of the catalog along with its `params`,
+/// or `command` is a raw shell command.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CmdRequest {
+    #[serde(default)]
     pub command: String,
     pub sender: i32,
     pub target: String,
+    /// The id of the command audit, set by the server and echoed in the response.
+    #[serde(default)]
+    pub id: Option<i32>,
+    #[serde(default)]
+    pub action: Option<String>,
+    #[serde(default)]
+    pub params: HashMap<String, String>,
 }
 
 /// CmdResponse.
@@ -83,13 +148,121 @@ pub struct CmdResponse {
     pub response: String,
     pub sender: i32,
     pub target: String,
+    /// The id of the command audit of the request.
+    #[serde(default)]
+    pub id: Option<i32>,
+    /// The exit status of the command, unset when it couldn't be run.
+    #[serde(default)]
+    pub status: Option<i32>,
 }
 
 /// Offer.
+/// `port_ids` and `zones` select the avs ports of the targets,
+/// the server resolves them into `outputs` for each target.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Offer {
     pub offer: String,
     pub target: Vec<String>,
+    #[serde(default)]
+    pub port_ids: Vec<i32>,
+    #[serde(default)]
+    pub zones: Vec<String>,
+    /// The output devices to play on, empty for the default output.
+    #[serde(default)]
+    pub outputs: Vec<String>,
+    /// Emergency streams bypass the volume profile.
+    #[serde(default)]
+    pub emergency: bool,
+    /// The ice servers issued to the avs for this stream.
+    #[serde(default)]
+    pub turn: Option<Turn>,
+    /// Record the stream into the records of the user.
+    #[serde(default)]
+    pub record: bool,
+    /// The priority of the stream, capped to the priority of the role of the user.
+    #[serde(default)]
+    pub priority: Option<u8>,
+    /// What to do with the targets that are busy with another stream.
+    #[serde(default)]
+    pub conflict: Conflict,
+}
+
+/// Priority of the emergency streams, above every role.
+pub const EMERGENCY_PRIORITY: u8 = 100;
+
+/// Get the priority of a stream of a user with the role.
+/// The requested priority can only lower the priority of the role.
+pub fn stream_priority(role_id: i32, emergency: bool, requested: Option<u8>) -> u8 {
+    if emergency {
+        return EMERGENCY_PRIORITY;
+    }
+    let max = match role_id {
+        1 => 30,
+        2 => 20,
+        _ => 10,
+    };
+    requested.map(|p| p.min(max)).unwrap_or(max)
+}
+
+/// Conflict.
+/// The policy for the targets that are busy with another stream.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Conflict {
+    /// Take over the targets of lower priority streams, they are handed back when this one ends.
+    Preempt,
+    /// Join the targets once they are released.
+    Queue,
+    /// Leave the busy targets out.
+    #[default]
+    Reject,
+}
+
+impl std::str::FromStr for Conflict {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "preempt" => Ok(Self::Preempt),
+            "queue" => Ok(Self::Queue),
+            "reject" => Ok(Self::Reject),
+            _ => Err(format!("unknown conflict policy: {}", s)),
+        }
+    }
+}
+
+/// BusyTarget.
+/// A target that was busy with another stream.
+/// `status` is `preempted`, `queued` or `rejected`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BusyTarget {
+    pub target: String,
+    pub status: String,
+}
+
+/// BusyTargets.
+/// This is the `offer:busy` data sent from the server to the client.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BusyTargets {
+    pub targets: Vec<BusyTarget>,
+}
+
+/// StreamTargets.
+/// This is the `stream:preempted` and `stream:resumed` data sent from the server to the client,
+/// the targets taken over by a higher priority stream or handed back.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StreamTargets {
+    pub targets: Vec<String>,
+}
+
+/// Listen.
+/// This is the `listen` data sent from the client to the server,
+/// to listen to an ongoing broadcast without being one of its targets.
+/// The server replies with `listen:offer` and `listen:ices`, the client answers with
+/// `listen:answer` and `listen:ices` and leaves with `listen:stop`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Listen {
+    pub broadcast_id: i32,
 }
 
 /// WsErr.
@@ -126,3 +299,288 @@ pub struct AvsInfo {
     pub disk_free: Option<String>,
     pub cpu_temp: Option<String>,
 }
+
+/// The record was played.
+pub const OUTCOME_PLAYED: i32 = 1;
+/// The play was skipped, blocked, muted, in quiet hours or the player was busy.
+pub const OUTCOME_SKIPPED: i32 = 2;
+/// The record file is missing.
+pub const OUTCOME_MISSING: i32 = 3;
+/// The play failed.
+pub const OUTCOME_ERROR: i32 = 4;
+
+/// PlayLog.
+/// A single playback outcome recorded by the client, sent as a `play_log` entry of the outbox.
+/// `started_at` and `ended_at` are in the device local time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayLog {
+    pub id: i32,
+    pub sid: i32,
+    pub record_url: String,
+    pub started_at: chrono::NaiveDateTime,
+    pub ended_at: Option<chrono::NaiveDateTime>,
+    /// The effective volume, with the multiplier of the volume profile applied.
+    pub volume: Option<f32>,
+    /// One of the `OUTCOME_*` constants.
+    pub outcome: i32,
+    pub message: Option<String>,
+    /// The offset of the local time from UTC in seconds when the play started.
+    #[serde(default)]
+    pub utc_offset: Option<i32>,
+}
+
+/// DeviceConfig.
+/// This is the `config` data sent from the server to the client.
+/// Unset fields fall back to the client defaults.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DeviceConfig {
+    /// Volume used by schedules without their own volume, `0.0` to `1.0`.
+    pub volume: Option<f32>,
+    /// `debug`, `info`, `warn` or `error`.
+    pub log_level: Option<String>,
+    /// Start of the quiet hours, formatted as `HH:MM`.
+    pub quiet_start: Option<String>,
+    /// End of the quiet hours, formatted as `HH:MM`.
+    pub quiet_end: Option<String>,
+    /// Interval of the device info report in seconds.
+    pub telemetry_interval: Option<i32>,
+    /// Programs the device is allowed to run as raw commands, none are allowed when unset.
+    pub allowed_commands: Option<Vec<String>>,
+    /// Name of the audio output device, the system default when unset.
+    pub audio_device: Option<String>,
+    /// Volume multipliers by time of day, the first matching range applies.
+    #[serde(default)]
+    pub volume_profile: Option<Vec<VolumeRange>>,
+}
+
+/// VolumeRange.
+/// A time range of the volume profile, the range may pass midnight.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VolumeRange {
+    /// Start of the range, formatted as `HH:MM`.
+    pub start: String,
+    /// End of the range, formatted as `HH:MM`.
+    pub end: String,
+    /// Multiplier of the volume, `0.0` mutes the device.
+    pub multiplier: f32,
+}
+
+/// UpdateAnnounce.
+/// This is the `update` data sent from the server to the client.
+/// `signature` is the hex encoded ed25519 signature of `myrts-client:{version}:{size}:{sha256}`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateAnnounce {
+    pub id: i32,
+    pub version: String,
+    pub url: String,
+    pub size: i64,
+    pub sha256: String,
+    pub signature: String,
+}
+
+/// UpdateStatus.
+/// This is the `update:status` data sent from the client to the server.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateStatus {
+    pub id: i32,
+    /// 1 = announced, 2 = downloading, 3 = verified, 4 = installed, 5 = completed, 6 = failed, 7 = rolled back.
+    pub status: i32,
+    /// Download progress in percent.
+    pub progress: Option<i32>,
+    pub message: Option<String>,
+    /// The version the client is running.
+    pub version: String,
+}
+
+/// InterfaceAddr.
+/// An address of a network interface.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InterfaceAddr {
+    pub ip: String,
+    pub netmask: String,
+}
+
+/// InterfaceTelemetry.
+/// The addresses and traffic counters of a network interface.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InterfaceTelemetry {
+    pub name: String,
+    pub addrs: Vec<InterfaceAddr>,
+    /// Received bytes since boot.
+    pub rx_bytes: Option<i64>,
+    /// Transmitted bytes since boot.
+    pub tx_bytes: Option<i64>,
+}
+
+/// Telemetry.
+/// This is the `telemetry` data sent from the client to the server.
+/// Sizes are in bytes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Telemetry {
+    /// Uptime of the system in seconds.
+    pub uptime: Option<i64>,
+    /// One minute load average.
+    pub cpu_load: Option<f32>,
+    /// Cpu temperature in celsius.
+    pub cpu_temp: Option<f32>,
+    pub mem_total: Option<i64>,
+    pub mem_free: Option<i64>,
+    pub disk_total: Option<i64>,
+    pub disk_free: Option<i64>,
+    pub networks: Vec<InterfaceTelemetry>,
+    /// Name of the audio output device, `None` for the system default.
+    pub audio_output: Option<String>,
+    /// Whether the audio output device is available.
+    pub audio_ok: bool,
+    pub audio_playing: bool,
+    /// The client version.
+    pub version: String,
+    /// Resident set size of the client process.
+    pub rss: Option<i64>,
+}
+
+/// Port.
+/// An audio output device of the avs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Port {
+    /// A stable number derived from the name of the output device.
+    pub speaker: i32,
+    /// The name of the output device.
+    pub device: String,
+}
+
+/// Ports.
+/// This is the `ports` data sent from the client to the server.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Ports {
+    pub ports: Vec<Port>,
+}
+
+/// Playlist.
+/// This is the `playlist` data sent from the server to the client.
+/// The playlist plays on the default output whenever the scheduler is idle, an empty playlist disables it.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Playlist {
+    pub tracks: Vec<PlaylistTrack>,
+    pub shuffle: bool,
+    pub repeat: bool,
+    /// Start of the time window, formatted as `HH:MM`, always playing when unset.
+    pub start: Option<String>,
+    /// End of the time window, formatted as `HH:MM`.
+    pub end: Option<String>,
+    /// Volume of the playlist, the device volume when unset.
+    pub volume: Option<f32>,
+    /// Volume multiplier while a schedule is playing, `0.0` pauses the playlist.
+    pub duck: f32,
+}
+
+/// PlaylistTrack.
+/// A record of the playlist.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlaylistTrack {
+    pub record_url: String,
+    pub format: String,
+}
+
+/// StreamStats.
+/// This is the `stream:stats` data sent from the client to the server while playing a live stream,
+/// the server forwards it to the user with `avs` set to the avs unique id.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StreamStats {
+    #[serde(default)]
+    pub avs: String,
+    /// Packets received.
+    pub received: u64,
+    /// Packets that never arrived in time to play.
+    pub lost: u64,
+    /// Lost packets recovered by the opus in-band fec.
+    pub recovered: u64,
+    /// Lost packets concealed by the opus plc.
+    pub concealed: u64,
+    /// Packets arrived after their play out time.
+    pub late: u64,
+    pub duplicate: u64,
+    /// Packets dropped to catch up with the sender.
+    pub dropped: u64,
+    /// Times the jitter buffer ran empty while playing.
+    pub underruns: u64,
+    /// Interarrival jitter in milliseconds.
+    pub jitter_ms: f32,
+    /// Audio buffered in milliseconds.
+    pub delay_ms: f32,
+    /// Adaptive target of the buffered audio in milliseconds.
+    pub target_ms: f32,
+}
+
+/// SelfTestReq.
+/// This is the `self_test` data sent from the server to the client.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelfTestReq {
+    pub id: i32,
+    /// Play a test tone on the default output.
+    pub tone: bool,
+}
+
+/// SelfTestCheck.
+/// The result of a single check of the self test.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelfTestCheck {
+    /// `audio_device`, `tone`, `decoders`, `disk` or `database`.
+    pub name: String,
+    /// `None` when the check is skipped.
+    pub passed: Option<bool>,
+    pub detail: String,
+    pub duration_ms: u64,
+}
+
+/// SelfTestReport.
+/// This is the `self_test` data sent from the client to the server.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelfTestReport {
+    pub id: i32,
+    pub version: String,
+    pub checks: Vec<SelfTestCheck>,
+}
+
+/// OutboxEntry.
+/// An event queued by the client while it was offline or waiting for the server.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutboxEntry {
+    pub id: i32,
+    /// `telemetry`, `play_log` or `command`.
+    pub kind: String,
+    /// The json encoded [`Telemetry`], [`PlayLog`] or [`CmdResponse`].
+    pub payload: String,
+    /// When the event happened, in UTC.
+    pub created_at: chrono::NaiveDateTime,
+}
+
+/// OutboxBatch.
+/// This is the `outbox` data sent from the client to the server, ordered by id.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OutboxBatch {
+    pub entries: Vec<OutboxEntry>,
+}
+
+/// OutboxAck.
+/// This is the `outbox:ack` data sent from the server to the client.
+/// Entries that are not acknowledged are sent again.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OutboxAck {
+    pub ids: Vec<i32>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stream_priority_follows_the_role() {
+        assert_eq!(stream_priority(1, false, None), 30);
+        assert_eq!(stream_priority(2, false, None), 20);
+        assert_eq!(stream_priority(3, false, None), 10);
+        assert_eq!(stream_priority(2, false, Some(5)), 5);
+        assert_eq!(stream_priority(2, false, Some(90)), 20);
+        assert_eq!(stream_priority(3, true, Some(1)), EMERGENCY_PRIORITY);
+    }
+}
@@ -21,7 +21,39 @@ use metadata::MediaFileMetadata;
 use std::env;
 use std::path::Path;
 
-use crate::crypto::uuid;
+use crate::{crypto::uuid, media::AudioFormat};
+
+/// Probe the format of an audio file.
+pub fn probe(path: &str) -> Result<AudioFormat, String> {
+    ffmpeg::init().map_err(|e| e.to_string())?;
+    let ictx = format::input(&path).map_err(|e| e.to_string())?;
+    let stream = ictx
+        .streams()
+        .best(media::Type::Audio)
+        .ok_or("no audio stream found".to_string())?;
+    let codec = stream.parameters().id();
+    let container = ictx.format().name().to_string();
+    match (container.as_str(), codec) {
+        ("mp3", codec::Id::MP3) => Ok(AudioFormat::Mp3),
+        ("ogg", codec::Id::OPUS) => Ok(AudioFormat::Opus),
+        ("flac", codec::Id::FLAC) => Ok(AudioFormat::Flac),
+        ("wav", _) if codec.medium() == media::Type::Audio => Ok(AudioFormat::Wav),
+        _ => Err(format!(
+            "unsupported audio format: {} ({:?})",
+            container, codec
+        )),
+    }
+}
+
+/// Probe the format of an audio data.
+pub fn probe_audio(input: &[u8], ext: &str) -> Result<AudioFormat, String> {
+    let mut path = env::temp_dir();
+    path.push(format!("{}.{}", uuid(), ext));
+    std::fs::write(&path, input).map_err(|e| e.to_string())?;
+    let res = probe(path.to_str().unwrap());
+    let _ = std::fs::remove_file(&path);
+    res
+}
 
 /// Calculate duration of an audio.
 pub fn duration_of(path: &str) -> Result<u64, String> {
@@ -265,3 +297,157 @@ pub fn decode_audio(input: Vec<u8>) -> Vec<u8> {
         Err(_) => input,
     }
 }
+
+/// Duration of an opus packet produced by [`encode_opus`] in samples at 48kHz.
+pub const OPUS_FRAME_SAMPLES: u32 = 960;
+
+fn drain_encoder(encoder: &mut codec::encoder::audio::Encoder, packets: &mut Vec<Vec<u8>>) {
+    let mut encoded = ffmpeg::Packet::empty();
+    while encoder.receive_packet(&mut encoded).is_ok() {
+        if let Some(data) = encoded.data() {
+            packets.push(data.to_vec());
+        }
+    }
+}
+
+fn drain_filter(
+    filter: &mut filter::Graph,
+    encoder: &mut codec::encoder::audio::Encoder,
+    pts: &mut i64,
+    packets: &mut Vec<Vec<u8>>,
+) -> Result<(), ffmpeg::Error> {
+    let mut filtered = frame::Audio::empty();
+    while filter
+        .get("out")
+        .unwrap()
+        .sink()
+        .frame(&mut filtered)
+        .is_ok()
+    {
+        filtered.set_pts(Some(*pts));
+        *pts += filtered.samples() as i64;
+        encoder.send_frame(&filtered)?;
+        drain_encoder(encoder, packets);
+    }
+    Ok(())
+}
+
+fn opus_packets(path: &str) -> Result<(codec::Parameters, Vec<Vec<u8>>), ffmpeg::Error> {
+    ffmpeg::init()?;
+
+    let mut ictx = format::input(&path)?;
+    let input = ictx
+        .streams()
+        .best(media::Type::Audio)
+        .ok_or(ffmpeg::Error::StreamNotFound)?;
+    let index = input.index();
+    let context = ffmpeg::codec::context::Context::from_parameters(input.parameters())?;
+    let mut decoder = context.decoder().audio()?;
+    decoder.set_parameters(input.parameters())?;
+
+    let codec = ffmpeg::encoder::find(codec::Id::OPUS)
+        .ok_or(ffmpeg::Error::EncoderNotFound)?
+        .audio()?;
+    let mut encoder = ffmpeg::codec::context::Context::new().encoder().audio()?;
+    let channel_layout = ffmpeg::channel_layout::ChannelLayout::STEREO;
+    encoder.set_rate(48000);
+    encoder.set_channel_layout(channel_layout);
+    encoder.set_channels(channel_layout.channels());
+    if let Some(mut f) = codec.formats() {
+        if let Some(f) = f.next() {
+            encoder.set_format(f);
+        }
+    }
+    encoder.set_bit_rate(64000);
+    encoder.set_time_base((1, 48000));
+    let mut encoder = encoder.open_as(codec)?;
+
+    let mut filter = filter("anull", &decoder, &encoder)?;
+    let mut packets = vec![];
+    let mut pts = 0;
+    let mut decoded = frame::Audio::empty();
+
+    for (stream, packet) in ictx.packets() {
+        if stream.index() != index || decoder.send_packet(&packet).is_err() {
+            continue;
+        }
+        while decoder.receive_frame(&mut decoded).is_ok() {
+            let timestamp = decoded.timestamp();
+            decoded.set_pts(timestamp);
+            filter.get("in").unwrap().source().add(&decoded)?;
+            drain_filter(&mut filter, &mut encoder, &mut pts, &mut packets)?;
+        }
+    }
+
+    decoder.send_eof()?;
+    while decoder.receive_frame(&mut decoded).is_ok() {
+        let timestamp = decoded.timestamp();
+        decoded.set_pts(timestamp);
+        filter.get("in").unwrap().source().add(&decoded)?;
+        drain_filter(&mut filter, &mut encoder, &mut pts, &mut packets)?;
+    }
+
+    filter.get("in").unwrap().source().flush()?;
+    drain_filter(&mut filter, &mut encoder, &mut pts, &mut packets)?;
+
+    encoder.send_eof()?;
+    drain_encoder(&mut encoder, &mut packets);
+    Ok((codec::Parameters::from(&encoder), packets))
+}
+
+/// Decode the audio file and encode it into opus packets of [`OPUS_FRAME_SAMPLES`]
+/// at 48kHz stereo, ready to be sent over rtp.
+pub fn encode_opus(path: &str) -> Result<Vec<Vec<u8>>, String> {
+    opus_packets(path)
+        .map(|(_, packets)| packets)
+        .map_err(|e| e.to_string())
+}
+
+fn write_ogg(
+    output: &str,
+    parameters: codec::Parameters,
+    packets: Vec<Vec<u8>>,
+) -> Result<(), ffmpeg::Error> {
+    let mut octx = format::output_as(&output, "ogg")?;
+    {
+        let mut stream = octx.add_stream(codec::Id::OPUS)?;
+        stream.set_parameters(parameters);
+        stream.set_time_base((1, 48000));
+    }
+    octx.write_header()?;
+    let time_base = octx
+        .stream(0)
+        .ok_or(ffmpeg::Error::StreamNotFound)?
+        .time_base();
+    let mut pts = 0;
+    for data in packets {
+        let mut packet = ffmpeg::Packet::copy(&data);
+        packet.set_stream(0);
+        packet.set_pts(Some(pts));
+        packet.set_dts(Some(pts));
+        packet.set_duration(OPUS_FRAME_SAMPLES as i64);
+        packet.rescale_ts((1, 48000), time_base);
+        packet.write_interleaved(&mut octx)?;
+        pts += OPUS_FRAME_SAMPLES as i64;
+    }
+    octx.write_trailer()
+}
+
+/// Transcode the audio data into opus in an ogg container, the delivery format of the records.
+/// `ext` is the extension of the probed format of the data.
+pub fn transcode_opus(input: &[u8], ext: &str) -> Result<Vec<u8>, String> {
+    let mut path = env::temp_dir();
+    path.push(format!("{}.{}", uuid(), ext));
+    let mut out = env::temp_dir();
+    out.push(format!("{}.{}", uuid(), AudioFormat::Opus.name()));
+    let res = std::fs::write(&path, input)
+        .map_err(|e| e.to_string())
+        .and_then(|_| opus_packets(path.to_str().unwrap()).map_err(|e| e.to_string()))
+        .and_then(|(parameters, packets)| {
+            write_ogg(out.to_str().unwrap(), parameters, packets).map_err(|e| e.to_string())
+        })
+        .and_then(|_| std::fs::read(&out).map_err(|e| e.to_string()));
+    let _ = std::fs::remove_file(&path);
+    let _ = std::fs::remove_file(&out);
+    res
+}
@@ -0,0 +1,545 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+//! Layered configuration.
+//!
+//! Values are read from TOML files in order, every file overrides the previous ones,
+//! then from environment variables. Every value remembers where it came from so errors
+//! can point at the file or variable to fix.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    path::{Path, PathBuf},
+};
+
+/// Source.
+/// Where a config value came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    Default,
+    File(PathBuf),
+    Env(String),
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Default => write!(f, "default"),
+            Source::File(path) => write!(f, "file {}", path.display()),
+            Source::Env(name) => write!(f, "env {}", name),
+        }
+    }
+}
+
+/// ConfigError.
+/// A single invalid config value.
+#[derive(Debug, Clone)]
+pub struct ConfigError {
+    pub key: String,
+    pub source: Source,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.key.is_empty() {
+            write!(f, "{}: {}", self.source, self.message)
+        } else {
+            write!(f, "{} ({}): {}", self.key, self.source, self.message)
+        }
+    }
+}
+
+/// ConfigErrors.
+/// Every error found while loading the config.
+#[derive(Debug, Clone)]
+pub struct ConfigErrors(pub Vec<ConfigError>);
+
+impl fmt::Display for ConfigErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "invalid configuration:")?;
+        for e in &self.0 {
+            writeln!(f, "  - {}", e)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigErrors {}
+
+/// A raw value before it is converted.
+#[derive(Debug, Clone)]
+enum Raw {
+    Toml(toml::Value),
+    Text(String),
+}
+
+/// FromConfig.
+/// A type that can be read from a config value.
+pub trait FromConfig: Sized {
+    /// Read from a TOML value.
+    fn from_toml(value: toml::Value) -> Result<Self, String>;
+    /// Read from an environment variable.
+    fn from_text(value: &str) -> Result<Self, String>;
+}
+
+impl FromConfig for String {
+    fn from_toml(value: toml::Value) -> Result<Self, String> {
+        match value {
+            toml::Value::String(s) => Ok(s),
+            v => Err(format!("expected a string, found {}", v.type_str())),
+        }
+    }
+
+    fn from_text(value: &str) -> Result<Self, String> {
+        Ok(value.to_owned())
+    }
+}
+
+impl FromConfig for bool {
+    fn from_toml(value: toml::Value) -> Result<Self, String> {
+        match value {
+            toml::Value::Boolean(b) => Ok(b),
+            v => Err(format!("expected a boolean, found {}", v.type_str())),
+        }
+    }
+
+    fn from_text(value: &str) -> Result<Self, String> {
+        match value {
+            "1" | "true" | "yes" => Ok(true),
+            "0" | "false" | "no" => Ok(false),
+            v => Err(format!("expected a boolean, found `{}`", v)),
+        }
+    }
+}
+
+impl FromConfig for f64 {
+    fn from_toml(value: toml::Value) -> Result<Self, String> {
+        match value {
+            toml::Value::Float(f) => Ok(f),
+            toml::Value::Integer(i) => Ok(i as f64),
+            v => Err(format!("expected a number, found {}", v.type_str())),
+        }
+    }
+
+    fn from_text(value: &str) -> Result<Self, String> {
+        value
+            .parse::<f64>()
+            .map_err(|_| format!("expected a number, found `{}`", value))
+    }
+}
+
+macro_rules! from_config_int {
+    ($($ty: ty),*) => {
+        $(
+            impl FromConfig for $ty {
+                fn from_toml(value: toml::Value) -> Result<Self, String> {
+                    match value {
+                        toml::Value::Integer(i) => <$ty>::try_from(i).map_err(|_| {
+                            format!(
+                                "{} is out of range {}..={}",
+                                i,
+                                <$ty>::MIN,
+                                <$ty>::MAX
+                            )
+                        }),
+                        v => Err(format!("expected an integer, found {}", v.type_str())),
+                    }
+                }
+
+                fn from_text(value: &str) -> Result<Self, String> {
+                    value.parse::<$ty>().map_err(|_| {
+                        format!(
+                            "expected an integer in {}..={}, found `{}`",
+                            <$ty>::MIN,
+                            <$ty>::MAX,
+                            value
+                        )
+                    })
+                }
+            }
+        )*
+    };
+}
+
+from_config_int!(u16, u32, u64, i64);
+
+impl FromConfig for Vec<String> {
+    fn from_toml(value: toml::Value) -> Result<Self, String> {
+        match value {
+            toml::Value::Array(values) => values.into_iter().map(String::from_toml).collect(),
+            v => Err(format!(
+                "expected an array of strings, found {}",
+                v.type_str()
+            )),
+        }
+    }
+
+    /// Comma separated values.
+    fn from_text(value: &str) -> Result<Self, String> {
+        Ok(value
+            .split(',')
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(str::to_owned)
+            .collect())
+    }
+}
+
+/// ConfigLoader.
+/// Collects the config layers and reads typed values from them.
+/// Reading never fails, invalid values fall back to the default and are reported by [`ConfigLoader::finish`].
+pub struct ConfigLoader {
+    values: BTreeMap<String, (Raw, Source)>,
+    used: BTreeSet<String>,
+    errors: Vec<ConfigError>,
+}
+
+impl ConfigLoader {
+    /// Create an empty loader.
+    pub fn new() -> Self {
+        Self {
+            values: BTreeMap::new(),
+            used: BTreeSet::new(),
+            errors: vec![],
+        }
+    }
+
+    /// Add a TOML file layer, a missing file is an error only when `required`.
+    pub fn file(mut self, path: impl AsRef<Path>, required: bool) -> Self {
+        let path = path.as_ref().to_path_buf();
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) => {
+                if required || e.kind() != std::io::ErrorKind::NotFound {
+                    self.error("", Source::File(path), e.to_string());
+                }
+                return self;
+            }
+        };
+        match toml::from_str::<toml::Table>(&content) {
+            Ok(table) => self.flatten("", table, &path),
+            Err(e) => self.error("", Source::File(path), e.to_string().trim().to_owned()),
+        }
+        self
+    }
+
+    /// Flatten the table into dotted keys.
+    fn flatten(&mut self, prefix: &str, table: toml::Table, path: &Path) {
+        for (key, value) in table {
+            let key = if prefix.is_empty() {
+                key
+            } else {
+                format!("{}.{}", prefix, key)
+            };
+            match value {
+                toml::Value::Table(table) => self.flatten(&key, table, path),
+                value => {
+                    self.values
+                        .insert(key, (Raw::Toml(value), Source::File(path.to_path_buf())));
+                }
+            }
+        }
+    }
+
+    /// Override `key` with the environment variable `name` when it is set.
+    pub fn env(mut self, key: &str, name: &str) -> Self {
+        if let Ok(value) = std::env::var(name) {
+            self.values.insert(
+                key.to_owned(),
+                (Raw::Text(value), Source::Env(name.to_owned())),
+            );
+        }
+        self
+    }
+
+    /// Record an error.
+    fn error(&mut self, key: &str, source: Source, message: impl Into<String>) {
+        self.errors.push(ConfigError {
+            key: key.to_owned(),
+            source,
+            message: message.into(),
+        });
+    }
+
+    /// Read an optional value.
+    pub fn get_opt<T: FromConfig>(&mut self, key: &str) -> Option<T> {
+        self.used.insert(key.to_owned());
+        let (raw, source) = self.values.get(key).cloned()?;
+        let res = match raw {
+            Raw::Toml(value) => T::from_toml(value),
+            Raw::Text(value) => T::from_text(&value),
+        };
+        match res {
+            Ok(value) => Some(value),
+            Err(e) => {
+                self.error(key, source, e);
+                None
+            }
+        }
+    }
+
+    /// Read a value or the default.
+    pub fn get<T: FromConfig>(&mut self, key: &str, default: T) -> T {
+        self.get_opt(key).unwrap_or(default)
+    }
+
+    /// Read a secret, either from `key` or from the file at `{key}_file`.
+    /// The environment variables `{name}` and `{name}_FILE` override both, in this order.
+    /// Surrounding whitespace of the file is trimmed.
+    pub fn secret(&mut self, key: &str, name: &str, default: &str) -> String {
+        let file_key = format!("{}_file", key);
+        if let Ok(value) = std::env::var(name) {
+            self.used.insert(file_key);
+            self.values.insert(
+                key.to_owned(),
+                (Raw::Text(value), Source::Env(name.to_owned())),
+            );
+            return self.get(key, default.to_owned());
+        }
+        let file_name = format!("{}_FILE", name);
+        if let Ok(path) = std::env::var(&file_name) {
+            self.values
+                .insert(file_key.clone(), (Raw::Text(path), Source::Env(file_name)));
+        }
+        match self.get_opt::<String>(&file_key) {
+            Some(path) => {
+                self.used.insert(key.to_owned());
+                match std::fs::read_to_string(&path) {
+                    Ok(value) => value.trim().to_owned(),
+                    Err(e) => {
+                        let source = self.source(&file_key);
+                        self.error(&file_key, source, format!("{}: {}", path, e));
+                        default.to_owned()
+                    }
+                }
+            }
+            None => self.get(key, default.to_owned()),
+        }
+    }
+
+    /// Get the source of a key.
+    pub fn source(&self, key: &str) -> Source {
+        self.values
+            .get(key)
+            .map(|(_, source)| source.clone())
+            .unwrap_or(Source::Default)
+    }
+
+    /// Report an invalid value of `key` unless `valid`.
+    pub fn check(&mut self, key: &str, valid: bool, message: &str) {
+        if !valid {
+            let source = self.source(key);
+            self.error(key, source, message);
+        }
+    }
+
+    /// Finish loading, reporting every invalid value and every unknown key of the files.
+    pub fn finish(mut self) -> Result<(), ConfigErrors> {
+        let unknown = self
+            .values
+            .iter()
+            .filter(|(key, (_, source))| {
+                !self.used.contains(*key) && matches!(source, Source::File(_))
+            })
+            .map(|(key, (_, source))| (key.clone(), source.clone()))
+            .collect::<Vec<_>>();
+        for (key, source) in unknown {
+            self.error(&key, source, "unknown key");
+        }
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigErrors(self.errors))
+        }
+    }
+}
+
+impl Default for ConfigLoader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Args.
+/// The command line arguments shared by the binaries.
+#[derive(Debug, Clone, Default)]
+pub struct Args {
+    /// The config file given by `--config <path>`.
+    pub config: Option<PathBuf>,
+    /// `--check-config`, validate the config and exit.
+    pub check_config: bool,
+}
+
+impl Args {
+    /// Parse the arguments of the process.
+    pub fn parse() -> Result<Self, String> {
+        let mut res = Self::default();
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--check-config" => res.check_config = true,
+                "--config" => match args.next() {
+                    Some(path) => res.config = Some(PathBuf::from(path)),
+                    None => return Err("--config requires a path".to_owned()),
+                },
+                arg => {
+                    if let Some(path) = arg.strip_prefix("--config=") {
+                        res.config = Some(PathBuf::from(path));
+                    } else {
+                        return Err(format!("unknown argument `{}`", arg));
+                    }
+                }
+            }
+        }
+        Ok(res)
+    }
+
+    /// Create the loader with the system file, `/etc/myrts/{name}.toml`,
+    /// and the file given on the command line.
+    pub fn loader(&self, name: &str) -> ConfigLoader {
+        let mut loader = ConfigLoader::new().file(format!("/etc/myrts/{}.toml", name), false);
+        if let Some(path) = &self.config {
+            loader = loader.file(path, true);
+        }
+        loader
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Write a temporary file, removed when the test is done with it.
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(content: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("{}.toml", crate::crypto::uuid()));
+            std::fs::write(&path, content).unwrap();
+            Self(path)
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    fn keys(errors: &ConfigErrors) -> Vec<&str> {
+        errors.0.iter().map(|e| e.key.as_str()).collect()
+    }
+
+    #[test]
+    fn later_files_override_earlier_ones() {
+        let system = TempFile::new("port = 1\nname = \"system\"\n[turn]\nurls = [\"turn:a\"]\n");
+        let local = TempFile::new("port = 2\n");
+        let mut l = ConfigLoader::new()
+            .file(&system.0, true)
+            .file(&local.0, true);
+        assert_eq!(l.get::<u16>("port", 0), 2);
+        assert_eq!(l.source("port"), Source::File(local.0.clone()));
+        assert_eq!(l.get("name", String::new()), "system");
+        assert_eq!(l.get::<Vec<String>>("turn.urls", vec![]), vec!["turn:a"]);
+        assert_eq!(l.get("missing", 7u64), 7);
+        assert!(l.finish().is_ok());
+    }
+
+    #[test]
+    fn environment_overrides_files() {
+        std::env::set_var("MYRTS_TEST_ENV_PORT", "3");
+        std::env::set_var("MYRTS_TEST_ENV_URLS", "turn:a, turn:b,");
+        let file = TempFile::new("port = 1\n");
+        let mut l = ConfigLoader::new()
+            .file(&file.0, true)
+            .env("port", "MYRTS_TEST_ENV_PORT")
+            .env("urls", "MYRTS_TEST_ENV_URLS")
+            .env("unset", "MYRTS_TEST_ENV_UNSET");
+        assert_eq!(l.get::<u16>("port", 0), 3);
+        assert_eq!(
+            l.source("port"),
+            Source::Env("MYRTS_TEST_ENV_PORT".to_owned())
+        );
+        assert_eq!(
+            l.get::<Vec<String>>("urls", vec![]),
+            vec!["turn:a".to_owned(), "turn:b".to_owned()]
+        );
+        assert_eq!(l.get_opt::<String>("unset"), None);
+        assert!(l.finish().is_ok());
+    }
+
+    #[test]
+    fn reports_every_invalid_value() {
+        std::env::set_var("MYRTS_TEST_INVALID_FLAG", "maybe");
+        let file = TempFile::new("port = 70000\nname = 1\ntypo = true\n");
+        let mut l = ConfigLoader::new()
+            .file(&file.0, true)
+            .file("/nonexistent/myrts.toml", false)
+            .env("flag", "MYRTS_TEST_INVALID_FLAG");
+        assert_eq!(l.get::<u16>("port", 1451), 1451);
+        assert_eq!(l.get("name", "default".to_owned()), "default");
+        assert!(!l.get("flag", false));
+        l.check("name", false, "must not be empty");
+        let errors = l.finish().unwrap_err();
+        assert_eq!(keys(&errors), vec!["port", "name", "flag", "name", "typo"]);
+        assert_eq!(
+            errors.0[2].source,
+            Source::Env("MYRTS_TEST_INVALID_FLAG".to_owned())
+        );
+        assert_eq!(errors.0[4].message, "unknown key");
+    }
+
+    #[test]
+    fn missing_required_file_is_an_error() {
+        let errors = ConfigLoader::new()
+            .file("/nonexistent/myrts.toml", true)
+            .finish()
+            .unwrap_err();
+        assert_eq!(errors.0.len(), 1);
+        assert_eq!(
+            errors.0[0].source,
+            Source::File(PathBuf::from("/nonexistent/myrts.toml"))
+        );
+    }
+
+    #[test]
+    fn secrets_are_read_from_files() {
+        let secret = TempFile::new("  s3cret\n");
+        let file = TempFile::new(&format!(
+            "[jwt]\nsecret_file = \"{}\"\n[smtp]\nkey = \"inline\"\n",
+            secret.0.display()
+        ));
+        std::env::set_var("MYRTS_TEST_SECRET_TURN", "from-env");
+        let mut l = ConfigLoader::new().file(&file.0, true);
+        assert_eq!(
+            l.secret("jwt.secret", "MYRTS_TEST_SECRET_JWT", ""),
+            "s3cret"
+        );
+        assert_eq!(l.secret("smtp.key", "MYRTS_TEST_SECRET_SMTP", ""), "inline");
+        assert_eq!(
+            l.secret("turn.secret", "MYRTS_TEST_SECRET_TURN", ""),
+            "from-env"
+        );
+        assert_eq!(
+            l.secret("none", "MYRTS_TEST_SECRET_NONE", "fallback"),
+            "fallback"
+        );
+        assert!(l.finish().is_ok());
+    }
+}
@@ -45,6 +45,7 @@ mod api {
     pub struct Jwt {
         enc: jsonwebtoken::EncodingKey,
         dec: jsonwebtoken::DecodingKey,
+        lifetime: std::time::Duration,
     }
 
     /// JwtClaim.
@@ -61,13 +62,23 @@ mod api {
         pub fn new(secret: &str) -> Self {
             let enc = jsonwebtoken::EncodingKey::from_secret(secret.as_bytes());
             let dec = jsonwebtoken::DecodingKey::from_secret(secret.as_bytes());
-            Self { enc, dec }
+            Self {
+                enc,
+                dec,
+                lifetime: std::time::Duration::from_secs(60 * 60 * 24 * 30),
+            }
+        }
+
+        /// Set the lifetime of the created tokens, 30 days by default.
+        pub fn with_lifetime(mut self, lifetime: std::time::Duration) -> Self {
+            self.lifetime = lifetime;
+            self
         }
 
         /// Create a new jwt token by the given user id.
         pub fn create(&self, user_id: i32) -> Result<String, String> {
             let now = std::time::SystemTime::now();
-            let exp = now + std::time::Duration::from_secs(60 * 60 * 24 * 30);
+            let exp = now + self.lifetime;
             let claim = JwtClaim {
                 sub: user_id,
                 company: "myrts".to_string(),
@@ -95,6 +106,27 @@ mod api {
             ))
         }
     }
+
+    /// Issue ephemeral turn credentials with the turn rest api scheme.
+    /// The username is `{expiry}:{user}` and the password is the base64 hmac-sha1 of the username,
+    /// keyed with the secret shared with the turn server.
+    pub fn turn_credentials(
+        secret: &str,
+        user: &str,
+        ttl: std::time::Duration,
+    ) -> (String, String) {
+        use base64::Engine;
+        let expiry = (std::time::SystemTime::now() + ttl)
+            .duration_since(std::time::SystemTime::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let username = format!("{}:{}", expiry, user);
+        let key =
+            ring::hmac::Key::new(ring::hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY, secret.as_bytes());
+        let tag = ring::hmac::sign(&key, username.as_bytes());
+        let password = base64::engine::general_purpose::STANDARD.encode(tag.as_ref());
+        (username, password)
+    }
 }
 
 /// Create random string.
@@ -119,3 +151,106 @@ pub fn hash(bytes: &[u8]) -> String {
     let result = hasher.finalize();
     hex::encode(result)
 }
+
+/// Compare two bytes in constant time, only the length is leaked.
+pub fn constant_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Sha256 hash of a bytes.
+pub fn sha256(bytes: &[u8]) -> String {
+    let result = ring::digest::digest(&ring::digest::SHA256, bytes);
+    hex::encode(result.as_ref())
+}
+
+/// Digest of the schedules of a device, given as `(sid, rev)` pairs in any order.
+pub fn sync_digest(entries: &[(i32, String)]) -> String {
+    let mut entries = entries.iter().collect::<Vec<_>>();
+    entries.sort();
+    let joined = entries
+        .into_iter()
+        .map(|(sid, rev)| format!("{}:{}", sid, rev))
+        .collect::<Vec<String>>()
+        .join(";");
+    hash(joined.as_bytes())
+}
+
+/// The message signed for a client update.
+pub fn update_message(version: &str, size: i64, sha256: &str) -> String {
+    format!("myrts-client:{}:{}:{}", version, size, sha256)
+}
+
+/// Verify an ed25519 signature, the key and signature are hex encoded.
+pub fn verify_ed25519(public_key: &str, message: &[u8], signature: &str) -> bool {
+    let (public_key, signature) = match (hex::decode(public_key), hex::decode(signature)) {
+        (Ok(public_key), Ok(signature)) => (public_key, signature),
+        _ => return false,
+    };
+    ring::signature::UnparsedPublicKey::new(&ring::signature::ED25519, public_key)
+        .verify(message, &signature)
+        .is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(entries: &[(i32, &str)]) -> Vec<(i32, String)> {
+        entries
+            .iter()
+            .map(|(sid, rev)| (*sid, rev.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn sync_digest_ignores_order() {
+        let a = sync_digest(&entries(&[(1, "a"), (2, "b"), (10, "c")]));
+        let b = sync_digest(&entries(&[(10, "c"), (1, "a"), (2, "b")]));
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn sync_digest_follows_revisions() {
+        let base = sync_digest(&entries(&[(1, "a"), (2, "b")]));
+        assert_ne!(base, sync_digest(&entries(&[(1, "a"), (2, "c")])));
+        assert_ne!(base, sync_digest(&entries(&[(1, "a")])));
+        assert_ne!(base, sync_digest(&entries(&[(1, "a"), (3, "b")])));
+        assert_eq!(sync_digest(&[]), sync_digest(&[]));
+    }
+
+    #[test]
+    fn constant_eq_compares_bytes() {
+        assert!(constant_eq(b"token", b"token"));
+        assert!(!constant_eq(b"token", b"tokem"));
+        assert!(!constant_eq(b"token", b"token2"));
+        assert!(constant_eq(b"", b""));
+    }
+
+    #[cfg(feature = "api")]
+    #[test]
+    fn turn_credentials_follow_the_rest_api_scheme() {
+        use base64::Engine;
+        let ttl = std::time::Duration::from_secs(3600);
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let (username, password) = turn_credentials("secret", "stream-1", ttl);
+
+        let (expiry, user) = username.split_once(':').unwrap();
+        assert_eq!(user, "stream-1");
+        let expiry = expiry.parse::<u64>().unwrap();
+        assert!(expiry >= now + 3600 && expiry <= now + 3601);
+
+        let tag = base64::engine::general_purpose::STANDARD
+            .decode(password)
+            .unwrap();
+        let key = ring::hmac::Key::new(ring::hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY, b"secret");
+        assert!(ring::hmac::verify(&key, username.as_bytes(), &tag).is_ok());
+        let other = ring::hmac::Key::new(ring::hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY, b"other");
+        assert!(ring::hmac::verify(&other, username.as_bytes(), &tag).is_err());
+    }
+}
@@ -111,6 +111,11 @@ impl ApiAssets {
         remove_file(self.audio.join(name))
     }
 
+    /// Get the audio directory.
+    pub fn audio_dir(&self) -> &Path {
+        &self.audio
+    }
+
     #[cfg(feature = "api")]
     /// Calculate audio duration.
     pub fn duration_audio(&self, name: &str) -> Result<u64, String> {
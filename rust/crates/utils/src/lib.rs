@@ -17,19 +17,48 @@ If MyRTS violates any of these terms, their license to use the software will aut
 
 #[cfg(feature = "api")]
 pub mod audio;
+pub mod config;
 pub mod crypto;
 #[cfg(feature = "api")]
 pub mod data;
 #[cfg(feature = "api")]
 pub mod encoding;
-pub mod env;
 pub mod files;
 #[cfg(feature = "api")]
 pub mod mail;
+pub mod media;
 pub mod time;
 
-/// Download audio/mp3.
+/// Download audio.
 pub async fn download_audio(url: &str) -> Result<Vec<u8>, reqwest::Error> {
     let res = reqwest::get(url).await?;
     Ok(res.bytes().await?.to_vec())
 }
+
+/// Download a file of the expected size, reporting the downloaded and total bytes.
+/// The download is aborted as soon as the file turns out larger than expected,
+/// so a broken or hostile url can't exhaust the memory.
+pub async fn download_file<F>(url: &str, size: u64, mut progress: F) -> Result<Vec<u8>, String>
+where
+    F: FnMut(u64, Option<u64>),
+{
+    let mut res = reqwest::get(url)
+        .await
+        .and_then(|res| res.error_for_status())
+        .map_err(|e| e.to_string())?;
+    let total = res.content_length();
+    if let Some(total) = total {
+        if total != size {
+            return Err(format!("size mismatch, expected {} got {}", size, total));
+        }
+    }
+    let mut data = Vec::new();
+    while let Some(chunk) = res.chunk().await.map_err(|e| e.to_string())? {
+        if (data.len() + chunk.len()) as u64 > size {
+            return Err(format!("size mismatch, expected {} got more", size));
+        }
+        data.extend_from_slice(&chunk);
+        progress(data.len() as u64, total);
+    }
+    Ok(data)
+}
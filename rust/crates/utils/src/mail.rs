@@ -19,6 +19,7 @@ use resend_rs::{types::CreateEmailBaseOptions, Resend};
 
 const CONFIRM_TEMPLATE: &'static str = include_str!("./mail/confirm_mail.html");
 const RESET_TEMPLATE: &'static str = include_str!("./mail/reset_mail.html");
+const ALERT_TEMPLATE: &'static str = include_str!("./mail/alert_mail.html");
 
 /// Mail.
 /// The mailing utility.
@@ -79,4 +80,17 @@ impl Mail {
             .replace("{{{name}}}", name);
         self.send(to, "MYRTS - Reset password", &html).await
     }
+
+    /// Send device alert email.
+    pub async fn send_alert(
+        &self,
+        name: &str,
+        to: &str,
+        message: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let html = ALERT_TEMPLATE
+            .replace("{{{message}}}", message)
+            .replace("{{{name}}}", name);
+        self.send(to, "MYRTS - Device alert", &html).await
+    }
 }
@@ -0,0 +1,71 @@
+/*
+Copyright (c) 2023 Ade M Ramdani <qcynaut@gmail.com>
+
+This software is proprietary and licensed to MyRTS under the terms of the Closed-Source Software License for Freelancers, which is available at https://dictionary.cambridge.org/us/dictionary/english/license.
+
+MyRTS owns all right, title, and interest in and to the software, including all intellectual property rights therein.
+MyRTS may use the software for any purpose, including commercial use.
+MyRTS may modify the software, but only for their own internal use.
+MyRTS may not distribute the software or any modified versions of the software to third parties.
+MyRTS may not reverse engineer the software.
+MyRTS may not create derivative works from the software.
+
+MyRTS agrees to credit you as the developer of the software in all promotional materials and documentation for the software.
+
+If MyRTS violates any of these terms, their license to use the software will automatically terminate.
+*/
+
+/// AudioFormat.
+/// The supported audio delivery formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioFormat {
+    Mp3,
+    /// Opus in an ogg container.
+    Opus,
+    Wav,
+    Flac,
+}
+
+impl AudioFormat {
+    /// Get the format by its name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "mp3" => Some(Self::Mp3),
+            "opus" => Some(Self::Opus),
+            "wav" => Some(Self::Wav),
+            "flac" => Some(Self::Flac),
+            _ => None,
+        }
+    }
+
+    /// Get the format by the file extension.
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        match &*ext.to_lowercase() {
+            "mp3" => Some(Self::Mp3),
+            "ogg" | "oga" | "opus" => Some(Self::Opus),
+            "wav" => Some(Self::Wav),
+            "flac" => Some(Self::Flac),
+            _ => None,
+        }
+    }
+
+    /// Get the format name, also the file extension used to store the format.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Mp3 => "mp3",
+            Self::Opus => "opus",
+            Self::Wav => "wav",
+            Self::Flac => "flac",
+        }
+    }
+
+    /// Get the mime type.
+    pub fn mime(&self) -> &'static str {
+        match self {
+            Self::Mp3 => "audio/mpeg",
+            Self::Opus => "audio/ogg",
+            Self::Wav => "audio/wav",
+            Self::Flac => "audio/flac",
+        }
+    }
+}
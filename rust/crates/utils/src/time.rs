@@ -58,9 +58,18 @@ pub fn get_first_and_last_date_of_month(now: NaiveDateTime) -> (NaiveDateTime, N
     (first, last)
 }
 
+/// Get local now timestamp.
+pub fn local_now() -> chrono::NaiveDateTime {
+    chrono::Local::now().naive_local()
+}
+
 /// Get week number.
 pub fn week_no() -> u32 {
-    let now = chrono::Local::now().naive_local();
+    week_no_of(local_now())
+}
+
+/// Get week number of the given timestamp.
+pub fn week_no_of(now: NaiveDateTime) -> u32 {
     let (first, last) = get_first_and_last_date_of_month(now);
     let num_from_sunday = first.weekday().number_from_sunday();
     let end = first + Duration::days((7 - num_from_sunday) as i64);
@@ -103,3 +112,39 @@ pub fn day_of_week() -> u32 {
 pub fn time_24() -> String {
     chrono::Local::now().format("%H:%M").to_string()
 }
+
+/// Recurrence.
+/// The recurrence of a schedule, shared by the api and the devices.
+#[derive(Debug, Clone, Copy)]
+pub struct Recurrence<'a> {
+    /// 1 repeats monthly on the `weeks` and `days` or the `dates`,
+    /// 2 fires on the `dates` of the `month` and `year`.
+    pub kind: i32,
+    pub weeks: &'a [Option<i32>],
+    /// 1 is sunday, 7 is saturday.
+    pub days: &'a [Option<i32>],
+    pub dates: &'a [Option<i32>],
+    pub month: Option<i32>,
+    pub year: Option<i32>,
+}
+
+impl Recurrence<'_> {
+    /// Check if the schedule fires at the given timestamp.
+    pub fn fires_at(&self, at: NaiveDateTime) -> bool {
+        let has = |values: &[Option<i32>], value: i32| values.contains(&Some(value));
+        let day = at.day() as i32;
+        match self.kind {
+            1 => {
+                let week = week_no_of(at) as i32;
+                let weekday = at.weekday().number_from_sunday() as i32;
+                (has(self.weeks, week) && has(self.days, weekday)) || has(self.dates, day)
+            }
+            2 => {
+                self.month == Some(at.month() as i32)
+                    && self.year == Some(at.year())
+                    && has(self.dates, day)
+            }
+            _ => false,
+        }
+    }
+}